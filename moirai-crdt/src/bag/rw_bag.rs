@@ -7,7 +7,11 @@ use moirai_protocol::{
         query::{QueryOperation, Read},
     },
     event::Event,
-    state::{effect_context::EffectContext, log::IsLog, po_log::VecLog},
+    state::{
+        effect_context::EffectContext,
+        log::{IsLog, PruneStats},
+        po_log::VecLog,
+    },
     utils::intern_str::{InternalizeOp, Interner},
 };
 
@@ -75,6 +79,10 @@ where
     fn is_default(&self) -> bool {
         self.0.is_default()
     }
+
+    fn prune_stats(&self) -> PruneStats {
+        self.0.prune_stats()
+    }
 }
 
 impl<V> EvalNested<Read<HashMap<V, usize>>> for RWBagLog<V>
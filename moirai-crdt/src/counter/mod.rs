@@ -1,3 +1,4 @@
 pub mod resettable_counter;
+pub mod saturating_counter;
 pub mod simple_counter;
 mod stable;
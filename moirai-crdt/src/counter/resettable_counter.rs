@@ -8,6 +8,8 @@ use std::{
 use deepsize::DeepSizeOf;
 #[cfg(feature = "fuzz")]
 use moirai_fuzz::{op_generator::OpGenerator, value_generator::ValueGenerator};
+#[cfg(feature = "fuzz")]
+use moirai_protocol::state::unstable_state::CausalReplay;
 use moirai_protocol::{
     crdt::{
         eval::Eval,
@@ -15,7 +17,7 @@ use moirai_protocol::{
         query::{QueryOperation, Read},
     },
     event::{tag::Tag, tagged_op::TaggedOp},
-    state::unstable_state::{CausalReplay, IsUnstableCore},
+    state::unstable_state::{IsUnstableCore, IsUnstablePrune},
     utils::intern_str::{InternalizeOp, Interner},
 };
 #[cfg(feature = "fuzz")]
@@ -25,27 +27,44 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "serde")]
 use tsify::Tsify;
 
-use crate::counter::stable::CounterStable;
+use crate::{
+    counter::stable::{Component, CounterComponents},
+    policy::{fold_lww, stabilize_lww},
+    undo::Invertible,
+};
 
-#[derive(Clone, Debug)]
+/// Resettable counter.
+/// A `Set` assigns an absolute value and overrides the operations it causally follows.
+/// Among concurrent `Set`s, the one with the greatest `(lamport, replica id)` wins,
+/// and the increments and decrements concurrent with it are applied on top.
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize, Tsify))]
 #[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
 pub enum Counter<V: Add + AddAssign + SubAssign + Default + Copy> {
     Inc(V),
     Dec(V),
     Reset,
+    Set(V),
+}
+
+fn set<V>(op: &Counter<V>) -> Option<&V>
+where
+    V: Add + AddAssign + SubAssign + Default + Copy,
+{
+    match op {
+        Counter::Set(v) => Some(v),
+        _ => None,
+    }
 }
 
 impl<V> PureCRDT for Counter<V>
 where
-    V: Add<Output = V> + AddAssign + SubAssign + Default + Copy + Debug + PartialEq,
+    V: Add<Output = V> + AddAssign + SubAssign + Default + Copy + Debug + PartialEq + Component,
 {
     type Value = V;
-    type StableState = CounterStable<V>;
+    type StableState = CounterComponents<V>;
     type Rejection = Infallible;
 
-    const DISABLE_R_WHEN_NOT_R: bool = true;
-
     fn redundant_itself<'a>(
         new_tagged_op: &TaggedOp<Self>,
         _stable: &Self::StableState,
@@ -65,11 +84,86 @@ where
     ) -> bool {
         !is_conc && matches!(new_tagged_op.op(), Counter::Reset)
     }
+
+    fn redundant_by_when_not_redundant(
+        _old_op: &Self,
+        _old_event_id: Option<&Tag>,
+        is_conc: bool,
+        new_tagged_op: &TaggedOp<Self>,
+    ) -> bool {
+        !is_conc && matches!(new_tagged_op.op(), Counter::Set(_))
+    }
+
+    /// Only a `Set` prunes the operations it follows, increments and decrements do not scan the log.
+    fn prunes_when_not_redundant(new_tagged_op: &TaggedOp<Self>) -> bool {
+        matches!(new_tagged_op.op(), Counter::Set(_))
+    }
+
+    /// The winning `Set` is added to the stable increments, which are all concurrent with it.
+    fn stabilize(
+        tagged_op: &TaggedOp<Self>,
+        stable: &mut Self::StableState,
+        unstable: &mut impl IsUnstablePrune<Self>,
+    ) {
+        if let Some(v) = stabilize_lww(tagged_op, unstable, set) {
+            stable.net += *v;
+        }
+    }
+}
+
+/// Components of the value: the increments and decrements that survive the last reset or
+/// set, and the net value, i.e., the winning set plus the increments minus the decrements.
+pub struct ReadComponents<V>(std::marker::PhantomData<V>);
+
+impl<V> ReadComponents<V> {
+    pub fn new() -> Self {
+        Self(std::marker::PhantomData)
+    }
+}
+
+impl<V> Default for ReadComponents<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> QueryOperation for ReadComponents<V> {
+    type Response = CounterComponents<V>;
+}
+
+impl<V, U> Eval<ReadComponents<V>, U> for Counter<V>
+where
+    V: Add<Output = V> + AddAssign + SubAssign + Default + Copy + Debug + PartialEq + Component,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        _q: ReadComponents<V>,
+        stable: &Self::StableState,
+        unstable: &U,
+    ) -> <ReadComponents<V> as QueryOperation>::Response {
+        let mut components = *stable;
+        let winner = fold_lww(unstable.iter(), set, |op| match op {
+            Counter::Inc(v) => {
+                components.incs = components.incs.add_component(*v);
+                components.net += *v;
+            }
+            Counter::Dec(v) => {
+                components.decs = components.decs.add_component(*v);
+                components.net -= *v;
+            }
+            Counter::Reset | Counter::Set(_) => unreachable!(),
+        });
+        // The stable state only holds what is concurrent with an unstable `Set`
+        if let Some(v) = winner {
+            components.net += *v;
+        }
+        components
+    }
 }
 
 impl<V, U> Eval<Read<<Self as PureCRDT>::Value>, U> for Counter<V>
 where
-    V: Add<Output = V> + AddAssign + SubAssign + Default + Copy + Debug + PartialEq,
+    V: Add<Output = V> + AddAssign + SubAssign + Default + Copy + Debug + PartialEq + Component,
     U: IsUnstableCore<Self>,
 {
     fn execute_query(
@@ -77,15 +171,17 @@ where
         stable: &Self::StableState,
         unstable: &U,
     ) -> <Read<<Self as PureCRDT>::Value> as QueryOperation>::Response {
-        let mut counter = *stable;
-        for op in unstable.iter().map(|t| t.op()) {
-            match op {
-                Counter::Inc(v) => *counter.as_inner_mut() += *v,
-                Counter::Dec(v) => *counter.as_inner_mut() -= *v,
-                Counter::Reset => unreachable!(),
-            }
+        // Only the net value: the increments and decrements may overflow when summed apart
+        let mut net = stable.net;
+        let winner = fold_lww(unstable.iter(), set, |op| match op {
+            Counter::Inc(v) => net += *v,
+            Counter::Dec(v) => net -= *v,
+            Counter::Reset | Counter::Set(_) => unreachable!(),
+        });
+        if let Some(v) = winner {
+            net += *v;
         }
-        *counter.as_inner()
+        net
     }
 }
 
@@ -98,6 +194,7 @@ where
             Counter::Inc(v) => write!(f, "Inc({v})"),
             Counter::Dec(v) => write!(f, "Dec({v})"),
             Counter::Reset => write!(f, "Reset"),
+            Counter::Set(v) => write!(f, "Set({v})"),
         }
     }
 }
@@ -112,13 +209,14 @@ where
         + Copy
         + Debug
         + PartialEq
+        + Component
         + ValueGenerator,
 {
     type Config = ();
 
     fn generate(
         rng: &mut impl Rng,
-        _config: &Self::Config,
+        _config: &mut Self::Config,
         _stable: &<Self as PureCRDT>::StableState,
         _unstable: &impl CausalReplay<Self>,
     ) -> Self {
@@ -126,9 +224,10 @@ where
             Inc,
             Dec,
             Reset,
+            Set,
         }
         let choice = rand::seq::IteratorRandom::choose(
-            [Choice::Inc, Choice::Dec, Choice::Reset].iter(),
+            [Choice::Inc, Choice::Dec, Choice::Reset, Choice::Set].iter(),
             rng,
         )
         .unwrap();
@@ -137,6 +236,20 @@ where
             Choice::Inc => Counter::Inc(value),
             Choice::Dec => Counter::Dec(value),
             Choice::Reset => Counter::Reset,
+            Choice::Set => Counter::Set(value),
+        }
+    }
+}
+
+impl<V> Invertible for Counter<V>
+where
+    V: Add<Output = V> + AddAssign + SubAssign + Default + Copy + Debug + PartialEq + Component,
+{
+    fn inverse(&self) -> Option<Self> {
+        match self {
+            Counter::Inc(v) => Some(Counter::Dec(*v)),
+            Counter::Dec(v) => Some(Counter::Inc(*v)),
+            Counter::Reset | Counter::Set(_) => None,
         }
     }
 }
@@ -155,7 +268,10 @@ mod tests {
     use moirai_protocol::{crdt::query::Read, replica::IsReplica};
 
     use crate::{
-        counter::resettable_counter::Counter,
+        counter::{
+            resettable_counter::{Counter, ReadComponents},
+            stable::CounterComponents,
+        },
         utils::membership::{triplet, twins},
     };
 
@@ -226,6 +342,167 @@ mod tests {
         assert_eq!(replica_a.query(Read::new()), replica_c.query(Read::new()));
     }
 
+    #[test]
+    pub fn set_vs_concurrent_inc() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet::<Counter<i32>>();
+
+        let event = replica_a.send(Counter::Inc(3)).unwrap();
+        replica_b.receive(event.clone());
+        replica_c.receive(event);
+
+        // The set overrides the increment it follows, not the concurrent one
+        let event_a = replica_a.send(Counter::Set(10)).unwrap();
+        let event_b = replica_b.send(Counter::Inc(2)).unwrap();
+        replica_a.receive(event_b.clone());
+        replica_b.receive(event_a.clone());
+        replica_c.receive(event_b);
+        replica_c.receive(event_a);
+
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), 12);
+        }
+
+        // Once everything is stable, the value is unchanged
+        let event = replica_c.send(Counter::Inc(1)).unwrap();
+        replica_a.receive(event.clone());
+        replica_b.receive(event);
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), 13);
+        }
+    }
+
+    #[test]
+    pub fn concurrent_set_and_inc() {
+        let (mut replica_a, mut replica_b) = twins::<Counter<i32>>();
+
+        // The increment is applied on top of the concurrent set, whichever is delivered first
+        let event_a = replica_a.send(Counter::Set(10)).unwrap();
+        let event_b = replica_b.send(Counter::Inc(2)).unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+        for replica in [&replica_a, &replica_b] {
+            assert_eq!(replica.query(Read::new()), 12);
+        }
+
+        // A set following both of them overrides them
+        let event = replica_b.send(Counter::Set(4)).unwrap();
+        replica_a.receive(event);
+        let event = replica_a.send(Counter::Inc(1)).unwrap();
+        replica_b.receive(event);
+        for replica in [&replica_a, &replica_b] {
+            assert_eq!(replica.query(Read::new()), 5);
+        }
+    }
+
+    #[test]
+    pub fn concurrent_sets_converge() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet::<Counter<i32>>();
+
+        let event_a = replica_a.send(Counter::Set(5)).unwrap();
+        let event_b = replica_b.send(Counter::Set(7)).unwrap();
+        let event_c = replica_c.send(Counter::Dec(1)).unwrap();
+
+        replica_a.receive(event_b.clone());
+        replica_a.receive(event_c.clone());
+        // Opposite delivery order
+        replica_b.receive(event_c);
+        replica_b.receive(event_a.clone());
+        replica_c.receive(event_b);
+        replica_c.receive(event_a);
+
+        // Same Lamport clock: the greatest replica id wins
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), 6);
+        }
+
+        // Stabilize both sets
+        let event_a = replica_a.send(Counter::Inc(1)).unwrap();
+        let event_b = replica_b.send(Counter::Inc(1)).unwrap();
+        let event_c = replica_c.send(Counter::Inc(1)).unwrap();
+        for (replica, events) in [
+            (&mut replica_a, [&event_b, &event_c]),
+            (&mut replica_b, [&event_a, &event_c]),
+            (&mut replica_c, [&event_a, &event_b]),
+        ] {
+            for event in events {
+                replica.receive(event.clone());
+            }
+        }
+
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), 9);
+        }
+    }
+
+    #[test]
+    pub fn read_components() {
+        let (mut replica_a, mut replica_b) = twins::<Counter<i32>>();
+
+        for op in [Counter::Inc(5), Counter::Dec(2), Counter::Inc(1)] {
+            let event = replica_a.send(op).unwrap();
+            replica_b.receive(event);
+        }
+        let result = CounterComponents {
+            incs: 6,
+            decs: 2,
+            net: 4,
+        };
+        assert_eq!(replica_a.query(ReadComponents::new()), result);
+        assert_eq!(replica_b.query(ReadComponents::new()), result);
+
+        // The reset zeroes the components it follows, not the concurrent decrement
+        let event_a = replica_a.send(Counter::Reset).unwrap();
+        let event_b = replica_b.send(Counter::Dec(3)).unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+        let event = replica_a.send(Counter::Inc(7)).unwrap();
+        replica_b.receive(event);
+
+        let result = CounterComponents {
+            incs: 7,
+            decs: 3,
+            net: 4,
+        };
+        assert_eq!(replica_a.query(ReadComponents::new()), result);
+        assert_eq!(replica_b.query(ReadComponents::new()), result);
+
+        // A set is part of the net value only
+        let event = replica_b.send(Counter::Set(10)).unwrap();
+        replica_a.receive(event);
+        let event = replica_a.send(Counter::Dec(1)).unwrap();
+        replica_b.receive(event);
+
+        let result = CounterComponents {
+            incs: 0,
+            decs: 1,
+            net: 9,
+        };
+        assert_eq!(replica_a.query(ReadComponents::new()), result);
+        assert_eq!(replica_b.query(ReadComponents::new()), result);
+        assert_eq!(replica_a.query(Read::new()), 9);
+    }
+
+    #[test]
+    pub fn read_only_sums_the_net_value() {
+        let (mut replica_a, mut replica_b) = twins::<Counter<i8>>();
+
+        // The increments sum to more than `i8::MAX`, not the net value
+        for op in [Counter::Inc(100), Counter::Dec(100), Counter::Inc(100)] {
+            let event = replica_a.send(op).unwrap();
+            replica_b.receive(event);
+        }
+        assert_eq!(replica_a.query(Read::new()), 100);
+        assert_eq!(replica_b.query(Read::new()), 100);
+        // Only the components wrap around
+        let result = CounterComponents {
+            incs: 200u8 as i8,
+            decs: 100,
+            net: 100,
+        };
+        assert_eq!(replica_a.query(ReadComponents::new()), result);
+        assert_eq!(replica_b.query(ReadComponents::new()), result);
+    }
+
     #[cfg(feature = "fuzz")]
     #[test]
     #[ignore]
@@ -0,0 +1,221 @@
+use std::{
+    convert::Infallible,
+    fmt::{Debug, Display},
+    ops::{Add, AddAssign, SubAssign},
+};
+
+#[cfg(feature = "test_utils")]
+use deepsize::DeepSizeOf;
+#[cfg(feature = "fuzz")]
+use moirai_fuzz::op_generator::OpGenerator;
+#[cfg(feature = "fuzz")]
+use moirai_protocol::state::unstable_state::CausalReplay;
+use moirai_protocol::{
+    crdt::{
+        eval::Eval,
+        pure_crdt::{PureCRDT, StableFold},
+        query::{QueryOperation, Read},
+    },
+    state::unstable_state::IsUnstableCore,
+    utils::intern_str::{InternalizeOp, Interner},
+};
+#[cfg(feature = "fuzz")]
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use tsify::Tsify;
+
+use crate::counter::{simple_counter::Counter, stable::CounterStable};
+
+/// Integer types whose counter value is clamped to their bounds instead of wrapping.
+pub trait Saturate: Add + AddAssign + SubAssign + Default + Copy + Debug + PartialEq {
+    fn widen(self) -> i128;
+    fn saturate(total: i128) -> Self;
+}
+
+macro_rules! impl_saturate {
+    ($($t:ty),*) => {
+        $(
+            impl Saturate for $t {
+                fn widen(self) -> i128 {
+                    self as i128
+                }
+
+                fn saturate(total: i128) -> Self {
+                    total.clamp(<$t>::MIN as i128, <$t>::MAX as i128) as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_saturate!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// Counter that clamps its value to `V::MIN`/`V::MAX` instead of overflowing.
+///
+/// Operations are folded in an `i128` accumulator and the result is clamped only on read,
+/// so the value does not depend on the delivery order and is identical on every replica.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize, Tsify))]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub struct SaturatingCounter<V: Add + AddAssign + SubAssign + Default + Copy>(pub Counter<V>);
+
+impl<V> SaturatingCounter<V>
+where
+    V: Add + AddAssign + SubAssign + Default + Copy,
+{
+    pub fn inc(v: V) -> Self {
+        Self(Counter::Inc(v))
+    }
+
+    pub fn dec(v: V) -> Self {
+        Self(Counter::Dec(v))
+    }
+
+    pub(crate) fn delta(&self) -> i128
+    where
+        V: Saturate,
+    {
+        match self.0 {
+            Counter::Inc(v) => v.widen(),
+            Counter::Dec(v) => -v.widen(),
+        }
+    }
+}
+
+impl<V> PureCRDT for SaturatingCounter<V>
+where
+    V: Saturate,
+{
+    type Value = V;
+    type StableState = CounterStable<i128>;
+    type Rejection = Infallible;
+
+    const DISABLE_R_WHEN_R: bool = true;
+    const DISABLE_R_WHEN_NOT_R: bool = true;
+}
+
+impl<V> StableFold for SaturatingCounter<V> where V: Saturate {}
+
+impl<V, U> Eval<Read<<Self as PureCRDT>::Value>, U> for SaturatingCounter<V>
+where
+    V: Saturate,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        _q: Read<<Self as PureCRDT>::Value>,
+        stable: &Self::StableState,
+        unstable: &U,
+    ) -> <Read<<Self as PureCRDT>::Value> as QueryOperation>::Response {
+        let total = unstable
+            .iter()
+            .fold(*stable.as_inner(), |acc, t| acc + t.op().delta());
+        V::saturate(total)
+    }
+}
+
+impl<V> Display for SaturatingCounter<V>
+where
+    V: Add + AddAssign + SubAssign + Default + Copy + Debug + Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(feature = "fuzz")]
+impl OpGenerator for SaturatingCounter<i8> {
+    type Config = ();
+
+    fn generate(
+        rng: &mut impl Rng,
+        _config: &mut Self::Config,
+        _stable: &<Self as PureCRDT>::StableState,
+        _unstable: &impl CausalReplay<Self>,
+    ) -> Self {
+        let value = (rng.next_u32() % 128) as i8;
+        // Favor increments so that the upper bound is actually reached
+        if rng.next_u32().is_multiple_of(4) {
+            SaturatingCounter::dec(value)
+        } else {
+            SaturatingCounter::inc(value)
+        }
+    }
+}
+
+impl<V> InternalizeOp for SaturatingCounter<V>
+where
+    V: Add + AddAssign + SubAssign + Default + Copy,
+{
+    fn internalize(self, _interner: &Interner) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::{crdt::query::Read, replica::IsReplica};
+
+    use crate::{counter::saturating_counter::SaturatingCounter, utils::membership::twins};
+
+    #[test]
+    pub fn saturate_past_max() {
+        let (mut replica_a, mut replica_b) = twins::<SaturatingCounter<i8>>();
+
+        let event_a = replica_a.send(SaturatingCounter::inc(100)).unwrap();
+        let event_b = replica_b.send(SaturatingCounter::inc(100)).unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+
+        assert_eq!(replica_a.query(Read::new()), i8::MAX);
+        assert_eq!(replica_b.query(Read::new()), i8::MAX);
+
+        let event = replica_a.send(SaturatingCounter::inc(127)).unwrap();
+        replica_b.receive(event);
+
+        assert_eq!(replica_a.query(Read::new()), 127);
+        assert_eq!(replica_b.query(Read::new()), 127);
+    }
+
+    #[test]
+    pub fn saturate_order_independent() {
+        let (mut replica_a, mut replica_b) = twins::<SaturatingCounter<i8>>();
+
+        // A sees +100, +100, -100 while B sees -100, +100, +100
+        let event_a_1 = replica_a.send(SaturatingCounter::inc(100)).unwrap();
+        let event_a_2 = replica_a.send(SaturatingCounter::inc(100)).unwrap();
+        let event_b = replica_b.send(SaturatingCounter::dec(100)).unwrap();
+
+        replica_a.receive(event_b);
+        replica_b.receive(event_a_1);
+        replica_b.receive(event_a_2);
+
+        assert_eq!(replica_a.query(Read::new()), 100);
+        assert_eq!(replica_b.query(Read::new()), 100);
+    }
+
+    #[cfg(feature = "fuzz")]
+    #[test]
+    #[ignore]
+    fn fuzz_saturating_counter() {
+        use moirai_fuzz::{
+            config::{FuzzerConfig, RunConfig},
+            fuzzer::fuzzer,
+        };
+        use moirai_protocol::state::po_log::VecLog;
+
+        let run = RunConfig::new(0.4, 8, 1_000, None, None, false, false);
+        let runs = vec![run.clone(); 1];
+
+        let config = FuzzerConfig::<VecLog<SaturatingCounter<i8>>>::new(
+            "saturating_counter",
+            runs,
+            true,
+            |a, b| a == b,
+            false,
+        );
+
+        fuzzer::<VecLog<SaturatingCounter<i8>>>(config);
+    }
+}
@@ -11,7 +11,7 @@ use moirai_fuzz::op_generator::OpGenerator;
 use moirai_protocol::{
     crdt::{
         eval::Eval,
-        pure_crdt::PureCRDT,
+        pure_crdt::{PureCRDT, StableFold},
         query::{QueryOperation, Read},
     },
     state::unstable_state::{CausalReplay, IsUnstableCore},
@@ -44,6 +44,12 @@ where
 
     const DISABLE_R_WHEN_R: bool = true;
     const DISABLE_R_WHEN_NOT_R: bool = true;
+    const NEEDS_CAUSAL_DEPS: bool = false;
+}
+
+impl<V> StableFold for Counter<V> where
+    V: Add + AddAssign + SubAssign + Default + Copy + Debug + PartialEq
+{
 }
 
 impl<V, U> Eval<Read<<Self as PureCRDT>::Value>, U> for Counter<V>
@@ -80,19 +86,22 @@ where
 }
 
 #[cfg(feature = "fuzz")]
-impl OpGenerator for Counter<i32> {
+impl<V> OpGenerator for Counter<V>
+where
+    V: Add + AddAssign + SubAssign + Default + Copy + Debug + PartialEq + From<i32>,
+{
     type Config = ();
 
     fn generate(
         rng: &mut impl Rng,
-        _config: &Self::Config,
+        _config: &mut Self::Config,
         _stable: &<Self as PureCRDT>::StableState,
         _unstable: &impl CausalReplay<Self>,
     ) -> Self {
         let choice = ["Inc", "Dec"][rng.next_u32() as usize % 2];
         match choice {
-            "Inc" => Counter::Inc(rng.next_u32() as i32),
-            "Dec" => Counter::Dec(rng.next_u32() as i32),
+            "Inc" => Counter::Inc(V::from(rng.next_u32() as i32)),
+            "Dec" => Counter::Dec(V::from(rng.next_u32() as i32)),
             _ => unreachable!(),
         }
     }
@@ -109,9 +118,29 @@ where
 
 #[cfg(test)]
 mod tests {
-    use moirai_protocol::{crdt::query::Read, replica::IsReplica};
+    use moirai_protocol::{
+        broadcast::{
+            batch::Batch,
+            tcsb::{IsTcsbTest, Tcsb},
+        },
+        clock::version_vector::Version,
+        codec::encode_batch,
+        crdt::query::Read,
+        event::Event,
+        persistence::OpCodec,
+        replica::{IsReplica, NotReconstructible, Replica, ReplicaIdx},
+        state::{graph_log::GraphLog, log::IsLog, po_log::VecLog},
+    };
+
+    use proptest::prelude::*;
 
-    use crate::{counter::simple_counter::Counter, utils::membership::twins};
+    use crate::{
+        counter::simple_counter::Counter,
+        utils::{
+            convergence::{self, Step},
+            membership::{triplet, twins, twins_log},
+        },
+    };
 
     #[test]
     pub fn simple_counter() {
@@ -146,6 +175,214 @@ mod tests {
         assert_eq!(replica_a.query(Read::new()), replica_b.query(Read::new()));
     }
 
+    #[test]
+    pub fn simple_counter_without_dependencies() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet::<Counter<isize>>();
+
+        let event_a = replica_a.send(Counter::Inc(5)).unwrap();
+        replica_b.receive(event_a.clone());
+        let event_b = replica_b.send(Counter::Dec(2)).unwrap();
+        // Only the counter of b is sent
+        assert_eq!(
+            event_b
+                .event()
+                .version()
+                .iter()
+                .filter(|(_, seq)| *seq > 0)
+                .count(),
+            1
+        );
+
+        // Delivered before the event of a it followed
+        replica_c.receive(event_b.clone());
+        assert_eq!(replica_c.query(Read::new()), -2);
+        replica_c.receive(event_a);
+        replica_a.receive(event_b);
+
+        let event_c = replica_c.send(Counter::Inc(1)).unwrap();
+        replica_a.receive(event_c.clone());
+        replica_b.receive(event_c);
+
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), 4);
+        }
+    }
+
+    #[test]
+    pub fn simple_counter_without_dependencies_stabilizes() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet::<Counter<isize>>();
+
+        for _ in 0..2 {
+            let event_a = replica_a.send(Counter::Inc(1)).unwrap();
+            let event_b = replica_b.send(Counter::Inc(2)).unwrap();
+            let event_c = replica_c.send(Counter::Dec(1)).unwrap();
+            replica_a.receive(event_b.clone());
+            replica_a.receive(event_c.clone());
+            replica_b.receive(event_a.clone());
+            replica_b.receive(event_c);
+            replica_c.receive(event_a);
+            replica_c.receive(event_b);
+        }
+
+        // The events only carry the counter of their origin: none of them is known to be stable
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.tcsb().last_stable_version().sum(), 0);
+            assert_eq!(replica.tcsb().outbox_len(), 6);
+            assert_eq!(replica.query(Read::new()), 4);
+        }
+
+        // The clocks exchanged by the pulls acknowledge every event
+        let mut replicas = [replica_a, replica_b, replica_c];
+        for i in 0..replicas.len() {
+            for j in 0..replicas.len() {
+                if i != j {
+                    let batch = replicas[j].pull(replicas[i].since());
+                    replicas[i].receive_batch(batch);
+                }
+            }
+        }
+        for replica in &replicas {
+            let lsv = replica.tcsb().last_stable_version();
+            assert!(lsv.iter().all(|(_, seq)| seq == 2), "{lsv}");
+            assert_eq!(replica.tcsb().outbox_len(), 0);
+            assert_eq!(replica.query(Read::new()), 4);
+        }
+    }
+
+    #[test]
+    pub fn simple_counter_bounded_history() {
+        let (mut replica_a, mut replica_b) = twins::<Counter<isize>>();
+
+        for _ in 0..2_000 {
+            let event_a = replica_a.send(Counter::Inc(2)).unwrap();
+            let event_b = replica_b.send(Counter::Dec(1)).unwrap();
+            replica_a.receive(event_b);
+            replica_b.receive(event_a);
+            // The events only carry the counter of their origin: the pulls acknowledge them
+            replica_a.receive_batch(replica_b.pull(replica_a.since()));
+            replica_b.receive_batch(replica_a.pull(replica_b.since()));
+
+            // The stable events are folded into a single value and dropped from the outbox
+            for replica in [&replica_a, &replica_b] {
+                let stats = replica.state().prune_stats();
+                assert!(stats.live_events + stats.tombstones <= 2, "{stats:?}");
+                assert!(stats.stable_ops <= 1, "{stats:?}");
+                assert!(replica.tcsb().outbox_len() <= 2);
+            }
+        }
+
+        assert_eq!(replica_a.query(Read::new()), 2_000);
+        assert_eq!(replica_b.query(Read::new()), 2_000);
+    }
+
+    impl OpCodec for Counter<isize> {
+        fn encode(&self, buf: &mut Vec<u8>) {
+            let (tag, value) = match self {
+                Counter::Inc(v) => (0, v),
+                Counter::Dec(v) => (1, v),
+            };
+            buf.push(tag);
+            buf.extend(value.to_le_bytes());
+        }
+
+        fn decode(bytes: &[u8]) -> Option<Self> {
+            let (tag, value) = bytes.split_first()?;
+            let value = isize::from_le_bytes(value.try_into().ok()?);
+            match tag {
+                0 => Some(Counter::Inc(value)),
+                1 => Some(Counter::Dec(value)),
+                _ => None,
+            }
+        }
+    }
+
+    /// Encoded size of the events broadcast by the first of 8 replicas, each of them
+    /// incrementing the counter in turn and receiving the increments of the others.
+    fn broadcast_size<L>() -> usize
+    where
+        L: IsLog<Op = Counter<isize>>,
+    {
+        let members: Vec<String> = (0..8).map(|i| format!("r{i}")).collect();
+        let members: Vec<&str> = members.iter().map(String::as_str).collect();
+        let mut replicas: Vec<Replica<L, Tcsb<Counter<isize>>>> = members
+            .iter()
+            .map(|id| Replica::bootstrap(id.to_string(), &members))
+            .collect();
+        let mut events: Vec<Event<Counter<isize>>> = Vec::new();
+        for _ in 0..50 {
+            for i in 0..replicas.len() {
+                let message = replicas[i].send(Counter::Inc(1)).unwrap();
+                for (j, replica) in replicas.iter_mut().enumerate() {
+                    if j != i {
+                        replica.receive(message.clone());
+                    }
+                }
+                if i == 0 {
+                    events.push(message.event().clone());
+                }
+            }
+        }
+        let version = Version::new(ReplicaIdx(0), events[0].version().resolver().clone());
+        // Without the members and the clock of the batch, written in both cases
+        let header = encode_batch::<Counter<isize>>(&Batch::new(Vec::new(), version.clone())).len();
+        encode_batch(&Batch::new(events, version)).len() - header
+    }
+
+    #[test]
+    pub fn simple_counter_events_without_dependencies_are_smaller() {
+        // The graph log needs the full dependencies of the events
+        const {
+            assert!(!VecLog::<Counter<isize>>::NEEDS_CAUSAL_DEPS);
+            assert!(GraphLog::<Counter<isize>>::NEEDS_CAUSAL_DEPS);
+        }
+
+        let elided = broadcast_size::<VecLog<Counter<isize>>>();
+        let full = broadcast_size::<GraphLog<Counter<isize>>>();
+        assert!(elided * 2 < full, "{elided} bytes against {full}");
+    }
+
+    #[test]
+    pub fn simple_counter_query_at() {
+        let (mut replica_a, mut replica_b) = twins::<Counter<isize>>();
+
+        let event = replica_b.send(Counter::Dec(1)).unwrap();
+        replica_a.receive(event);
+        replica_a.send(Counter::Inc(5)).unwrap();
+        let version = replica_a.tcsb().matrix_clock().origin_version().clone();
+        replica_a.send(Counter::Inc(3)).unwrap();
+
+        assert_eq!(replica_a.query(Read::new()), 7);
+        assert_eq!(replica_a.query_at(Read::new(), version.clone()), Ok(4));
+
+        // The new event of b is stable, but not included in the version
+        let event = replica_b.send(Counter::Dec(2)).unwrap();
+        replica_a.receive(event);
+        assert_eq!(
+            replica_a.query_at(Read::new(), version),
+            Err(NotReconstructible)
+        );
+    }
+
+    #[test]
+    pub fn simple_counter_query_at_graph() {
+        let (mut replica_a, mut replica_b) = twins_log::<GraphLog<Counter<isize>>>();
+
+        let event = replica_b.send(Counter::Dec(1)).unwrap();
+        replica_a.receive(event);
+        replica_a.send(Counter::Inc(5)).unwrap();
+        let version = replica_a.tcsb().matrix_clock().origin_version().clone();
+        replica_a.send(Counter::Inc(3)).unwrap();
+        assert_eq!(replica_a.query_at(Read::new(), version.clone()), Ok(4));
+
+        // The stable state of the graph holds the new event of b
+        let event = replica_b.send(Counter::Dec(2)).unwrap();
+        replica_a.receive(event);
+        assert_eq!(
+            replica_a.query_at(Read::new(), version),
+            Err(NotReconstructible)
+        );
+    }
+
     #[cfg(feature = "fuzz")]
     #[test]
     #[ignore]
@@ -164,4 +401,78 @@ mod tests {
 
         fuzzer::<VecLog<Counter<i32>>>(config);
     }
+
+    #[cfg(feature = "fuzz")]
+    #[test]
+    fn intermediate_convergence_counter() {
+        use moirai_fuzz::{
+            config::{FuzzerConfig, RunConfig},
+            fuzzer::fuzzer,
+        };
+        use moirai_protocol::state::po_log::VecLog;
+
+        let run = RunConfig::new(0.3, 4, 300, None, Some([11; 32]), false, false)
+            .with_intermediate_checks();
+
+        // 300 values of the `i32` range cannot overflow an `i64`
+        let config = FuzzerConfig::<VecLog<Counter<i64>>>::new(
+            "counter_intermediate",
+            vec![run],
+            true,
+            |a, b| a == b,
+            false,
+        );
+
+        fuzzer::<VecLog<Counter<i64>>>(config);
+    }
+
+    #[cfg(feature = "fuzz")]
+    #[test]
+    fn weave_counter() {
+        use moirai_fuzz::op_weaver::{EventGraphConfig, op_weaver};
+        use moirai_protocol::state::po_log::VecLog;
+
+        let operations = vec![
+            (0, Counter::Inc(1)),
+            (1, Counter::Inc(5)),
+            (0, Counter::Dec(2)),
+            (2, Counter::Inc(3)),
+            (1, Counter::Dec(4)),
+            (2, Counter::Inc(7)),
+        ];
+        let config = EventGraphConfig::<VecLog<Counter<i32>>>::new(
+            "counter_weave",
+            3,
+            operations,
+            200,
+            [7; 32],
+            |a, b| a == b,
+        );
+
+        op_weaver::<VecLog<Counter<i32>>>(config);
+    }
+
+    proptest! {
+        #[test]
+        fn converges_under_random_schedules(
+            schedule in convergence::schedule(
+                prop_oneof![
+                    (-10..10i32).prop_map(Counter::Inc),
+                    (-10..10i32).prop_map(Counter::Dec),
+                ],
+                40,
+            )
+        ) {
+            let expected: i32 = schedule
+                .iter()
+                .map(|step| match step {
+                    Step::Send(_, Counter::Inc(v)) => *v,
+                    Step::Send(_, Counter::Dec(v)) => -*v,
+                    Step::Deliver(..) => 0,
+                })
+                .sum();
+            let values = convergence::run(schedule);
+            prop_assert!(values.iter().all(|v| *v == expected), "{values:?} != {expected}");
+        }
+    }
 }
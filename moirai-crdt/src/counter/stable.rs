@@ -11,7 +11,9 @@ use moirai_protocol::{
 };
 
 use crate::counter::{
-    resettable_counter::Counter as ResettableCounter, simple_counter::Counter as SimpleCounter,
+    resettable_counter::Counter as ResettableCounter,
+    saturating_counter::{Saturate, SaturatingCounter},
+    simple_counter::Counter as SimpleCounter,
 };
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
@@ -30,24 +32,79 @@ impl<V> CounterStable<V> {
     }
 }
 
-impl<V> IsStableState<ResettableCounter<V>> for CounterStable<V>
+/// Value of a counter whose increments and decrements can be summed apart, see [`CounterComponents`].
+pub trait Component: Copy {
+    /// Sum of two components, wrapping around for integers.
+    fn add_component(self, other: Self) -> Self;
+}
+
+macro_rules! impl_wrapping_component {
+    ($($t:ty),*) => {
+        $(
+            impl Component for $t {
+                fn add_component(self, other: Self) -> Self {
+                    self.wrapping_add(other)
+                }
+            }
+        )*
+    };
+}
+
+impl_wrapping_component!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+);
+
+impl Component for f32 {
+    fn add_component(self, other: Self) -> Self {
+        self + other
+    }
+}
+
+impl Component for f64 {
+    fn add_component(self, other: Self) -> Self {
+        self + other
+    }
+}
+
+/// Increments and decrements that survive the last reset or set, and the resulting value.
+///
+/// The increments and decrements are summed apart from the net value: with an integer `V`,
+/// they wrap around on a long stream of both, where the net value does not overflow.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub struct CounterComponents<V> {
+    pub incs: V,
+    pub decs: V,
+    pub net: V,
+}
+
+impl<V> IsStableState<ResettableCounter<V>> for CounterComponents<V>
 where
-    V: Add + AddAssign + SubAssign + Default + Copy + Debug + PartialEq,
+    V: Add + AddAssign + SubAssign + Default + Copy + Debug + PartialEq + Component,
 {
     fn is_default(&self) -> bool {
-        self.0 == V::default()
+        *self == Self::default()
     }
 
     fn apply(&mut self, value: ResettableCounter<V>) {
         match value {
-            ResettableCounter::Inc(v) => self.0 += v,
-            ResettableCounter::Dec(v) => self.0 -= v,
+            ResettableCounter::Inc(v) => {
+                self.incs = self.incs.add_component(v);
+                self.net += v;
+            }
+            ResettableCounter::Dec(v) => {
+                self.decs = self.decs.add_component(v);
+                self.net -= v;
+            }
             ResettableCounter::Reset => unreachable!(),
+            ResettableCounter::Set(_) => {
+                unreachable!("sets are stabilized in `Counter::stabilize`")
+            }
         }
     }
 
     fn clear(&mut self) {
-        self.0 = V::default();
+        *self = Self::default();
     }
 
     fn prune_redundant_ops(
@@ -55,8 +112,8 @@ where
         _rdnt: RedundancyRelation<ResettableCounter<V>>,
         tagged_op: &TaggedOp<ResettableCounter<V>>,
     ) {
-        if let ResettableCounter::Reset = tagged_op.op() {
-            <CounterStable<V> as IsStableState<ResettableCounter<V>>>::clear(self)
+        if let ResettableCounter::Reset | ResettableCounter::Set(_) = tagged_op.op() {
+            <CounterComponents<V> as IsStableState<ResettableCounter<V>>>::clear(self)
         }
     }
 }
@@ -87,3 +144,29 @@ where
     ) {
     }
 }
+
+/// The stable part of a saturating counter is kept unclamped, so that
+/// later operations are folded on the exact total.
+impl<V> IsStableState<SaturatingCounter<V>> for CounterStable<i128>
+where
+    V: Saturate,
+{
+    fn is_default(&self) -> bool {
+        self.0 == 0
+    }
+
+    fn apply(&mut self, value: SaturatingCounter<V>) {
+        self.0 += value.delta();
+    }
+
+    fn clear(&mut self) {
+        self.0 = 0;
+    }
+
+    fn prune_redundant_ops(
+        &mut self,
+        _rdnt: RedundancyRelation<SaturatingCounter<V>>,
+        _tagged_op: &TaggedOp<SaturatingCounter<V>>,
+    ) {
+    }
+}
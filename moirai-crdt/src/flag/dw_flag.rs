@@ -130,7 +130,7 @@ impl OpGenerator for DWFlag {
 
     fn generate(
         rng: &mut impl Rng,
-        _config: &Self::Config,
+        _config: &mut Self::Config,
         _stable: &<Self as PureCRDT>::StableState,
         _unstable: &impl CausalReplay<Self>,
     ) -> Self {
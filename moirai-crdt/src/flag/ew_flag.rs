@@ -119,7 +119,7 @@ impl OpGenerator for EWFlag {
 
     fn generate(
         rng: &mut impl Rng,
-        _config: &Self::Config,
+        _config: &mut Self::Config,
         _stable: &<Self as PureCRDT>::StableState,
         _unstable: &impl CausalReplay<Self>,
     ) -> Self {
@@ -638,7 +638,7 @@ mod tests {
 
         fn generate(
             rng: &mut impl rand::Rng,
-            _config: &Self::Config,
+            _config: &mut Self::Config,
             stable: &Self::StableState,
             unstable: &impl CausalReplay<Self>,
         ) -> Self {
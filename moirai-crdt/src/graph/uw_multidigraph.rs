@@ -10,12 +10,15 @@ use moirai_protocol::{
         query::{QueryOperation, Read},
     },
     event::Event,
-    state::{effect_context::EffectContext, log::IsLog},
+    state::{
+        effect_context::EffectContext,
+        log::{IsLog, PruneStats},
+    },
     utils::intern_str::{InternalizeOp, Interner},
 };
 use petgraph::graph::DiGraph;
 
-use crate::HashMap;
+use crate::{HashMap, HashSet};
 
 type LabeledMultidigraph<V, E, Vl, El> =
     DiGraph<Content<V, <Vl as IsLog>::Value>, Content<(V, V, E), <El as IsLog>::Value>>;
@@ -54,6 +57,38 @@ where
     vertex_content: HashMap<V, Vl>,
 }
 
+/// Same vertex and arc logs. Children reset to their default state, e.g., by removing their
+/// vertex, are ignored, as a replica that never updated them has none.
+impl<V, E, Vl, El> PartialEq for UWGraphLog<V, E, Vl, El>
+where
+    V: Clone + Debug + Eq + PartialEq + Hash,
+    E: Clone + Debug + Eq + PartialEq + Hash,
+    Vl: IsLog + PartialEq,
+    El: IsLog + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        fn same_children<K: Eq + Hash, L: IsLog + PartialEq>(
+            children: &HashMap<K, L>,
+            other: &HashMap<K, L>,
+        ) -> bool {
+            let live = |children: &HashMap<K, L>| {
+                children
+                    .values()
+                    .filter(|child| !child.is_default())
+                    .count()
+            };
+            live(children) == live(other)
+                && children
+                    .iter()
+                    .filter(|(_, child)| !child.is_default())
+                    .all(|(key, child)| other.get(key) == Some(child))
+        }
+
+        same_children(&self.vertex_content, &other.vertex_content)
+            && same_children(&self.arc_content, &other.arc_content)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum LabelledGraphRejection<V, E, Vl, El>
 where
@@ -184,6 +219,18 @@ where
         self.arc_content.is_empty() && self.vertex_content.is_empty()
     }
 
+    fn prune_stats(&self) -> PruneStats {
+        let mut stats: PruneStats = self
+            .vertex_content
+            .values()
+            .map(IsLog::prune_stats)
+            .chain(self.arc_content.values().map(IsLog::prune_stats))
+            .sum();
+        stats.bytes_estimate += self.vertex_content.len() * size_of::<V>()
+            + self.arc_content.len() * size_of::<(V, V, E)>();
+        stats
+    }
+
     fn is_enabled(&self, op: &Self::Op) -> Result<(), LabelledGraphRejection<V, E, Vl, El>> {
         match op {
             UWGraph::UpdateVertex { id, child } => {
@@ -319,6 +366,294 @@ where
     }
 }
 
+/// Targets of the live outgoing arcs of a vertex, one entry per arc, sorted.
+///
+/// Equivalent to the neighbors of the vertex in the evaluated graph,
+/// without evaluating the content of any vertex or arc.
+pub struct Neighbors<'a, V> {
+    pub vertex: &'a V,
+}
+
+impl<'a, V> Neighbors<'a, V> {
+    pub fn new(vertex: &'a V) -> Self {
+        Self { vertex }
+    }
+}
+
+impl<'a, V> QueryOperation for Neighbors<'a, V> {
+    type Response = Vec<V>;
+}
+
+/// Evaluated content of a single vertex, `None` if the vertex is not in the graph.
+pub struct VertexValue<'a, V, T> {
+    pub vertex: &'a V,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<'a, V, T> VertexValue<'a, V, T> {
+    pub fn new(vertex: &'a V) -> Self {
+        Self {
+            vertex,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, V, T> QueryOperation for VertexValue<'a, V, T> {
+    type Response = Option<T>;
+}
+
+/// Weakly connected components of the graph, i.e., ignoring the direction of the arcs.
+///
+/// Each component is a set of live vertices. Components are sorted by their smallest vertex.
+pub struct ConnectedComponents<V> {
+    _marker: std::marker::PhantomData<V>,
+}
+
+impl<V> ConnectedComponents<V> {
+    pub fn new() -> Self {
+        Self {
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<V> Default for ConnectedComponents<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> QueryOperation for ConnectedComponents<V> {
+    type Response = Vec<HashSet<V>>;
+}
+
+/// Whether the live arcs form a directed cycle, e.g., an inheritance loop.
+/// A self-loop is a cycle.
+pub struct HasCycle<V> {
+    _marker: std::marker::PhantomData<V>,
+}
+
+impl<V> HasCycle<V> {
+    pub fn new() -> Self {
+        Self {
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<V> Default for HasCycle<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> QueryOperation for HasCycle<V> {
+    type Response = bool;
+}
+
+/// One directed cycle of the live arcs, as the vertices along it, or `None` if there is none.
+///
+/// The vertices and their arcs are explored in order, so every replica in the same
+/// state finds the same cycle.
+pub struct FindCycle<V> {
+    _marker: std::marker::PhantomData<V>,
+}
+
+impl<V> FindCycle<V> {
+    pub fn new() -> Self {
+        Self {
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<V> Default for FindCycle<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> QueryOperation for FindCycle<V> {
+    type Response = Option<Vec<V>>;
+}
+
+impl<V, E, Vl, El> UWGraphLog<V, E, Vl, El>
+where
+    V: Clone + Debug + Eq + PartialEq + Hash,
+    E: Clone + Debug + Eq + PartialEq + Hash,
+    Vl: IsLog,
+    El: IsLog,
+{
+    fn is_live_vertex(&self, v: &V) -> bool {
+        self.vertex_content
+            .get(v)
+            .is_some_and(|child| !child.is_default())
+    }
+
+    /// First cycle met by a depth-first search from each live vertex in order.
+    fn find_cycle(&self) -> Option<Vec<V>>
+    where
+        V: Ord,
+    {
+        let mut successors: HashMap<&V, Vec<&V>> = HashMap::default();
+        for ((v1, v2, _), child) in self.arc_content.iter() {
+            if child.is_default() || !self.is_live_vertex(v1) || !self.is_live_vertex(v2) {
+                continue;
+            }
+            successors.entry(v1).or_default().push(v2);
+        }
+        for targets in successors.values_mut() {
+            targets.sort();
+            targets.dedup();
+        }
+        let mut roots: Vec<&V> = successors.keys().copied().collect();
+        roots.sort();
+
+        // Vertices whose successors are all explored, without reaching a cycle
+        let mut done: HashSet<&V> = HashSet::default();
+        for root in roots {
+            if done.contains(root) {
+                continue;
+            }
+            // Current path, with the index of the next successor to explore
+            let mut path: Vec<(&V, usize)> = vec![(root, 0)];
+            while let Some((v, next)) = path.last_mut() {
+                let Some(&target) = successors.get(*v).and_then(|targets| targets.get(*next))
+                else {
+                    done.insert(*v);
+                    path.pop();
+                    continue;
+                };
+                *next += 1;
+                if let Some(start) = path.iter().position(|(u, _)| *u == target) {
+                    return Some(path[start..].iter().map(|(u, _)| (*u).clone()).collect());
+                }
+                if !done.contains(target) {
+                    path.push((target, 0));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<'a, V, E, Vl, El> EvalNested<Neighbors<'a, V>> for UWGraphLog<V, E, Vl, El>
+where
+    Vl: IsLog,
+    El: IsLog,
+    V: Clone + Debug + Ord + PartialOrd + Hash + Eq + Default + Display,
+    E: Clone + Debug + Eq + PartialEq + Hash,
+{
+    fn execute_query(&self, q: Neighbors<'a, V>) -> <Neighbors<'a, V> as QueryOperation>::Response {
+        if !self.is_live_vertex(q.vertex) {
+            return Vec::new();
+        }
+        let mut targets: Vec<V> = self
+            .arc_content
+            .iter()
+            .filter(|((v1, v2, _), child)| {
+                v1 == q.vertex && !child.is_default() && self.is_live_vertex(v2)
+            })
+            .map(|((_, v2, _), _)| v2.clone())
+            .collect();
+        targets.sort();
+        targets
+    }
+}
+
+impl<'a, V, E, Vl, El> EvalNested<VertexValue<'a, V, <Vl as IsLog>::Value>>
+    for UWGraphLog<V, E, Vl, El>
+where
+    Vl: IsLog + EvalNested<Read<<Vl as IsLog>::Value>>,
+    El: IsLog,
+    V: Clone + Debug + Ord + PartialOrd + Hash + Eq + Default + Display,
+    E: Clone + Debug + Eq + PartialEq + Hash,
+{
+    fn execute_query(
+        &self,
+        q: VertexValue<'a, V, <Vl as IsLog>::Value>,
+    ) -> <VertexValue<'a, V, <Vl as IsLog>::Value> as QueryOperation>::Response {
+        self.vertex_content
+            .get(q.vertex)
+            .filter(|child| !child.is_default())
+            .map(|child| child.execute_query(Read::new()))
+    }
+}
+
+impl<V, E, Vl, El> EvalNested<ConnectedComponents<V>> for UWGraphLog<V, E, Vl, El>
+where
+    Vl: IsLog,
+    El: IsLog,
+    V: Clone + Debug + Ord + PartialOrd + Hash + Eq + Default + Display,
+    E: Clone + Debug + Eq + PartialEq + Hash,
+{
+    fn execute_query(
+        &self,
+        _q: ConnectedComponents<V>,
+    ) -> <ConnectedComponents<V> as QueryOperation>::Response {
+        let mut adjacency: HashMap<&V, Vec<&V>> = self
+            .vertex_content
+            .iter()
+            .filter(|(_, child)| !child.is_default())
+            .map(|(v, _)| (v, Vec::new()))
+            .collect();
+        for ((v1, v2, _), child) in self.arc_content.iter() {
+            if child.is_default() || !self.is_live_vertex(v1) || !self.is_live_vertex(v2) {
+                continue;
+            }
+            adjacency.get_mut(v1).unwrap().push(v2);
+            adjacency.get_mut(v2).unwrap().push(v1);
+        }
+
+        let mut roots: Vec<&V> = adjacency.keys().copied().collect();
+        roots.sort();
+        let mut visited: HashSet<&V> = HashSet::default();
+        let mut components = Vec::new();
+        for root in roots {
+            if !visited.insert(root) {
+                continue;
+            }
+            let mut component = HashSet::default();
+            let mut stack = vec![root];
+            while let Some(v) = stack.pop() {
+                component.insert(v.clone());
+                for &neighbor in &adjacency[v] {
+                    if visited.insert(neighbor) {
+                        stack.push(neighbor);
+                    }
+                }
+            }
+            components.push(component);
+        }
+        components
+    }
+}
+
+impl<V, E, Vl, El> EvalNested<HasCycle<V>> for UWGraphLog<V, E, Vl, El>
+where
+    Vl: IsLog,
+    El: IsLog,
+    V: Clone + Debug + Ord + PartialOrd + Hash + Eq + Default + Display,
+    E: Clone + Debug + Eq + PartialEq + Hash,
+{
+    fn execute_query(&self, _q: HasCycle<V>) -> <HasCycle<V> as QueryOperation>::Response {
+        self.find_cycle().is_some()
+    }
+}
+
+impl<V, E, Vl, El> EvalNested<FindCycle<V>> for UWGraphLog<V, E, Vl, El>
+where
+    Vl: IsLog,
+    El: IsLog,
+    V: Clone + Debug + Ord + PartialOrd + Hash + Eq + Default + Display,
+    E: Clone + Debug + Eq + PartialEq + Hash,
+{
+    fn execute_query(&self, _q: FindCycle<V>) -> <FindCycle<V> as QueryOperation>::Response {
+        self.find_cycle()
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Content<Id, Val> {
     pub id: Id,
@@ -346,8 +681,11 @@ mod tests {
     use petgraph::graph::DiGraph;
 
     use crate::{
+        HashSet,
         counter::resettable_counter::Counter,
-        graph::uw_multidigraph::{UWGraph, UWGraphLog},
+        graph::uw_multidigraph::{
+            ConnectedComponents, FindCycle, HasCycle, Neighbors, UWGraph, UWGraphLog, VertexValue,
+        },
         policy::LwwPolicy,
         register::unique_register::Register,
         utils::membership::{triplet_log, twins_log},
@@ -356,6 +694,58 @@ mod tests {
     type Lww = VecLog<Register<i32, LwwPolicy>>;
     type Cntr = VecLog<Counter<i32>>;
 
+    #[test]
+    fn synced_logs_are_equal() {
+        // c does not send anything, so that no event is stable and both logs keep all of them
+        let (mut replica_a, mut replica_b, _) = triplet_log::<UWGraphLog<&str, u8, Cntr, Cntr>>();
+
+        let events_a = [
+            replica_a
+                .send(UWGraph::UpdateVertex {
+                    id: "A",
+                    child: Counter::Inc(1),
+                })
+                .unwrap(),
+            replica_a
+                .send(UWGraph::UpdateArc {
+                    source: "A",
+                    target: "A",
+                    id: 1,
+                    child: Counter::Inc(3),
+                })
+                .unwrap(),
+        ];
+        let events_b = [
+            replica_b
+                .send(UWGraph::UpdateVertex {
+                    id: "B",
+                    child: Counter::Inc(2),
+                })
+                .unwrap(),
+            replica_b
+                .send(UWGraph::UpdateVertex {
+                    id: "A",
+                    child: Counter::Dec(4),
+                })
+                .unwrap(),
+        ];
+        assert_ne!(replica_a.state(), replica_b.state());
+
+        for event in events_b {
+            replica_a.receive(event);
+        }
+        for event in events_a {
+            replica_b.receive(event);
+        }
+        // The counter of A got its operations in different orders on a and b
+        assert_eq!(replica_a.state(), replica_b.state());
+
+        let event = replica_a.send(UWGraph::RemoveVertex { id: "B" }).unwrap();
+        assert_ne!(replica_a.state(), replica_b.state());
+        replica_b.receive(event);
+        assert_eq!(replica_a.state(), replica_b.state());
+    }
+
     #[test]
     fn nested_graph() {
         let (mut replica_a, mut replica_b) = twins_log::<UWGraphLog<&str, u8, Lww, Cntr>>();
@@ -743,5 +1133,202 @@ mod tests {
         );
     }
 
+    #[test]
+    fn neighbors_and_vertex_value() {
+        let (mut replica_a, mut replica_b) = twins_log::<UWGraphLog<&str, u8, Lww, Cntr>>();
+
+        for (id, val) in [("A", 1), ("B", 2), ("C", 3)] {
+            let event = replica_a
+                .send(UWGraph::UpdateVertex {
+                    id,
+                    child: Register::Write(val),
+                })
+                .unwrap();
+            replica_b.receive(event);
+        }
+        for (target, id) in [("B", 1), ("B", 2), ("C", 1)] {
+            let event = replica_a
+                .send(UWGraph::UpdateArc {
+                    source: "A",
+                    target,
+                    id,
+                    child: Counter::Inc(1),
+                })
+                .unwrap();
+            replica_b.receive(event);
+        }
+
+        // Concurrently: A rewrites "A" and adds A -> A, B removes "C" and rewrites "A"
+        let event_a_1 = replica_a
+            .send(UWGraph::UpdateVertex {
+                id: "A",
+                child: Register::Write(10),
+            })
+            .unwrap();
+        let event_a_2 = replica_a
+            .send(UWGraph::UpdateArc {
+                source: "A",
+                target: "A",
+                id: 1,
+                child: Counter::Inc(4),
+            })
+            .unwrap();
+        let event_b_1 = replica_b.send(UWGraph::RemoveVertex { id: "C" }).unwrap();
+        let event_b_2 = replica_b
+            .send(UWGraph::UpdateVertex {
+                id: "A",
+                child: Register::Write(20),
+            })
+            .unwrap();
+        replica_a.receive(event_b_1);
+        replica_a.receive(event_b_2);
+        replica_b.receive(event_a_1);
+        replica_b.receive(event_a_2);
+
+        for replica in [&replica_a, &replica_b] {
+            let graph = replica.query(Read::new());
+            for v in ["A", "B", "C", "D"] {
+                let node = graph.node_indices().find(|i| graph[*i].id == v);
+
+                let mut expected: Vec<&str> = node
+                    .map(|n| graph.neighbors(n).map(|i| graph[i].id).collect())
+                    .unwrap_or_default();
+                expected.sort();
+                assert_eq!(replica.query(Neighbors::new(&v)), expected);
+
+                let expected = node.map(|n| graph[n].val);
+                assert_eq!(replica.query(VertexValue::new(&v)), expected);
+            }
+        }
+        assert_eq!(replica_a.query(Neighbors::new(&"A")), vec!["A", "B", "B"]);
+        assert_eq!(replica_a.query(VertexValue::new(&"C")), None);
+    }
+
+    #[test]
+    fn connected_components_of_two_triangles() {
+        let (mut replica_a, mut replica_b) = twins_log::<UWGraphLog<&str, u8, Lww, Cntr>>();
+
+        for id in ["A", "B", "C", "D", "E", "F"] {
+            let event = replica_a
+                .send(UWGraph::UpdateVertex {
+                    id,
+                    child: Register::Write(0),
+                })
+                .unwrap();
+            replica_b.receive(event);
+        }
+        for (source, target) in [
+            ("A", "B"),
+            ("B", "C"),
+            ("C", "A"),
+            ("D", "E"),
+            ("E", "F"),
+            ("F", "D"),
+        ] {
+            let event = replica_a
+                .send(UWGraph::UpdateArc {
+                    source,
+                    target,
+                    id: 0,
+                    child: Counter::Inc(1),
+                })
+                .unwrap();
+            replica_b.receive(event);
+        }
+
+        // Concurrently: A removes A -> B, B updates it and adds a parallel arc D -> E
+        let event_a = replica_a
+            .send(UWGraph::RemoveArc {
+                source: "A",
+                target: "B",
+                id: 0,
+            })
+            .unwrap();
+        let event_b_1 = replica_b
+            .send(UWGraph::UpdateArc {
+                source: "A",
+                target: "B",
+                id: 0,
+                child: Counter::Inc(2),
+            })
+            .unwrap();
+        let event_b_2 = replica_b
+            .send(UWGraph::UpdateArc {
+                source: "D",
+                target: "E",
+                id: 1,
+                child: Counter::Inc(1),
+            })
+            .unwrap();
+        replica_a.receive(event_b_1);
+        replica_a.receive(event_b_2);
+        replica_b.receive(event_a);
+
+        let expected: Vec<HashSet<&str>> = vec![
+            ["A", "B", "C"].into_iter().collect(),
+            ["D", "E", "F"].into_iter().collect(),
+        ];
+        assert_eq!(replica_a.query(ConnectedComponents::new()), expected);
+        assert_eq!(replica_b.query(ConnectedComponents::new()), expected);
+
+        // Removing a vertex splits its component
+        let event = replica_a.send(UWGraph::RemoveVertex { id: "E" }).unwrap();
+        replica_b.receive(event);
+        let expected: Vec<HashSet<&str>> = vec![
+            ["A", "B", "C"].into_iter().collect(),
+            ["D", "F"].into_iter().collect(),
+        ];
+        assert_eq!(replica_b.query(ConnectedComponents::new()), expected);
+    }
+
+    #[test]
+    fn cycle_detection() {
+        let (mut replica_a, mut replica_b) = twins_log::<UWGraphLog<&str, u8, Lww, Cntr>>();
+
+        for id in ["A", "B", "C", "D"] {
+            let event = replica_a
+                .send(UWGraph::UpdateVertex {
+                    id,
+                    child: Register::Write(0),
+                })
+                .unwrap();
+            replica_b.receive(event);
+        }
+        // A DAG with two paths from A to C
+        for (source, target) in [("A", "B"), ("B", "C"), ("A", "C"), ("C", "D")] {
+            let event = replica_a
+                .send(UWGraph::UpdateArc {
+                    source,
+                    target,
+                    id: 0,
+                    child: Counter::Inc(1),
+                })
+                .unwrap();
+            replica_b.receive(event);
+        }
+        assert!(!replica_a.query(HasCycle::new()));
+        assert_eq!(replica_b.query(FindCycle::new()), None);
+
+        // Closing the loop A -> B -> C -> A
+        let event = replica_b
+            .send(UWGraph::UpdateArc {
+                source: "C",
+                target: "A",
+                id: 0,
+                child: Counter::Inc(1),
+            })
+            .unwrap();
+        replica_a.receive(event);
+        for replica in [&replica_a, &replica_b] {
+            assert!(replica.query(HasCycle::new()));
+            assert_eq!(replica.query(FindCycle::new()), Some(vec!["A", "B", "C"]));
+        }
+
+        // A cycle through a removed vertex is gone
+        let event = replica_a.send(UWGraph::RemoveVertex { id: "C" }).unwrap();
+        replica_b.receive(event);
+        assert!(!replica_b.query(HasCycle::new()));
+    }
+
     // TODO: fuzzer test
 }
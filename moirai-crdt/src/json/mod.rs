@@ -1,12 +1,22 @@
+pub mod schema;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 #[cfg(feature = "fuzz")]
 use moirai_fuzz::op_generator::OpGeneratorNested;
 use moirai_macros::union;
-use moirai_protocol::state::{graph_log::GraphLog, po_log::VecLog};
+use moirai_protocol::crdt::{
+    eval::EvalNested,
+    query::{QueryOperation, Read},
+};
+use moirai_protocol::state::{graph_log::GraphLog, log::IsLog, po_log::VecLog};
 #[cfg(feature = "fuzz")]
-use moirai_protocol::{crdt::query::Read, utils::boxer::Boxer};
+use moirai_protocol::utils::boxer::Boxer;
 #[cfg(feature = "fuzz")]
 use rand::Rng;
 
+#[cfg(feature = "fuzz")]
+use crate::list::ListConfig;
 use crate::{
     counter::resettable_counter::Counter,
     flag::ew_flag::EWFlag,
@@ -25,11 +35,335 @@ union! {
         | Array(NestedList<Box<Json>>, NestedListLog::<JsonLog>)
 }
 
+/// Step of a path in a [`Json`] document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathSeg {
+    /// Key of an object
+    Key(String),
+    /// Position in an array
+    Index(usize),
+}
+
+impl Json {
+    /// Operation setting `leaf` at `path`, through the objects and arrays on the way.
+    ///
+    /// Keys are updated, creating the objects that do not exist yet. An index in the middle
+    /// of the path updates the element at that position, while a final index inserts `leaf` there.
+    pub fn set_path(path: &[PathSeg], leaf: Json) -> Json {
+        match path.split_first() {
+            None => leaf,
+            Some((PathSeg::Key(key), rest)) => Json::Object(UWMap::Update(
+                key.clone(),
+                Box::new(Self::set_path(rest, leaf)),
+            )),
+            Some((PathSeg::Index(pos), [])) => {
+                Json::Array(NestedList::insert(*pos, Box::new(leaf)))
+            }
+            Some((PathSeg::Index(pos), rest)) => Json::Array(NestedList::update(
+                *pos,
+                Box::new(Self::set_path(rest, leaf)),
+            )),
+        }
+    }
+
+    /// Operation removing the value at `path`, or `None` if the path is empty.
+    pub fn remove_path(path: &[PathSeg]) -> Option<Json> {
+        let (last, parents) = path.split_last()?;
+        let op = match last {
+            PathSeg::Key(key) => Json::Object(UWMap::Remove(key.clone())),
+            PathSeg::Index(pos) => Json::Array(NestedList::delete(*pos)),
+        };
+        Some(parents.iter().rev().fold(op, |op, seg| match seg {
+            PathSeg::Key(key) => Json::Object(UWMap::Update(key.clone(), Box::new(op))),
+            PathSeg::Index(pos) => Json::Array(NestedList::update(*pos, Box::new(op))),
+        }))
+    }
+}
+
+/// Type of a JSON value, as returned by [`TypeOf`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JsonType {
+    Null,
+    Number,
+    Boolean,
+    String,
+    Object,
+    Array,
+    /// Concurrent values of different types
+    Conflict,
+}
+
+/// Query on the type of the value at a path, without reading the value itself.
+///
+/// An absent value is `Null`. On the way, a conflict is crossed through its object or array
+/// value, according to the step of the path.
+#[derive(Debug)]
+pub struct TypeOf {
+    pub path: Vec<PathSeg>,
+}
+
+impl TypeOf {
+    pub fn new(path: Vec<PathSeg>) -> Self {
+        Self { path }
+    }
+}
+
+impl QueryOperation for TypeOf {
+    type Response = JsonType;
+}
+
+/// Walks the logs along the path, without reading the values on the way.
+impl EvalNested<TypeOf> for JsonLog {
+    fn execute_query(&self, q: TypeOf) -> JsonType {
+        type_at(self, &q.path)
+    }
+}
+
+fn type_at(log: &JsonLog, path: &[PathSeg]) -> JsonType {
+    let children = match &log.child {
+        JsonContainer::Unset => return JsonType::Null,
+        JsonContainer::Value(child) => std::slice::from_ref(child.as_ref()),
+        JsonContainer::Conflicts(children) => children.as_slice(),
+    };
+    let Some((seg, rest)) = path.split_first() else {
+        return match children {
+            [] => JsonType::Null,
+            [child] => child_type(child),
+            _ => JsonType::Conflict,
+        };
+    };
+    children
+        .iter()
+        .find_map(|child| match (seg, child) {
+            (PathSeg::Key(key), JsonChild::Object(map)) => map.get_child(key),
+            // The read value of the array skips its unset elements
+            (PathSeg::Index(pos), JsonChild::Array(list)) => list
+                .visible_children()
+                .filter(|element| !is_unset(element))
+                .nth(*pos),
+            _ => None,
+        })
+        .map_or(JsonType::Null, |next| type_at(next, rest))
+}
+
+/// Whether the log reads as [`JsonValue::Unset`].
+fn is_unset(log: &JsonLog) -> bool {
+    match &log.child {
+        JsonContainer::Unset => true,
+        JsonContainer::Value(_) => false,
+        JsonContainer::Conflicts(children) => children.is_empty(),
+    }
+}
+
+fn child_type(child: &JsonChild) -> JsonType {
+    match child {
+        JsonChild::Number(_) => JsonType::Number,
+        JsonChild::Boolean(_) => JsonType::Boolean,
+        JsonChild::String(_) => JsonType::String,
+        JsonChild::Object(_) => JsonType::Object,
+        JsonChild::Array(_) => JsonType::Array,
+    }
+}
+
+impl JsonLog {
+    /// Operations deep-merging `value` into the document, to be sent in order.
+    ///
+    /// Objects are merged key by key, and a `null` member removes its key. Numbers, booleans,
+    /// strings and arrays replace the current value, unless they are equal to it. Empty strings,
+    /// objects and arrays are created as such. A value of another kind than the one in
+    /// place cannot replace it in the union, so it yields no operation.
+    ///
+    /// The document has no `null` value: a `null` root or array element yields no operation.
+    pub fn apply_json_merge(&self, value: &serde_json::Value) -> Vec<Json> {
+        merge_ops(Some(self), value)
+    }
+}
+
+fn same_kind(child: &JsonChild, value: &serde_json::Value) -> bool {
+    use serde_json::Value;
+
+    matches!(
+        (child, value),
+        (JsonChild::Number(_), Value::Number(_))
+            | (JsonChild::Boolean(_), Value::Bool(_))
+            | (JsonChild::String(_), Value::String(_))
+            | (JsonChild::Object(_), Value::Object(_))
+            | (JsonChild::Array(_), Value::Array(_))
+    )
+}
+
+/// Whether the current value reads as `value`, numbers being compared as `f64`.
+fn same_value(current: &JsonValue, value: &serde_json::Value) -> bool {
+    use serde_json::Value;
+
+    let child = match current {
+        JsonValue::Unset => return value.is_null(),
+        JsonValue::Value(child) => child.as_ref(),
+        JsonValue::Conflict(_) => return false,
+    };
+    match (child, value) {
+        (JsonChildValue::Number(current), Value::Number(n)) => n.as_f64() == Some(*current),
+        (JsonChildValue::Boolean(current), Value::Bool(b)) => current == b,
+        (JsonChildValue::String(current), Value::String(s)) => {
+            current.iter().copied().eq(s.chars())
+        }
+        (JsonChildValue::Object(current), Value::Object(members)) => {
+            current.len() == members.values().filter(|member| !member.is_null()).count()
+                && members.iter().all(|(key, member)| {
+                    current
+                        .get(key)
+                        .map_or(member.is_null(), |current| same_value(current, member))
+                })
+        }
+        (JsonChildValue::Array(current), Value::Array(items)) => {
+            current.len() == items.len()
+                && current
+                    .iter()
+                    .zip(items)
+                    .all(|(current, item)| same_value(current, item))
+        }
+        _ => false,
+    }
+}
+
+fn merge_ops(log: Option<&JsonLog>, value: &serde_json::Value) -> Vec<Json> {
+    use serde_json::Value;
+
+    if value.is_null() {
+        return Vec::new();
+    }
+    let child = match log.map(|log| &log.child) {
+        None | Some(JsonContainer::Unset) => None,
+        Some(JsonContainer::Value(child)) if same_kind(child, value) => Some(child.as_ref()),
+        Some(JsonContainer::Conflicts(children)) => {
+            match children.iter().find(|child| same_kind(child, value)) {
+                Some(child) => Some(child),
+                None => return Vec::new(),
+            }
+        }
+        Some(JsonContainer::Value(_)) => return Vec::new(),
+    };
+
+    match value {
+        Value::Null => Vec::new(),
+        Value::Bool(b) => {
+            if let Some(JsonChild::Boolean(log)) = child
+                && log.eval(Read::new()) == *b
+            {
+                return Vec::new();
+            }
+            let op = if *b { EWFlag::Enable } else { EWFlag::Disable };
+            vec![Json::Boolean(op)]
+        }
+        Value::Number(n) => {
+            let Some(n) = n.as_f64() else {
+                return Vec::new();
+            };
+            if let Some(JsonChild::Number(log)) = child
+                && log.eval(Read::new()) == n
+            {
+                return Vec::new();
+            }
+            vec![Json::Number(Counter::Set(n))]
+        }
+        Value::String(s) => {
+            let current = match child {
+                Some(JsonChild::String(log)) => log.eval(Read::new()),
+                _ => Vec::new(),
+            };
+            if child.is_some() && current.iter().copied().eq(s.chars()) {
+                return Vec::new();
+            }
+            if s.is_empty() && child.is_none() {
+                // Deleting nothing creates the empty string
+                return vec![Json::String(List::delete_range(0, 0))];
+            }
+            let mut ops = Vec::new();
+            if !current.is_empty() {
+                ops.push(Json::String(List::delete_range(0, current.len())));
+            }
+            ops.extend(
+                s.chars()
+                    .enumerate()
+                    .map(|(pos, c)| Json::String(List::insert(c, pos))),
+            );
+            ops
+        }
+        Value::Array(items) => {
+            let current = match child {
+                Some(JsonChild::Array(log)) => log.eval(Read::new()),
+                _ => Vec::new(),
+            };
+            if child.is_some()
+                && current.len() == items.len()
+                && current
+                    .iter()
+                    .zip(items)
+                    .all(|(current, item)| same_value(current, item))
+            {
+                return Vec::new();
+            }
+            let len = current.len();
+            let mut ops: Vec<Json> = (0..len)
+                .map(|_| Json::Array(NestedList::delete(0)))
+                .collect();
+            let mut pos = 0;
+            for item in items {
+                let mut item_ops = merge_ops(None, item).into_iter();
+                // The first operation creates the element, the next ones update it
+                let Some(first) = item_ops.next() else {
+                    continue;
+                };
+                ops.push(Json::Array(NestedList::insert(pos, Box::new(first))));
+                ops.extend(item_ops.map(|op| Json::Array(NestedList::update(pos, Box::new(op)))));
+                pos += 1;
+            }
+            if ops.is_empty() && child.is_none() {
+                // A list has no operation creating it empty: insert an element and delete it
+                ops.push(Json::Array(NestedList::insert(
+                    0,
+                    Box::new(Json::Object(UWMap::Clear)),
+                )));
+                ops.push(Json::Array(NestedList::delete(0)));
+            }
+            ops
+        }
+        Value::Object(members) => {
+            let map = match child {
+                Some(JsonChild::Object(log)) => Some(log),
+                _ => None,
+            };
+            let mut ops = Vec::new();
+            for (key, member) in members {
+                let current = map.and_then(|map| map.get_child(key));
+                if member.is_null() {
+                    if current.is_some_and(|log| !log.is_default()) {
+                        ops.push(Json::Object(UWMap::Remove(key.clone())));
+                    }
+                    continue;
+                }
+                ops.extend(
+                    merge_ops(current, member)
+                        .into_iter()
+                        .map(|op| Json::Object(UWMap::Update(key.clone(), Box::new(op)))),
+                );
+            }
+            if ops.is_empty() && child.is_none() {
+                // Clearing nothing creates the empty object
+                ops.push(Json::Object(UWMap::Clear));
+            }
+            ops
+        }
+    }
+}
+
 // TODO: the code must be factorized
 #[cfg(feature = "fuzz")]
 impl OpGeneratorNested for JsonLog {
-    fn generate(&self, rng: &mut impl Rng) -> Self::Op {
-        use moirai_protocol::state::log::IsLog;
+    /// Values are generated with their default configuration.
+    type Config = ();
+
+    fn generate(&self, rng: &mut impl Rng, _config: &mut Self::Config) -> Self::Op {
         use rand::distr::{Distribution, weighted::WeightedIndex};
 
         enum Choice {
@@ -43,29 +377,38 @@ impl OpGeneratorNested for JsonLog {
 
         fn generate_number(log: &VecLog<Counter<f64>>, rng: &mut impl Rng) -> Json {
             Json::Number(<VecLog<Counter<f64>> as OpGeneratorNested>::generate(
-                log, rng,
+                log,
+                rng,
+                &mut (),
             ))
         }
 
         fn generate_boolean(log: &VecLog<EWFlag>, rng: &mut impl Rng) -> Json {
-            Json::Boolean(<VecLog<EWFlag> as OpGeneratorNested>::generate(log, rng))
+            Json::Boolean(<VecLog<EWFlag> as OpGeneratorNested>::generate(
+                log,
+                rng,
+                &mut (),
+            ))
         }
 
         fn generate_string(log: &GraphLog<List<char>>, rng: &mut impl Rng) -> Json {
             Json::String(<GraphLog<List<char>> as OpGeneratorNested>::generate(
-                log, rng,
+                log,
+                rng,
+                &mut ListConfig::default(),
             ))
         }
 
         fn generate_object(log: &UWMapLog<String, JsonLog>, rng: &mut impl Rng) -> Json {
-            let op = <UWMapLog<String, JsonLog> as OpGeneratorNested>::generate(log, rng);
+            let op = <UWMapLog<String, JsonLog> as OpGeneratorNested>::generate(log, rng, &mut ());
             Json::Object(Boxer::<UWMap<String, Box<Json>>>::boxer(op))
         }
 
         fn generate_array(log: &NestedListLog<JsonLog>, rng: &mut impl Rng) -> Json {
             use moirai_protocol::utils::boxer::Boxer;
 
-            let list_op = <NestedListLog<JsonLog> as OpGeneratorNested>::generate(log, rng);
+            let list_op =
+                <NestedListLog<JsonLog> as OpGeneratorNested>::generate(log, rng, &mut ());
             let o = Boxer::<NestedList<Box<Json>>>::boxer(list_op);
             Json::Array(o)
         }
@@ -95,8 +438,6 @@ impl OpGeneratorNested for JsonLog {
 
         match value {
             JsonValue::Unset => {
-                use moirai_protocol::state::log::IsLog;
-
                 let available_choices: Vec<Choice> = match &self.child {
                     JsonContainer::Unset => vec![
                         Choice::Number,
@@ -197,16 +538,20 @@ impl OpGeneratorNested for JsonLog {
 
 #[cfg(test)]
 mod tests {
-    use moirai_protocol::{crdt::query::Read, replica::IsReplica};
+    use moirai_protocol::{
+        broadcast::tcsb::Tcsb,
+        crdt::query::Read,
+        replica::{IsReplica, Replica},
+    };
     use serde_json::{Number, Value, json};
 
     use crate::{
         counter::resettable_counter::Counter,
         flag::ew_flag::EWFlag,
-        json::{Json, JsonLog, JsonVariant},
+        json::{Json, JsonChildValue, JsonLog, JsonType, JsonValue, JsonVariant, PathSeg, TypeOf},
         list::{eg_walker::List, nested_list::NestedList},
         map::uw_map::UWMap,
-        query::read_as_json::ReadAsJson,
+        query::read_as_json::{CountConflicts, ReadAsAnnotatedJson, ReadAsJson},
         utils::membership::{triplet_log, twins_log},
     };
 
@@ -229,6 +574,53 @@ mod tests {
         assert_eq!(result, replica_b.query(ReadAsJson::new()));
     }
 
+    #[test]
+    fn concurrent_insert_annotated() {
+        let (mut replica_a, mut replica_b) = twins_log::<JsonLog>();
+
+        let event_a = replica_a.send(Json::Boolean(EWFlag::Enable)).unwrap();
+        let event_b = replica_b.send(Json::Number(Counter::Inc(5.0))).unwrap();
+
+        replica_b.receive(event_a);
+        replica_a.receive(event_b);
+
+        let result = json!({ "$conflict": [true, 5.0] });
+        assert_eq!(result, replica_a.query(ReadAsAnnotatedJson::new()));
+        assert_eq!(result, replica_b.query(ReadAsAnnotatedJson::new()));
+    }
+
+    #[test]
+    fn annotated_keys_are_escaped() {
+        let (mut replica_a, mut replica_b) = twins_log::<JsonLog>();
+
+        let event_a = replica_a
+            .send(Json::Object(UWMap::Update(
+                "$conflict".to_string(),
+                Box::new(Json::Array(NestedList::insert(
+                    0,
+                    Box::new(Json::Boolean(EWFlag::Enable)),
+                ))),
+            )))
+            .unwrap();
+        let event_b = replica_b
+            .send(Json::Object(UWMap::Update(
+                "$conflict".to_string(),
+                Box::new(Json::Number(Counter::Inc(1.0))),
+            )))
+            .unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+
+        // The user key is escaped, and the conflict between a number and an array is wrapped
+        let result = json!({ "$$conflict": { "$conflict": [1.0, [true]] } });
+        assert_eq!(result, replica_a.query(ReadAsAnnotatedJson::new()));
+        assert_eq!(result, replica_b.query(ReadAsAnnotatedJson::new()));
+        assert_eq!(
+            json!({ "$conflict": [1.0, [true]] }),
+            replica_a.query(ReadAsJson::new())
+        );
+    }
+
     #[test]
     fn choose_absent_variant_is_disabled() {
         let (mut replica_a, _) = twins_log::<JsonLog>();
@@ -361,6 +753,145 @@ mod tests {
         assert_eq!(result, replica_c.query(ReadAsJson::new()));
     }
 
+    #[test]
+    fn set_and_remove_path() {
+        let (mut replica_a, mut replica_b) = twins_log::<JsonLog>();
+        let path = |index| {
+            vec![
+                PathSeg::Key("a".to_string()),
+                PathSeg::Key("b".to_string()),
+                PathSeg::Index(index),
+            ]
+        };
+
+        // $.a.b[0] = 1, then $.a.b[1] = true
+        let event = replica_a
+            .send(Json::set_path(&path(0), Json::Number(Counter::Inc(1.0))))
+            .unwrap();
+        replica_b.receive(event);
+        let event = replica_b
+            .send(Json::set_path(&path(1), Json::Boolean(EWFlag::Enable)))
+            .unwrap();
+        replica_a.receive(event);
+
+        let result = json!({ "a": { "b": [1.0, true] } });
+        assert_eq!(result, replica_a.query(ReadAsJson::new()));
+        assert_eq!(result, replica_b.query(ReadAsJson::new()));
+
+        let event = replica_a
+            .send(Json::remove_path(&path(0)).unwrap())
+            .unwrap();
+        replica_b.receive(event);
+
+        let result = json!({ "a": { "b": [true] } });
+        assert_eq!(result, replica_a.query(ReadAsJson::new()));
+        assert_eq!(result, replica_b.query(ReadAsJson::new()));
+        assert!(Json::remove_path(&[]).is_none());
+    }
+
+    #[test]
+    fn removed_value_reads_as_unset() {
+        let (mut replica_a, mut replica_b) = twins_log::<JsonLog>();
+
+        let event = replica_a
+            .send(Json::set_path(
+                &[PathSeg::Key("flag".to_string())],
+                Json::Boolean(EWFlag::Enable),
+            ))
+            .unwrap();
+        replica_b.receive(event);
+        let event = replica_b
+            .send(Json::remove_path(&[PathSeg::Key("flag".to_string())]).unwrap())
+            .unwrap();
+        replica_a.receive(event);
+
+        // The removed flag is absent, not `false`
+        for replica in [&replica_a, &replica_b] {
+            assert_eq!(json!({}), replica.query(ReadAsJson::new()));
+            assert_eq!(
+                replica.query(TypeOf::new(vec![PathSeg::Key("flag".to_string())])),
+                JsonType::Null
+            );
+        }
+    }
+
+    #[test]
+    fn apply_json_merge() {
+        let (mut replica_a, mut replica_b) = twins_log::<JsonLog>();
+
+        type JsonReplica = Replica<JsonLog, Tcsb<Json>>;
+        fn merge(from: &mut JsonReplica, to: &mut JsonReplica, value: &Value) {
+            for op in from.state().apply_json_merge(value) {
+                let event = from.send(op).unwrap();
+                to.receive(event);
+            }
+        }
+
+        merge(
+            &mut replica_a,
+            &mut replica_b,
+            &json!({ "a": 1, "b": { "c": true } }),
+        );
+        let result = json!({ "a": 1.0, "b": { "c": true } });
+        assert_eq!(result, replica_a.query(ReadAsJson::new()));
+        assert_eq!(result, replica_b.query(ReadAsJson::new()));
+
+        // Scalars are replaced, objects merged, and null removes a key
+        merge(
+            &mut replica_b,
+            &mut replica_a,
+            &json!({ "a": 3, "b": { "d": "xy" }, "e": [true, 2] }),
+        );
+        merge(
+            &mut replica_a,
+            &mut replica_b,
+            &json!({ "b": { "c": null, "d": "z" } }),
+        );
+        let result = json!({ "a": 3.0, "b": { "d": "z" }, "e": [true, 2.0] });
+        assert_eq!(result, replica_a.query(ReadAsJson::new()));
+        assert_eq!(result, replica_b.query(ReadAsJson::new()));
+
+        // A value of another kind does not replace the current one
+        assert!(
+            replica_a
+                .state()
+                .apply_json_merge(&json!({ "a": "x" }))
+                .is_empty()
+        );
+
+        // An unchanged array is kept as is
+        assert!(
+            replica_a
+                .state()
+                .apply_json_merge(&json!({ "e": [true, 2] }))
+                .is_empty()
+        );
+
+        // Empty values are created
+        merge(
+            &mut replica_b,
+            &mut replica_a,
+            &json!({ "f": "", "g": {}, "h": [], "i": [{}, [], ""] }),
+        );
+        let result = json!({
+            "a": 3.0,
+            "b": { "d": "z" },
+            "e": [true, 2.0],
+            "f": "",
+            "g": {},
+            "h": [],
+            "i": [{}, [], ""],
+        });
+        assert_eq!(result, replica_a.query(ReadAsJson::new()));
+        assert_eq!(result, replica_b.query(ReadAsJson::new()));
+        assert!(
+            replica_a
+                .state()
+                .apply_json_merge(&json!({ "f": "", "g": {}, "h": [], "i": [{}, [], ""] }))
+                .is_empty()
+        );
+    }
+
     #[test]
     fn nested_conflicts() {
         let (mut replica_a, mut replica_b, mut replica_c) = triplet_log::<JsonLog>();
@@ -399,6 +930,20 @@ mod tests {
             replica_a.query(ReadAsJson::new()),
             replica_c.query(ReadAsJson::new())
         );
+
+        // The root is a number or an object, whose "foo" is a number or an object
+        assert_eq!(replica_a.query(CountConflicts::new()), 2);
+        let choose = replica_a
+            .send(Json::Object(UWMap::Update(
+                "foo".to_string(),
+                Box::new(Json::Choose(JsonVariant::Object)),
+            )))
+            .unwrap();
+        replica_b.receive(choose);
+        assert_eq!(replica_a.query(CountConflicts::new()), 1);
+        assert_eq!(replica_b.query(CountConflicts::new()), 1);
+        replica_a.send(Json::Choose(JsonVariant::Object)).unwrap();
+        assert_eq!(replica_a.query(CountConflicts::new()), 0);
     }
 
     #[test]
@@ -514,6 +1059,41 @@ mod tests {
         assert_eq!(replica_b.query(Read::new()), replica_a.query(Read::new()));
     }
 
+    #[test]
+    fn move_counter_concurrent_inc() {
+        let (mut replica_a, mut replica_b) = twins_log::<JsonLog>();
+
+        for (pos, v) in [1.0, 2.0].into_iter().enumerate() {
+            let event = replica_a
+                .send(Json::Array(NestedList::insert(
+                    pos,
+                    Box::new(Json::Number(Counter::Inc(v))),
+                )))
+                .unwrap();
+            replica_b.receive(event);
+        }
+
+        let event_a = replica_a
+            .send(Json::Array(NestedList::move_to(0, 1)))
+            .unwrap();
+        let event_b = replica_b
+            .send(Json::Array(NestedList::update(
+                0,
+                Box::new(Json::Number(Counter::Inc(5.0))),
+            )))
+            .unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+
+        let number = |v: f64| JsonValue::Value(Box::new(JsonChildValue::Number(v)));
+        let result = JsonValue::Value(Box::new(JsonChildValue::Array(vec![
+            number(2.0),
+            number(6.0),
+        ])));
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+    }
+
     #[cfg(feature = "fuzz")]
     #[test]
     #[ignore]
@@ -530,4 +1110,87 @@ mod tests {
 
         fuzzer::<JsonLog>(config);
     }
+
+    #[test]
+    fn type_of_path() {
+        let (mut replica_a, mut replica_b) = twins_log::<JsonLog>();
+
+        let path = |keys: &[&str]| {
+            keys.iter()
+                .map(|key| PathSeg::Key(key.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        let event = replica_a
+            .send(Json::set_path(
+                &path(&["a", "b"]),
+                Json::Boolean(EWFlag::Enable),
+            ))
+            .unwrap();
+        replica_b.receive(event);
+        let event = replica_a
+            .send(Json::set_path(
+                &[PathSeg::Key("list".to_string()), PathSeg::Index(0)],
+                Json::Number(Counter::Set(1.0)),
+            ))
+            .unwrap();
+        replica_b.receive(event);
+
+        // Concurrent values of different types at `$.c`
+        let event_a = replica_a
+            .send(Json::set_path(
+                &path(&["c"]),
+                Json::Number(Counter::Set(2.0)),
+            ))
+            .unwrap();
+        let event_b = replica_b
+            .send(Json::set_path(
+                &path(&["c"]),
+                Json::String(List::insert('x', 0)),
+            ))
+            .unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+
+        for replica in [&replica_a, &replica_b] {
+            assert_eq!(
+                replica.query(TypeOf::new(path(&["a", "b"]))),
+                JsonType::Boolean
+            );
+            assert_eq!(replica.query(TypeOf::new(path(&["a"]))), JsonType::Object);
+            assert_eq!(replica.query(TypeOf::new(vec![])), JsonType::Object);
+            assert_eq!(replica.query(TypeOf::new(path(&["list"]))), JsonType::Array);
+            assert_eq!(
+                replica.query(TypeOf::new(vec![
+                    PathSeg::Key("list".to_string()),
+                    PathSeg::Index(0)
+                ])),
+                JsonType::Number
+            );
+            assert_eq!(replica.query(TypeOf::new(path(&["c"]))), JsonType::Conflict);
+            assert_eq!(
+                replica.query(TypeOf::new(path(&["a", "x"]))),
+                JsonType::Null
+            );
+            assert_eq!(
+                replica.query(TypeOf::new(path(&["a", "b", "x"]))),
+                JsonType::Null
+            );
+        }
+
+        // The positions of the array are the visible ones
+        let item = |pos| vec![PathSeg::Key("list".to_string()), PathSeg::Index(pos)];
+        let event = replica_a
+            .send(Json::set_path(&item(1), Json::Boolean(EWFlag::Enable)))
+            .unwrap();
+        replica_b.receive(event);
+        let event = replica_b
+            .send(Json::remove_path(&item(0)).unwrap())
+            .unwrap();
+        replica_a.receive(event);
+        for replica in [&replica_a, &replica_b] {
+            assert_eq!(replica.query(TypeOf::new(item(0))), JsonType::Boolean);
+            assert_eq!(replica.query(TypeOf::new(item(1))), JsonType::Null);
+        }
+    }
 }
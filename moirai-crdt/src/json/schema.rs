@@ -0,0 +1,295 @@
+use std::fmt::Display;
+
+use moirai_protocol::{
+    clock::version_vector::Version,
+    crdt::{eval::EvalNested, query::QueryOperation},
+    event::Event,
+    state::{
+        effect_context::EffectContext,
+        log::{IsLog, PruneStats},
+    },
+};
+
+use crate::{
+    HashMap,
+    json::{Json, JsonLog, JsonRejection, JsonVariant, PathSeg},
+    list::nested_list::NestedList,
+    map::uw_map::UWMap,
+};
+
+/// Variant allowed at each path of a [`Json`] document.
+///
+/// Keys missing from an object schema, and the contents of [`JsonSchema::Any`], are not constrained.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum JsonSchema {
+    #[default]
+    Any,
+    Number,
+    Boolean,
+    String,
+    Object(HashMap<String, JsonSchema>),
+    /// Every element of the array follows the same schema.
+    Array(Box<JsonSchema>),
+}
+
+/// Operation writing a variant that the schema does not allow at `path`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchemaViolation {
+    pub path: Vec<PathSeg>,
+    pub expected: JsonVariant,
+    pub found: JsonVariant,
+}
+
+impl Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "$")?;
+        for seg in &self.path {
+            match seg {
+                PathSeg::Key(key) => write!(f, ".{key}")?,
+                PathSeg::Index(pos) => write!(f, "[{pos}]")?,
+            }
+        }
+        write!(f, " expects {:?}, found {:?}", self.expected, self.found)
+    }
+}
+
+impl JsonSchema {
+    pub fn object<'a>(fields: impl IntoIterator<Item = (&'a str, JsonSchema)>) -> Self {
+        JsonSchema::Object(
+            fields
+                .into_iter()
+                .map(|(key, schema)| (key.to_string(), schema))
+                .collect(),
+        )
+    }
+
+    pub fn array(items: JsonSchema) -> Self {
+        JsonSchema::Array(Box::new(items))
+    }
+
+    /// Variant required by the schema, `None` for [`JsonSchema::Any`].
+    pub fn variant(&self) -> Option<JsonVariant> {
+        match self {
+            JsonSchema::Any => None,
+            JsonSchema::Number => Some(JsonVariant::Number),
+            JsonSchema::Boolean => Some(JsonVariant::Boolean),
+            JsonSchema::String => Some(JsonVariant::String),
+            JsonSchema::Object(_) => Some(JsonVariant::Object),
+            JsonSchema::Array(_) => Some(JsonVariant::Array),
+        }
+    }
+
+    /// Check every value that `op` writes, down to the leaf.
+    pub fn check(&self, op: &Json) -> Result<(), SchemaViolation> {
+        self.check_at(op, &mut Vec::new())
+    }
+
+    fn check_at(&self, op: &Json, path: &mut Vec<PathSeg>) -> Result<(), SchemaViolation> {
+        let found = match op {
+            Json::Number(_) => JsonVariant::Number,
+            Json::Boolean(_) => JsonVariant::Boolean,
+            Json::String(_) => JsonVariant::String,
+            Json::Object(_) => JsonVariant::Object,
+            Json::Array(_) => JsonVariant::Array,
+            Json::Choose(variant) => *variant,
+        };
+        if let Some(expected) = self.variant().filter(|expected| *expected != found) {
+            return Err(SchemaViolation {
+                path: path.clone(),
+                expected,
+                found,
+            });
+        }
+        let (seg, schema, op) = match (self, op) {
+            (JsonSchema::Object(fields), Json::Object(UWMap::Update(key, op))) => {
+                let Some(schema) = fields.get(key) else {
+                    return Ok(());
+                };
+                (PathSeg::Key(key.clone()), schema, op)
+            }
+            (
+                JsonSchema::Array(schema),
+                Json::Array(NestedList::Insert { pos, op } | NestedList::Update { pos, op }),
+            ) => (PathSeg::Index(*pos), schema.as_ref(), op),
+            _ => return Ok(()),
+        };
+        path.push(seg);
+        schema.check_at(op, path)?;
+        path.pop();
+        Ok(())
+    }
+}
+
+/// Why an operation on a [`SchemaJsonLog`] is not enabled.
+#[derive(Debug)]
+pub enum SchemaRejection {
+    Violation(SchemaViolation),
+    Json(JsonRejection),
+}
+
+impl Display for SchemaRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaRejection::Violation(violation) => write!(f, "schema violation: {violation}"),
+            SchemaRejection::Json(rejection) => write!(f, "{rejection}"),
+        }
+    }
+}
+
+/// [`JsonLog`] whose local operations must follow a [`JsonSchema`].
+///
+/// Concurrent writes of different variants are what makes the union keep conflicting values.
+/// When every replica checks the same schema, a path only ever holds one variant,
+/// so the document has no type conflict.
+/// Remote operations are not checked: they were checked by the replica that sent them.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaJsonLog {
+    log: JsonLog,
+    schema: JsonSchema,
+}
+
+impl SchemaJsonLog {
+    pub fn with_schema(schema: JsonSchema) -> Self {
+        Self {
+            log: JsonLog::default(),
+            schema,
+        }
+    }
+
+    pub fn schema(&self) -> &JsonSchema {
+        &self.schema
+    }
+
+    pub fn inner(&self) -> &JsonLog {
+        &self.log
+    }
+}
+
+impl IsLog for SchemaJsonLog {
+    type Value = <JsonLog as IsLog>::Value;
+    type Op = Json;
+    type Rejection = SchemaRejection;
+    const NEEDS_CAUSAL_DEPS: bool = <JsonLog as IsLog>::NEEDS_CAUSAL_DEPS;
+
+    fn prepare(op: Self::Op) -> Self::Op {
+        JsonLog::prepare(op)
+    }
+
+    fn is_enabled(&self, op: &Self::Op) -> Result<(), Self::Rejection> {
+        self.schema.check(op).map_err(SchemaRejection::Violation)?;
+        self.log.is_enabled(op).map_err(SchemaRejection::Json)
+    }
+
+    fn effect(&mut self, event: Event<Self::Op>, ctx: &mut EffectContext<'_>) {
+        self.log.effect(event, ctx);
+    }
+
+    fn stabilize(&mut self, version: &Version) {
+        self.log.stabilize(version);
+    }
+
+    fn redundant_by_parent(&mut self, version: &Version, conservative: bool) {
+        self.log.redundant_by_parent(version, conservative);
+    }
+
+    fn is_default(&self) -> bool {
+        self.log.is_default()
+    }
+
+    fn prune_stats(&self) -> PruneStats {
+        self.log.prune_stats()
+    }
+}
+
+impl<Q> EvalNested<Q> for SchemaJsonLog
+where
+    Q: QueryOperation,
+    JsonLog: EvalNested<Q>,
+{
+    fn execute_query(&self, q: Q) -> Q::Response {
+        self.log.execute_query(q)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::{
+        broadcast::tcsb::Tcsb,
+        replica::{IsReplica, Replica},
+    };
+    use serde_json::json;
+
+    use crate::{
+        counter::resettable_counter::Counter,
+        flag::ew_flag::EWFlag,
+        json::{
+            Json, JsonVariant, PathSeg,
+            schema::{JsonSchema, SchemaJsonLog, SchemaRejection, SchemaViolation},
+        },
+        query::read_as_json::ReadAsJson,
+    };
+
+    type SchemaReplica = Replica<SchemaJsonLog, Tcsb<Json>>;
+
+    #[test]
+    fn schema_rejects_wrong_variant() {
+        let schema = JsonSchema::object([
+            ("count", JsonSchema::Number),
+            ("tags", JsonSchema::array(JsonSchema::String)),
+        ]);
+        let mut replicas: Vec<SchemaReplica> = ["a", "b"]
+            .iter()
+            .map(|id| {
+                Replica::bootstrap_with_state(
+                    id.to_string(),
+                    &["a", "b"],
+                    SchemaJsonLog::with_schema(schema.clone()),
+                )
+            })
+            .collect();
+
+        let count = [PathSeg::Key("count".to_string())];
+        let rejection = replicas[0]
+            .send(Json::set_path(&count, Json::Boolean(EWFlag::Enable)))
+            .unwrap_err();
+        let SchemaRejection::Violation(violation) = rejection else {
+            panic!("expected a schema violation, got {rejection}");
+        };
+        assert_eq!(
+            violation,
+            SchemaViolation {
+                path: count.to_vec(),
+                expected: JsonVariant::Number,
+                found: JsonVariant::Boolean,
+            }
+        );
+        assert_eq!(
+            violation.to_string(),
+            "$.count expects Number, found Boolean"
+        );
+
+        // Elements of the array are checked too
+        let tags = [PathSeg::Key("tags".to_string()), PathSeg::Index(0)];
+        assert!(
+            replicas[1]
+                .send(Json::set_path(&tags, Json::Number(Counter::Inc(1.0))))
+                .is_err()
+        );
+
+        let event = replicas[0]
+            .send(Json::set_path(&count, Json::Number(Counter::Inc(3.0))))
+            .unwrap();
+        replicas[1].receive(event);
+        // Keys outside the schema are not constrained
+        let other = [PathSeg::Key("other".to_string())];
+        let event = replicas[1]
+            .send(Json::set_path(&other, Json::Boolean(EWFlag::Enable)))
+            .unwrap();
+        replicas[0].receive(event);
+
+        let result = json!({ "count": 3.0, "other": true });
+        for replica in &replicas {
+            assert_eq!(replica.query(ReadAsJson::new()), result);
+        }
+    }
+}
@@ -0,0 +1,408 @@
+//! Browser bindings for the JSON CRDT.
+//!
+//! Messages cross the JS boundary as JSON-encoded byte buffers. Replica indices are
+//! only meaningful with the sender's resolver, so every message carries the list of
+//! members it was encoded with, and the receiver rebuilds an equivalent resolver.
+
+use moirai_protocol::{
+    broadcast::{
+        batch::Batch,
+        message::{BatchMessage, EventMessage, SinceMessage},
+        since::Since,
+        tcsb::Tcsb,
+    },
+    clock::version_vector::{Seq, Version},
+    event::{Event, id::EventId, lamport::Lamport},
+    replica::{IsReplica, Replica, ReplicaIdx},
+    utils::intern_str::{Interner, Resolver},
+};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    json::{Json, JsonLog},
+    query::read_as_json::ReadAsJson,
+};
+
+#[derive(Serialize, Deserialize)]
+struct WireMessage<P> {
+    /// Members of the sender's view, in the order of its replica indices.
+    members: Vec<String>,
+    payload: P,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireVersion {
+    origin: usize,
+    entries: Vec<Seq>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireEvent {
+    origin: usize,
+    seq: Seq,
+    lamport: usize,
+    version: WireVersion,
+    op: Json,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireBatch {
+    version: WireVersion,
+    events: Vec<WireEvent>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireSince {
+    version: WireVersion,
+    except: Vec<(usize, Seq)>,
+}
+
+fn members(resolver: &Resolver) -> Vec<String> {
+    resolver.into_vec()
+}
+
+/// Resolver of the sender's view, whose members must be distinct: a duplicate would shift the
+/// indices of the later members.
+fn resolver_of(members: &[String]) -> Result<Resolver, JsValue> {
+    let mut interner = Interner::new();
+    for member in members {
+        let (_, is_new) = interner.intern(member);
+        if !is_new {
+            return Err(JsValue::from_str(&format!(
+                "member {member} listed twice in the view"
+            )));
+        }
+    }
+    Ok(interner.resolver().clone())
+}
+
+fn encode_version(version: &Version) -> WireVersion {
+    WireVersion {
+        origin: version.origin_idx().0,
+        entries: (0..version.resolver().len())
+            .map(|i| version.seq_by_idx(ReplicaIdx(i)))
+            .collect(),
+    }
+}
+
+/// Index of a replica read from the wire, which must be a member of the sender's view.
+fn decode_idx(idx: usize, resolver: &Resolver) -> Result<ReplicaIdx, JsValue> {
+    if idx < resolver.len() {
+        Ok(ReplicaIdx(idx))
+    } else {
+        Err(JsValue::from_str(&format!(
+            "replica index {idx} out of a view of {} members",
+            resolver.len()
+        )))
+    }
+}
+
+fn decode_version(version: WireVersion, resolver: &Resolver) -> Result<Version, JsValue> {
+    if version.entries.len() != resolver.len() {
+        return Err(JsValue::from_str(&format!(
+            "version of {} entries in a view of {} members",
+            version.entries.len(),
+            resolver.len()
+        )));
+    }
+    let mut decoded = Version::new(decode_idx(version.origin, resolver)?, resolver.clone());
+    for (i, seq) in version.entries.into_iter().enumerate() {
+        decoded.set_by_idx(ReplicaIdx(i), seq);
+    }
+    Ok(decoded)
+}
+
+fn encode_event(event: Event<Json>) -> WireEvent {
+    WireEvent {
+        origin: event.id().idx().0,
+        seq: event.id().seq(),
+        lamport: event.lamport().val(),
+        version: encode_version(event.version()),
+        op: event.into_op(),
+    }
+}
+
+fn decode_event(event: WireEvent, resolver: &Resolver) -> Result<Event<Json>, JsValue> {
+    Ok(Event::new(
+        EventId::new(
+            decode_idx(event.origin, resolver)?,
+            event.seq,
+            resolver.clone(),
+        ),
+        Lamport::new(event.lamport),
+        event.op,
+        decode_version(event.version, resolver)?,
+    ))
+}
+
+fn to_bytes<P: Serialize>(message: &WireMessage<P>) -> Vec<u8> {
+    serde_json::to_vec(message).expect("wire messages are always serializable")
+}
+
+fn from_bytes<'a, P: Deserialize<'a>>(bytes: &'a [u8]) -> Result<WireMessage<P>, JsValue> {
+    serde_json::from_slice(bytes).map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+fn encode_event_message(message: EventMessage<Json>) -> Vec<u8> {
+    to_bytes(&WireMessage {
+        members: members(message.resolver()),
+        payload: encode_event(message.event().clone()),
+    })
+}
+
+fn decode_event_message(bytes: &[u8]) -> Result<EventMessage<Json>, JsValue> {
+    let message = from_bytes::<WireEvent>(bytes)?;
+    let resolver = resolver_of(&message.members)?;
+    let event = decode_event(message.payload, &resolver)?;
+    Ok(EventMessage::new(event, resolver))
+}
+
+fn encode_batch_message(message: BatchMessage<Json>) -> Vec<u8> {
+    let (batch, resolver) = message.into_parts();
+    let version = encode_version(batch.version());
+    to_bytes(&WireMessage {
+        members: members(&resolver),
+        payload: WireBatch {
+            version,
+            events: batch.into_events().into_iter().map(encode_event).collect(),
+        },
+    })
+}
+
+fn decode_batch_message(bytes: &[u8]) -> Result<BatchMessage<Json>, JsValue> {
+    let message = from_bytes::<WireBatch>(bytes)?;
+    let resolver = resolver_of(&message.members)?;
+    let events = message
+        .payload
+        .events
+        .into_iter()
+        .map(|event| decode_event(event, &resolver))
+        .collect::<Result<_, _>>()?;
+    let version = decode_version(message.payload.version, &resolver)?;
+    Ok(BatchMessage::new(Batch::new(events, version), resolver))
+}
+
+fn encode_since_message(message: SinceMessage) -> Vec<u8> {
+    let since = message.since();
+    to_bytes(&WireMessage {
+        members: members(message.resolver()),
+        payload: WireSince {
+            version: encode_version(since.version()),
+            except: since
+                .except()
+                .iter()
+                .map(|id| (id.idx().0, id.seq()))
+                .collect(),
+        },
+    })
+}
+
+fn decode_since_message(bytes: &[u8]) -> Result<SinceMessage, JsValue> {
+    let message = from_bytes::<WireSince>(bytes)?;
+    let resolver = resolver_of(&message.members)?;
+    let version = decode_version(message.payload.version, &resolver)?;
+    let except = message
+        .payload
+        .except
+        .into_iter()
+        .map(|(idx, seq)| {
+            Ok(EventId::new(
+                decode_idx(idx, &resolver)?,
+                seq,
+                resolver.clone(),
+            ))
+        })
+        .collect::<Result<_, JsValue>>()?;
+    Ok(SinceMessage::new(Since::new(version, except), resolver))
+}
+
+/// A replica of a collaborative JSON document, exposed to JavaScript.
+///
+/// Operations are given as the JSON encoding of a [`Json`] operation,
+/// e.g. `{"Number":{"Inc":1.0}}`. The returned buffers are meant to be
+/// forwarded as-is to the other replicas.
+#[wasm_bindgen]
+pub struct JsonReplica {
+    replica: Replica<JsonLog, Tcsb<Json>>,
+}
+
+#[wasm_bindgen]
+impl JsonReplica {
+    /// Create the replica `id` of a group made of `members`, which must include `id`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(id: String, members: Vec<String>) -> JsonReplica {
+        let members: Vec<&str> = members.iter().map(String::as_str).collect();
+        Self {
+            replica: Replica::bootstrap(id, &members),
+        }
+    }
+
+    pub fn id(&self) -> String {
+        self.replica.id().to_string()
+    }
+
+    /// Apply a local operation and return the event to broadcast.
+    pub fn send_json(&mut self, op_json: &str) -> Result<Vec<u8>, JsValue> {
+        let op: Json =
+            serde_json::from_str(op_json).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let message = self
+            .replica
+            .send(op)
+            .map_err(|err| JsValue::from_str(&format!("{err:?}")))?;
+        Ok(encode_event_message(message))
+    }
+
+    /// Receive an event broadcast by another replica.
+    pub fn receive(&mut self, event_bytes: &[u8]) -> Result<(), JsValue> {
+        let message = decode_event_message(event_bytes)?;
+        self.replica.receive(message);
+        Ok(())
+    }
+
+    /// Receive the batch answering a `pull`.
+    pub fn receive_batch(&mut self, batch_bytes: &[u8]) -> Result<(), JsValue> {
+        let message = decode_batch_message(batch_bytes)?;
+        self.replica.receive_batch(message);
+        Ok(())
+    }
+
+    /// Current value of the document, as a plain JavaScript value.
+    pub fn read(&self) -> Result<JsValue, JsValue> {
+        let value = self.replica.query(ReadAsJson::new());
+        value
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(JsValue::from)
+    }
+
+    /// Version of this replica, to be sent to a replica that will answer with `pull`.
+    pub fn since(&self) -> Vec<u8> {
+        encode_since_message(self.replica.since())
+    }
+
+    /// Answer the `since` of another replica with the batch of events it misses.
+    pub fn pull(&mut self, since_bytes: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let since = decode_since_message(since_bytes)?;
+        Ok(encode_batch_message(self.replica.pull(since)))
+    }
+}
+
+impl JsonReplica {
+    pub fn replica(&self) -> &Replica<JsonLog, Tcsb<Json>> {
+        &self.replica
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::replica::IsReplica;
+    use serde_json::json;
+
+    use crate::{json::wasm::JsonReplica, query::read_as_json::ReadAsJson};
+
+    fn pair() -> (JsonReplica, JsonReplica) {
+        let members = vec!["a".to_string(), "b".to_string()];
+        (
+            JsonReplica::new("a".to_string(), members.clone()),
+            JsonReplica::new("b".to_string(), members),
+        )
+    }
+
+    #[test]
+    fn event_bytes_round_trip() {
+        let (mut replica_a, mut replica_b) = pair();
+
+        let event = replica_a
+            .send_json(r#"{"Object":{"Update":["count",{"Number":{"Inc":2.0}}]}}"#)
+            .unwrap();
+        replica_b.receive(&event).unwrap();
+        let event = replica_b
+            .send_json(r#"{"Object":{"Update":["count",{"Number":{"Inc":3.0}}]}}"#)
+            .unwrap();
+        replica_a.receive(&event).unwrap();
+
+        let expected = json!({ "count": 5.0 });
+        assert_eq!(expected, replica_a.replica().query(ReadAsJson::new()));
+        assert_eq!(expected, replica_b.replica().query(ReadAsJson::new()));
+    }
+
+    #[test]
+    fn pull_round_trip() {
+        let (mut replica_a, mut replica_b) = pair();
+
+        replica_a.send_json(r#"{"Boolean":"Enable"}"#).unwrap();
+        replica_a.send_json(r#"{"Boolean":"Disable"}"#).unwrap();
+
+        let batch = replica_a.pull(&replica_b.since()).unwrap();
+        replica_b.receive_batch(&batch).unwrap();
+
+        assert_eq!(json!(false), replica_b.replica().query(ReadAsJson::new()));
+        assert_eq!(replica_b.replica().num_delivered_events(), 2);
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::json::wasm::JsonReplica;
+
+    #[wasm_bindgen_test]
+    fn send_receive_read() {
+        let members = vec!["a".to_string(), "b".to_string()];
+        let mut replica_a = JsonReplica::new("a".to_string(), members.clone());
+        let mut replica_b = JsonReplica::new("b".to_string(), members);
+
+        let event = replica_a
+            .send_json(
+                r#"{"Object":{"Update":["title",{"String":{"Insert":{"content":"a","pos":0}}}]}}"#,
+            )
+            .unwrap();
+        replica_b.receive(&event).unwrap();
+
+        let value: serde_json::Value =
+            serde_wasm_bindgen::from_value(replica_b.read().unwrap()).unwrap();
+        assert_eq!(value, serde_json::json!({ "title": "a" }));
+    }
+
+    #[wasm_bindgen_test]
+    fn out_of_view_indices_are_rejected() {
+        let members = vec!["a".to_string(), "b".to_string()];
+        let mut replica_a = JsonReplica::new("a".to_string(), members.clone());
+        let mut replica_b = JsonReplica::new("b".to_string(), members);
+
+        let event = replica_a.send_json(r#"{"Boolean":"Enable"}"#).unwrap();
+        let forge = |edit: fn(&mut serde_json::Value)| {
+            let mut message: serde_json::Value = serde_json::from_slice(&event).unwrap();
+            edit(&mut message);
+            serde_json::to_vec(&message).unwrap()
+        };
+
+        let unknown_origin = forge(|message| message["payload"]["origin"] = 7.into());
+        assert!(replica_b.receive(&unknown_origin).is_err());
+        let short_version =
+            forge(|message| message["payload"]["version"]["entries"] = serde_json::json!([1]));
+        assert!(replica_b.receive(&short_version).is_err());
+        let since = forge(|message| {
+            message["payload"] = serde_json::json!({
+                "version": { "origin": 0, "entries": [0, 0] },
+                "except": [[5, 1]],
+            })
+        });
+        assert!(replica_a.pull(&since).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn duplicate_members_are_rejected() {
+        let members = vec!["a".to_string(), "b".to_string()];
+        let mut replica_a = JsonReplica::new("a".to_string(), members.clone());
+        let mut replica_b = JsonReplica::new("b".to_string(), members);
+
+        let event = replica_a.send_json(r#"{"Boolean":"Enable"}"#).unwrap();
+        let mut message: serde_json::Value = serde_json::from_slice(&event).unwrap();
+        // Merged into a view of two members, the version would still match it
+        message["members"] = serde_json::json!(["a", "a", "b"]);
+        let forged = serde_json::to_vec(&message).unwrap();
+        assert!(replica_b.receive(&forged).is_err());
+    }
+}
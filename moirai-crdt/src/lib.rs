@@ -9,8 +9,10 @@ pub mod model;
 pub mod option;
 pub mod policy;
 pub mod query;
+pub mod queue;
 pub mod register;
 pub mod set;
+pub mod undo;
 pub mod utils;
 
 type HashMap<K, V> = rustc_hash::FxHashMap<K, V>;
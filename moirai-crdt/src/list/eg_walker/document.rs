@@ -6,7 +6,7 @@ use crate::{
     HashMap,
     list::eg_walker::{
         DeleteTarget,
-        item::{Item, ItemId},
+        item::{EventIdx, Item, ItemId},
     },
 };
 
@@ -64,10 +64,14 @@ pub struct Document<'a, V> {
     pub records: Vec<Record<V>>,
     /// Last processed event in the topological replay.
     pub current_version: Option<EventId>,
-    /// Key = update op id, value = item updated by that op.
-    pub update_targets: HashMap<EventId, ItemId>,
-    /// Key = delete op id, value = item/dots removed by that op.
-    pub delete_targets: HashMap<EventId, DeleteTarget>,
+    /// Events seen by the replay, indexed by their `EventIdx`.
+    pub event_ids: Vec<EventId>,
+    /// Reverse of `event_ids`, the only map keyed by a full `EventId`.
+    pub event_idxs: HashMap<EventId, EventIdx>,
+    /// Key = update op, value = item updated by that op.
+    pub update_targets: HashMap<EventIdx, ItemId>,
+    /// Key = delete op, value = item/dots removed by that op.
+    pub delete_targets: HashMap<EventIdx, DeleteTarget>,
     /// Concrete item identity to record index.
     ///
     /// Stable items that are still inside a `StableRange` are located by scanning
@@ -85,6 +89,8 @@ impl<'a, V> Document<'a, V> {
             stable,
             records: Vec::new(),
             current_version: None,
+            event_ids: Vec::new(),
+            event_idxs: HashMap::default(),
             update_targets: HashMap::default(),
             delete_targets: HashMap::default(),
             items_by_idx: HashMap::default(),
@@ -98,6 +104,29 @@ impl<'a, V> Document<'a, V> {
         document
     }
 
+    /// Index of `event_id` in this document, assigning the next one on first sight.
+    pub fn intern(&mut self, event_id: &EventId) -> EventIdx {
+        if let Some(idx) = self.event_idxs.get(event_id) {
+            return *idx;
+        }
+        let idx = EventIdx(
+            u32::try_from(self.event_ids.len()).expect("more than u32::MAX events in a document"),
+        );
+        self.event_ids.push(event_id.clone());
+        self.event_idxs.insert(event_id.clone(), idx);
+        idx
+    }
+
+    /// Index of an event already seen by the replay.
+    pub fn event_idx(&self, event_id: &EventId) -> Option<EventIdx> {
+        self.event_idxs.get(event_id).copied()
+    }
+
+    /// Full identity of an interned event.
+    pub fn event_id(&self, idx: EventIdx) -> &EventId {
+        &self.event_ids[idx.0 as usize]
+    }
+
     /// Rebuild the item-to-record index after a splice or insertion.
     pub fn rebuild_index(&mut self) {
         self.items_by_idx.clear();
@@ -240,6 +269,28 @@ where
         }
         value
     }
+
+    /// Every value of the sequence with the insert event of its item and whether it is deleted.
+    pub fn items_with_ids(&self) -> Vec<(V, Option<EventId>, bool)> {
+        let mut items = Vec::new();
+        for record in &self.records {
+            match record {
+                Record::StableRange { start, end } => {
+                    items.extend(
+                        self.stable[*start..*end]
+                            .iter()
+                            .map(|v| (v.clone(), None, false)),
+                    );
+                }
+                Record::Item(item) => items.push((
+                    item.content.clone(),
+                    item.id.event_idx().map(|idx| self.event_id(idx).clone()),
+                    !item.effect.is_visible(),
+                )),
+            }
+        }
+        items
+    }
 }
 
 impl<V> Display for Document<'_, V> {
@@ -289,7 +340,7 @@ impl<V> Display for Document<'_, V> {
             "update targets: {}",
             self.update_targets
                 .iter()
-                .map(|(k, v)| format!("{}: {:?}", k, v))
+                .map(|(k, v)| format!("{}: {:?}", self.event_id(*k), v))
                 .collect::<Vec<_>>()
                 .join(", ")
         )?;
@@ -299,7 +350,7 @@ impl<V> Display for Document<'_, V> {
             "delete targets: {}",
             self.delete_targets
                 .iter()
-                .map(|(k, v)| format!("{}: {:?}", k, v))
+                .map(|(k, v)| format!("{}: {:?}", self.event_id(*k), v))
                 .collect::<Vec<_>>()
                 .join(", ")
         )?;
@@ -1,15 +1,22 @@
 use std::collections::BTreeSet;
 
-use moirai_protocol::event::id::EventId;
+use moirai_protocol::event::tag::Tag;
 
 use crate::list::eg_walker::presence_state::PreparePresence;
 
+/// Compact identity of an unstable event, valid within one [`Document`](super::document::Document).
+///
+/// Events are numbered in the order the document first sees them. Items, dots and
+/// targets key on this index instead of cloning full `EventId`s.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct EventIdx(pub(super) u32);
+
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 enum ItemKey {
     /// Character that belongs to the stable baseline materialized outside the event graph.
     Stable(usize),
     /// Character or life dot introduced by an unstable event.
-    Event(EventId),
+    Event(EventIdx),
 }
 
 /// Identity of the list item used for anchoring and lookup.
@@ -26,8 +33,8 @@ impl ItemId {
         Self(ItemKey::Stable(index))
     }
 
-    pub fn event(event_id: EventId) -> Self {
-        Self(ItemKey::Event(event_id))
+    pub fn event(event_idx: EventIdx) -> Self {
+        Self(ItemKey::Event(event_idx))
     }
 
     pub fn stable_index(&self) -> Option<usize> {
@@ -36,6 +43,13 @@ impl ItemId {
             Self(ItemKey::Event(_)) => None,
         }
     }
+
+    pub fn event_idx(&self) -> Option<EventIdx> {
+        match self {
+            Self(ItemKey::Stable(_)) => None,
+            Self(ItemKey::Event(event_idx)) => Some(*event_idx),
+        }
+    }
 }
 
 /// Add/update dot used by the prepare and effect visibility states.
@@ -52,8 +66,8 @@ impl LifeDot {
         Self(ItemKey::Stable(index))
     }
 
-    pub fn event(event_id: EventId) -> Self {
-        Self(ItemKey::Event(event_id))
+    pub fn event(event_idx: EventIdx) -> Self {
+        Self(ItemKey::Event(event_idx))
     }
 }
 
@@ -101,18 +115,23 @@ pub struct Item<V> {
     pub effect: EffectPresence,
     /// Prepared-parent visibility state used while Eg-Walker jumps across the event graph.
     pub prepare: PreparePresence,
+    /// Insert event of the item, used to order concurrent insertions. `None` for stable items.
+    pub tag: Option<Tag>,
 }
 
 impl<V> Item<V> {
     /// Create an item introduced by an insert event in the unstable log.
+    ///
+    /// `event_idx` is the document's index of the insert event identified by `tag`.
     pub fn new_event(
-        id: EventId,
+        tag: Tag,
+        event_idx: EventIdx,
         origin_left: Option<ItemId>,
         origin_right: Option<ItemId>,
         content: V,
     ) -> Self {
-        let item_id = ItemId::event(id.clone());
-        let dot = LifeDot::event(id);
+        let item_id = ItemId::event(event_idx);
+        let dot = LifeDot::event(event_idx);
         Self {
             id: item_id,
             origin_left,
@@ -120,6 +139,7 @@ impl<V> Item<V> {
             content,
             effect: EffectPresence::new(dot.clone()),
             prepare: PreparePresence::new(dot),
+            tag: Some(tag),
         }
     }
 
@@ -138,6 +158,7 @@ impl<V> Item<V> {
             content,
             effect: EffectPresence::new(dot.clone()),
             prepare: PreparePresence::new(dot),
+            tag: None,
         }
     }
 }
@@ -21,6 +21,7 @@ mod presence_state;
 use std::{
     collections::{BTreeSet, BinaryHeap},
     fmt::{Debug, Display},
+    marker::PhantomData,
 };
 
 #[cfg(feature = "test_utils")]
@@ -30,21 +31,28 @@ use moirai_fuzz::{op_generator::OpGenerator, value_generator::ValueGenerator};
 use moirai_protocol::{
     clock::version_vector::Version,
     crdt::{
-        eval::Eval,
+        eval::{Eval, EvalNested},
         pure_crdt::{CausalReset, PureCRDT},
         query::{QueryOperation, Read},
         redundancy::RedundancyRelation,
     },
-    event::{id::EventId, tagged_op::TaggedOp},
+    event::{Event, id::EventId, tag::Tag, tagged_op::TaggedOp},
     state::{
+        effect_context::EffectContext,
+        graph_log::GraphLog,
+        log::{IsLog, PruneStats},
         stable_state::IsStableState,
-        unstable_state::{CausalReplay, IsUnstableCore},
+        unstable_state::{CausalReplay, IsUnstableCore, event_graph::EventGraph},
     },
     utils::intern_str::{InternalizeOp, Interner},
 };
 #[cfg(feature = "fuzz")]
 use rand::{Rng, RngExt};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "fuzz")]
+use crate::list::{ListConfig, ListOpKind};
 use crate::{
     HashMap,
     list::eg_walker::{
@@ -55,6 +63,7 @@ use crate::{
 
 // Single-character, position-based, pure op-based CRDT operations
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
 pub enum List<V> {
     /// Insert `content` at the visible position observed by the issuing replica.
@@ -200,9 +209,9 @@ where
     ///
     /// The visible position gives an initial location, but concurrent insertions
     /// at the same position must be ordered deterministically. The origin-left and
-    /// origin-right anchors restrict the scan to the insertion window; the local
-    /// item id tie-breaker is the current deterministic ordering rule.
-    fn integrate(doc: &mut Document<V>, new_item: Item<V>, mut idx: usize) {
+    /// origin-right anchors restrict the scan to the insertion window; the [`TieBreak`]
+    /// orders the insertions between the same anchors.
+    fn integrate<T: TieBreak>(doc: &mut Document<V>, new_item: Item<V>, mut idx: usize) {
         let mut scan_idx = idx;
 
         // If origin_left is None, we'll pretend there's an item at position -1 which we were inserted to the right of.
@@ -244,7 +253,9 @@ where
             };
 
             // TODO: use Fair Tag
-            if oleft < left || (oleft == left && oright == right && new_item.id < other.id) {
+            if oleft < left
+                || (oleft == left && oright == right && Self::precedes::<T>(&new_item, other))
+            {
                 break;
             }
             if oleft == left {
@@ -261,6 +272,15 @@ where
         doc.rebuild_index();
     }
 
+    /// The scan only meets the items of insert events being integrated: stable items are
+    /// integrated from the start. Both items thus have a tag, and `T` alone orders them.
+    fn precedes<T: TieBreak>(a: &Item<V>, b: &Item<V>) -> bool {
+        let (Some(a), Some(b)) = (&a.tag, &b.tag) else {
+            unreachable!("stable items are never scanned");
+        };
+        T::precedes(a, b)
+    }
+
     /// Move the prepare view backwards across `event_id`.
     ///
     /// During replay, consecutive events can have different parent versions. To
@@ -271,10 +291,11 @@ where
     where
         U: IsUnstableCore<List<V>>,
     {
+        let event_idx = doc.event_idx(event_id).unwrap();
         match &state.get(event_id).unwrap().op() {
             List::Insert { .. } => {
-                let item_id = ItemId::event(event_id.clone());
-                let life_dot = LifeDot::event(event_id.clone());
+                let item_id = ItemId::event(event_idx);
+                let life_dot = LifeDot::event(event_idx);
                 if let Some(item_idx) = doc.position_of(&item_id) {
                     let item = doc.item_mut(item_idx).unwrap();
                     item.prepare.remove_life_dot(&life_dot);
@@ -282,17 +303,16 @@ where
                 }
             }
             List::Update { .. } => {
-                let target = doc.update_targets.get(event_id).unwrap();
+                let target = doc.update_targets.get(&event_idx).unwrap();
                 if let Some(item_idx) = doc.position_of(target) {
                     let item = doc.item_mut(item_idx).unwrap();
-                    item.prepare
-                        .remove_life_dot(&LifeDot::event(event_id.clone()));
+                    item.prepare.remove_life_dot(&LifeDot::event(event_idx));
                 }
             }
             List::DeleteRange { .. } | List::Delete { .. } => {
                 let targets: Vec<DeleteEffect> = doc
                     .delete_targets
-                    .get(event_id)
+                    .get(&event_idx)
                     .map(|t| match t {
                         DeleteTarget::Single(effect) => vec![effect.clone()],
                         DeleteTarget::Range(effects) => effects.clone(),
@@ -320,10 +340,11 @@ where
     where
         U: IsUnstableCore<List<V>>,
     {
+        let event_idx = doc.event_idx(event_id).unwrap();
         match &state.get(event_id).unwrap().op() {
             List::Insert { .. } => {
-                let item_id = ItemId::event(event_id.clone());
-                let life_dot = LifeDot::event(event_id.clone());
+                let item_id = ItemId::event(event_idx);
+                let life_dot = LifeDot::event(event_idx);
                 if let Some(item_idx) = doc.position_of(&item_id) {
                     let item = doc.item_mut(item_idx).unwrap();
                     item.prepare.inserted = true;
@@ -331,16 +352,16 @@ where
                 }
             }
             List::Update { .. } => {
-                let target = doc.update_targets.get(event_id).unwrap();
+                let target = doc.update_targets.get(&event_idx).unwrap();
                 if let Some(item_idx) = doc.position_of(target) {
                     let item = doc.item_mut(item_idx).unwrap();
-                    item.prepare.add_life_dot(LifeDot::event(event_id.clone()));
+                    item.prepare.add_life_dot(LifeDot::event(event_idx));
                 }
             }
             List::DeleteRange { .. } | List::Delete { .. } => {
                 let targets: Vec<DeleteEffect> = doc
                     .delete_targets
-                    .get(event_id)
+                    .get(&event_idx)
                     .map(|t| match t {
                         DeleteTarget::Single(effect) => vec![effect.clone()],
                         DeleteTarget::Range(effects) => effects.clone(),
@@ -363,7 +384,8 @@ where
     ///
     /// Positional indices are interpreted against `prepare`. The operation also
     /// updates `effect`, which is the state eventually materialized by reads.
-    fn apply(doc: &mut Document<V>, tagged_op: &TaggedOp<List<V>>) {
+    fn apply<T: TieBreak>(doc: &mut Document<V>, tagged_op: &TaggedOp<List<V>>) {
+        let event_idx = doc.intern(tagged_op.id());
         match tagged_op.op() {
             List::Delete { pos } => {
                 let Some(idx) = Self::find_visible_item(doc, *pos) else {
@@ -385,7 +407,7 @@ where
                     (item.id.clone(), removed_dots)
                 };
                 doc.delete_targets.insert(
-                    tagged_op.id().clone(),
+                    event_idx,
                     DeleteTarget::Single(DeleteEffect {
                         item_id,
                         removed_dots,
@@ -420,7 +442,7 @@ where
                 }
 
                 doc.delete_targets
-                    .insert(tagged_op.id().clone(), DeleteTarget::Range(effects));
+                    .insert(event_idx, DeleteTarget::Range(effects));
             }
             List::Update { pos } => {
                 let Some(idx) = Self::find_visible_item(doc, *pos) else {
@@ -432,12 +454,12 @@ where
                     let item = doc.item_mut(idx).unwrap();
                     // Updating an existing element adds a fresh life dot for the same identity.
                     // If concurrent with a delete, that dot is not part of the delete effect.
-                    let update_dot = LifeDot::event(tagged_op.id().clone());
+                    let update_dot = LifeDot::event(event_idx);
                     item.effect.add_life_dot(update_dot.clone());
                     item.prepare.add_life_dot(update_dot);
                     item.id.clone()
                 };
-                doc.update_targets.insert(tagged_op.id().clone(), item_id);
+                doc.update_targets.insert(event_idx, item_id);
             }
             List::Insert { content, pos } => {
                 let idx = Self::find_insert_position(doc, *pos);
@@ -451,12 +473,13 @@ where
                 let origin_right = Self::next_integrated_id(doc, idx);
 
                 let item = Item::new_event(
-                    tagged_op.id().clone(),
+                    tagged_op.tag().clone(),
+                    event_idx,
                     origin_left,
                     origin_right,
                     content.clone(),
                 );
-                Self::integrate(doc, item, idx)
+                Self::integrate::<T>(doc, item, idx)
             }
         }
     }
@@ -573,8 +596,20 @@ where
     /// `events` can be the whole unstable log for `Read`, or a predecessor stream
     /// for `ReadAt`. The document keeps its prepare view at the parent version of
     /// each event, applies the event, and finally materializes the effect view.
-    fn replay<'a, U, I>(stable: &'a [V], unstable: &'a U, events: I) -> Vec<V>
+    fn replay<'a, T, U, I>(stable: &'a [V], unstable: &'a U, events: I) -> Vec<V>
     where
+        T: TieBreak,
+        U: CausalReplay<List<V>> + 'a,
+        I: IntoIterator<Item = &'a TaggedOp<List<V>>>,
+        V: 'a,
+    {
+        Self::walk::<T, _, _>(stable, unstable, events).materialize()
+    }
+
+    /// Replay the events into the internal document, without materializing it.
+    fn walk<'a, T, U, I>(stable: &'a [V], unstable: &'a U, events: I) -> Document<'a, V>
+    where
+        T: TieBreak,
         U: CausalReplay<List<V>> + 'a,
         I: IntoIterator<Item = &'a TaggedOp<List<V>>>,
         V: 'a,
@@ -593,11 +628,11 @@ where
                 Self::advance(&mut document, unstable, &event_id);
             }
 
-            Self::apply(&mut document, tagged_op);
+            Self::apply::<T>(&mut document, tagged_op);
             document.current_version = Some(tagged_op.id().clone());
         }
 
-        document.materialize()
+        document
     }
 }
 
@@ -632,6 +667,11 @@ where
     const DISABLE_R_WHEN_R: bool = true;
     const DISABLE_STABILIZE: bool = true;
 
+    /// Deletions stay in the event graph so that concurrent operations can be replayed around them.
+    fn is_tombstone(op: &Self) -> bool {
+        matches!(op, List::Delete { .. } | List::DeleteRange { .. })
+    }
+
     /// Validate positional operations against the current visible document.
     ///
     /// EgWalker operations store user-facing positions, so enablement is checked by
@@ -685,17 +725,35 @@ where
 }
 
 /// Normal read: replay all unstable events on top of the stable list snapshot.
+///
+/// The replay is the expensive part of the list: wrap the log in a
+/// [`CachedLog`](moirai_protocol::state::cache::CachedLog) to only replay it once per change.
 impl<V, U> Eval<Read<<Self as PureCRDT>::Value>, U> for List<V>
 where
     V: Debug + Clone,
     U: CausalReplay<Self>,
 {
     fn execute_query(
-        _q: Read<<Self as PureCRDT>::Value>,
+        q: Read<<Self as PureCRDT>::Value>,
         stable: &Self::StableState,
         unstable: &U,
     ) -> Vec<V> {
-        Self::replay(stable, unstable, unstable.iter())
+        Self::execute_query(Ordered::<_, IdOrder>::new(q), stable, unstable)
+    }
+}
+
+impl<V, T, U> Eval<Ordered<Read<<Self as PureCRDT>::Value>, T>, U> for List<V>
+where
+    T: TieBreak,
+    V: Debug + Clone,
+    U: CausalReplay<Self>,
+{
+    fn execute_query(
+        _q: Ordered<Read<<Self as PureCRDT>::Value>, T>,
+        stable: &Self::StableState,
+        unstable: &U,
+    ) -> Vec<V> {
+        Self::replay::<T, _, _>(stable, unstable, unstable.iter())
     }
 }
 
@@ -704,12 +762,255 @@ impl<U> Eval<Read<String>, U> for List<char>
 where
     U: CausalReplay<Self>,
 {
-    fn execute_query(_q: Read<String>, stable: &Self::StableState, unstable: &U) -> String {
-        let chars: Vec<char> = Self::execute_query(Read::new(), stable, unstable);
+    fn execute_query(q: Read<String>, stable: &Self::StableState, unstable: &U) -> String {
+        Self::execute_query(Ordered::<_, IdOrder>::new(q), stable, unstable)
+    }
+}
+
+impl<T, U> Eval<Ordered<Read<String>, T>, U> for List<char>
+where
+    T: TieBreak,
+    U: CausalReplay<Self>,
+{
+    fn execute_query(
+        _q: Ordered<Read<String>, T>,
+        stable: &Self::StableState,
+        unstable: &U,
+    ) -> String {
+        let q = Ordered::<Read<Vec<char>>, T>::new(Read::new());
+        let chars: Vec<char> = Self::execute_query(q, stable, unstable);
         chars.into_iter().collect()
     }
 }
 
+/// Read with each run of equal adjacent values collapsed to a single value, e.g., for a feed.
+///
+/// The list itself keeps every value: only the read is deduplicated.
+pub struct ReadDeduped<V>(PhantomData<V>);
+
+impl<V> ReadDeduped<V> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<V> Default for ReadDeduped<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> QueryOperation for ReadDeduped<V> {
+    type Response = V;
+}
+
+impl<V, U> Eval<ReadDeduped<<Self as PureCRDT>::Value>, U> for List<V>
+where
+    V: Debug + Clone + PartialEq,
+    U: CausalReplay<Self>,
+{
+    fn execute_query(
+        q: ReadDeduped<<Self as PureCRDT>::Value>,
+        stable: &Self::StableState,
+        unstable: &U,
+    ) -> Vec<V> {
+        Self::execute_query(Ordered::<_, IdOrder>::new(q), stable, unstable)
+    }
+}
+
+impl<V, T, U> Eval<Ordered<ReadDeduped<<Self as PureCRDT>::Value>, T>, U> for List<V>
+where
+    T: TieBreak,
+    V: Debug + Clone + PartialEq,
+    U: CausalReplay<Self>,
+{
+    fn execute_query(
+        _q: Ordered<ReadDeduped<<Self as PureCRDT>::Value>, T>,
+        stable: &Self::StableState,
+        unstable: &U,
+    ) -> Vec<V> {
+        let q = Ordered::<Read<Vec<V>>, T>::new(Read::new());
+        let mut values: Vec<V> = Self::execute_query(q, stable, unstable);
+        values.dedup();
+        values
+    }
+}
+
+/// Order of concurrent insertions at the same place, i.e., between the same neighbours.
+///
+/// The list converges with any strict total order on the insert events, which lets it
+/// place concurrent insertions like another editor, e.g., a Yjs or Automerge peer.
+/// Insert positions are relative to the list as seen by their issuer, so the order is part
+/// of the type of the log, see [`OrderedListLog`]: every replica must use the same one.
+pub trait TieBreak: 'static {
+    /// Whether the insertion `a` is placed before the concurrent insertion `b`.
+    fn precedes(a: &Tag, b: &Tag) -> bool;
+}
+
+/// Default order, by event id: origin replica id, then sequence number.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub struct IdOrder;
+
+impl TieBreak for IdOrder {
+    fn precedes(a: &Tag, b: &Tag) -> bool {
+        a.id() < b.id()
+    }
+}
+
+/// Lamport timestamp first, then origin replica id: the earliest insertion comes first.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub struct LamportOrder;
+
+impl TieBreak for LamportOrder {
+    fn precedes(a: &Tag, b: &Tag) -> bool {
+        (a.lamport(), a.id().origin_id()) < (b.lamport(), b.id().origin_id())
+    }
+}
+
+/// Query `Q` on a list whose concurrent insertions are ordered by `T` instead of [`IdOrder`].
+pub struct Ordered<Q, T>(Q, PhantomData<T>);
+
+impl<Q, T> Ordered<Q, T> {
+    pub fn new(q: Q) -> Self {
+        Self(q, PhantomData)
+    }
+}
+
+impl<Q, T> QueryOperation for Ordered<Q, T>
+where
+    Q: QueryOperation,
+{
+    type Response = Q::Response;
+}
+
+/// Log of a list whose concurrent insertions are ordered by `T`.
+///
+/// The operations are the ones of [`List`]: only the replay depends on the order,
+/// so the log answers each query `Q` as the [`Ordered`] query of the list.
+#[derive(Debug, Clone)]
+pub struct OrderedListLog<V, T>
+where
+    V: Debug + Clone,
+{
+    log: GraphLog<List<V>>,
+    _order: PhantomData<T>,
+}
+
+impl<V, T> Default for OrderedListLog<V, T>
+where
+    V: Debug + Clone,
+{
+    fn default() -> Self {
+        Self {
+            log: GraphLog::default(),
+            _order: PhantomData,
+        }
+    }
+}
+
+impl<V, T> IsLog for OrderedListLog<V, T>
+where
+    V: Debug + Clone,
+    T: TieBreak + Debug,
+{
+    type Value = Vec<V>;
+    type Op = List<V>;
+    type Rejection = ListRejection;
+
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn is_enabled(&self, op: &Self::Op) -> Result<(), Self::Rejection> {
+        self.log.is_enabled(op)
+    }
+
+    fn effect(&mut self, event: Event<Self::Op>, ctx: &mut EffectContext<'_>) {
+        self.log.effect(event, ctx);
+    }
+
+    fn stabilize(&mut self, version: &Version) {
+        self.log.stabilize(version);
+    }
+
+    fn redundant_by_parent(&mut self, version: &Version, conservative: bool) {
+        self.log.redundant_by_parent(version, conservative);
+    }
+
+    fn is_default(&self) -> bool {
+        self.log.is_default()
+    }
+
+    fn prune_stats(&self) -> PruneStats {
+        self.log.prune_stats()
+    }
+}
+
+impl<V, T, Q> EvalNested<Q> for OrderedListLog<V, T>
+where
+    V: Debug + Clone,
+    T: TieBreak + Debug,
+    Q: QueryOperation,
+    List<V>: Eval<Ordered<Q, T>, EventGraph<List<V>>>,
+{
+    fn execute_query(&self, q: Q) -> Q::Response {
+        self.log.execute_query(Ordered::<Q, T>::new(q))
+    }
+}
+
+/// Internal sequence of the list, deleted items included, for tests and debugging tools.
+///
+/// Each value comes with the insert event of its item, `None` for values of the stable
+/// state, and whether it is deleted. Deleted values are kept where they were inserted.
+pub struct ReadWithIds<V>(PhantomData<V>);
+
+impl<V> ReadWithIds<V> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<V> Default for ReadWithIds<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> QueryOperation for ReadWithIds<V> {
+    type Response = Vec<(V, Option<EventId>, bool)>;
+}
+
+impl<V, U> Eval<ReadWithIds<V>, U> for List<V>
+where
+    V: Debug + Clone,
+    U: CausalReplay<Self>,
+{
+    fn execute_query(
+        q: ReadWithIds<V>,
+        stable: &Self::StableState,
+        unstable: &U,
+    ) -> Vec<(V, Option<EventId>, bool)> {
+        Self::execute_query(Ordered::<_, IdOrder>::new(q), stable, unstable)
+    }
+}
+
+impl<V, T, U> Eval<Ordered<ReadWithIds<V>, T>, U> for List<V>
+where
+    T: TieBreak,
+    V: Debug + Clone,
+    U: CausalReplay<Self>,
+{
+    fn execute_query(
+        _q: Ordered<ReadWithIds<V>, T>,
+        stable: &Self::StableState,
+        unstable: &U,
+    ) -> Vec<(V, Option<EventId>, bool)> {
+        Self::walk::<T, _, _>(stable, unstable, unstable.iter()).items_with_ids()
+    }
+}
+
 /// Read the list at a historical version.
 ///
 /// The unstable log supplies the predecessor events for the requested version,
@@ -742,8 +1043,23 @@ where
         stable: &Self::StableState,
         unstable: &U,
     ) -> Vec<V> {
-        let predecessors = unstable.predecessors(q.version);
-        Self::replay(stable, unstable, predecessors)
+        Self::execute_query(Ordered::<_, IdOrder>::new(q), stable, unstable)
+    }
+}
+
+impl<'a, V, T, U> Eval<Ordered<ReadAt<'a, <Self as PureCRDT>::Value>, T>, U> for List<V>
+where
+    T: TieBreak,
+    V: Debug + Clone,
+    U: CausalReplay<Self>,
+{
+    fn execute_query(
+        q: Ordered<ReadAt<<Self as PureCRDT>::Value>, T>,
+        stable: &Self::StableState,
+        unstable: &U,
+    ) -> Vec<V> {
+        let predecessors = unstable.predecessors(q.0.version);
+        Self::replay::<T, _, _>(stable, unstable, predecessors)
     }
 }
 
@@ -793,50 +1109,49 @@ impl<V> OpGenerator for List<V>
 where
     V: ValueGenerator + Debug + Clone,
 {
-    type Config = ();
+    type Config = ListConfig;
 
     fn generate(
         rng: &mut impl Rng,
-        _config: &Self::Config,
+        config: &mut Self::Config,
         stable: &Self::StableState,
         unstable: &impl CausalReplay<Self>,
     ) -> Self {
         // Fuzzing generates only user operations that are enabled in the current
         // visible document, because out-of-bounds operations are rejected before
         // they enter the log.
-        use rand::distr::{Distribution, weighted::WeightedIndex};
-
-        enum Choice {
-            Insert,
-            Delete,
-            DeleteRange,
-        }
-
         let list = Self::execute_query(Read::new(), stable, unstable);
 
         let choice = if list.is_empty() {
-            &Choice::Insert
+            ListOpKind::Insert
         } else if list.len() < 3 {
-            let dist = WeightedIndex::new([3, 2]).unwrap();
-            &[Choice::Insert, Choice::Delete][dist.sample(rng)]
+            config
+                .ops_without_range
+                .sample(rng, &[ListOpKind::Insert, ListOpKind::Delete])
         } else {
-            let dist = WeightedIndex::new([7, 2, 1]).unwrap();
-            &[Choice::Insert, Choice::Delete, Choice::DeleteRange][dist.sample(rng)]
+            config.ops.sample(
+                rng,
+                &[
+                    ListOpKind::Insert,
+                    ListOpKind::Delete,
+                    ListOpKind::DeleteRange,
+                ],
+            )
         };
 
         match choice {
-            Choice::Insert => {
+            ListOpKind::Insert => {
                 use moirai_fuzz::value_generator::ValueGenerator;
 
                 let pos = rng.random_range(0..=list.len());
                 let c = V::generate(rng, &<V as ValueGenerator>::Config::default());
                 List::insert(c, pos)
             }
-            Choice::Delete => {
+            ListOpKind::Delete => {
                 let pos = rng.random_range(0..list.len());
                 List::delete(pos)
             }
-            Choice::DeleteRange => {
+            ListOpKind::DeleteRange => {
                 let start = rng.random_range(0..list.len());
                 let max_len = list.len() - start;
                 let len = if max_len == 0 {
@@ -891,6 +1206,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn reject_out_of_bounds() {
+        let (mut replica_a, _) = twins_log::<GraphLog<List<char>>>();
+
+        assert!(matches!(
+            replica_a.send(List::insert('A', 1)),
+            Err(ListRejection::OutOfBounds { pos: 1, len: 0 })
+        ));
+        replica_a.send(List::insert('A', 0)).unwrap();
+        assert!(matches!(
+            replica_a.send(List::delete(1)),
+            Err(ListRejection::OutOfBounds { pos: 1, len: 1 })
+        ));
+        assert!(replica_a.send(List::delete_range(0, 2)).is_err());
+
+        assert_eq!(&replica_a.query(Read::<String>::new()), "A");
+        assert_eq!(replica_a.num_delivered_events(), 1);
+    }
+
     #[test]
     fn starts_from_stable_document() {
         let (replica_a, replica_b) = stable_twins(vec!['a', 'b', 'c']);
@@ -1301,6 +1635,89 @@ mod tests {
         assert_eq!(state_b, result);
     }
 
+    #[test]
+    fn uuid_event_ids() {
+        use moirai_protocol::event::id_strategy::{EventIdStrategy, UuidIds};
+
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet_log::<GraphLog<List<char>>>();
+
+        let a1 = replica_a.send(List::insert('a', 0)).unwrap();
+        let b1 = replica_b.send(List::insert('b', 0)).unwrap();
+        let c1 = replica_c.send(List::insert('c', 0)).unwrap();
+        let uuids: Vec<_> = [&a1, &b1, &c1]
+            .iter()
+            .map(|message| UuidIds::external_id(message.event().id()))
+            .collect();
+
+        replica_a.receive(b1.clone());
+        replica_a.receive(c1.clone());
+        replica_b.receive(c1.clone());
+        replica_b.receive(a1.clone());
+        replica_c.receive(a1.clone());
+        replica_c.receive(b1.clone());
+        let a2 = replica_a.send(List::delete(1)).unwrap();
+        replica_b.receive(a2.clone());
+        replica_c.receive(a2.clone());
+
+        // Every replica resolves the UUIDs to the same events, whatever its indices
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            for (uuid, message) in uuids.iter().zip([&a1, &b1, &c1]) {
+                let id = replica.event_id::<UuidIds>(uuid).unwrap();
+                assert_eq!(&id, message.event().id());
+                assert_eq!(UuidIds::external_id(&id), *uuid);
+            }
+        }
+        assert_eq!(uuids.iter().collect::<crate::HashSet<_>>().len(), 3);
+
+        let result = replica_a.query(Read::<String>::new());
+        assert_eq!(result.len(), 2);
+        assert_eq!(replica_b.query(Read::<String>::new()), result);
+        assert_eq!(replica_c.query(Read::<String>::new()), result);
+    }
+
+    /// Cost of reading a 50k-event document that never stabilizes, as `c` stays silent,
+    /// so that every read replays all the events.
+    /// Run with `cargo test --release -p moirai-crdt replay_cost -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn replay_cost_unstable_document() {
+        use std::time::Instant;
+
+        use moirai_protocol::{broadcast::tcsb::IsTcsb, utils::intern_str::Interner};
+
+        const SIZE: usize = 50_000;
+        const READS: u32 = 3;
+
+        let mut interner = Interner::new();
+        let (idx, _) = interner.intern("a");
+        interner.intern("b");
+        interner.intern("c");
+        let mut tcsb_a = Tcsb::<List<char>>::new(idx, interner);
+        let mut replica_b = ListReplica::bootstrap("b".to_string(), &["a", "b", "c"]);
+
+        // Type in the middle of the document, deleting every fourth character
+        let mut len = 0;
+        for i in 0..SIZE {
+            let op = if i % 4 == 3 {
+                len -= 1;
+                List::delete(len / 2)
+            } else {
+                len += 1;
+                List::insert('a', len / 2)
+            };
+            replica_b.receive(tcsb_a.send(op));
+        }
+
+        let start = Instant::now();
+        for _ in 0..READS {
+            assert_eq!(replica_b.query(Read::<String>::new()).len(), len);
+        }
+        println!(
+            "{:?} per read of {SIZE} unstable events",
+            start.elapsed() / READS
+        );
+    }
+
     #[cfg(feature = "fuzz")]
     #[test]
     #[ignore]
@@ -1318,4 +1735,87 @@ mod tests {
 
         fuzzer::<GraphLog<List<char>>>(config);
     }
+
+    #[test]
+    fn lamport_tie_break_egwalker() {
+        let (mut replica_a, mut replica_b) = twins_log::<OrderedListLog<char, LamportOrder>>();
+
+        let event = replica_a.send(List::insert('m', 0)).unwrap();
+        replica_b.receive(event);
+
+        // Both insert at the start, between the same neighbours, with Lamport timestamps 3 and 2.
+        // By event id, the insertion of a would come first, by Lamport timestamp, the one of b does
+        let event_a_1 = replica_a.send(List::insert('k', 1)).unwrap();
+        let event_a_2 = replica_a.send(List::insert('a', 0)).unwrap();
+        let event_b = replica_b.send(List::insert('b', 0)).unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a_1);
+        replica_b.receive(event_a_2);
+
+        for replica in [&replica_a, &replica_b] {
+            assert_eq!(
+                replica.query(Read::<Vec<char>>::new()),
+                ['b', 'a', 'm', 'k'].to_vec()
+            );
+        }
+
+        // Positions after the tie are resolved with the same order by every replica
+        let event_a = replica_a.send(List::insert('x', 1)).unwrap();
+        let event_b = replica_b.send(List::delete(1)).unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+
+        let expected = ['b', 'x', 'm', 'k'].to_vec();
+        assert_eq!(replica_a.query(Read::<Vec<char>>::new()), expected);
+        assert_eq!(replica_b.query(Read::<Vec<char>>::new()), expected);
+    }
+
+    #[test]
+    fn read_deduped() {
+        let (mut replica_a, mut replica_b) = twins_log::<GraphLog<List<char>>>();
+
+        for (pos, c) in "aabbc".chars().enumerate() {
+            let event = replica_a.send(List::insert(c, pos)).unwrap();
+            replica_b.receive(event);
+        }
+
+        for replica in [&replica_a, &replica_b] {
+            assert_eq!(replica.query(Read::<String>::new()), "aabbc");
+            assert_eq!(
+                replica.query(ReadDeduped::<Vec<char>>::new()),
+                vec!['a', 'b', 'c']
+            );
+        }
+    }
+
+    #[test]
+    fn read_with_ids_after_concurrent_insert() {
+        let (mut replica_a, mut replica_b) = twins_log::<GraphLog<List<char>>>();
+
+        let event_a = replica_a.send(List::insert('a', 0)).unwrap();
+        let event_b = replica_b.send(List::insert('b', 0)).unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+        let event = replica_a.send(List::delete(0)).unwrap();
+        replica_b.receive(event);
+
+        for replica in [&replica_a, &replica_b] {
+            let items: Vec<_> = replica
+                .query(ReadWithIds::<char>::new())
+                .into_iter()
+                .map(|(c, id, deleted)| {
+                    let id = id.unwrap();
+                    (c, id.origin_id().to_string(), id.seq(), deleted)
+                })
+                .collect();
+            assert_eq!(
+                items,
+                vec![
+                    ('a', "a".to_string(), 1, true),
+                    ('b', "b".to_string(), 1, false),
+                ]
+            );
+            assert_eq!(replica.query(Read::<String>::new()), "b");
+        }
+    }
 }
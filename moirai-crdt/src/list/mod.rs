@@ -1,2 +1,39 @@
 pub mod eg_walker;
 pub mod nested_list;
+pub mod rga;
+
+#[cfg(feature = "fuzz")]
+use moirai_fuzz::op_config::OpConfig;
+
+#[cfg(feature = "fuzz")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListOpKind {
+    Insert,
+    Delete,
+    DeleteRange,
+}
+
+#[cfg(feature = "fuzz")]
+#[derive(Clone)]
+pub struct ListConfig {
+    pub ops: OpConfig<ListOpKind>,
+    /// Kinds drawn when no range can be deleted, e.g., while the list has fewer than 3 elements
+    pub ops_without_range: OpConfig<ListOpKind>,
+}
+
+#[cfg(feature = "fuzz")]
+impl Default for ListConfig {
+    fn default() -> Self {
+        Self {
+            ops_without_range: OpConfig::Weighted(vec![
+                (ListOpKind::Insert, 3.0),
+                (ListOpKind::Delete, 2.0),
+            ]),
+            ops: OpConfig::Weighted(vec![
+                (ListOpKind::Insert, 7.0),
+                (ListOpKind::Delete, 2.0),
+                (ListOpKind::DeleteRange, 1.0),
+            ]),
+        }
+    }
+}
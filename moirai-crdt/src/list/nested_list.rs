@@ -10,8 +10,12 @@ use moirai_protocol::{
         eval::{BorrowedRead, EvalNested},
         query::{QueryOperation, Read},
     },
-    event::{Event, id::EventId},
-    state::{effect_context::EffectContext, graph_log::GraphLog, log::IsLog},
+    event::{Event, id::EventId, lamport::Lamport},
+    state::{
+        effect_context::EffectContext,
+        graph_log::GraphLog,
+        log::{IsLog, PruneStats},
+    },
     utils::{
         boxer::Boxer,
         intern_str::{InternalizeOp, Interner},
@@ -19,13 +23,17 @@ use moirai_protocol::{
 };
 #[cfg(feature = "fuzz")]
 use rand::RngExt;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    HashMap, HashSet,
     list::eg_walker::{List as SimpleList, ReadAt},
     map::uw_map::{UWMap, UWMapLog},
 };
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
 pub enum NestedList<O> {
     /// Insert a new child CRDT at the given position
@@ -34,12 +42,21 @@ pub enum NestedList<O> {
     Update { pos: usize, op: O },
     /// Delete the child at the given position
     Delete { pos: usize },
+    /// Move the child at `pos` so that it ends up at `new_pos`, keeping its state
+    Move { pos: usize, new_pos: usize },
 }
 
 /// Internal state of a nested list CRDT
 ///
 /// Maintains both the logical ordering of children (via EgWalker) and the
 /// actual child CRDT instances.
+///
+/// A move inserts a new slot for the child instead of deleting the old one,
+/// so that the child keeps its identity. Among the slots of a child, only the
+/// one created by the greatest `(lamport, replica id)` is visible: a move
+/// hides the slots it observed, and concurrent moves are resolved by dot.
+/// A move concurrent with a delete of the same child does not revive it:
+/// a child is hidden as soon as one of its slots is deleted.
 #[derive(Debug, Clone)]
 pub struct NestedListLog<L>
 where
@@ -49,6 +66,16 @@ where
     positions: GraphLog<SimpleList<EventId>>,
     /// Map from EventId to child CRDT instance
     children: UWMapLog<EventId, L>,
+    /// Child of each slot created by a move
+    moved_slots: HashMap<EventId, EventId>,
+    /// Slots created by moves, for each moved child
+    moves: HashMap<EventId, Vec<(Lamport, EventId)>>,
+    /// Slots hidden by a stable move: every future operation observes the move,
+    /// so they no longer need their entries in `moved_slots` and `moves`
+    hidden: HashSet<EventId>,
+    /// Deletes that are not stable yet: until they are, a future operation may
+    /// observe one of the slots they target either alive or deleted
+    unstable_deletes: HashSet<EventId>,
 }
 
 impl<L> NestedListLog<L>
@@ -67,6 +94,122 @@ where
     pub fn children(&self) -> &UWMapLog<EventId, L> {
         &self.children
     }
+
+    /// Children at the visible positions, in order, without reading them.
+    /// Unlike the read value, it includes the children whose value is the default one.
+    pub fn visible_children(&self) -> impl Iterator<Item = &L> {
+        self.visible_slots(self.positions.read_ref().clone(), None)
+            .into_iter()
+            .filter_map(|slot| self.children.get_child(self.child_of(&slot)))
+    }
+
+    /// Child identifier of a slot of the positions list.
+    fn child_of<'a>(&'a self, slot: &'a EventId) -> &'a EventId {
+        self.moved_slots.get(slot).unwrap_or(slot)
+    }
+
+    /// Visible slots among `slots`, as observed at `version`, or by this replica if `None`.
+    /// `slots` are the slots alive at `version`.
+    fn visible_slots(&self, slots: Vec<EventId>, version: Option<&Version>) -> Vec<EventId> {
+        #[allow(clippy::mutable_key_type)]
+        let alive: HashSet<&EventId> = slots.iter().collect();
+        slots
+            .iter()
+            .filter(|slot| !self.hidden.contains(*slot))
+            .filter(|slot| {
+                let child = self.child_of(slot);
+                let winner = self
+                    .moves
+                    .get(child)
+                    .and_then(|moves| {
+                        moves
+                            .iter()
+                            .filter(|(_, id)| version.is_none_or(|v| id.is_predecessor_of(v)))
+                            .max_by(|(l1, id1), (l2, id2)| {
+                                (l1, id1.origin_id()).cmp(&(l2, id2.origin_id()))
+                            })
+                    })
+                    .map_or(child, |(_, id)| id);
+                winner == *slot && !self.is_deleted(child, &alive, version)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Whether one of the slots of `child` observed at `version` was deleted.
+    /// A delete of the child wins over the concurrent moves, whose slots are not deleted.
+    #[allow(clippy::mutable_key_type)]
+    fn is_deleted(
+        &self,
+        child: &EventId,
+        alive: &HashSet<&EventId>,
+        version: Option<&Version>,
+    ) -> bool {
+        !alive.contains(child)
+            || self.moves.get(child).is_some_and(|moves| {
+                moves.iter().any(|(_, id)| {
+                    version.is_none_or(|v| id.is_predecessor_of(v)) && !alive.contains(id)
+                })
+            })
+    }
+
+    /// Keep only the winning slot of each child whose moves are all stable.
+    /// The slots it replaces stay in `positions`, and are hidden for good.
+    ///
+    /// Every operation concurrent with these moves is delivered, so once no delete
+    /// is unstable, a slot alive at `version` stays alive for every future operation,
+    /// and a slot deleted at `version` and not revived since stays deleted. A child
+    /// with a deleted slot is deleted for good: all its slots are hidden. A child
+    /// with a slot revived since `version` keeps its moves.
+    #[allow(clippy::mutable_key_type)]
+    fn prune_stable_moves(&mut self, version: &Version) {
+        self.unstable_deletes
+            .retain(|id| !id.is_predecessor_of(version));
+        if !self.unstable_deletes.is_empty() {
+            return;
+        }
+        let now = self.positions.read_ref().clone();
+        let then = self.positions.eval(ReadAt::new(version));
+        let (alive_now, alive_then): (HashSet<&EventId>, HashSet<&EventId>) =
+            (now.iter().collect(), then.iter().collect());
+        let hidden = &mut self.hidden;
+        let moved_slots = &mut self.moved_slots;
+        self.moves.retain(|child, moves| {
+            if moves.len() == 1 && hidden.contains(child) {
+                return true;
+            }
+            if !moves.iter().all(|(_, id)| id.is_predecessor_of(version)) {
+                return true;
+            }
+            let slots = || moves.iter().map(|(_, id)| id).chain([child]);
+            if slots().any(|slot| alive_now.contains(slot) != alive_then.contains(slot)) {
+                return true;
+            }
+            if slots().any(|slot| !alive_now.contains(slot)) {
+                for (_, slot) in moves.drain(..) {
+                    moved_slots.remove(&slot);
+                    hidden.insert(slot);
+                }
+                hidden.insert(child.clone());
+                return false;
+            }
+            let winner = moves
+                .iter()
+                .max_by(|(l1, id1), (l2, id2)| (l1, id1.origin_id()).cmp(&(l2, id2.origin_id())))
+                .map(|(_, id)| id.clone())
+                .unwrap();
+            moves.retain(|(_, slot)| {
+                if *slot == winner {
+                    return true;
+                }
+                moved_slots.remove(slot);
+                hidden.insert(slot.clone());
+                false
+            });
+            hidden.insert(child.clone());
+            true
+        });
+    }
 }
 
 #[derive(Debug)]
@@ -130,10 +273,13 @@ where
                 );
             }
             NestedList::Delete { pos } => {
-                let positions_at_version = self.positions.eval(ReadAt::new(event.version()));
-                let target = positions_at_version[pos].clone();
-                let list_event = Event::unfold(event.clone(), SimpleList::Delete { pos });
+                let raw = self.positions.eval(ReadAt::new(event.version()));
+                let slots = self.visible_slots(raw.clone(), Some(event.version()));
+                let raw_pos = raw.iter().position(|s| *s == slots[pos]).unwrap();
+                let target = self.child_of(&slots[pos]).clone();
+                let list_event = Event::unfold(event.clone(), SimpleList::Delete { pos: raw_pos });
                 let map_event = Event::unfold(event.clone(), UWMap::Remove(target.clone()));
+                self.unstable_deletes.insert(event.id().clone());
                 ctx.with_list_element(
                     || target.clone(),
                     |ctx| {
@@ -144,9 +290,11 @@ where
                 );
             }
             NestedList::Update { pos, op } => {
-                let positions_at_version = self.positions.eval(ReadAt::new(event.version()));
-                let target = positions_at_version[pos].clone();
-                let list_event = Event::unfold(event.clone(), SimpleList::Update { pos });
+                let raw = self.positions.eval(ReadAt::new(event.version()));
+                let slots = self.visible_slots(raw.clone(), Some(event.version()));
+                let raw_pos = raw.iter().position(|s| *s == slots[pos]).unwrap();
+                let target = self.child_of(&slots[pos]).clone();
+                let list_event = Event::unfold(event.clone(), SimpleList::Update { pos: raw_pos });
                 let map_event = Event::unfold(event.clone(), UWMap::Update(target.clone(), op));
                 ctx.with_list_element(
                     || target.clone(),
@@ -157,21 +305,69 @@ where
                     },
                 );
             }
+            NestedList::Move { pos, new_pos } => {
+                let raw = self.positions.eval(ReadAt::new(event.version()));
+                let mut slots = self.visible_slots(raw.clone(), Some(event.version()));
+                let slot = slots.remove(pos);
+                let target = self.child_of(&slot).clone();
+                // Insert the new slot right before the element it must precede
+                let raw_pos = slots.get(new_pos).map_or(raw.len(), |next| {
+                    raw.iter().position(|s| s == next).unwrap()
+                });
+                let list_event = Event::unfold(
+                    event.clone(),
+                    SimpleList::Insert {
+                        pos: raw_pos,
+                        content: event.id().clone(),
+                    },
+                );
+                self.moved_slots.insert(event.id().clone(), target.clone());
+                self.moves
+                    .entry(target.clone())
+                    .or_default()
+                    .push((*event.lamport(), event.id().clone()));
+                ctx.with_list_element(
+                    || target.clone(),
+                    |ctx| {
+                        ctx.update();
+                        ctx.with_delegated(|ctx| self.positions.effect(list_event, ctx));
+                    },
+                );
+            }
         }
     }
 
     fn stabilize(&mut self, version: &Version) {
         self.children.stabilize(version);
         self.positions.stabilize(version);
+        self.prune_stable_moves(version);
     }
 
     fn redundant_by_parent(&mut self, version: &Version, conservative: bool) {
         self.children.redundant_by_parent(version, conservative);
         self.positions.redundant_by_parent(version, conservative);
+        if self.positions.is_default() {
+            self.moved_slots.clear();
+            self.moves.clear();
+            self.hidden.clear();
+            self.unstable_deletes.clear();
+        }
     }
 
     fn is_default(&self) -> bool {
-        self.positions.is_default() && self.children.is_default()
+        self.positions.is_default() && self.children.is_default() && self.moves.is_empty()
+    }
+
+    fn prune_stats(&self) -> PruneStats {
+        let mut stats = self.positions.prune_stats() + self.children.prune_stats();
+        let num_moves: usize = self.moves.values().map(Vec::len).sum();
+        stats.bytes_estimate += (self.moved_slots.len() * 2
+            + self.moves.len()
+            + self.hidden.len()
+            + self.unstable_deletes.len())
+            * size_of::<EventId>()
+            + num_moves * size_of::<(Lamport, EventId)>();
+        stats
     }
 
     fn prepare(op: Self::Op) -> Self::Op {
@@ -179,7 +375,7 @@ where
     }
 
     fn is_enabled(&self, op: &Self::Op) -> Result<(), Self::Rejection> {
-        let positions = self.positions.read_ref();
+        let positions = self.visible_slots(self.positions.read_ref().clone(), None);
         match op {
             NestedList::Insert { pos, op } => {
                 if *pos > positions.len() {
@@ -199,7 +395,7 @@ where
                         len: positions.len(),
                     });
                 }
-                let target = positions[*pos].clone();
+                let target = self.child_of(&positions[*pos]).clone();
                 let map_op = UWMap::Update(target, op.clone());
                 self.children
                     .is_enabled(&map_op)
@@ -215,6 +411,15 @@ where
                     })
                 }
             }
+            NestedList::Move { pos, new_pos } => {
+                match [*pos, *new_pos].into_iter().find(|p| *p >= positions.len()) {
+                    Some(pos) => Err(NestedListRejection::InvalidPosition {
+                        pos,
+                        len: positions.len(),
+                    }),
+                    None => Ok(()),
+                }
+            }
         }
     }
 }
@@ -229,11 +434,11 @@ where
         _q: Read<<Self as IsLog>::Value>,
     ) -> <Read<<Self as IsLog>::Value> as QueryOperation>::Response {
         let mut list = Vec::new();
-        let positions = self.positions.read_ref();
+        let positions = self.visible_slots(self.positions.read_ref().clone(), None);
         #[allow(clippy::mutable_key_type)]
         let map = self.children.execute_query(Read::new());
-        for eid in positions {
-            if let Some(child) = map.get(eid) {
+        for slot in &positions {
+            if let Some(child) = map.get(self.child_of(slot)) {
                 list.push(child.clone());
             }
         }
@@ -247,39 +452,42 @@ where
     L: OpGeneratorNested + IsLog + EvalNested<Read<<L as IsLog>::Value>>,
     <L as IsLog>::Value: Clone + PartialEq,
 {
-    fn generate(&self, rng: &mut impl rand::Rng) -> Self::Op {
+    type Config = L::Config;
+
+    fn generate(&self, rng: &mut impl rand::Rng, config: &mut Self::Config) -> Self::Op {
         use rand::distr::{Distribution, weighted::WeightedIndex};
 
         enum Choice {
             Insert,
             Update,
             Delete,
+            Move,
         }
-        let dist = WeightedIndex::new([2, 2, 1]).unwrap();
+        let dist = WeightedIndex::new([2, 2, 1, 1]).unwrap();
 
-        let positions = self.positions.read_ref();
+        let positions = self.visible_slots(self.positions.read_ref().clone(), None);
         let choice = if positions.is_empty() {
             &Choice::Insert
         } else {
-            &[Choice::Insert, Choice::Update, Choice::Delete][dist.sample(rng)]
+            &[Choice::Insert, Choice::Update, Choice::Delete, Choice::Move][dist.sample(rng)]
         };
 
         let op = match choice {
             Choice::Insert => {
                 let pos = rng.random_range(0..=positions.len());
                 let default_child = L::new();
-                let op = <L as OpGeneratorNested>::generate(&default_child, rng);
+                let op = <L as OpGeneratorNested>::generate(&default_child, rng, config);
                 NestedList::Insert { pos, op }
             }
             Choice::Update => {
                 let pos = rng.random_range(0..positions.len());
-                let target_id = &positions[pos];
+                let target_id = self.child_of(&positions[pos]);
                 let child = self.children.get_child(target_id);
                 let op = if let Some(c) = child {
-                    <L as OpGeneratorNested>::generate(c, rng)
+                    <L as OpGeneratorNested>::generate(c, rng, config)
                 } else {
                     let default_child = L::new();
-                    <L as OpGeneratorNested>::generate(&default_child, rng)
+                    <L as OpGeneratorNested>::generate(&default_child, rng, config)
                 };
                 NestedList::Update { pos, op }
             }
@@ -287,6 +495,11 @@ where
                 let pos = rng.random_range(0..positions.len());
                 NestedList::Delete { pos }
             }
+            Choice::Move => {
+                let pos = rng.random_range(0..positions.len());
+                let new_pos = rng.random_range(0..positions.len());
+                NestedList::Move { pos, new_pos }
+            }
         };
         assert!(self.is_enabled(&op).is_ok());
         op
@@ -308,6 +521,7 @@ where
                 op: op.internalize(interner),
             },
             NestedList::Delete { pos } => NestedList::Delete { pos },
+            NestedList::Move { pos, new_pos } => NestedList::Move { pos, new_pos },
         }
     }
 }
@@ -580,6 +794,137 @@ mod tests {
         assert_eq!(replica_a.query(Read::new()), replica_b.query(Read::new()));
     }
 
+    #[test]
+    fn concurrent_moves_resolved_by_dot() {
+        let (mut replica_a, mut replica_b, mut replica_c) =
+            triplet_log::<NestedListLog<VecLog<Counter<i32>>>>();
+
+        for i in 0..3 {
+            let event = replica_a
+                .send(NestedList::insert(i, Counter::Inc(i as i32 + 1)))
+                .unwrap();
+            replica_b.receive(event.clone());
+            replica_c.receive(event);
+        }
+
+        let event_a = replica_a.send(NestedList::move_to(0, 2)).unwrap();
+        let event_b = replica_b.send(NestedList::move_to(0, 1)).unwrap();
+        assert_eq!(replica_a.query(Read::new()), vec![2, 3, 1]);
+        assert_eq!(replica_b.query(Read::new()), vec![2, 1, 3]);
+
+        replica_a.receive(event_b.clone());
+        replica_b.receive(event_a.clone());
+        replica_c.receive(event_b);
+        replica_c.receive(event_a);
+
+        // Same Lamport clock: the move of the greatest replica id wins
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), vec![2, 1, 3]);
+        }
+
+        // Positions refer to the visible list
+        let event = replica_c
+            .send(NestedList::update(1, Counter::Inc(10)))
+            .unwrap();
+        replica_a.receive(event.clone());
+        replica_b.receive(event);
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), vec![2, 11, 3]);
+        }
+    }
+
+    #[test]
+    fn concurrent_move_delete() {
+        let (mut replica_a, mut replica_b) = twins_log::<NestedListLog<VecLog<Counter<i32>>>>();
+
+        for i in 0..3 {
+            let event = replica_a
+                .send(NestedList::insert(i, Counter::Inc(i as i32 + 1)))
+                .unwrap();
+            replica_b.receive(event);
+        }
+
+        let event_a = replica_a.send(NestedList::move_to(0, 1)).unwrap();
+        let event_b = replica_b.send(NestedList::delete(0)).unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+
+        // The delete wins: the slot created by the move is not counted
+        for replica in [&replica_a, &replica_b] {
+            assert_eq!(replica.query(Read::new()), vec![2, 3]);
+        }
+        assert!(replica_a.send(NestedList::move_to(2, 0)).is_err());
+
+        // Positions refer to the visible list, and do not revive the deleted child
+        let event = replica_a
+            .send(NestedList::update(1, Counter::Inc(10)))
+            .unwrap();
+        replica_b.receive(event);
+        for replica in [&replica_a, &replica_b] {
+            assert_eq!(replica.query(Read::new()), vec![2, 13]);
+        }
+
+        // Both replicas deliver every event: the moves are pruned
+        let event = replica_b
+            .send(NestedList::update(0, Counter::Inc(1)))
+            .unwrap();
+        replica_a.receive(event);
+        let event = replica_a
+            .send(NestedList::update(0, Counter::Inc(1)))
+            .unwrap();
+        replica_b.receive(event);
+        for replica in [&replica_a, &replica_b] {
+            assert!(replica.state().moves.is_empty());
+            assert_eq!(replica.query(Read::new()), vec![4, 13]);
+        }
+    }
+
+    #[test]
+    fn stable_moves_are_pruned() {
+        let (mut replica_a, mut replica_b) = twins_log::<NestedListLog<VecLog<Counter<i32>>>>();
+
+        for i in 0..3 {
+            let event = replica_a
+                .send(NestedList::insert(i, Counter::Inc(i as i32 + 1)))
+                .unwrap();
+            replica_b.receive(event);
+        }
+        // The first child is moved back and forth
+        for (pos, new_pos) in [(0, 2), (2, 0)] {
+            let event = replica_a.send(NestedList::move_to(pos, new_pos)).unwrap();
+            replica_b.receive(event);
+        }
+
+        // Each replica learns that the other delivered the moves
+        let event = replica_b
+            .send(NestedList::update(0, Counter::Inc(10)))
+            .unwrap();
+        replica_a.receive(event);
+        let event = replica_a
+            .send(NestedList::update(0, Counter::Inc(10)))
+            .unwrap();
+        replica_b.receive(event);
+
+        for replica in [&replica_a, &replica_b] {
+            let state = replica.state();
+            assert_eq!(state.moves.values().map(Vec::len).sum::<usize>(), 1);
+            assert_eq!(state.moved_slots.len(), 1);
+            // The original slot of the moved child and the slot of the first move
+            assert_eq!(state.hidden.len(), 2);
+            assert_eq!(replica.query(Read::new()), vec![21, 2, 3]);
+        }
+
+        // The moved child can still be moved and updated
+        let event = replica_b.send(NestedList::move_to(0, 2)).unwrap();
+        replica_a.receive(event);
+        let event = replica_a
+            .send(NestedList::update(2, Counter::Inc(1)))
+            .unwrap();
+        replica_b.receive(event);
+        assert_eq!(replica_a.query(Read::new()), vec![2, 3, 22]);
+        assert_eq!(replica_a.query(Read::new()), replica_b.query(Read::new()));
+    }
+
     #[test]
     fn map_of_list() {
         let (mut replica_a, mut replica_b) =
@@ -674,6 +1019,7 @@ impl<O> Boxer<NestedList<O>> for NestedList<Box<O>> {
             NestedList::Insert { pos, op } => NestedList::Insert { pos, op: *op },
             NestedList::Update { pos, op } => NestedList::Update { pos, op: *op },
             NestedList::Delete { pos } => NestedList::Delete { pos },
+            NestedList::Move { pos, new_pos } => NestedList::Move { pos, new_pos },
         }
     }
 }
@@ -690,6 +1036,7 @@ impl<O> Boxer<NestedList<Box<O>>> for NestedList<O> {
                 op: Box::new(op),
             },
             NestedList::Delete { pos } => NestedList::Delete { pos },
+            NestedList::Move { pos, new_pos } => NestedList::Move { pos, new_pos },
         }
     }
 }
@@ -702,6 +1049,10 @@ where
         Self {
             positions: GraphLog::default(),
             children: Default::default(),
+            moved_slots: HashMap::default(),
+            moves: HashMap::default(),
+            hidden: HashSet::default(),
+            unstable_deletes: HashSet::default(),
         }
     }
 }
@@ -718,4 +1069,8 @@ impl<O> NestedList<O> {
     pub fn update(pos: usize, op: O) -> Self {
         Self::Update { pos, op }
     }
+
+    pub fn move_to(pos: usize, new_pos: usize) -> Self {
+        Self::Move { pos, new_pos }
+    }
 }
@@ -0,0 +1,1001 @@
+//! Replicated Growable Array.
+//!
+//! Unlike the EgWalker `List`, operations refer to elements by the id of the
+//! event that inserted them instead of by position. Each insertion can therefore
+//! be integrated on its own, and the stable state keeps the already-integrated
+//! sequence: a read only walks that sequence and integrates the (usually few)
+//! unstable operations on top of it, instead of replaying the whole history.
+//!
+//! Deleted elements are kept as tombstones so that concurrent insertions can
+//! still be anchored to them. Once their deletion is stable, tombstones are
+//! collected from the stable sequence, see [`RGAStable::collect_tombstones`].
+
+use std::fmt::{Debug, Display};
+
+#[cfg(feature = "test_utils")]
+use deepsize::DeepSizeOf;
+#[cfg(feature = "fuzz")]
+use moirai_fuzz::{op_generator::OpGenerator, value_generator::ValueGenerator};
+use moirai_protocol::{
+    crdt::{
+        eval::Eval,
+        pure_crdt::PureCRDT,
+        query::{QueryOperation, Read},
+        redundancy::RedundancyRelation,
+    },
+    event::{id::EventId, lamport::Lamport, tagged_op::TaggedOp},
+    state::{
+        po_log::VecLog,
+        stable_state::IsStableState,
+        unstable_state::{CausalReplay, IsUnstableCore, IsUnstablePrune},
+    },
+    utils::intern_str::{InternalizeOp, Interner},
+};
+#[cfg(feature = "fuzz")]
+use rand::{Rng, RngExt};
+
+#[cfg(feature = "fuzz")]
+use crate::list::{ListConfig, ListOpKind};
+use crate::{HashMap, HashSet};
+
+pub type RGAListLog<V> = VecLog<RGAList<V>>;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub enum RGAList<V> {
+    /// Insert `content` right after the element inserted by `after`, or at the head if `None`.
+    Insert { content: V, after: Option<EventId> },
+    /// Delete the element inserted by `target`.
+    Delete { target: EventId },
+}
+
+impl<V> RGAList<V> {
+    pub fn insert(content: V, after: Option<EventId>) -> Self {
+        Self::Insert { content, after }
+    }
+
+    pub fn delete(target: EventId) -> Self {
+        Self::Delete { target }
+    }
+
+    /// Insert at the visible position `pos`, `ids` being the result of a [`ReadIds`] query.
+    pub fn insert_at(ids: &[EventId], pos: usize, content: V) -> Self {
+        Self::Insert {
+            content,
+            after: pos.checked_sub(1).map(|p| ids[p].clone()),
+        }
+    }
+
+    /// Delete the element at the visible position `pos`, `ids` being the result of a [`ReadIds`] query.
+    pub fn delete_at(ids: &[EventId], pos: usize) -> Self {
+        Self::Delete {
+            target: ids[pos].clone(),
+        }
+    }
+}
+
+/// Ids of the visible elements, in list order.
+pub struct ReadIds;
+
+impl QueryOperation for ReadIds {
+    type Response = Vec<EventId>;
+}
+
+#[derive(Clone, Debug)]
+struct RGANode<V> {
+    id: EventId,
+    lamport: Lamport,
+    content: V,
+    deleted: bool,
+    /// Index of the next node in list order.
+    next: Option<usize>,
+}
+
+impl<V> RGANode<V> {
+    /// Siblings with a higher priority are placed closer to their anchor.
+    /// Later insertions come first; concurrent ones with the same Lamport timestamp are ordered by id.
+    fn has_priority_over(&self, other_id: &EventId, other_lamport: &Lamport) -> bool {
+        self.lamport > *other_lamport || (self.lamport == *other_lamport && &self.id < other_id)
+    }
+}
+
+/// An integrated sequence, including tombstones.
+///
+/// Nodes are stored in insertion order and linked in list order,
+/// so that an element is integrated without shifting the others.
+#[derive(Clone, Debug)]
+pub struct RGAStable<V> {
+    nodes: Vec<RGANode<V>>,
+    head: Option<usize>,
+    index: HashMap<EventId, usize>,
+    /// Number of visible elements.
+    len: usize,
+    /// Number of deleted elements.
+    tombstones: usize,
+    /// Number of tombstones above which they are collected.
+    gc_threshold: usize,
+}
+
+impl<V> Default for RGAStable<V> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            head: None,
+            index: HashMap::default(),
+            len: 0,
+            tombstones: 0,
+            gc_threshold: 0,
+        }
+    }
+}
+
+impl<V> RGAStable<V> {
+    /// Number of visible elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn contains(&self, id: &EventId) -> bool {
+        self.index.contains_key(id)
+    }
+
+    /// Remove the tombstones that no operation can refer to anymore, and compact the nodes.
+    ///
+    /// The deletion of a stable tombstone is stable: every operation concurrent with it is already
+    /// delivered, and later operations can neither target the tombstone nor insert after it.
+    /// A tombstone is still kept while an unstable operation refers to it, or while it or the next
+    /// kept node has priority over an unstable insertion: integrating the insertion would then
+    /// not stop at the same place without the tombstone.
+    /// Later insertions are causally after the stable nodes, which never have priority over them.
+    fn collect_tombstones<U>(&mut self, unstable: &U)
+    where
+        U: IsUnstableCore<RGAList<V>>,
+    {
+        #[allow(clippy::mutable_key_type)]
+        let mut referenced: HashSet<&EventId> = HashSet::default();
+        let mut first_insertion: Option<Lamport> = None;
+        for tagged_op in unstable.iter() {
+            match tagged_op.op() {
+                RGAList::Insert { after, .. } => {
+                    referenced.extend(after);
+                    let lamport = *tagged_op.lamport();
+                    first_insertion = Some(first_insertion.map_or(lamport, |l| l.min(lamport)));
+                }
+                RGAList::Delete { target } => {
+                    referenced.insert(target);
+                }
+            }
+        }
+        let stops = |node: &RGANode<V>| first_insertion.is_none_or(|l| node.lamport < l);
+
+        let order: Vec<usize> =
+            std::iter::successors(self.head, |idx| self.nodes[*idx].next).collect();
+        // Kept nodes, in reverse list order
+        let mut kept: Vec<usize> = Vec::with_capacity(order.len());
+        for &idx in order.iter().rev() {
+            let node = &self.nodes[idx];
+            let collectable = node.deleted
+                && !referenced.contains(&node.id)
+                && stops(node)
+                && kept.last().is_none_or(|next| stops(&self.nodes[*next]));
+            if !collectable {
+                kept.push(idx);
+            }
+        }
+        if kept.len() == self.nodes.len() {
+            self.gc_threshold = self.tombstones + self.len;
+            return;
+        }
+
+        let mut is_kept = vec![false; self.nodes.len()];
+        for &idx in &kept {
+            is_kept[idx] = true;
+        }
+        // Kept nodes stay in insertion order
+        let mut new_idx = vec![0; self.nodes.len()];
+        let nodes = std::mem::take(&mut self.nodes);
+        for (idx, node) in nodes.into_iter().enumerate() {
+            if is_kept[idx] {
+                new_idx[idx] = self.nodes.len();
+                self.nodes.push(node);
+            }
+        }
+        let mut next = None;
+        for &idx in &kept {
+            self.nodes[new_idx[idx]].next = next;
+            next = Some(new_idx[idx]);
+        }
+        self.head = next;
+        self.index = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(idx, node)| (node.id.clone(), idx))
+            .collect();
+        self.tombstones = self.nodes.len() - self.len;
+        self.gc_threshold = self.tombstones + self.len;
+    }
+}
+
+/// Sequence in which operations are integrated by [`Sequence::integrate`].
+/// Nodes are referred to by index.
+trait Sequence<V> {
+    fn find(&self, id: &EventId) -> Option<usize>;
+    fn node(&self, idx: usize) -> &RGANode<V>;
+    fn head(&self) -> Option<usize>;
+    fn next(&self, idx: usize) -> Option<usize>;
+    fn is_deleted(&self, idx: usize) -> bool;
+    /// Link `idx` after `prev`, or at the head if `None`.
+    fn link(&mut self, prev: Option<usize>, idx: usize);
+    /// Add a node, not linked yet, and return its index.
+    fn push(&mut self, node: RGANode<V>) -> usize;
+    fn delete(&mut self, idx: usize);
+
+    /// Visible nodes, in list order.
+    fn visible<'s>(&'s self) -> impl Iterator<Item = &'s RGANode<V>>
+    where
+        V: 's,
+    {
+        std::iter::successors(self.head(), |idx| self.next(*idx))
+            .filter(|idx| !self.is_deleted(*idx))
+            .map(|idx| self.node(idx))
+    }
+
+    /// Integrate one operation.
+    /// Operations must be integrated in a causal order, so that anchors and targets are already present.
+    ///
+    /// # Panics
+    /// If the anchor or the target of the operation is missing.
+    fn integrate(&mut self, tagged_op: &TaggedOp<RGAList<V>>)
+    where
+        V: Clone,
+    {
+        let find = |id: &EventId| {
+            self.find(id).unwrap_or_else(|| {
+                panic!(
+                    "Element {id} referred to by operation {} is not integrated",
+                    tagged_op.id()
+                )
+            })
+        };
+        match tagged_op.op() {
+            RGAList::Insert { content, after } => {
+                let mut prev = after.as_ref().map(find);
+                let mut cur = match prev {
+                    None => self.head(),
+                    Some(idx) => self.next(idx),
+                };
+                // Skip the siblings inserted with a higher priority, along with the elements inserted after them,
+                // which all have a greater Lamport timestamp.
+                while let Some(idx) = cur
+                    && self
+                        .node(idx)
+                        .has_priority_over(tagged_op.id(), tagged_op.lamport())
+                {
+                    prev = cur;
+                    cur = self.next(idx);
+                }
+
+                let new_idx = self.push(RGANode {
+                    id: tagged_op.id().clone(),
+                    lamport: *tagged_op.lamport(),
+                    content: content.clone(),
+                    deleted: false,
+                    next: cur,
+                });
+                self.link(prev, new_idx);
+            }
+            RGAList::Delete { target } => {
+                let idx = find(target);
+                if !self.is_deleted(idx) {
+                    self.delete(idx);
+                }
+            }
+        }
+    }
+}
+
+impl<V> Sequence<V> for RGAStable<V> {
+    fn find(&self, id: &EventId) -> Option<usize> {
+        self.index.get(id).copied()
+    }
+
+    fn node(&self, idx: usize) -> &RGANode<V> {
+        &self.nodes[idx]
+    }
+
+    fn head(&self) -> Option<usize> {
+        self.head
+    }
+
+    fn next(&self, idx: usize) -> Option<usize> {
+        self.nodes[idx].next
+    }
+
+    fn is_deleted(&self, idx: usize) -> bool {
+        self.nodes[idx].deleted
+    }
+
+    fn link(&mut self, prev: Option<usize>, idx: usize) {
+        match prev {
+            None => self.head = Some(idx),
+            Some(prev) => self.nodes[prev].next = Some(idx),
+        }
+    }
+
+    fn push(&mut self, node: RGANode<V>) -> usize {
+        let idx = self.nodes.len();
+        self.index.insert(node.id.clone(), idx);
+        self.nodes.push(node);
+        self.len += 1;
+        idx
+    }
+
+    fn delete(&mut self, idx: usize) {
+        self.nodes[idx].deleted = true;
+        self.len -= 1;
+        self.tombstones += 1;
+    }
+}
+
+/// Unstable operations integrated on top of a borrowed stable sequence, which is left untouched.
+/// The nodes it inserts are indexed after the stable ones.
+struct RGAOverlay<'a, V> {
+    stable: &'a RGAStable<V>,
+    nodes: Vec<RGANode<V>>,
+    index: HashMap<EventId, usize>,
+    head: Option<usize>,
+    /// Links of the stable nodes changed by the overlay.
+    next: HashMap<usize, Option<usize>>,
+    /// Stable nodes deleted by the overlay.
+    deleted: HashSet<usize>,
+}
+
+impl<'a, V> RGAOverlay<'a, V> {
+    fn new(stable: &'a RGAStable<V>) -> Self {
+        Self {
+            stable,
+            nodes: Vec::new(),
+            index: HashMap::default(),
+            head: stable.head,
+            next: HashMap::default(),
+            deleted: HashSet::default(),
+        }
+    }
+}
+
+impl<V> Sequence<V> for RGAOverlay<'_, V> {
+    fn find(&self, id: &EventId) -> Option<usize> {
+        self.stable.find(id).or_else(|| self.index.get(id).copied())
+    }
+
+    fn node(&self, idx: usize) -> &RGANode<V> {
+        match idx.checked_sub(self.stable.nodes.len()) {
+            None => &self.stable.nodes[idx],
+            Some(idx) => &self.nodes[idx],
+        }
+    }
+
+    fn head(&self) -> Option<usize> {
+        self.head
+    }
+
+    fn next(&self, idx: usize) -> Option<usize> {
+        match self.next.get(&idx) {
+            Some(next) => *next,
+            None => self.node(idx).next,
+        }
+    }
+
+    fn is_deleted(&self, idx: usize) -> bool {
+        self.node(idx).deleted || self.deleted.contains(&idx)
+    }
+
+    fn link(&mut self, prev: Option<usize>, idx: usize) {
+        match prev {
+            None => self.head = Some(idx),
+            Some(prev) => match prev.checked_sub(self.stable.nodes.len()) {
+                None => {
+                    self.next.insert(prev, Some(idx));
+                }
+                Some(prev) => self.nodes[prev].next = Some(idx),
+            },
+        }
+    }
+
+    fn push(&mut self, node: RGANode<V>) -> usize {
+        let idx = self.stable.nodes.len() + self.nodes.len();
+        self.index.insert(node.id.clone(), idx);
+        self.nodes.push(node);
+        idx
+    }
+
+    fn delete(&mut self, idx: usize) {
+        match idx.checked_sub(self.stable.nodes.len()) {
+            None => {
+                self.deleted.insert(idx);
+            }
+            Some(idx) => self.nodes[idx].deleted = true,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum RGARejection {
+    /// The element referred to by the operation was never inserted, or was collected after its deletion.
+    UnknownElement(EventId),
+    /// The element to delete, or to insert after, is already deleted.
+    AlreadyDeleted(EventId),
+}
+
+impl Display for RGARejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RGARejection::UnknownElement(id) => write!(f, "Element {id} is not in the list"),
+            RGARejection::AlreadyDeleted(id) => write!(f, "Element {id} is already deleted"),
+        }
+    }
+}
+
+impl<V> RGAList<V>
+where
+    V: Clone + Debug,
+{
+    /// Call `f` on the full sequence: the unstable operations integrated on top of the stable one.
+    /// Costs O(stable + u log u) for `u` unstable operations, whatever the number of visible elements.
+    fn with_sequence<U, R>(
+        stable: &RGAStable<V>,
+        unstable: &U,
+        f: impl FnOnce(&RGAOverlay<'_, V>) -> R,
+    ) -> R
+    where
+        U: IsUnstableCore<Self>,
+    {
+        let mut sequence = RGAOverlay::new(stable);
+        // The Lamport order is a linear extension of the causal order
+        let mut ops: Vec<&TaggedOp<Self>> = unstable.iter().collect();
+        ops.sort_by(|a, b| {
+            a.lamport()
+                .cmp(b.lamport())
+                .then_with(|| a.id().cmp(b.id()))
+        });
+        for tagged_op in ops {
+            sequence.integrate(tagged_op);
+        }
+        f(&sequence)
+    }
+}
+
+impl<V> PureCRDT for RGAList<V>
+where
+    V: Debug + Clone,
+{
+    type Value = Vec<V>;
+    type StableState = RGAStable<V>;
+    type Rejection = RGARejection;
+
+    const DISABLE_R_WHEN_R: bool = true;
+    const DISABLE_R_WHEN_NOT_R: bool = true;
+
+    /// Stable operations are integrated into the stable sequence with their id and timestamp.
+    /// Tombstones are collected once there are more of them than elements of the sequence.
+    fn stabilize(
+        tagged_op: &TaggedOp<Self>,
+        stable: &mut Self::StableState,
+        unstable: &mut impl IsUnstablePrune<Self>,
+    ) {
+        stable.integrate(tagged_op);
+        unstable.remove(tagged_op.id());
+        if stable.tombstones > stable.gc_threshold {
+            stable.collect_tombstones(unstable);
+        }
+    }
+
+    fn is_enabled(
+        op: &Self,
+        stable: &Self::StableState,
+        unstable: &impl CausalReplay<Self>,
+    ) -> Result<(), Self::Rejection> {
+        let is_inserted = |id: &EventId| {
+            stable.contains(id)
+                || unstable
+                    .get(id)
+                    .is_some_and(|t| matches!(t.op(), RGAList::Insert { .. }))
+        };
+        // Inserting after a deleted element is rejected, so that its tombstone can be collected.
+        let is_visible = |id: &EventId| {
+            if !is_inserted(id) {
+                return Err(RGARejection::UnknownElement(id.clone()));
+            }
+            // Checked on the operations directly, without integrating the unstable ones
+            let is_deleted = stable.find(id).is_some_and(|idx| stable.is_deleted(idx))
+                || unstable
+                    .iter()
+                    .any(|t| matches!(t.op(), RGAList::Delete { target } if target == id));
+            if is_deleted {
+                Err(RGARejection::AlreadyDeleted(id.clone()))
+            } else {
+                Ok(())
+            }
+        };
+        match op {
+            RGAList::Insert { after: None, .. } => Ok(()),
+            RGAList::Insert {
+                after: Some(anchor),
+                ..
+            } => is_visible(anchor),
+            RGAList::Delete { target } => is_visible(target),
+        }
+    }
+}
+
+impl<V, U> Eval<Read<<Self as PureCRDT>::Value>, U> for RGAList<V>
+where
+    V: Debug + Clone,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        _q: Read<<Self as PureCRDT>::Value>,
+        stable: &Self::StableState,
+        unstable: &U,
+    ) -> Vec<V> {
+        Self::with_sequence(stable, unstable, |sequence| {
+            sequence.visible().map(|n| n.content.clone()).collect()
+        })
+    }
+}
+
+/// Convenience read for character lists.
+impl<U> Eval<Read<String>, U> for RGAList<char>
+where
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(_q: Read<String>, stable: &Self::StableState, unstable: &U) -> String {
+        Self::with_sequence(stable, unstable, |sequence| {
+            sequence.visible().map(|n| n.content).collect()
+        })
+    }
+}
+
+impl<V, U> Eval<ReadIds, U> for RGAList<V>
+where
+    V: Debug + Clone,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(_q: ReadIds, stable: &Self::StableState, unstable: &U) -> Vec<EventId> {
+        Self::with_sequence(stable, unstable, |sequence| {
+            sequence.visible().map(|n| n.id.clone()).collect()
+        })
+    }
+}
+
+impl<V> InternalizeOp for RGAList<V> {
+    fn internalize(self, interner: &Interner) -> Self {
+        match self {
+            RGAList::Insert { content, after } => RGAList::Insert {
+                content,
+                after: after.map(|id| id.internalize(interner)),
+            },
+            RGAList::Delete { target } => RGAList::Delete {
+                target: target.internalize(interner),
+            },
+        }
+    }
+}
+
+impl<V> IsStableState<RGAList<V>> for RGAStable<V>
+where
+    V: Debug + Clone,
+{
+    fn is_default(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    fn apply(&mut self, _value: RGAList<V>) {
+        unreachable!("operations are stabilized with their tag in `RGAList::stabilize`")
+    }
+
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.head = None;
+        self.index.clear();
+        self.len = 0;
+        self.tombstones = 0;
+        self.gc_threshold = 0;
+    }
+
+    fn prune_redundant_ops(
+        &mut self,
+        _rdnt: RedundancyRelation<RGAList<V>>,
+        _tagged_op: &TaggedOp<RGAList<V>>,
+    ) {
+    }
+}
+
+#[cfg(feature = "fuzz")]
+impl<V> OpGenerator for RGAList<V>
+where
+    V: ValueGenerator + Debug + Clone,
+{
+    type Config = ListConfig;
+
+    fn generate(
+        rng: &mut impl Rng,
+        config: &mut Self::Config,
+        stable: &Self::StableState,
+        unstable: &impl CausalReplay<Self>,
+    ) -> Self {
+        let ids = Self::execute_query(ReadIds, stable, unstable);
+
+        let choice = if ids.is_empty() {
+            ListOpKind::Insert
+        } else {
+            config
+                .ops_without_range
+                .sample(rng, &[ListOpKind::Insert, ListOpKind::Delete])
+        };
+
+        match choice {
+            ListOpKind::Insert => {
+                let pos = rng.random_range(0..=ids.len());
+                let content = V::generate(rng, &<V as ValueGenerator>::Config::default());
+                RGAList::insert_at(&ids, pos, content)
+            }
+            ListOpKind::Delete | ListOpKind::DeleteRange => {
+                let pos = rng.random_range(0..ids.len());
+                RGAList::delete_at(&ids, pos)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::{
+        broadcast::{message::EventMessage, tcsb::Tcsb},
+        crdt::{eval::EvalNested, query::Read},
+        replica::{IsReplica, Replica},
+        state::{
+            graph_log::GraphLog,
+            log::{IsLog, IsLogTest},
+        },
+    };
+
+    use super::*;
+    use crate::{
+        list::eg_walker::List,
+        utils::membership::{triplet_log, twins_log},
+    };
+
+    /// Issue positional operations on both list implementations.
+    trait PositionalList: IsLog + EvalNested<Read<String>> + Sized {
+        fn insert(
+            replica: &mut Replica<Self, Tcsb<Self::Op>>,
+            content: char,
+            pos: usize,
+        ) -> EventMessage<Self::Op>;
+        fn delete(
+            replica: &mut Replica<Self, Tcsb<Self::Op>>,
+            pos: usize,
+        ) -> EventMessage<Self::Op>;
+    }
+
+    impl PositionalList for GraphLog<List<char>> {
+        fn insert(
+            replica: &mut Replica<Self, Tcsb<Self::Op>>,
+            content: char,
+            pos: usize,
+        ) -> EventMessage<Self::Op> {
+            replica.send(List::insert(content, pos)).unwrap()
+        }
+
+        fn delete(
+            replica: &mut Replica<Self, Tcsb<Self::Op>>,
+            pos: usize,
+        ) -> EventMessage<Self::Op> {
+            replica.send(List::delete(pos)).unwrap()
+        }
+    }
+
+    impl PositionalList for RGAListLog<char> {
+        fn insert(
+            replica: &mut Replica<Self, Tcsb<Self::Op>>,
+            content: char,
+            pos: usize,
+        ) -> EventMessage<Self::Op> {
+            let ids = replica.query(ReadIds);
+            replica
+                .send(RGAList::insert_at(&ids, pos, content))
+                .unwrap()
+        }
+
+        fn delete(
+            replica: &mut Replica<Self, Tcsb<Self::Op>>,
+            pos: usize,
+        ) -> EventMessage<Self::Op> {
+            let ids = replica.query(ReadIds);
+            replica.send(RGAList::delete_at(&ids, pos)).unwrap()
+        }
+    }
+
+    fn concurrent_insertions<L>() -> (String, String)
+    where
+        L: PositionalList,
+        L::Op: InternalizeOp,
+    {
+        let (mut replica_a, mut replica_b) = twins_log::<L>();
+
+        let e1 = L::insert(&mut replica_a, 'H', 0);
+        replica_b.receive(e1);
+
+        let e2a = L::insert(&mut replica_a, 'e', 1);
+        let e2b = L::insert(&mut replica_b, 'i', 1);
+        replica_b.receive(e2a);
+        replica_a.receive(e2b);
+
+        let e3 = L::insert(&mut replica_a, ' ', 2);
+        replica_b.receive(e3);
+
+        (
+            replica_a.query(Read::<String>::new()),
+            replica_b.query(Read::<String>::new()),
+        )
+    }
+
+    fn concurrent_delete_insert<L>() -> (String, String)
+    where
+        L: PositionalList,
+        L::Op: InternalizeOp,
+    {
+        let (mut replica_a, mut replica_b) = twins_log::<L>();
+
+        let e1 = L::insert(&mut replica_a, 'A', 0);
+        replica_b.receive(e1);
+
+        let edel = L::delete(&mut replica_a, 0);
+        let eins = L::insert(&mut replica_b, 'B', 1);
+        replica_a.receive(eins);
+        replica_b.receive(edel);
+
+        (
+            replica_a.query(Read::<String>::new()),
+            replica_b.query(Read::<String>::new()),
+        )
+    }
+
+    fn in_paper<L>() -> (String, String)
+    where
+        L: PositionalList,
+        L::Op: InternalizeOp,
+    {
+        let (mut replica_a, mut replica_b) = twins_log::<L>();
+
+        let e1 = L::insert(&mut replica_a, 'h', 0);
+        replica_b.receive(e1);
+        let e2 = L::insert(&mut replica_a, 'i', 1);
+        replica_b.receive(e2);
+
+        let e3 = L::insert(&mut replica_a, 'H', 0);
+        let e4 = L::delete(&mut replica_a, 1);
+        let e5 = L::delete(&mut replica_b, 1);
+        let e6 = L::insert(&mut replica_b, 'e', 1);
+        let e7 = L::insert(&mut replica_b, 'y', 2);
+
+        replica_b.receive(e3);
+        replica_b.receive(e4);
+        replica_a.receive(e5);
+        replica_a.receive(e6);
+        replica_a.receive(e7);
+
+        let e8 = L::insert(&mut replica_b, '!', 3);
+        replica_a.receive(e8);
+
+        (
+            replica_a.query(Read::<String>::new()),
+            replica_b.query(Read::<String>::new()),
+        )
+    }
+
+    #[test]
+    fn concurrent_insertions_rga() {
+        let (a, b) = concurrent_insertions::<RGAListLog<char>>();
+        assert_eq!(a, b);
+        assert_eq!(a, "He i");
+        assert_eq!((a, b), concurrent_insertions::<GraphLog<List<char>>>());
+    }
+
+    #[test]
+    fn concurrent_delete_insert_rga() {
+        let (a, b) = concurrent_delete_insert::<RGAListLog<char>>();
+        assert_eq!(a, "B");
+        assert_eq!(b, "B");
+        assert_eq!((a, b), concurrent_delete_insert::<GraphLog<List<char>>>());
+    }
+
+    #[test]
+    fn in_paper_rga() {
+        let (a, b) = in_paper::<RGAListLog<char>>();
+        assert_eq!(a, "Hey!");
+        assert_eq!(b, "Hey!");
+        assert_eq!((a, b), in_paper::<GraphLog<List<char>>>());
+    }
+
+    #[test]
+    fn stable_sequence_rga() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet_log::<RGAListLog<char>>();
+
+        let mut events = Vec::new();
+        for (pos, c) in "abc".chars().enumerate() {
+            let event = RGAListLog::<char>::insert(&mut replica_a, c, pos);
+            replica_b.receive(event.clone());
+            events.push(event);
+        }
+        // Acknowledgments from `b` and `c` make the insertions stable at `a`
+        for event in events {
+            replica_c.receive(event);
+        }
+        let event_b = RGAListLog::<char>::delete(&mut replica_b, 1);
+        let event_c = RGAListLog::<char>::insert(&mut replica_c, 'X', 3);
+        replica_a.receive(event_b.clone());
+        replica_a.receive(event_c.clone());
+        replica_b.receive(event_c);
+        replica_c.receive(event_b);
+
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::<String>::new()), "acX");
+        }
+        // `b` is deleted by an operation that is not stable yet
+        assert_eq!(replica_a.state().stable().len(), 3);
+    }
+
+    #[test]
+    fn collect_stable_tombstones() {
+        let (mut replica_a, mut replica_b) = twins_log::<RGAListLog<char>>();
+
+        for (pos, c) in "abc".chars().enumerate() {
+            let event = RGAListLog::<char>::insert(&mut replica_a, c, pos);
+            replica_b.receive(event);
+        }
+        let event = RGAListLog::<char>::delete(&mut replica_b, 1);
+        replica_a.receive(event);
+
+        // The deletion is stable at `a` only
+        assert_eq!(replica_a.state().stable().nodes.len(), 2);
+        assert_eq!(replica_b.state().stable().nodes.len(), 3);
+
+        let event = RGAListLog::<char>::insert(&mut replica_a, 'd', 2);
+        replica_b.receive(event);
+
+        assert_eq!(replica_b.state().stable().nodes.len(), 3);
+        for replica in [&replica_a, &replica_b] {
+            assert_eq!(replica.query(Read::<String>::new()), "acd");
+        }
+    }
+
+    #[test]
+    fn keep_tombstone_of_unstable_anchor() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet_log::<RGAListLog<char>>();
+
+        let event_a = RGAListLog::<char>::insert(&mut replica_a, 'x', 0);
+        let x = event_a.event().id().clone();
+        replica_b.receive(event_a.clone());
+        replica_c.receive(event_a);
+
+        // `c` inserts after `x` concurrently with its deletion by `a`
+        let delete = RGAListLog::<char>::delete(&mut replica_a, 0);
+        let insert = RGAListLog::<char>::insert(&mut replica_c, 'y', 1);
+        replica_b.receive(delete.clone());
+        replica_c.receive(delete);
+        let ack_b = RGAListLog::<char>::insert(&mut replica_b, 'b', 0);
+        let ack_c = RGAListLog::<char>::insert(&mut replica_c, 'c', 0);
+        replica_a.receive(insert.clone());
+        replica_a.receive(ack_b.clone());
+        replica_a.receive(ack_c.clone());
+
+        // The deletion is stable at `a`, but not the insertion after `x`
+        let stable = replica_a.state().stable();
+        assert!(stable.contains(&x));
+        assert_eq!(stable.len(), 0);
+
+        replica_b.receive(insert);
+        replica_b.receive(ack_c);
+        replica_c.receive(ack_b);
+        let value = replica_a.query(Read::<String>::new());
+        for replica in [&replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::<String>::new()), value);
+        }
+    }
+
+    #[test]
+    fn reject_unknown_element() {
+        let (mut replica_a, replica_b) = twins_log::<RGAListLog<char>>();
+
+        let event = RGAListLog::<char>::insert(&mut replica_a, 'a', 0);
+        let id = event.event().id().clone();
+        replica_a.send(RGAList::delete(id.clone())).unwrap();
+
+        assert!(matches!(
+            replica_a.send(RGAList::delete(id.clone())),
+            Err(RGARejection::AlreadyDeleted(_))
+        ));
+        assert!(matches!(
+            replica_a.send(RGAList::insert('b', Some(id.clone()))),
+            Err(RGARejection::AlreadyDeleted(_))
+        ));
+        let ids = replica_b.query(ReadIds);
+        assert!(ids.is_empty());
+        assert!(matches!(
+            replica_a.send(RGAList::insert(
+                'b',
+                Some(EventId::new(id.idx(), 42, id.resolver().clone()))
+            )),
+            Err(RGARejection::UnknownElement(_))
+        ));
+    }
+
+    /// Compare the read cost of both implementations on a 50k-element document.
+    /// Run with `cargo test --release -p moirai-crdt read_cost -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn read_cost_rga_eg_walker() {
+        use std::time::Instant;
+
+        use moirai_protocol::broadcast::tcsb::IsTcsb;
+
+        const SIZE: usize = 50_000;
+        const READS: u32 = 10;
+
+        /// The document is typed at `a` and delivered to `b`, where it is read.
+        /// Events are generated by `a`'s broadcast layer alone, so that the setup does not
+        /// depend on the cost of reads (and of `is_enabled`) at the origin.
+        fn bench<L>(name: &str, op: impl Fn(usize, Option<EventId>) -> L::Op)
+        where
+            L: IsLog + EvalNested<Read<String>>,
+            L::Op: InternalizeOp,
+        {
+            let mut interner = Interner::new();
+            let (idx, _) = interner.intern("a");
+            interner.intern("b");
+            let mut tcsb_a = Tcsb::<L::Op>::new(idx, interner);
+            let mut replica_b = Replica::<L, Tcsb<L::Op>>::bootstrap("b".to_string(), &["a", "b"]);
+
+            let mut last = None;
+            for i in 0..SIZE {
+                let message = tcsb_a.send(op(i, last));
+                last = Some(message.event().id().clone());
+                replica_b.receive(message);
+            }
+
+            let start = Instant::now();
+            for _ in 0..READS {
+                assert_eq!(replica_b.query(Read::<String>::new()).len(), SIZE);
+            }
+            println!("{name}: {:?} per read", start.elapsed() / READS);
+        }
+
+        bench::<RGAListLog<char>>("rga", |_, last| RGAList::insert('a', last));
+        bench::<GraphLog<List<char>>>("eg_walker", |i, _| List::insert('a', i));
+    }
+
+    #[cfg(feature = "fuzz")]
+    #[test]
+    #[ignore]
+    fn fuzz_rga_list() {
+        use moirai_fuzz::{
+            config::{FuzzerConfig, RunConfig},
+            fuzzer::fuzzer,
+        };
+
+        let run = RunConfig::new(0.5, 8, 1_000, None, None, false, false);
+        let runs = vec![run; 1];
+
+        let config =
+            FuzzerConfig::<RGAListLog<char>>::new("rga_list", runs, true, |a, b| a == b, false);
+
+        fuzzer::<RGAListLog<char>>(config);
+    }
+}
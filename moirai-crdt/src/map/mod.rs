@@ -1,2 +1,3 @@
+pub mod o_map;
 pub mod rw_map;
 pub mod uw_map;
@@ -0,0 +1,291 @@
+use std::{fmt::Debug, hash::Hash};
+
+use moirai_protocol::{
+    clock::version_vector::Version,
+    crdt::{
+        eval::EvalNested,
+        query::{QueryOperation, Read},
+    },
+    event::{Event, id::EventId, lamport::Lamport},
+    state::{
+        effect_context::EffectContext,
+        log::{IsLog, PruneStats},
+    },
+};
+
+use crate::{
+    HashMap,
+    map::uw_map::{UWMap, UWMapLog},
+};
+
+/// Operations of an ordered map, the same as the ones of [`UWMap`].
+pub type OMap<K, O> = UWMap<K, O>;
+
+/// Update-wins map whose entries are read in insertion order.
+///
+/// A key takes the position of its first update that is still alive, i.e., not observed by a
+/// remove or a clear of the key. Updating the value of a key does not move it, while a key
+/// removed and then updated again goes to the end. Concurrent insertions are ordered by Lamport
+/// clock, then by replica id.
+#[derive(Clone, Debug)]
+pub struct OMapLog<K, L>
+where
+    K: Clone + Eq + Hash,
+    L: IsLog,
+{
+    map: UWMapLog<K, L>,
+    /// Updates of each key not observed by a remove. Among the stable ones, only the first is kept.
+    insertions: HashMap<K, Vec<(Lamport, EventId)>>,
+}
+
+impl<K, L> Default for OMapLog<K, L>
+where
+    K: Clone + Debug + Eq + Hash,
+    L: IsLog,
+{
+    fn default() -> Self {
+        Self {
+            map: UWMapLog::default(),
+            insertions: HashMap::default(),
+        }
+    }
+}
+
+impl<K, L> OMapLog<K, L>
+where
+    K: Clone + Debug + Eq + Hash,
+    L: IsLog,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn map(&self) -> &UWMapLog<K, L> {
+        &self.map
+    }
+
+    /// Position of a key: its first alive update.
+    fn position(&self, key: &K) -> Option<(&Lamport, &str)> {
+        self.insertions
+            .get(key)?
+            .iter()
+            .map(|(lamport, id)| (lamport, id.origin_id()))
+            .min()
+    }
+
+    /// Forget the updates observed at `version`.
+    fn forget_observed(insertions: &mut Vec<(Lamport, EventId)>, version: &Version) {
+        insertions.retain(|(_, id)| !id.is_predecessor_of(version));
+    }
+}
+
+impl<K, L> IsLog for OMapLog<K, L>
+where
+    L: IsLog + EvalNested<Read<<L as IsLog>::Value>>,
+    K: Clone + Debug + Hash + Eq,
+    <L as IsLog>::Value: Clone + Default + PartialEq,
+{
+    type Value = Vec<(K, L::Value)>;
+    type Op = OMap<K, L::Op>;
+    type Rejection = L::Rejection;
+
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn effect(&mut self, event: Event<Self::Op>, ctx: &mut EffectContext<'_>) {
+        match event.op() {
+            UWMap::Update(k, _) => {
+                self.insertions
+                    .entry(k.clone())
+                    .or_default()
+                    .push((*event.lamport(), event.id().clone()));
+            }
+            UWMap::Remove(k) => {
+                if let Some(insertions) = self.insertions.get_mut(k) {
+                    Self::forget_observed(insertions, event.version());
+                }
+            }
+            UWMap::Clear => {
+                for insertions in self.insertions.values_mut() {
+                    Self::forget_observed(insertions, event.version());
+                }
+            }
+        }
+        self.insertions
+            .retain(|_, insertions| !insertions.is_empty());
+        self.map.effect(event, ctx);
+    }
+
+    /// A later remove observes all the stable updates of a key at once:
+    /// only the first one can still be the position of the key.
+    fn stabilize(&mut self, version: &Version) {
+        for insertions in self.insertions.values_mut() {
+            let first_stable = insertions
+                .iter()
+                .filter(|(_, id)| id.is_predecessor_of(version))
+                .min_by(|(l1, id1), (l2, id2)| (l1, id1.origin_id()).cmp(&(l2, id2.origin_id())))
+                .cloned();
+            if let Some(first_stable) = first_stable {
+                Self::forget_observed(insertions, version);
+                insertions.push(first_stable);
+            }
+        }
+        self.map.stabilize(version);
+    }
+
+    fn redundant_by_parent(&mut self, version: &Version, conservative: bool) {
+        for insertions in self.insertions.values_mut() {
+            Self::forget_observed(insertions, version);
+        }
+        self.insertions
+            .retain(|_, insertions| !insertions.is_empty());
+        self.map.redundant_by_parent(version, conservative);
+    }
+
+    fn is_default(&self) -> bool {
+        self.map.is_default()
+    }
+
+    fn is_enabled(&self, op: &Self::Op) -> Result<(), Self::Rejection> {
+        self.map.is_enabled(op)
+    }
+
+    fn prune_stats(&self) -> PruneStats {
+        self.map.prune_stats()
+    }
+}
+
+impl<K, L> EvalNested<Read<<Self as IsLog>::Value>> for OMapLog<K, L>
+where
+    L: IsLog + EvalNested<Read<<L as IsLog>::Value>>,
+    K: Clone + Debug + Hash + Eq,
+    <L as IsLog>::Value: Clone + Default + PartialEq,
+{
+    fn execute_query(
+        &self,
+        _q: Read<Self::Value>,
+    ) -> <Read<Self::Value> as QueryOperation>::Response {
+        let mut entries: Vec<_> = self
+            .map
+            .children()
+            .iter()
+            .filter_map(|(k, child)| {
+                let value = child.execute_query(Read::new());
+                if value == <L as IsLog>::Value::default() {
+                    return None;
+                }
+                // A key with a value has an update that no remove observed
+                Some((self.position(k)?, k, value))
+            })
+            .collect();
+        entries.sort_unstable_by_key(|(position, _, _)| *position);
+        entries
+            .into_iter()
+            .map(|(_, k, value)| (k.clone(), value))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::{crdt::query::Read, replica::IsReplica, state::po_log::VecLog};
+
+    use crate::{
+        counter::resettable_counter::Counter,
+        map::o_map::{OMap, OMapLog},
+        utils::membership::twins_log,
+    };
+
+    type Log = OMapLog<String, VecLog<Counter<i32>>>;
+
+    fn keys(entries: Vec<(String, i32)>) -> Vec<String> {
+        entries.into_iter().map(|(k, _)| k).collect()
+    }
+
+    #[test]
+    fn concurrent_inserts_are_ordered() {
+        let (mut replica_a, mut replica_b) = twins_log::<Log>();
+
+        let event_a = replica_a
+            .send(OMap::Update("y".to_string(), Counter::Inc(1)))
+            .unwrap();
+        let event_b = replica_b
+            .send(OMap::Update("x".to_string(), Counter::Inc(2)))
+            .unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+
+        // Same Lamport clock: the insertion of replica a comes first
+        let result = vec![("y".to_string(), 1), ("x".to_string(), 2)];
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+
+        let event = replica_b
+            .send(OMap::Update("a".to_string(), Counter::Inc(3)))
+            .unwrap();
+        replica_a.receive(event);
+
+        assert_eq!(keys(replica_a.query(Read::new())), ["y", "x", "a"]);
+        assert_eq!(replica_a.query(Read::new()), replica_b.query(Read::new()));
+    }
+
+    #[test]
+    fn update_keeps_position() {
+        let (mut replica_a, mut replica_b) = twins_log::<Log>();
+
+        for key in ["x", "y", "z"] {
+            let event = replica_a
+                .send(OMap::Update(key.to_string(), Counter::Inc(1)))
+                .unwrap();
+            replica_b.receive(event);
+        }
+        let event = replica_b
+            .send(OMap::Update("x".to_string(), Counter::Inc(5)))
+            .unwrap();
+        replica_a.receive(event);
+
+        let result = vec![
+            ("x".to_string(), 6),
+            ("y".to_string(), 1),
+            ("z".to_string(), 1),
+        ];
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+
+        // A key removed then updated again goes to the end
+        let event = replica_a.send(OMap::Remove("x".to_string())).unwrap();
+        replica_b.receive(event);
+        let event = replica_a
+            .send(OMap::Update("x".to_string(), Counter::Inc(2)))
+            .unwrap();
+        replica_b.receive(event);
+
+        assert_eq!(keys(replica_a.query(Read::new())), ["y", "z", "x"]);
+        assert_eq!(replica_a.query(Read::new()), replica_b.query(Read::new()));
+    }
+
+    #[test]
+    fn update_concurrent_with_remove_moves_key() {
+        let (mut replica_a, mut replica_b) = twins_log::<Log>();
+
+        for key in ["x", "y"] {
+            let event = replica_a
+                .send(OMap::Update(key.to_string(), Counter::Inc(1)))
+                .unwrap();
+            replica_b.receive(event);
+        }
+
+        let remove = replica_a.send(OMap::Remove("x".to_string())).unwrap();
+        let update = replica_b
+            .send(OMap::Update("x".to_string(), Counter::Inc(4)))
+            .unwrap();
+        replica_a.receive(update);
+        replica_b.receive(remove);
+
+        // The update wins, and gives the key its position
+        let result = vec![("y".to_string(), 1), ("x".to_string(), 4)];
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+    }
+}
@@ -14,7 +14,11 @@ use moirai_protocol::{
         query::{Contains, Get, QueryOperation, Read},
     },
     event::Event,
-    state::{effect_context::EffectContext, log::IsLog, po_log::VecLog},
+    state::{
+        effect_context::EffectContext,
+        log::{IsLog, PruneStats},
+        po_log::VecLog,
+    },
     utils::{
         boxer::Boxer,
         intern_str::{InternalizeOp, Interner},
@@ -179,6 +183,13 @@ where
         self.set.is_default() && self.children.values().all(IsLog::is_default)
     }
 
+    fn prune_stats(&self) -> PruneStats {
+        let mut stats = self.set.prune_stats();
+        stats += self.children.values().map(IsLog::prune_stats).sum();
+        stats.bytes_estimate += self.children.len() * size_of::<K>();
+        stats
+    }
+
     fn is_enabled(&self, op: &Self::Op) -> Result<(), Self::Rejection> {
         match op {
             RWMap::Update(k, v) => {
@@ -245,7 +256,9 @@ where
     K: Clone + Debug + Hash + Eq + PartialEq + ValueGenerator,
     <L as IsLog>::Value: Clone + Default + PartialEq,
 {
-    fn generate(&self, rng: &mut impl Rng) -> Self::Op {
+    type Config = L::Config;
+
+    fn generate(&self, rng: &mut impl Rng, config: &mut Self::Config) -> Self::Op {
         use moirai_fuzz::value_generator::ValueGenerator;
         use rand::distr::{Distribution, weighted::WeightedIndex};
 
@@ -261,9 +274,9 @@ where
         match choice {
             Choice::Update => {
                 let child_op = if let Some(child) = self.children.get(&key) {
-                    child.generate(rng)
+                    child.generate(rng, config)
                 } else {
-                    L::new().generate(rng)
+                    L::new().generate(rng, config)
                 };
                 RWMap::Update(key, child_op)
             }
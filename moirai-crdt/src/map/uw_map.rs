@@ -1,6 +1,8 @@
 use std::{
     fmt::{Debug, Display, Formatter},
     hash::Hash,
+    iter::Sum,
+    marker::PhantomData,
 };
 
 #[cfg(feature = "test_utils")]
@@ -11,10 +13,14 @@ use moirai_protocol::{
     clock::version_vector::Version,
     crdt::{
         eval::EvalNested,
-        query::{Get, QueryOperation, Read},
+        query::{Get, QueryOperation, Read, ReadSorted},
     },
     event::Event,
-    state::{effect_context::EffectContext, log::IsLog},
+    state::{
+        cache::CacheCell,
+        effect_context::EffectContext,
+        log::{IsLog, PruneStats},
+    },
     utils::{
         boxer::Boxer,
         intern_str::{InternalizeOp, Interner},
@@ -22,10 +28,13 @@ use moirai_protocol::{
 };
 #[cfg(feature = "fuzz")]
 use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::HashMap;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
 pub enum UWMap<K, O> {
     Update(K, O),
@@ -33,13 +42,29 @@ pub enum UWMap<K, O> {
     Clear,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct UWMapLog<K, L>
 where
     K: Clone + Eq + Hash,
     L: IsLog,
 {
     children: HashMap<K, L>,
+    /// Result of the last `ReadSorted`, until the next mutation.
+    sorted_cache: CacheCell<Vec<(K, L::Value)>>,
+}
+
+// Not derived, as the derive would require `L::Value: Clone` for the cache.
+impl<K, L> Clone for UWMapLog<K, L>
+where
+    K: Clone + Eq + Hash,
+    L: IsLog + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            children: self.children.clone(),
+            sorted_cache: self.sorted_cache.clone(),
+        }
+    }
 }
 
 impl<K, L> Default for UWMapLog<K, L>
@@ -50,6 +75,7 @@ where
     fn default() -> Self {
         Self {
             children: Default::default(),
+            sorted_cache: CacheCell::new(),
         }
     }
 }
@@ -85,6 +111,59 @@ where
     }
 }
 
+impl<K, O> UWMap<K, O> {
+    /// Nested updates along a path of keys, e.g., `UWMap::update_path(["a", "b"], op)` for
+    /// `UWMap::Update("a", UWMap::Update("b", op))`. Every level of the path has the same key type.
+    ///
+    /// # Panics
+    /// If the path does not have exactly one key per level of nesting down to the leaf operation.
+    pub fn update_path<Leaf, D>(path: impl IntoIterator<Item = impl Into<K>>, leaf: Leaf) -> Self
+    where
+        Self: UpdatePath<K, Leaf, D>,
+    {
+        let mut keys = path.into_iter().map(Into::into);
+        let op = Self::from_path(&mut keys, leaf);
+        assert!(
+            keys.next().is_none(),
+            "The path is longer than the nesting of the map"
+        );
+        op
+    }
+}
+
+/// Depth of a leaf in nested maps: the leaf is a child of this map.
+pub struct Here;
+/// Depth of a leaf in nested maps: the leaf is in the map nested at depth `D` in a child.
+pub struct There<D>(PhantomData<D>);
+
+/// Nested map operations built along a path of keys, down to a leaf operation at depth `D`.
+///
+/// The depth is inferred from the type of the leaf operation.
+pub trait UpdatePath<K, Leaf, D>: Sized {
+    fn from_path(keys: &mut impl Iterator<Item = K>, leaf: Leaf) -> Self;
+}
+
+impl<K, O> UpdatePath<K, O, Here> for UWMap<K, O> {
+    fn from_path(keys: &mut impl Iterator<Item = K>, leaf: O) -> Self {
+        let key = keys
+            .next()
+            .expect("The path is shorter than the nesting of the map");
+        UWMap::Update(key, leaf)
+    }
+}
+
+impl<K, O, Leaf, D> UpdatePath<K, Leaf, There<D>> for UWMap<K, O>
+where
+    O: UpdatePath<K, Leaf, D>,
+{
+    fn from_path(keys: &mut impl Iterator<Item = K>, leaf: Leaf) -> Self {
+        let key = keys
+            .next()
+            .expect("The path is shorter than the nesting of the map");
+        UWMap::Update(key, O::from_path(keys, leaf))
+    }
+}
+
 impl<K, L> IsLog for UWMapLog<K, L>
 where
     L: IsLog + EvalNested<Read<<L as IsLog>::Value>>,
@@ -100,6 +179,7 @@ where
     }
 
     fn effect(&mut self, event: Event<Self::Op>, ctx: &mut EffectContext<'_>) {
+        self.sorted_cache.invalidate();
         match event.op().clone() {
             UWMap::Update(k, v) => {
                 let owns_path = ctx.is_owned();
@@ -152,12 +232,14 @@ where
     }
 
     fn stabilize(&mut self, version: &Version) {
+        self.sorted_cache.invalidate();
         for child in self.children.values_mut() {
             child.stabilize(version);
         }
     }
 
     fn redundant_by_parent(&mut self, version: &Version, conservative: bool) {
+        self.sorted_cache.invalidate();
         for child in self.children.values_mut() {
             child.redundant_by_parent(version, conservative);
         }
@@ -167,6 +249,12 @@ where
         self.children.is_empty()
     }
 
+    fn prune_stats(&self) -> PruneStats {
+        let mut stats: PruneStats = self.children.values().map(IsLog::prune_stats).sum();
+        stats.bytes_estimate += self.children.len() * size_of::<K>();
+        stats
+    }
+
     fn is_enabled(&self, op: &Self::Op) -> Result<(), Self::Rejection> {
         match op {
             UWMap::Update(k, v) => self
@@ -199,6 +287,30 @@ where
     }
 }
 
+impl<K, L> EvalNested<ReadSorted<K, <L as IsLog>::Value>> for UWMapLog<K, L>
+where
+    L: IsLog + EvalNested<Read<<L as IsLog>::Value>>,
+    K: Clone + Debug + Hash + Eq + Ord,
+    <L as IsLog>::Value: Clone + Default + PartialEq,
+{
+    /// Same entries as `Read`, sorted by key. Computed once until the map changes.
+    fn execute_query(
+        &self,
+        _q: ReadSorted<K, L::Value>,
+    ) -> <ReadSorted<K, L::Value> as QueryOperation>::Response {
+        self.sorted_cache
+            .get_or_compute(|| {
+                let mut entries: Vec<(K, L::Value)> =
+                    EvalNested::<Read<<Self as IsLog>::Value>>::execute_query(self, Read::new())
+                        .into_iter()
+                        .collect();
+                entries.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+                entries
+            })
+            .clone()
+    }
+}
+
 impl<'a, K, Q, L> EvalNested<Get<'a, K, Q>> for UWMapLog<K, L>
 where
     Q: QueryOperation,
@@ -215,6 +327,140 @@ where
     }
 }
 
+/// Query on the value at a path of keys in nested maps, the counterpart of [`UWMap::update_path`].
+///
+/// `None` if one of the keys is absent. The depth `D` of the value is inferred from the query.
+///
+/// # Panics
+/// If the path does not have exactly one key per level of nesting down to the queried value.
+pub struct GetPath<'a, K, Q, D> {
+    pub path: &'a [K],
+    pub nested_query: Q,
+    _depth: PhantomData<D>,
+}
+
+impl<'a, K, Q, D> GetPath<'a, K, Q, D> {
+    pub fn new(path: &'a [K], nested_query: Q) -> Self {
+        Self {
+            path,
+            nested_query,
+            _depth: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, Q, D> QueryOperation for GetPath<'a, K, Q, D>
+where
+    Q: QueryOperation,
+{
+    type Response = Option<Q::Response>;
+}
+
+impl<'a, K, Q, L> EvalNested<GetPath<'a, K, Q, Here>> for UWMapLog<K, L>
+where
+    Q: QueryOperation,
+    L: IsLog + EvalNested<Q> + EvalNested<Read<<L as IsLog>::Value>>,
+    K: Clone + Debug + Hash + Eq + PartialEq,
+    <L as IsLog>::Value: Clone + Default + PartialEq,
+{
+    fn execute_query(&self, q: GetPath<'a, K, Q, Here>) -> Option<Q::Response> {
+        let [key] = q.path else {
+            panic!("The path does not match the nesting of the map");
+        };
+        self.children
+            .get(key)
+            .map(|child| child.execute_query(q.nested_query))
+    }
+}
+
+impl<'a, K, Q, L, D> EvalNested<GetPath<'a, K, Q, There<D>>> for UWMapLog<K, L>
+where
+    Q: QueryOperation,
+    L: IsLog + EvalNested<GetPath<'a, K, Q, D>> + EvalNested<Read<<L as IsLog>::Value>>,
+    K: Clone + Debug + Hash + Eq + PartialEq,
+    <L as IsLog>::Value: Clone + Default + PartialEq,
+{
+    fn execute_query(&self, q: GetPath<'a, K, Q, There<D>>) -> Option<Q::Response> {
+        let (key, rest) = q
+            .path
+            .split_first()
+            .expect("The path does not match the nesting of the map");
+        self.children
+            .get(key)
+            .and_then(|child| child.execute_query(GetPath::new(rest, q.nested_query)))
+    }
+}
+
+/// Sum of the values of all the entries, e.g., the total of a map of counters.
+pub struct SumAll<V>(PhantomData<V>);
+
+impl<V> SumAll<V> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<V> Default for SumAll<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> QueryOperation for SumAll<V> {
+    type Response = V;
+}
+
+impl<K, L> EvalNested<SumAll<<L as IsLog>::Value>> for UWMapLog<K, L>
+where
+    L: IsLog + EvalNested<Read<<L as IsLog>::Value>>,
+    K: Clone + Debug + Hash + Eq,
+    <L as IsLog>::Value: Clone + Default + PartialEq + Sum,
+{
+    fn execute_query(&self, _q: SumAll<L::Value>) -> L::Value {
+        self.children
+            .values()
+            .map(|child| child.execute_query(Read::new()))
+            .sum()
+    }
+}
+
+/// The `k` entries with the highest values, highest first.
+/// Entries with the same value are ordered by key, so that every replica returns the same keys.
+pub struct TopK<K, V> {
+    pub k: usize,
+    _entry: PhantomData<(K, V)>,
+}
+
+impl<K, V> TopK<K, V> {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            _entry: PhantomData,
+        }
+    }
+}
+
+impl<K, V> QueryOperation for TopK<K, V> {
+    type Response = Vec<(K, V)>;
+}
+
+impl<K, L> EvalNested<TopK<K, <L as IsLog>::Value>> for UWMapLog<K, L>
+where
+    L: IsLog + EvalNested<Read<<L as IsLog>::Value>>,
+    K: Clone + Debug + Hash + Eq + Ord,
+    <L as IsLog>::Value: Clone + Default + Ord,
+{
+    fn execute_query(&self, q: TopK<K, L::Value>) -> Vec<(K, L::Value)> {
+        let mut entries: Vec<(K, L::Value)> =
+            EvalNested::<Read<<Self as IsLog>::Value>>::execute_query(self, Read::new())
+                .into_iter()
+                .collect();
+        entries.sort_unstable_by(|(k1, v1), (k2, v2)| v2.cmp(v1).then_with(|| k1.cmp(k2)));
+        entries.truncate(q.k);
+        entries
+    }
+}
+
 #[cfg(feature = "fuzz")]
 impl<K, L> OpGeneratorNested for UWMapLog<K, L>
 where
@@ -222,7 +468,9 @@ where
     K: Clone + Debug + Hash + Eq + PartialEq + ValueGenerator,
     <L as IsLog>::Value: Clone + Default + PartialEq,
 {
-    fn generate(&self, rng: &mut impl Rng) -> Self::Op {
+    type Config = L::Config;
+
+    fn generate(&self, rng: &mut impl Rng, config: &mut Self::Config) -> Self::Op {
         use moirai_fuzz::value_generator::ValueGenerator;
         use rand::distr::{Distribution, weighted::WeightedIndex};
 
@@ -238,9 +486,9 @@ where
         match choice {
             Choice::Update => {
                 let child_op = if let Some(child) = self.children.get(&key) {
-                    child.generate(rng)
+                    child.generate(rng, config)
                 } else {
-                    L::new().generate(rng)
+                    L::new().generate(rng, config)
                 };
                 UWMap::Update(key, child_op)
             }
@@ -265,11 +513,17 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+
     use moirai_macros::record;
     use moirai_protocol::{
-        crdt::query::{Contains, Get, Read},
+        crdt::{
+            eval::EvalNested,
+            query::{Contains, Get, QueryOperation, Read, ReadSorted},
+            to_json::ToJson,
+        },
         replica::IsReplica,
-        state::{graph_log::GraphLog, po_log::VecLog},
+        state::{graph_log::GraphLog, log::IsLog, po_log::VecLog},
     };
 
     use crate::{
@@ -279,7 +533,7 @@ mod tests {
             eg_walker::List,
             nested_list::{NestedList, NestedListLog},
         },
-        map::uw_map::{UWMap, UWMapLog},
+        map::uw_map::{GetPath, SumAll, TopK, UWMap, UWMapLog},
         set::aw_set::AWSet,
         utils::membership::{triplet_log, twins_log},
     };
@@ -289,6 +543,24 @@ mod tests {
         second: VecLog<Counter<i32>>,
     });
 
+    thread_local! {
+        static COUNTER_EVALS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Counter read that records how many counter logs were evaluated.
+    struct CountingRead;
+
+    impl QueryOperation for CountingRead {
+        type Response = i32;
+    }
+
+    impl EvalNested<CountingRead> for VecLog<Counter<i32>> {
+        fn execute_query(&self, _q: CountingRead) -> i32 {
+            COUNTER_EVALS.with(|evals| evals.set(evals.get() + 1));
+            EvalNested::<Read<i32>>::execute_query(self, Read::new())
+        }
+    }
+
     #[test]
     fn nested_query() {
         let (mut replica_a, mut _replica_b) = twins_log::<UWMapLog<String, VecLog<AWSet<i32>>>>();
@@ -350,6 +622,88 @@ mod tests {
         );
     }
 
+    #[test]
+    fn read_sorted_is_deterministic() {
+        let (mut replica_a, mut replica_b) = twins_log::<UWMapLog<String, VecLog<Counter<i32>>>>();
+
+        let mut events_a = vec![];
+        let mut events_b = vec![];
+        for (i, key) in ["m", "c", "x", "a", "q"].iter().enumerate() {
+            events_a.push(
+                replica_a
+                    .send(UWMap::Update(key.to_string(), Counter::Inc(i as i32 + 1)))
+                    .unwrap(),
+            );
+            events_b.push(
+                replica_b
+                    .send(UWMap::Update(
+                        key.to_uppercase(),
+                        Counter::Dec(i as i32 + 1),
+                    ))
+                    .unwrap(),
+            );
+        }
+        events_b.push(replica_b.send(UWMap::Remove("c".to_string())).unwrap());
+        for event in events_b {
+            replica_a.receive(event);
+        }
+        for event in events_a {
+            replica_b.receive(event);
+        }
+
+        let sorted_a = replica_a.query(ReadSorted::new());
+        let sorted_b = replica_b.query(ReadSorted::new());
+        assert!(sorted_a.is_sorted_by(|(k1, _), (k2, _)| k1 < k2));
+        assert_eq!(sorted_a.len(), 10);
+        assert_eq!(
+            format!("{sorted_a:?}").as_bytes(),
+            format!("{sorted_b:?}").as_bytes()
+        );
+
+        // The cached result is dropped by the next update
+        let event = replica_a
+            .send(UWMap::Update("b".to_string(), Counter::Inc(1)))
+            .unwrap();
+        replica_b.receive(event);
+        let sorted_a = replica_a.query(ReadSorted::new());
+        assert_eq!(sorted_a.len(), 11);
+        assert_eq!(sorted_a, replica_b.query(ReadSorted::new()));
+    }
+
+    #[test]
+    fn tally_aggregates() {
+        let (mut replica_a, mut replica_b) = twins_log::<UWMapLog<String, VecLog<Counter<i32>>>>();
+
+        let tally = [
+            ("pear", 3),
+            ("fig", 7),
+            ("apple", 3),
+            ("kiwi", 1),
+            ("plum", 7),
+        ];
+        for (key, count) in tally {
+            let event = replica_a
+                .send(UWMap::Update(key.to_string(), Counter::Inc(count)))
+                .unwrap();
+            replica_b.receive(event);
+        }
+        // An increment from b moves apple ahead of pear
+        let event = replica_b
+            .send(UWMap::Update("apple".to_string(), Counter::Inc(1)))
+            .unwrap();
+        replica_a.receive(event);
+
+        for replica in [&replica_a, &replica_b] {
+            assert_eq!(replica.query(SumAll::new()), 22);
+            // fig and plum are tied, fig comes first by key order
+            assert_eq!(
+                replica.query(TopK::new(2)),
+                vec![("fig".to_string(), 7), ("plum".to_string(), 7)]
+            );
+            assert_eq!(replica.query(TopK::new(3))[2], ("apple".to_string(), 4));
+        }
+    }
+
     #[test]
     fn uw_map_duet_counter() {
         let (mut replica_a, mut replica_b) = twins_log::<UWMapLog<String, DuetLog>>();
@@ -402,6 +756,71 @@ mod tests {
         assert_eq!(map, replica_b.query(Read::new()));
     }
 
+    #[test]
+    fn prune_stats_sum_the_children() {
+        let (mut replica_a, _) = twins_log::<UWMapLog<String, DuetLog>>();
+
+        replica_a
+            .send(UWMap::Update("a".to_string(), Duet::First(Counter::Inc(3))))
+            .unwrap();
+        replica_a
+            .send(UWMap::Update(
+                "a".to_string(),
+                Duet::Second(Counter::Dec(7)),
+            ))
+            .unwrap();
+        replica_a
+            .send(UWMap::Update("b".to_string(), Duet::First(Counter::Inc(5))))
+            .unwrap();
+
+        let stats = replica_a.state().prune_stats();
+        assert_eq!(stats.live_events, 3);
+        assert!(stats.bytes_estimate > 0);
+        assert_eq!(replica_a.stats().log_size, 3);
+    }
+
+    #[test]
+    fn duet_to_json() {
+        let (mut replica_a, _) = twins_log::<DuetLog>();
+
+        replica_a.send(Duet::First(Counter::Inc(3))).unwrap();
+        replica_a.send(Duet::Second(Counter::Dec(7))).unwrap();
+        assert_eq!(
+            replica_a.query(Read::new()).to_json(),
+            serde_json::json!({ "first": 3, "second": -7 })
+        );
+    }
+
+    #[test]
+    fn record_field_query() {
+        let (mut replica_a, _) = twins_log::<UWMapLog<String, DuetLog>>();
+
+        replica_a
+            .send(UWMap::Update("a".to_string(), Duet::First(Counter::Inc(3))))
+            .unwrap();
+        replica_a
+            .send(UWMap::Update(
+                "a".to_string(),
+                Duet::Second(Counter::Dec(7)),
+            ))
+            .unwrap();
+
+        let key = "a".to_string();
+        COUNTER_EVALS.with(|evals| evals.set(0));
+        assert_eq!(
+            Some(-7),
+            replica_a.query(Get::new(&key, DuetSecondQuery::new(CountingRead)))
+        );
+        // Only the `second` field was evaluated
+        assert_eq!(COUNTER_EVALS.with(Cell::get), 1);
+
+        assert_eq!(
+            Some(3),
+            replica_a.query(Get::new(&key, DuetFirstQuery::new(Read::<i32>::new())))
+        );
+        assert_eq!(COUNTER_EVALS.with(Cell::get), 1);
+    }
+
     #[test]
     fn uw_map_concurrent_duet_counter() {
         let (mut replica_a, mut replica_b) = twins_log::<UWMapLog<String, DuetLog>>();
@@ -555,6 +974,45 @@ mod tests {
         assert_eq!(replica_c.query(Read::new()), replica_b.query(Read::new()));
     }
 
+    #[test]
+    fn uw_map_paths() {
+        type Nested = UWMapLog<String, UWMapLog<String, UWMapLog<String, VecLog<Counter<i32>>>>>;
+        let (mut replica_a, mut replica_b) = twins_log::<Nested>();
+
+        let event = replica_a
+            .send(UWMap::update_path(["a", "b", "c"], Counter::Inc(3)))
+            .unwrap();
+        replica_b.receive(event);
+        let event = replica_b
+            .send(UWMap::update_path(["a", "b", "c"], Counter::Inc(4)))
+            .unwrap();
+        replica_a.receive(event);
+        let event = replica_b
+            .send(UWMap::update_path(
+                ["a", "x"],
+                UWMap::Update("y".to_string(), Counter::Dec(1)),
+            ))
+            .unwrap();
+        replica_a.receive(event);
+
+        let path = ["a", "b", "c"].map(String::from);
+        assert_eq!(
+            replica_a.query(GetPath::new(&path, Read::<i32>::new())),
+            Some(7)
+        );
+        assert_eq!(replica_a.query(Read::new()), replica_b.query(Read::new()));
+        let path = ["a", "x", "y"].map(String::from);
+        assert_eq!(
+            replica_b.query(GetPath::new(&path, Read::<i32>::new())),
+            Some(-1)
+        );
+        let path = ["a", "z", "c"].map(String::from);
+        assert_eq!(
+            replica_a.query(GetPath::new(&path, Read::<i32>::new())),
+            None
+        );
+    }
+
     #[test]
     fn uw_map_nested_list_duet() {
         let (mut replica_a, mut replica_b, mut replica_c) =
@@ -9,18 +9,22 @@ use std::cmp::Ordering;
 #[cfg(feature = "test_utils")]
 use deepsize::DeepSizeOf;
 use moirai_macros::record;
-use moirai_protocol::state::po_log::VecLog;
+use moirai_protocol::{crdt::to_json::ToJson, state::po_log::VecLog};
 use petgraph::{
     dot::{Config, Dot},
     graph::{DiGraph, NodeIndex},
 };
+use serde_json::Value;
 
 use crate::{
-    HashMap,
+    HashMap, HashSet,
     flag::ew_flag::EWFlag,
     graph::uw_multidigraph::{Content, UWGraphLog},
     map::uw_map::UWMapLog,
-    register::{mv_register::MVRegister, to_register::TORegister},
+    register::{
+        mv_register::{MVRegister, SharedMVRegister},
+        to_register::TORegister,
+    },
 };
 
 #[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
@@ -176,6 +180,43 @@ impl Ord for Multiplicity {
     }
 }
 
+impl ToJson for PrimitiveType {
+    fn to_json(&self) -> Value {
+        Value::String(format!("{self:?}"))
+    }
+}
+
+impl ToJson for TypeRef {
+    fn to_json(&self) -> Value {
+        match self {
+            TypeRef::Primitive(p) => p.to_json(),
+            TypeRef::Class(name) => Value::String(name.clone()),
+        }
+    }
+}
+
+impl ToJson for Visibility {
+    fn to_json(&self) -> Value {
+        Value::String(format!("{self:?}"))
+    }
+}
+
+impl ToJson for RelationType {
+    fn to_json(&self) -> Value {
+        Value::String(format!("{self:?}"))
+    }
+}
+
+/// Rendered as in the diagram export, e.g. `"0..*"`; unspecified is `null`.
+impl ToJson for Multiplicity {
+    fn to_json(&self) -> Value {
+        match self {
+            Multiplicity::Unspecified => Value::Null,
+            m => Value::String(format_mult(m)),
+        }
+    }
+}
+
 record!(Feature {
     typ: VecLog::<MVRegister::<PrimitiveType>>,
     visibility: VecLog::<TORegister::<Visibility>>,
@@ -190,7 +231,7 @@ record!(Operation {
 
 record!(Class {
     is_abstract: VecLog::<EWFlag>,
-    name: VecLog::<MVRegister::<String>>,
+    name: VecLog::<SharedMVRegister::<String>>,
     features: UWMapLog::<String, FeatureLog>,
     operations: UWMapLog::<String, OperationLog>,
 });
@@ -202,7 +243,7 @@ record!(Ends {
 
 record!(Relation {
     ends: EndsLog,
-    label: VecLog::<MVRegister::<String>>,
+    label: VecLog::<SharedMVRegister::<String>>,
     typ: VecLog::<TORegister::<RelationType>>,
 });
 
@@ -378,6 +419,290 @@ fn format_operations(g: &ClassDiagram, operations: &HashMap<String, OperationVal
         .join("\\l")
 }
 
+/// Same diagram as [`export_fancy_class_diagram`], in the Mermaid `classDiagram` syntax.
+///
+/// Mermaid identifies classes by name: concurrent names are joined with `_`,
+/// and a class without a name is named by its id.
+pub fn export_mermaid_class_diagram(graph: &ClassDiagram) -> String {
+    let mut out = String::from("classDiagram\n    direction BT\n");
+    for node in graph.raw_nodes() {
+        let class = &node.weight;
+        out.push_str(&format!("    class {} {{\n", mermaid_name(class)));
+        if class.val.is_abstract {
+            out.push_str("        <<abstract>>\n");
+        }
+        let mut features: Vec<_> = class.val.features.iter().collect();
+        features.sort_unstable_by_key(|(name, _)| *name);
+        for (name, feature) in features {
+            let types: Vec<String> = feature.typ.iter().map(|t| format!("{t:?}")).collect();
+            out.push_str(&format!(
+                "        {}{name}: {}\n",
+                visibility_prefix(&feature.visibility),
+                types.join("/")
+            ));
+        }
+        let mut operations: Vec<_> = class.val.operations.iter().collect();
+        operations.sort_unstable_by_key(|(name, _)| *name);
+        for (name, operation) in operations {
+            let params: Vec<String> = operation
+                .parameters
+                .iter()
+                .map(|(p, t)| {
+                    let types: Vec<String> = t.iter().map(|ty| mermaid_type(graph, ty)).collect();
+                    format!("{p}: {}", types.join("/"))
+                })
+                .collect();
+            let return_types: Vec<String> = operation
+                .return_type
+                .iter()
+                .map(|ty| mermaid_type(graph, ty))
+                .collect();
+            out.push_str(&format!(
+                "        {}{name}({}){} {}\n",
+                visibility_prefix(&operation.visibility),
+                params.join(", "),
+                if operation.is_abstract { "*" } else { "" },
+                return_types.join("/")
+            ));
+        }
+        out.push_str("    }\n");
+    }
+    for edge in graph.raw_edges() {
+        let relation = &edge.weight.val;
+        let arrow = match relation.typ {
+            RelationType::Extends => "<|--",
+            RelationType::Implements => "<|..",
+            RelationType::Composes => "*--",
+            RelationType::Aggregates => "o--",
+            RelationType::Associates => "<--",
+        };
+        let mult = |m: &Multiplicity| match m {
+            Multiplicity::Unspecified => String::new(),
+            m => format!(" \"{}\"", format_mult(m)),
+        };
+        let mut labels: Vec<&String> = relation.label.iter().collect();
+        labels.sort_unstable();
+        let label = if labels.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " : {}",
+                labels
+                    .iter()
+                    .map(|l| l.as_str())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            )
+        };
+        // The arrow points to the target, which Mermaid expects on the left
+        out.push_str(&format!(
+            "    {}{} {arrow}{} {}{label}\n",
+            mermaid_name(&graph[edge.target()]),
+            mult(&relation.ends.target),
+            mult(&relation.ends.source),
+            mermaid_name(&graph[edge.source()]),
+        ));
+    }
+    out
+}
+
+fn mermaid_name(class: &Content<&str, ClassValue>) -> String {
+    let mut names: Vec<&String> = class.val.name.iter().collect();
+    if names.is_empty() {
+        return class.id.to_string();
+    }
+    names.sort_unstable();
+    names
+        .iter()
+        .map(|n| n.as_str())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+fn mermaid_type(g: &ClassDiagram, ty: &TypeRef) -> String {
+    match ty {
+        TypeRef::Primitive(pt) => format!("{pt:?}"),
+        TypeRef::Class(c) => g
+            .raw_nodes()
+            .iter()
+            .find(|n| n.weight.id == c)
+            .map(|n| mermaid_name(&n.weight))
+            .unwrap_or_else(|| c.clone()),
+    }
+}
+
+fn visibility_prefix(visibility: &Visibility) -> &'static str {
+    match visibility {
+        Visibility::Public => "+",
+        Visibility::Private => "-",
+        Visibility::Protected => "#",
+        Visibility::Package => "~",
+    }
+}
+
+/// Change of a class present in both diagrams.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassChange {
+    /// Concurrent names are sorted.
+    Renamed {
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    AbstractChanged(bool),
+    FeatureAdded(String),
+    FeatureRemoved(String),
+    /// The type or the visibility of the feature changed.
+    FeatureChanged(String),
+    OperationAdded(String),
+    OperationRemoved(String),
+    /// The signature, the visibility or the abstractness of the operation changed.
+    OperationChanged(String),
+}
+
+/// Differences between two evaluated class diagrams. Classes and relations are matched by id.
+/// Every list is sorted, so that two diffs of the same diagrams are equal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassDiagramDiff<'a> {
+    pub added_classes: Vec<&'a str>,
+    pub removed_classes: Vec<&'a str>,
+    pub changed_classes: Vec<(&'a str, Vec<ClassChange>)>,
+    pub added_relations: Vec<(&'a str, &'a str, &'a str)>,
+    pub removed_relations: Vec<(&'a str, &'a str, &'a str)>,
+    /// The label, the type or the multiplicities of the relation changed.
+    pub changed_relations: Vec<(&'a str, &'a str, &'a str)>,
+}
+
+impl ClassDiagramDiff<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.added_classes.is_empty()
+            && self.removed_classes.is_empty()
+            && self.changed_classes.is_empty()
+            && self.added_relations.is_empty()
+            && self.removed_relations.is_empty()
+            && self.changed_relations.is_empty()
+    }
+}
+
+pub fn diff_class_diagrams<'a>(
+    old: &ClassDiagram<'a>,
+    new: &ClassDiagram<'a>,
+) -> ClassDiagramDiff<'a> {
+    let old_classes: HashMap<&str, &ClassValue> = old
+        .raw_nodes()
+        .iter()
+        .map(|node| (node.weight.id, &node.weight.val))
+        .collect();
+    let new_classes: HashMap<&str, &ClassValue> = new
+        .raw_nodes()
+        .iter()
+        .map(|node| (node.weight.id, &node.weight.val))
+        .collect();
+    let old_relations: HashMap<(&str, &str, &str), &RelationValue> = old
+        .raw_edges()
+        .iter()
+        .map(|edge| (edge.weight.id, &edge.weight.val))
+        .collect();
+    let new_relations: HashMap<(&str, &str, &str), &RelationValue> = new
+        .raw_edges()
+        .iter()
+        .map(|edge| (edge.weight.id, &edge.weight.val))
+        .collect();
+
+    let mut diff = ClassDiagramDiff::default();
+    for (&id, class) in &new_classes {
+        match old_classes.get(id) {
+            None => diff.added_classes.push(id),
+            Some(old_class) => {
+                let changes = diff_classes(old_class, class);
+                if !changes.is_empty() {
+                    diff.changed_classes.push((id, changes));
+                }
+            }
+        }
+    }
+    diff.removed_classes = old_classes
+        .keys()
+        .filter(|id| !new_classes.contains_key(*id))
+        .copied()
+        .collect();
+    for (&id, relation) in &new_relations {
+        match old_relations.get(&id) {
+            None => diff.added_relations.push(id),
+            Some(old_relation) if old_relation != relation => diff.changed_relations.push(id),
+            Some(_) => {}
+        }
+    }
+    diff.removed_relations = old_relations
+        .keys()
+        .filter(|id| !new_relations.contains_key(*id))
+        .copied()
+        .collect();
+
+    diff.added_classes.sort_unstable();
+    diff.removed_classes.sort_unstable();
+    diff.changed_classes.sort_unstable_by_key(|(id, _)| *id);
+    diff.added_relations.sort_unstable();
+    diff.removed_relations.sort_unstable();
+    diff.changed_relations.sort_unstable();
+    diff
+}
+
+fn diff_classes(old: &ClassValue, new: &ClassValue) -> Vec<ClassChange> {
+    let mut changes = Vec::new();
+    if old.name != new.name {
+        let sorted = |names: &HashSet<String>| {
+            let mut names: Vec<String> = names.iter().cloned().collect();
+            names.sort();
+            names
+        };
+        changes.push(ClassChange::Renamed {
+            from: sorted(&old.name),
+            to: sorted(&new.name),
+        });
+    }
+    if old.is_abstract != new.is_abstract {
+        changes.push(ClassChange::AbstractChanged(new.is_abstract));
+    }
+    changes.extend(diff_members(
+        &old.features,
+        &new.features,
+        ClassChange::FeatureAdded,
+        ClassChange::FeatureRemoved,
+        ClassChange::FeatureChanged,
+    ));
+    changes.extend(diff_members(
+        &old.operations,
+        &new.operations,
+        ClassChange::OperationAdded,
+        ClassChange::OperationRemoved,
+        ClassChange::OperationChanged,
+    ));
+    changes
+}
+
+fn diff_members<V: PartialEq>(
+    old: &HashMap<String, V>,
+    new: &HashMap<String, V>,
+    added: fn(String) -> ClassChange,
+    removed: fn(String) -> ClassChange,
+    changed: fn(String) -> ClassChange,
+) -> Vec<ClassChange> {
+    let mut names: Vec<&String> = old
+        .keys()
+        .chain(new.keys().filter(|k| !old.contains_key(*k)))
+        .collect();
+    names.sort();
+    names
+        .into_iter()
+        .filter_map(|name| match (old.get(name), new.get(name)) {
+            (None, Some(_)) => Some(added(name.clone())),
+            (Some(_), None) => Some(removed(name.clone())),
+            (Some(o), Some(n)) if o != n => Some(changed(name.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -435,21 +760,26 @@ mod tests {
 
     use moirai_protocol::{
         broadcast::tcsb::Tcsb,
-        crdt::query::Read,
+        crdt::{query::Read, to_json::ToJson},
         replica::{IsReplica, Replica},
     };
+    use serde_json::json;
 
     use crate::{
         flag::ew_flag::EWFlag,
         graph::uw_multidigraph::{UWGraph, UWGraphLog},
         map::uw_map::UWMap,
         model::class_diagram::{
-            Class, ClassDiagramCrdt, ClassLog, Ends, Feature, Multiplicity, Operation,
-            PrimitiveType, Relation, RelationLog, RelationType, TypeRef, Visibility,
-            export_fancy_class_diagram,
+            Class, ClassChange, ClassDiagramCrdt, ClassDiagramDiff, ClassLog, Ends, EndsValue,
+            Feature, Multiplicity, Operation, PrimitiveType, Relation, RelationLog, RelationType,
+            RelationValue, TypeRef, Visibility, diff_class_diagrams, export_fancy_class_diagram,
+            export_mermaid_class_diagram,
         },
-        register::{mv_register::MVRegister, to_register::TORegister},
-        utils::membership::twins_log,
+        register::{
+            mv_register::{MVRegister, SharedMVRegister},
+            to_register::TORegister,
+        },
+        utils::{membership::twins_log, set_from_slice},
     };
 
     fn wind_turbine_diagram() -> (
@@ -467,7 +797,7 @@ mod tests {
         // WindTurbine class
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "wt",
-            child: Class::Name(MVRegister::Write("WindTurbine".to_string())),
+            child: Class::Name(SharedMVRegister::write_shared("WindTurbine".to_string())),
         });
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "wt",
@@ -486,7 +816,9 @@ mod tests {
         // EnergyGenerator class
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "eg",
-            child: Class::Name(MVRegister::Write("EnergyGenerator".to_string())),
+            child: Class::Name(SharedMVRegister::write_shared(
+                "EnergyGenerator".to_string(),
+            )),
         });
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "eg",
@@ -516,7 +848,7 @@ mod tests {
         // Rotor class
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "rotor",
-            child: Class::Name(MVRegister::Write("Rotor".to_string())),
+            child: Class::Name(SharedMVRegister::write_shared("Rotor".to_string())),
         });
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "rotor",
@@ -542,7 +874,7 @@ mod tests {
         // Blade class
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "blade",
-            child: Class::Name(MVRegister::Write("Blade".to_string())),
+            child: Class::Name(SharedMVRegister::write_shared("Blade".to_string())),
         });
         let _ = replica_a.send(UWGraph::UpdateArc {
             source: "blade",
@@ -566,7 +898,7 @@ mod tests {
             source: "blade",
             target: "rotor",
             id: "comprises",
-            child: Relation::Label(MVRegister::Write("comprises".to_string())),
+            child: Relation::Label(SharedMVRegister::write_shared("comprises".to_string())),
         });
         let _ = replica_a.send(UWGraph::UpdateArc {
             source: "rotor",
@@ -578,12 +910,12 @@ mod tests {
             source: "rotor",
             target: "wt",
             id: "hasRotor",
-            child: Relation::Label(MVRegister::Write("hasRotor".to_string())),
+            child: Relation::Label(SharedMVRegister::write_shared("hasRotor".to_string())),
         });
         // Tower class
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "tower",
-            child: Class::Name(MVRegister::Write("Tower".to_string())),
+            child: Class::Name(SharedMVRegister::write_shared("Tower".to_string())),
         });
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "tower",
@@ -609,12 +941,12 @@ mod tests {
             source: "tower",
             target: "wt",
             id: "mountedOn",
-            child: Relation::Label(MVRegister::Write("mountedOn".to_string())),
+            child: Relation::Label(SharedMVRegister::write_shared("mountedOn".to_string())),
         });
         // Nacelle class
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "nacelle",
-            child: Class::Name(MVRegister::Write("Nacelle".to_string())),
+            child: Class::Name(SharedMVRegister::write_shared("Nacelle".to_string())),
         });
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "nacelle",
@@ -647,12 +979,12 @@ mod tests {
             source: "nacelle",
             target: "wt",
             id: "hasNacelle",
-            child: Relation::Label(MVRegister::Write("hasNacelle".to_string())),
+            child: Relation::Label(SharedMVRegister::write_shared("hasNacelle".to_string())),
         });
         // EnergyGrid class
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "energy_grid",
-            child: Class::Name(MVRegister::Write("EnergyGrid".to_string())),
+            child: Class::Name(SharedMVRegister::write_shared("EnergyGrid".to_string())),
         });
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "energy_grid",
@@ -678,7 +1010,7 @@ mod tests {
             source: "eg",
             target: "energy_grid",
             id: "feedsInto",
-            child: Relation::Label(MVRegister::Write("feedsInto".to_string())),
+            child: Relation::Label(SharedMVRegister::write_shared("feedsInto".to_string())),
         });
         let _ = replica_a.send(UWGraph::UpdateArc {
             source: "eg",
@@ -702,12 +1034,12 @@ mod tests {
             source: "energy_grid",
             target: "energy_grid",
             id: "connectedTo",
-            child: Relation::Label(MVRegister::Write("connectedTo".to_string())),
+            child: Relation::Label(SharedMVRegister::write_shared("connectedTo".to_string())),
         });
         // Manufacturer class
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "manufacturer",
-            child: Class::Name(MVRegister::Write("Manufacturer".to_string())),
+            child: Class::Name(SharedMVRegister::write_shared("Manufacturer".to_string())),
         });
         let _ = replica_a.send(UWGraph::UpdateVertex {
             id: "manufacturer",
@@ -726,7 +1058,7 @@ mod tests {
             source: "manufacturer",
             target: "wt",
             id: "owns",
-            child: Relation::Label(MVRegister::Write("owns".to_string())),
+            child: Relation::Label(SharedMVRegister::write_shared("owns".to_string())),
         });
         let _ = replica_a.send(UWGraph::UpdateArc {
             source: "manufacturer",
@@ -750,7 +1082,7 @@ mod tests {
             source: "manufacturer",
             target: "wt",
             id: "repairs",
-            child: Relation::Label(MVRegister::Write("repairs".to_string())),
+            child: Relation::Label(SharedMVRegister::write_shared("repairs".to_string())),
         });
         let _ = replica_a.send(UWGraph::UpdateArc {
             source: "manufacturer",
@@ -777,6 +1109,74 @@ mod tests {
         (replica_a, replica_b)
     }
 
+    #[test]
+    fn diff_rename_and_add_feature() {
+        let (mut replica_a, _) = wind_turbine_diagram();
+        let before = replica_a.query(Read::new());
+
+        replica_a
+            .send(UWGraph::UpdateVertex {
+                id: "wt",
+                child: Class::Name(SharedMVRegister::write_shared("WindGenerator".to_string())),
+            })
+            .unwrap();
+        replica_a
+            .send(UWGraph::UpdateVertex {
+                id: "rotor",
+                child: Class::Features(UWMap::Update(
+                    "weight".to_string(),
+                    Feature::Typ(MVRegister::Write(PrimitiveType::Number)),
+                )),
+            })
+            .unwrap();
+        let after = replica_a.query(Read::new());
+
+        assert_eq!(
+            diff_class_diagrams(&before, &after),
+            ClassDiagramDiff {
+                changed_classes: vec![
+                    (
+                        "rotor",
+                        vec![ClassChange::FeatureAdded("weight".to_string())]
+                    ),
+                    (
+                        "wt",
+                        vec![ClassChange::Renamed {
+                            from: vec!["WindTurbine".to_string()],
+                            to: vec!["WindGenerator".to_string()],
+                        }]
+                    ),
+                ],
+                ..Default::default()
+            }
+        );
+        assert!(diff_class_diagrams(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn mermaid_export() {
+        let (replica_a, _) = wind_turbine_diagram();
+        let mermaid = export_mermaid_class_diagram(&replica_a.query(Read::new()));
+        let lines: Vec<&str> = mermaid.lines().map(str::trim).collect();
+
+        assert_eq!(lines[0], "classDiagram");
+        for expected in [
+            "class EnergyGenerator {",
+            "<<abstract>>",
+            "+getEnergyOutput()* WindTurbine",
+            "-maxRpm: Number",
+            "EnergyGenerator <|-- WindTurbine",
+            "Rotor \"3\" *-- \"1\" Blade : comprises",
+            "WindTurbine o-- Rotor : hasRotor",
+            "WindTurbine \"0..*\" <-- \"1\" Manufacturer : owns",
+        ] {
+            assert!(
+                lines.contains(&expected),
+                "missing `{expected}` in\n{mermaid}"
+            );
+        }
+    }
+
     // Conflict resolution tests
 
     // Alice and Bob both concurrently edit the WindTurbine class diagram name
@@ -787,13 +1187,15 @@ mod tests {
         let event_a = replica_a
             .send(UWGraph::UpdateVertex {
                 id: "wt",
-                child: Class::Name(MVRegister::Write("WindGenerator".to_string())),
+                child: Class::Name(SharedMVRegister::write_shared("WindGenerator".to_string())),
             })
             .unwrap();
         let event_b = replica_b
             .send(UWGraph::UpdateVertex {
                 id: "wt",
-                child: Class::Name(MVRegister::Write("WindTurbineGenerator".to_string())),
+                child: Class::Name(SharedMVRegister::write_shared(
+                    "WindTurbineGenerator".to_string(),
+                )),
             })
             .unwrap();
         // Deliver events
@@ -807,6 +1209,51 @@ mod tests {
         println!("Class Diagram B: {}", export_fancy_class_diagram(&eval_b));
     }
 
+    /// Compare the cost of renaming classes with long names, and of reading the diagram.
+    /// Run with `cargo test --release -p moirai-crdt rename_cost -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn rename_cost_class_diagram() {
+        use std::time::Instant;
+
+        const RENAMES: u32 = 20_000;
+
+        let (mut replica_a, mut replica_b) = wind_turbine_diagram();
+        let classes = [
+            "wt",
+            "eg",
+            "rotor",
+            "blade",
+            "tower",
+            "nacelle",
+            "energy_grid",
+        ];
+        let long_name = |i: u32| format!("{i}{}", "WindTurbineGenerator".repeat(50));
+
+        let start = Instant::now();
+        for i in 0..RENAMES {
+            let id = classes[i as usize % classes.len()];
+            let event_a = replica_a
+                .send(UWGraph::UpdateVertex {
+                    id,
+                    child: Class::Name(SharedMVRegister::write_shared(long_name(i))),
+                })
+                .unwrap();
+            let event_b = replica_b
+                .send(UWGraph::UpdateVertex {
+                    id,
+                    child: Class::Name(SharedMVRegister::write_shared(long_name(i + RENAMES))),
+                })
+                .unwrap();
+            replica_a.receive(event_b);
+            replica_b.receive(event_a);
+            replica_a.query(Read::new());
+        }
+        println!("{:?} per rename", start.elapsed() / RENAMES);
+        let (eval_a, eval_b) = (replica_a.query(Read::new()), replica_b.query(Read::new()));
+        assert!(diff_class_diagrams(&eval_a, &eval_b).is_empty());
+    }
+
     /// Alice believes that the WindTurbine class should be removed, while Bob believes it should be renamed to WindGenerator.
     #[test]
     fn concurrent_remove_update_class() {
@@ -824,7 +1271,7 @@ mod tests {
         let event_b = replica_b
             .send(UWGraph::UpdateVertex {
                 id: "wt",
-                child: Class::Name(MVRegister::Write("WindGenerator".to_string())),
+                child: Class::Name(SharedMVRegister::write_shared("WindGenerator".to_string())),
             })
             .unwrap();
 
@@ -932,7 +1379,7 @@ mod tests {
                 id: "operates",
                 source: "energy_grid",
                 target: "manufacturer",
-                child: Relation::Label(MVRegister::Write("operates".to_string())),
+                child: Relation::Label(SharedMVRegister::write_shared("operates".to_string())),
             })
             .unwrap();
 
@@ -1006,7 +1453,7 @@ mod tests {
         let event_a_5 = replica_a
             .send(UWGraph::UpdateVertex {
                 id: "rotor",
-                child: Class::Name(MVRegister::Write("RotorUnit".to_string())),
+                child: Class::Name(SharedMVRegister::write_shared("RotorUnit".to_string())),
             })
             .unwrap();
 
@@ -1037,7 +1484,7 @@ mod tests {
         let event_b_3 = replica_b
             .send(UWGraph::UpdateVertex {
                 id: "rotor",
-                child: Class::Name(MVRegister::Write("RotorSystem".to_string())),
+                child: Class::Name(SharedMVRegister::write_shared("RotorSystem".to_string())),
             })
             .unwrap();
         let event_b_4 = replica_b
@@ -1159,7 +1606,7 @@ mod tests {
                 source: "manufacturer",
                 target: "energy_grid",
                 id: "rel",
-                child: Relation::Label(MVRegister::Write("employs".to_string())),
+                child: Relation::Label(SharedMVRegister::write_shared("employs".to_string())),
             })
             .unwrap();
 
@@ -1198,7 +1645,7 @@ mod tests {
                 source: "manufacturer",
                 target: "energy_grid",
                 id: "rel",
-                child: Relation::Label(MVRegister::Write("operates".to_string())),
+                child: Relation::Label(SharedMVRegister::write_shared("operates".to_string())),
             })
             .unwrap();
 
@@ -1215,4 +1662,24 @@ mod tests {
         let eval_b = replica_b.query(Read::new());
         assert!(vf2::isomorphisms(&eval_a, &eval_b).first().is_some());
     }
+
+    #[test]
+    fn relation_to_json() {
+        let relation = RelationValue {
+            ends: EndsValue {
+                source: Multiplicity::One,
+                target: Multiplicity::ZeroOrMany,
+            },
+            label: set_from_slice(&["operates".to_string()]),
+            typ: RelationType::Aggregates,
+        };
+        assert_eq!(
+            relation.to_json(),
+            json!({
+                "ends": { "source": "1", "target": "0..*" },
+                "label": ["operates"],
+                "typ": "Aggregates",
+            })
+        );
+    }
 }
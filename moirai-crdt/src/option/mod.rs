@@ -9,7 +9,10 @@ use moirai_protocol::{
         query::{QueryOperation, Read},
     },
     event::Event,
-    state::{effect_context::EffectContext, log::IsLog},
+    state::{
+        effect_context::EffectContext,
+        log::{IsLog, PruneStats},
+    },
     utils::intern_str::{InternalizeOp, Interner},
 };
 #[cfg(feature = "fuzz")]
@@ -123,6 +126,12 @@ where
             None => true,
         }
     }
+
+    fn prune_stats(&self) -> PruneStats {
+        self.child
+            .as_ref()
+            .map_or_else(PruneStats::default, IsLog::prune_stats)
+    }
 }
 
 #[cfg(feature = "fuzz")]
@@ -130,16 +139,22 @@ impl<L> OpGeneratorNested for OptionLog<L>
 where
     L: OpGeneratorNested,
 {
-    fn generate(&self, rng: &mut impl rand::Rng) -> Self::Op {
+    type Config = L::Config;
+
+    fn generate(&self, rng: &mut impl rand::Rng, config: &mut Self::Config) -> Self::Op {
         match &self.child {
             Some(child) => {
                 if rng.random_bool(1.0 / 5.0) {
                     Optional::Unset
                 } else {
-                    Optional::Set(child.generate(rng))
+                    Optional::Set(child.generate(rng, config))
                 }
             }
-            None => Optional::Set(<L as OpGeneratorNested>::generate(&L::default(), rng)),
+            None => Optional::Set(<L as OpGeneratorNested>::generate(
+                &L::default(),
+                rng,
+                config,
+            )),
         }
     }
 }
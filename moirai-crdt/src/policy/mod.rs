@@ -1,6 +1,10 @@
 use std::{cmp::Ordering, fmt::Debug};
 
-use moirai_protocol::{crdt::policy::Policy, event::tag::Tag};
+use moirai_protocol::{
+    crdt::policy::Policy,
+    event::{tag::Tag, tagged_op::TaggedOp},
+    state::unstable_state::IsUnstablePrune,
+};
 
 /// # Last-Writer-Wins (LWW)
 ///
@@ -53,6 +57,55 @@ impl PartialOrd for Lww<'_> {
     }
 }
 
+/// Fold operations where the writes, selected by `write`, are resolved by [`LwwPolicy`]:
+/// `f` is called on every other operation, and the value of the winning write is returned.
+pub fn fold_lww<'a, O, V>(
+    tagged_ops: impl Iterator<Item = &'a TaggedOp<O>>,
+    write: impl Fn(&O) -> Option<&V>,
+    mut f: impl FnMut(&O),
+) -> Option<&'a V>
+where
+    O: 'a,
+{
+    let mut winner: Option<(&V, Lww)> = None;
+    for tagged_op in tagged_ops {
+        match write(tagged_op.op()) {
+            Some(v) => {
+                let candidate = Lww(tagged_op.tag());
+                if winner.as_ref().is_none_or(|(_, best)| candidate > *best) {
+                    winner = Some((v, candidate));
+                }
+            }
+            None => f(tagged_op.op()),
+        }
+    }
+    winner.map(|(v, _)| v)
+}
+
+/// Stabilize a write, selected by `write`, resolved by [`LwwPolicy`] among the unstable writes.
+///
+/// Concurrent writes stabilize together: a losing write is dropped, and the value of the winning
+/// one is returned after dropping the other unstable writes, which are all concurrent with it.
+/// Returns `None` for other operations and for writes that are not unstable anymore.
+pub fn stabilize_lww<'a, O, V>(
+    tagged_op: &'a TaggedOp<O>,
+    unstable: &mut impl IsUnstablePrune<O>,
+    write: impl Fn(&O) -> Option<&V>,
+) -> Option<&'a V> {
+    let v = write(tagged_op.op())?;
+    unstable.get(tagged_op.id())?;
+    let candidate = Lww(tagged_op.tag());
+    let loses = unstable
+        .iter()
+        .any(|t| write(t.op()).is_some() && Lww(t.tag()) > candidate);
+    if loses {
+        unstable.remove(tagged_op.id());
+        return None;
+    }
+    unstable.retain(|t| write(t.op()).is_none());
+    Some(v)
+}
+
 /// Fair (https://amturing.acm.org/p558-lamport.pdf)
 /// Use a round-robin policy to break ties.
 /// For example, if C_i(a) = C_j(b) and j < i then we can let a -> b
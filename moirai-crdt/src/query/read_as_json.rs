@@ -1,6 +1,7 @@
 use moirai_protocol::crdt::{
     eval::EvalNested,
     query::{QueryOperation, Read},
+    to_json::ToJson,
 };
 use serde_json::{Map, Number, Value};
 
@@ -29,6 +30,55 @@ impl Default for ReadAsJson {
     }
 }
 
+/// Same as [`ReadAsJson`], except that a conflict is rendered as `{"$conflict": [...]}`
+/// instead of a plain array, so that it can be told apart from a genuine array.
+///
+/// To keep the encoding unambiguous, object keys starting with `$` get an extra `$`:
+/// an object is a conflict if and only if its single key is exactly `$conflict`.
+#[derive(Debug)]
+pub struct ReadAsAnnotatedJson;
+
+/// Key of the object wrapping the values of a conflict in [`ReadAsAnnotatedJson`].
+pub const CONFLICT_KEY: &str = "$conflict";
+
+impl QueryOperation for ReadAsAnnotatedJson {
+    type Response = Value;
+}
+
+impl ReadAsAnnotatedJson {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ReadAsAnnotatedJson {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Number of conflicts in a JSON document, nested ones included.
+///
+/// A conflict counts once, whatever the number of its values.
+#[derive(Debug)]
+pub struct CountConflicts;
+
+impl QueryOperation for CountConflicts {
+    type Response = usize;
+}
+
+impl CountConflicts {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for CountConflicts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl EvalNested<ReadAsJson> for NestedListLog<JsonLog> {
     fn execute_query(&self, _q: ReadAsJson) -> <ReadAsJson as QueryOperation>::Response {
         // let mut list = Vec::new();
@@ -53,6 +103,25 @@ impl EvalNested<ReadAsJson> for UWMapLog<String, JsonLog> {
     }
 }
 
+impl EvalNested<ReadAsAnnotatedJson> for UWMapLog<String, JsonLog> {
+    fn execute_query(
+        &self,
+        _q: ReadAsAnnotatedJson,
+    ) -> <ReadAsAnnotatedJson as QueryOperation>::Response {
+        let mut map: Map<String, Value> = Map::new();
+        for (k, l) in self.children() {
+            let val = l.execute_query(ReadAsAnnotatedJson::new());
+            let key = if k.starts_with('$') {
+                format!("${k}")
+            } else {
+                k.clone()
+            };
+            map.insert(key, val);
+        }
+        Value::Object(map)
+    }
+}
+
 fn variant_rank(v: &Value) -> u8 {
     match v {
         Value::Null => 0,
@@ -64,41 +133,99 @@ fn variant_rank(v: &Value) -> u8 {
     }
 }
 
-impl EvalNested<ReadAsJson> for JsonLog {
-    fn execute_query(&self, _q: ReadAsJson) -> <ReadAsJson as QueryOperation>::Response {
-        fn eval_child(child: &JsonChildValue) -> Value {
-            match child {
-                JsonChildValue::Number(value) => {
-                    Value::Number(Number::from_f64(*value).unwrap())
-                }
-                JsonChildValue::Boolean(value) => Value::Bool(*value),
-                JsonChildValue::String(value) => Value::String(value.iter().collect()),
-                JsonChildValue::Object(map) => {
-                    let mut object = Map::new();
-                    for (key, value) in map {
-                        object.insert(key.clone(), eval_value(value));
-                    }
-                    Value::Object(object)
-                }
-                JsonChildValue::Array(list) => {
-                    Value::Array(list.iter().map(eval_value).collect())
-                }
+/// Evaluate `value`. If `annotated`, conflicts are wrapped as `{"$conflict": [...]}`
+/// and object keys starting with `$` are escaped with an extra `$`.
+fn eval_value(value: &JsonValue, annotated: bool) -> Value {
+    match value {
+        JsonValue::Unset => Value::Null,
+        JsonValue::Value(child) => eval_child(child, annotated),
+        JsonValue::Conflict(children) => {
+            let mut evaluated = children
+                .iter()
+                .map(|child| eval_child(child, annotated))
+                .collect::<Vec<Value>>();
+            evaluated.sort_by_key(variant_rank);
+            if annotated {
+                let mut conflict = Map::new();
+                conflict.insert(CONFLICT_KEY.to_string(), Value::Array(evaluated));
+                Value::Object(conflict)
+            } else {
+                Value::Array(evaluated)
             }
         }
+    }
+}
 
-        fn eval_value(value: &JsonValue) -> Value {
-            match value {
-                JsonValue::Unset => Value::Null,
-                JsonValue::Value(child) => eval_child(child),
-                JsonValue::Conflict(children) => {
-                    let mut evaluated = children.iter().map(eval_child).collect::<Vec<Value>>();
-                    evaluated.sort_by_key(variant_rank);
-                    Value::Array(evaluated)
-                }
+fn eval_child(child: &JsonChildValue, annotated: bool) -> Value {
+    match child {
+        JsonChildValue::Number(value) => Value::Number(Number::from_f64(*value).unwrap()),
+        JsonChildValue::Boolean(value) => Value::Bool(*value),
+        JsonChildValue::String(value) => Value::String(value.iter().collect()),
+        JsonChildValue::Object(map) => {
+            let mut object = Map::new();
+            for (key, value) in map {
+                let key = if annotated && key.starts_with('$') {
+                    format!("${key}")
+                } else {
+                    key.clone()
+                };
+                object.insert(key, eval_value(value, annotated));
             }
+            Value::Object(object)
+        }
+        JsonChildValue::Array(list) => Value::Array(
+            list.iter()
+                .map(|value| eval_value(value, annotated))
+                .collect(),
+        ),
+    }
+}
+
+fn count_conflicts(value: &JsonValue) -> usize {
+    match value {
+        JsonValue::Unset => 0,
+        JsonValue::Value(child) => count_child_conflicts(child),
+        JsonValue::Conflict(children) => {
+            1 + children.iter().map(count_child_conflicts).sum::<usize>()
         }
+    }
+}
+
+fn count_child_conflicts(child: &JsonChildValue) -> usize {
+    match child {
+        JsonChildValue::Object(map) => map.values().map(count_conflicts).sum(),
+        JsonChildValue::Array(list) => list.iter().map(count_conflicts).sum(),
+        _ => 0,
+    }
+}
+
+/// A JSON document renders as [`ReadAsJson`] does, so it can be nested in other values.
+impl ToJson for JsonValue {
+    fn to_json(&self) -> Value {
+        eval_value(self, false)
+    }
+}
+
+impl EvalNested<ReadAsJson> for JsonLog {
+    fn execute_query(&self, _q: ReadAsJson) -> <ReadAsJson as QueryOperation>::Response {
+        let value = <JsonLog as EvalNested<Read<JsonValue>>>::execute_query(self, Read::new());
+        eval_value(&value, false)
+    }
+}
+
+impl EvalNested<ReadAsAnnotatedJson> for JsonLog {
+    fn execute_query(
+        &self,
+        _q: ReadAsAnnotatedJson,
+    ) -> <ReadAsAnnotatedJson as QueryOperation>::Response {
+        let value = <JsonLog as EvalNested<Read<JsonValue>>>::execute_query(self, Read::new());
+        eval_value(&value, true)
+    }
+}
 
+impl EvalNested<CountConflicts> for JsonLog {
+    fn execute_query(&self, _q: CountConflicts) -> <CountConflicts as QueryOperation>::Response {
         let value = <JsonLog as EvalNested<Read<JsonValue>>>::execute_query(self, Read::new());
-        eval_value(&value)
+        count_conflicts(&value)
     }
 }
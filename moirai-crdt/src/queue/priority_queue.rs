@@ -0,0 +1,398 @@
+use std::{
+    cmp::Reverse,
+    fmt::{Debug, Display},
+};
+
+#[cfg(feature = "test_utils")]
+use deepsize::DeepSizeOf;
+#[cfg(feature = "fuzz")]
+use moirai_fuzz::op_generator::OpGenerator;
+use moirai_protocol::{
+    crdt::{
+        eval::Eval,
+        pure_crdt::PureCRDT,
+        query::{QueryOperation, Read},
+        redundancy::RedundancyRelation,
+    },
+    event::{id::EventId, tag::Tag, tagged_op::TaggedOp},
+    state::{
+        po_log::VecLog,
+        stable_state::IsStableState,
+        unstable_state::{CausalReplay, IsUnstableCore, IsUnstablePrune},
+    },
+    utils::intern_str::{InternalizeOp, Interner},
+};
+#[cfg(feature = "fuzz")]
+use rand::{Rng, RngExt};
+
+use crate::HashMap;
+
+pub type PriorityQueueLog<V> = VecLog<PriorityQueue<V>>;
+
+/// Replicated priority queue.
+/// A dequeue refers to the id of the enqueue event it removes,
+/// so concurrent dequeues of the same item remove it only once.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub enum PriorityQueue<V> {
+    Enqueue { item: V, priority: u64 },
+    Dequeue { item_id: EventId },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueItem<V> {
+    pub id: EventId,
+    pub item: V,
+    pub priority: u64,
+}
+
+/// Stable items, indexed by the id of their enqueue event.
+#[derive(Debug, Clone)]
+pub struct PriorityQueueStable<V> {
+    items: HashMap<EventId, (V, u64)>,
+}
+
+impl<V> Default for PriorityQueueStable<V> {
+    fn default() -> Self {
+        Self {
+            items: HashMap::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum QueueRejection {
+    /// The dequeued item is not in the queue.
+    UnknownItem(EventId),
+}
+
+impl Display for QueueRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueueRejection::UnknownItem(id) => write!(f, "Item {id} is not in the queue"),
+        }
+    }
+}
+
+impl<V> PureCRDT for PriorityQueue<V>
+where
+    V: Debug + Clone,
+{
+    /// Remaining items, highest priority first, ties broken by event id.
+    type Value = Vec<QueueItem<V>>;
+    type StableState = PriorityQueueStable<V>;
+    type Rejection = QueueRejection;
+
+    fn redundant_itself<'a>(
+        new_tagged_op: &TaggedOp<Self>,
+        _stable: &Self::StableState,
+        _unstable: impl Iterator<Item = &'a TaggedOp<Self>>,
+    ) -> bool
+    where
+        Self: 'a,
+    {
+        matches!(new_tagged_op.op(), PriorityQueue::Dequeue { .. })
+    }
+
+    fn redundant_by_when_redundant(
+        old_op: &Self,
+        old_tag: Option<&Tag>,
+        _is_conc: bool,
+        new_tagged_op: &TaggedOp<Self>,
+    ) -> bool {
+        match (old_op, new_tagged_op.op()) {
+            (PriorityQueue::Enqueue { .. }, PriorityQueue::Dequeue { item_id }) => {
+                old_tag.is_some_and(|tag| tag.id() == item_id)
+            }
+            (PriorityQueue::Dequeue { .. }, _) => unreachable!(),
+            _ => false,
+        }
+    }
+
+    /// Enqueues are moved to the stable state with their id, so that they can still be dequeued.
+    fn stabilize(
+        tagged_op: &TaggedOp<Self>,
+        stable: &mut Self::StableState,
+        unstable: &mut impl IsUnstablePrune<Self>,
+    ) {
+        if let PriorityQueue::Enqueue { item, priority } = tagged_op.op() {
+            stable
+                .items
+                .insert(tagged_op.id().clone(), (item.clone(), *priority));
+            unstable.remove(tagged_op.id());
+        }
+    }
+
+    fn is_enabled(
+        op: &Self,
+        stable: &Self::StableState,
+        unstable: &impl CausalReplay<Self>,
+    ) -> Result<(), Self::Rejection> {
+        match op {
+            PriorityQueue::Enqueue { .. } => Ok(()),
+            PriorityQueue::Dequeue { item_id } => {
+                if stable.items.contains_key(item_id) || unstable.get(item_id).is_some() {
+                    Ok(())
+                } else {
+                    Err(QueueRejection::UnknownItem(item_id.clone()))
+                }
+            }
+        }
+    }
+}
+
+impl<V, U> Eval<Read<<Self as PureCRDT>::Value>, U> for PriorityQueue<V>
+where
+    V: Debug + Clone,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        _q: Read<<Self as PureCRDT>::Value>,
+        stable: &Self::StableState,
+        unstable: &U,
+    ) -> <Read<<Self as PureCRDT>::Value> as QueryOperation>::Response {
+        let mut items: Vec<QueueItem<V>> = stable
+            .items
+            .iter()
+            .map(|(id, (item, priority))| QueueItem {
+                id: id.clone(),
+                item: item.clone(),
+                priority: *priority,
+            })
+            .collect();
+        for tagged_op in unstable.iter() {
+            if let PriorityQueue::Enqueue { item, priority } = tagged_op.op() {
+                items.push(QueueItem {
+                    id: tagged_op.id().clone(),
+                    item: item.clone(),
+                    priority: *priority,
+                });
+            }
+        }
+        items.sort_by(|a, b| {
+            Reverse(a.priority)
+                .cmp(&Reverse(b.priority))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        items
+    }
+}
+
+impl<V> InternalizeOp for PriorityQueue<V> {
+    fn internalize(self, interner: &Interner) -> Self {
+        match self {
+            PriorityQueue::Dequeue { item_id } => PriorityQueue::Dequeue {
+                item_id: item_id.internalize(interner),
+            },
+            op => op,
+        }
+    }
+}
+
+impl<V> IsStableState<PriorityQueue<V>> for PriorityQueueStable<V>
+where
+    V: Debug + Clone,
+{
+    fn is_default(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    fn apply(&mut self, _value: PriorityQueue<V>) {
+        unreachable!("enqueues are stabilized with their id in `PriorityQueue::stabilize`")
+    }
+
+    fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    fn prune_redundant_ops(
+        &mut self,
+        _rdnt: RedundancyRelation<PriorityQueue<V>>,
+        tagged_op: &TaggedOp<PriorityQueue<V>>,
+    ) {
+        if let PriorityQueue::Dequeue { item_id } = tagged_op.op() {
+            self.items.remove(item_id);
+        }
+    }
+}
+
+#[cfg(feature = "fuzz")]
+impl OpGenerator for PriorityQueue<usize> {
+    type Config = ();
+
+    fn generate(
+        rng: &mut impl Rng,
+        _config: &mut Self::Config,
+        stable: &<Self as PureCRDT>::StableState,
+        unstable: &impl CausalReplay<Self>,
+    ) -> Self {
+        let items = Self::execute_query(Read::new(), stable, unstable);
+        if items.is_empty() || rng.random_bool(0.6) {
+            PriorityQueue::Enqueue {
+                item: rng.random_range(0..100),
+                priority: rng.random_range(0..10),
+            }
+        } else {
+            // Mostly dequeue the top item, as a work queue would
+            let idx = if rng.random_bool(0.8) {
+                0
+            } else {
+                rng.random_range(0..items.len())
+            };
+            PriorityQueue::Dequeue {
+                item_id: items[idx].id.clone(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::{crdt::query::Read, replica::IsReplica};
+
+    use crate::{
+        queue::priority_queue::{PriorityQueue, PriorityQueueLog},
+        utils::membership::{triplet_log, twins_log},
+    };
+
+    #[test]
+    fn priority_queue_order() {
+        let (mut replica_a, mut replica_b) = twins_log::<PriorityQueueLog<&str>>();
+
+        let event = replica_a
+            .send(PriorityQueue::Enqueue {
+                item: "low",
+                priority: 1,
+            })
+            .unwrap();
+        replica_b.receive(event);
+
+        let event = replica_b
+            .send(PriorityQueue::Enqueue {
+                item: "high",
+                priority: 5,
+            })
+            .unwrap();
+        replica_a.receive(event);
+
+        let items = replica_a.query(Read::new());
+        assert_eq!(
+            items.iter().map(|i| i.item).collect::<Vec<_>>(),
+            vec!["high", "low"]
+        );
+        assert_eq!(items, replica_b.query(Read::new()));
+    }
+
+    #[test]
+    fn concurrent_dequeue_same_item() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet_log::<PriorityQueueLog<&str>>();
+
+        let event_1 = replica_a
+            .send(PriorityQueue::Enqueue {
+                item: "top",
+                priority: 10,
+            })
+            .unwrap();
+        let event_2 = replica_a
+            .send(PriorityQueue::Enqueue {
+                item: "next",
+                priority: 5,
+            })
+            .unwrap();
+        replica_b.receive(event_1.clone());
+        replica_b.receive(event_2.clone());
+        replica_c.receive(event_1);
+        replica_c.receive(event_2);
+
+        let top = replica_b.query(Read::new())[0].id.clone();
+        let event_b = replica_b
+            .send(PriorityQueue::Dequeue {
+                item_id: top.clone(),
+            })
+            .unwrap();
+        let event_c = replica_c
+            .send(PriorityQueue::Dequeue { item_id: top })
+            .unwrap();
+
+        replica_a.receive(event_b.clone());
+        replica_a.receive(event_c.clone());
+        replica_b.receive(event_c);
+        replica_c.receive(event_b);
+
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            let items = replica.query(Read::new());
+            assert_eq!(
+                items.iter().map(|i| i.item).collect::<Vec<_>>(),
+                vec!["next"]
+            );
+        }
+    }
+
+    #[test]
+    fn concurrent_dequeue_different_items() {
+        let (mut replica_a, mut replica_b) = twins_log::<PriorityQueueLog<&str>>();
+
+        let event_1 = replica_a
+            .send(PriorityQueue::Enqueue {
+                item: "x",
+                priority: 3,
+            })
+            .unwrap();
+        let event_2 = replica_a
+            .send(PriorityQueue::Enqueue {
+                item: "y",
+                priority: 3,
+            })
+            .unwrap();
+        let event_3 = replica_a
+            .send(PriorityQueue::Enqueue {
+                item: "z",
+                priority: 1,
+            })
+            .unwrap();
+        replica_b.receive(event_1);
+        replica_b.receive(event_2);
+        replica_b.receive(event_3);
+
+        let items = replica_a.query(Read::new());
+        let event_a = replica_a
+            .send(PriorityQueue::Dequeue {
+                item_id: items[0].id.clone(),
+            })
+            .unwrap();
+        let event_b = replica_b
+            .send(PriorityQueue::Dequeue {
+                item_id: items[1].id.clone(),
+            })
+            .unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+
+        let result = replica_a.query(Read::new());
+        assert_eq!(result.iter().map(|i| i.item).collect::<Vec<_>>(), vec!["z"]);
+        assert_eq!(result, replica_b.query(Read::new()));
+    }
+
+    #[cfg(feature = "fuzz")]
+    #[test]
+    #[ignore]
+    fn fuzz_priority_queue() {
+        use moirai_fuzz::{
+            config::{FuzzerConfig, RunConfig},
+            fuzzer::fuzzer,
+        };
+
+        let run = RunConfig::new(0.4, 8, 1_000, None, None, false, false);
+        let runs = vec![run.clone(); 1];
+
+        let config = FuzzerConfig::<PriorityQueueLog<usize>>::new(
+            "priority_queue",
+            runs,
+            true,
+            |a, b| a == b,
+            false,
+        );
+
+        fuzzer::<PriorityQueueLog<usize>>(config);
+    }
+}
@@ -0,0 +1,323 @@
+use std::{convert::Infallible, fmt::Debug};
+
+#[cfg(feature = "test_utils")]
+use deepsize::DeepSizeOf;
+#[cfg(feature = "fuzz")]
+use moirai_fuzz::{op_generator::OpGenerator, value_generator::ValueGenerator};
+#[cfg(feature = "fuzz")]
+use moirai_protocol::state::unstable_state::CausalReplay;
+use moirai_protocol::{
+    crdt::{
+        eval::Eval,
+        pure_crdt::PureCRDT,
+        query::{QueryOperation, Read},
+        redundancy::RedundancyRelation,
+    },
+    event::{lamport::Lamport, tag::Tag, tagged_op::TaggedOp},
+    replica::ReplicaId,
+    state::{
+        stable_state::IsStableState,
+        unstable_state::{IsUnstableCore, IsUnstablePrune},
+    },
+    utils::intern_str::{InternalizeOp, Interner},
+};
+#[cfg(feature = "fuzz")]
+use rand::{Rng, RngExt};
+
+/// Last-writer-wins register.
+/// A write overwrites the writes it causally follows. Among concurrent writes,
+/// the winner has the greatest `(timestamp, lamport, replica id)`,
+/// a write without a wall-clock timestamp losing against any timestamped write.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub enum LWWRegister<V> {
+    Clear,
+    Write { value: V, timestamp: Option<u64> },
+}
+
+impl<V> LWWRegister<V> {
+    pub fn write(value: V) -> Self {
+        LWWRegister::Write {
+            value,
+            timestamp: None,
+        }
+    }
+
+    /// Write `value` at the wall-clock time `timestamp`, e.g., milliseconds since the epoch.
+    pub fn write_at(value: V, timestamp: u64) -> Self {
+        LWWRegister::Write {
+            value,
+            timestamp: Some(timestamp),
+        }
+    }
+}
+
+type Priority<'a> = (Option<u64>, Lamport, &'a ReplicaId);
+
+fn priority(timestamp: Option<u64>, tag: &Tag) -> Priority<'_> {
+    (timestamp, *tag.lamport(), tag.id().origin_id())
+}
+
+/// The winning stable write, with its tag to compare it against concurrent unstable writes.
+#[derive(Debug, Clone)]
+pub struct LWWStable<V> {
+    winner: Option<(V, Option<u64>, Tag)>,
+}
+
+impl<V> Default for LWWStable<V> {
+    fn default() -> Self {
+        Self { winner: None }
+    }
+}
+
+impl<V> PureCRDT for LWWRegister<V>
+where
+    V: Debug + Default + Clone,
+{
+    type Value = V;
+    type StableState = LWWStable<V>;
+    type Rejection = Infallible;
+
+    fn redundant_itself<'a>(
+        new_tagged_op: &TaggedOp<Self>,
+        _stable: &Self::StableState,
+        _unstable: impl Iterator<Item = &'a TaggedOp<Self>>,
+    ) -> bool
+    where
+        Self: 'a,
+    {
+        matches!(new_tagged_op.op(), LWWRegister::Clear)
+    }
+
+    fn redundant_by_when_redundant(
+        _old_op: &Self,
+        _old_tag: Option<&Tag>,
+        is_conc: bool,
+        _new_tagged_op: &TaggedOp<Self>,
+    ) -> bool {
+        !is_conc
+    }
+
+    fn redundant_by_when_not_redundant(
+        _old_op: &Self,
+        _old_tag: Option<&Tag>,
+        is_conc: bool,
+        _new_tagged_op: &TaggedOp<Self>,
+    ) -> bool {
+        !is_conc
+    }
+
+    /// Concurrent writes may stabilize together: only the winner is kept.
+    fn stabilize(
+        tagged_op: &TaggedOp<Self>,
+        stable: &mut Self::StableState,
+        unstable: &mut impl IsUnstablePrune<Self>,
+    ) {
+        if let LWWRegister::Write { value, timestamp } = tagged_op.op() {
+            let wins = stable.winner.as_ref().is_none_or(|(_, ts, tag)| {
+                priority(*timestamp, tagged_op.tag()) > priority(*ts, tag)
+            });
+            if wins {
+                stable.winner = Some((value.clone(), *timestamp, tagged_op.tag().clone()));
+            }
+            unstable.remove(tagged_op.id());
+        }
+    }
+}
+
+impl<V, U> Eval<Read<<Self as PureCRDT>::Value>, U> for LWWRegister<V>
+where
+    V: Debug + Default + Clone,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        _q: Read<<Self as PureCRDT>::Value>,
+        stable: &<LWWRegister<V> as PureCRDT>::StableState,
+        unstable: &U,
+    ) -> <Read<<Self as PureCRDT>::Value> as QueryOperation>::Response {
+        let mut winner = stable
+            .winner
+            .as_ref()
+            .map(|(value, ts, tag)| (value, priority(*ts, tag)));
+        for tagged_op in unstable.iter() {
+            if let LWWRegister::Write { value, timestamp } = tagged_op.op() {
+                let candidate = priority(*timestamp, tagged_op.tag());
+                if winner.as_ref().is_none_or(|(_, best)| candidate > *best) {
+                    winner = Some((value, candidate));
+                }
+            }
+        }
+        winner.map(|(value, _)| value.clone()).unwrap_or_default()
+    }
+}
+
+impl<V> IsStableState<LWWRegister<V>> for LWWStable<V>
+where
+    V: Debug + Default + Clone,
+{
+    fn is_default(&self) -> bool {
+        self.winner.is_none()
+    }
+
+    fn apply(&mut self, _value: LWWRegister<V>) {
+        unreachable!("writes are stabilized with their tag in `LWWRegister::stabilize`")
+    }
+
+    fn clear(&mut self) {
+        self.winner = None;
+    }
+
+    fn prune_redundant_ops(
+        &mut self,
+        _rdnt: RedundancyRelation<LWWRegister<V>>,
+        _tagged_op: &TaggedOp<LWWRegister<V>>,
+    ) {
+        // Every new operation causally follows the stable write.
+        self.winner = None;
+    }
+}
+
+#[cfg(feature = "fuzz")]
+impl<V> OpGenerator for LWWRegister<V>
+where
+    V: Debug + Default + Clone + ValueGenerator,
+{
+    type Config = ();
+
+    /// Timestamps are drawn from a small range, so that concurrent writes often tie on them.
+    fn generate(
+        rng: &mut impl Rng,
+        _config: &mut Self::Config,
+        _stable: &<Self as PureCRDT>::StableState,
+        _unstable: &impl CausalReplay<Self>,
+    ) -> Self {
+        enum Choice {
+            WriteAt,
+            Write,
+            Clear,
+        }
+        let choice = rand::seq::IteratorRandom::choose(
+            [
+                Choice::WriteAt,
+                Choice::WriteAt,
+                Choice::Write,
+                Choice::Clear,
+            ]
+            .iter(),
+            rng,
+        )
+        .unwrap();
+        let value = V::generate(rng, &<V as ValueGenerator>::Config::default());
+        match choice {
+            Choice::WriteAt => LWWRegister::write_at(value, rng.random_range(0..8)),
+            Choice::Write => LWWRegister::write(value),
+            Choice::Clear => LWWRegister::Clear,
+        }
+    }
+}
+
+impl<V> InternalizeOp for LWWRegister<V> {
+    fn internalize(self, _interner: &Interner) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::{crdt::query::Read, replica::IsReplica};
+
+    use crate::{
+        register::lww_register::LWWRegister,
+        utils::membership::{triplet, twins},
+    };
+
+    #[test]
+    fn causal_write_overwrites() {
+        let (mut replica_a, mut replica_b) = twins::<LWWRegister<&str>>();
+
+        let event = replica_a.send(LWWRegister::write_at("a", 200)).unwrap();
+        replica_b.receive(event);
+
+        // Causally after, even with an earlier wall-clock time
+        let event = replica_b.send(LWWRegister::write_at("b", 100)).unwrap();
+        replica_a.receive(event);
+
+        assert_eq!(replica_a.query(Read::new()), "b");
+        assert_eq!(replica_b.query(Read::new()), "b");
+    }
+
+    #[test]
+    fn later_timestamp_wins_over_higher_lamport() {
+        let (mut replica_a, mut replica_b) = twins::<LWWRegister<&str>>();
+
+        // Replica a is busy: its next write has a high Lamport clock but an early timestamp
+        let mut events_a: Vec<_> = (0..5)
+            .map(|i| replica_a.send(LWWRegister::write_at("busy", i)).unwrap())
+            .collect();
+        events_a.push(replica_a.send(LWWRegister::write_at("early", 10)).unwrap());
+        // Replica b is slow: its first write has Lamport 1 but a later timestamp
+        let event_b = replica_b.send(LWWRegister::write_at("late", 20)).unwrap();
+        assert!(events_a.last().unwrap().event().lamport() > event_b.event().lamport());
+
+        replica_a.receive(event_b);
+        for event in events_a {
+            replica_b.receive(event);
+        }
+
+        assert_eq!(replica_a.query(Read::new()), "late");
+        assert_eq!(replica_b.query(Read::new()), "late");
+    }
+
+    #[test]
+    fn same_timestamp_falls_back_to_lamport_and_replica() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet::<LWWRegister<&str>>();
+
+        let event_a = replica_a.send(LWWRegister::write_at("a", 10)).unwrap();
+        let event_b = replica_b.send(LWWRegister::write_at("b", 10)).unwrap();
+        replica_c.receive(event_a.clone());
+        replica_c.receive(event_b.clone());
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+
+        // Same timestamp and Lamport clock: the greatest replica id wins
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), "b");
+        }
+
+        let event_c = replica_c.send(LWWRegister::write("c")).unwrap();
+        let event_a = replica_a.send(LWWRegister::write_at("a", 10)).unwrap();
+        replica_c.receive(event_a.clone());
+        replica_b.receive(event_a);
+        replica_a.receive(event_c.clone());
+        replica_b.receive(event_c);
+
+        // A write without timestamp loses against any timestamped one
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), "a");
+        }
+    }
+
+    #[cfg(feature = "fuzz")]
+    #[test]
+    #[ignore]
+    fn fuzz_lww_register() {
+        use moirai_fuzz::{
+            config::{FuzzerConfig, RunConfig},
+            fuzzer::fuzzer,
+        };
+        use moirai_protocol::state::po_log::VecLog;
+
+        let run = RunConfig::new(0.4, 8, 1_000, None, None, false, false);
+        let runs = vec![run.clone(); 1];
+
+        let config = FuzzerConfig::<VecLog<LWWRegister<i32>>>::new(
+            "lww_register",
+            runs,
+            true,
+            |a, b| a == b,
+            false,
+        );
+
+        fuzzer::<VecLog<LWWRegister<i32>>>(config);
+    }
+}
@@ -1,4 +1,6 @@
+pub mod lww_register;
 pub mod mv_register;
+pub mod pn_register;
 pub mod po_register;
 pub mod to_register;
 pub mod unique_register;
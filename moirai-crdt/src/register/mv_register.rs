@@ -1,4 +1,4 @@
-use std::{convert::Infallible, fmt::Debug, hash::Hash};
+use std::{convert::Infallible, fmt::Debug, hash::Hash, rc::Rc};
 
 #[cfg(feature = "test_utils")]
 use deepsize::DeepSizeOf;
@@ -15,6 +15,9 @@ use moirai_protocol::{
 
 use crate::HashSet;
 
+/// Multi-value register: concurrent writes are all kept.
+/// `Clear` removes the writes that causally precede it, leaving the register unset
+/// unless a concurrent write survives; it is never stored itself.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
 pub enum MVRegister<V> {
@@ -22,6 +25,23 @@ pub enum MVRegister<V> {
     Write(V),
 }
 
+/// Multi-value register whose operations share their value instead of copying it:
+/// cloning an operation, e.g., to broadcast or to store it, only bumps a reference count.
+/// Unlike [`SharedTORegister`](crate::register::to_register::SharedTORegister), it is
+/// read as owned values, so it can replace an [`MVRegister`] without changing its value type.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub enum SharedMVRegister<V> {
+    Clear,
+    Write(Rc<V>),
+}
+
+impl<V> SharedMVRegister<V> {
+    pub fn write_shared(value: V) -> Self {
+        SharedMVRegister::Write(Rc::new(value))
+    }
+}
+
 impl<V> PureCRDT for MVRegister<V>
 where
     V: Debug + Clone + Eq + Hash,
@@ -80,19 +100,117 @@ where
     }
 }
 
+/// Resolve the concurrent values into a single one.
+///
+/// The resolver receives the values sorted and without duplicates, so replicas that see the
+/// same concurrent writes resolve them to the same result.
+pub struct ReadResolved<V, R>(pub fn(Vec<V>) -> R);
+
+impl<V, R> QueryOperation for ReadResolved<V, R> {
+    type Response = R;
+}
+
+impl<V, R, U> Eval<ReadResolved<V, R>, U> for MVRegister<V>
+where
+    V: Debug + Clone + Eq + Hash + Ord,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        q: ReadResolved<V, R>,
+        stable: &<MVRegister<V> as PureCRDT>::StableState,
+        unstable: &U,
+    ) -> <ReadResolved<V, R> as QueryOperation>::Response {
+        let mut values: Vec<V> = stable
+            .iter()
+            .chain(unstable.iter().map(|t| t.op()))
+            .filter_map(|o| match o {
+                MVRegister::Write(v) => Some(v.clone()),
+                MVRegister::Clear => None,
+            })
+            .collect();
+        values.sort_unstable();
+        values.dedup();
+        (q.0)(values)
+    }
+}
+
 impl<V> InternalizeOp for MVRegister<V> {
     fn internalize(self, _interner: &Interner) -> Self {
         self
     }
 }
 
+impl<V> PureCRDT for SharedMVRegister<V>
+where
+    V: Debug + Clone + Eq + Hash,
+{
+    type Value = HashSet<V>;
+    type StableState = Vec<Self>;
+    type Rejection = Infallible;
+
+    fn redundant_itself<'a>(
+        new_tagged_op: &TaggedOp<Self>,
+        _stable: &Self::StableState,
+        _unstable: impl Iterator<Item = &'a TaggedOp<Self>>,
+    ) -> bool
+    where
+        Self: 'a,
+    {
+        matches!(new_tagged_op.op(), SharedMVRegister::Clear)
+    }
+
+    fn redundant_by_when_redundant(
+        _old_op: &Self,
+        _old_tag: Option<&Tag>,
+        is_conc: bool,
+        _new_tagged_op: &TaggedOp<Self>,
+    ) -> bool {
+        !is_conc
+    }
+
+    fn redundant_by_when_not_redundant(
+        _old_op: &Self,
+        _old_tag: Option<&Tag>,
+        is_conc: bool,
+        _new_tagged_op: &TaggedOp<Self>,
+    ) -> bool {
+        !is_conc
+    }
+}
+
+impl<V, U> Eval<Read<<Self as PureCRDT>::Value>, U> for SharedMVRegister<V>
+where
+    V: Debug + Clone + Eq + Hash + Default,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        _q: Read<<Self as PureCRDT>::Value>,
+        stable: &<SharedMVRegister<V> as PureCRDT>::StableState,
+        unstable: &U,
+    ) -> <Read<<Self as PureCRDT>::Value> as QueryOperation>::Response {
+        let mut set = HashSet::<V>::default();
+        for o in stable.iter().chain(unstable.iter().map(|t| t.op())) {
+            if let SharedMVRegister::Write(v) = o {
+                set.insert(V::clone(v));
+            }
+        }
+        set
+    }
+}
+
+impl<V> InternalizeOp for SharedMVRegister<V> {
+    fn internalize(self, _interner: &Interner) -> Self {
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use moirai_protocol::{crdt::query::Read, replica::IsReplica};
 
     use crate::{
         HashSet,
-        register::mv_register::MVRegister,
+        register::mv_register::{MVRegister, ReadResolved, SharedMVRegister},
         utils::{
             membership::{triplet, twins},
             set_from_slice,
@@ -199,4 +317,102 @@ mod tests {
         assert_eq!(replica_a.query(Read::new()), set_from_slice(&[4, 2]));
         assert_eq!(replica_a.query(Read::new()), replica_b.query(Read::new()));
     }
+
+    #[test]
+    fn write_then_clear_mv_register() {
+        let (mut replica_a, mut replica_b) = twins::<MVRegister<&str>>();
+
+        let event = replica_a.send(MVRegister::Write("a")).unwrap();
+        replica_b.receive(event);
+        let event = replica_b.send(MVRegister::Write("b")).unwrap();
+        replica_a.receive(event);
+        let event = replica_a.send(MVRegister::Clear).unwrap();
+        replica_b.receive(event);
+
+        assert_eq!(replica_a.query(Read::new()), HashSet::default());
+        assert_eq!(replica_b.query(Read::new()), HashSet::default());
+
+        // The register can be written again
+        let event = replica_b.send(MVRegister::Write("c")).unwrap();
+        replica_a.receive(event);
+        assert_eq!(replica_a.query(Read::new()), set_from_slice(&["c"]));
+        assert_eq!(replica_b.query(Read::new()), set_from_slice(&["c"]));
+    }
+
+    #[test]
+    fn clear_concurrent_with_write_mv_register() {
+        let (mut replica_a, mut replica_b) = twins::<MVRegister<&str>>();
+
+        let event = replica_a.send(MVRegister::Write("a")).unwrap();
+        replica_b.receive(event);
+
+        let event_a = replica_a.send(MVRegister::Clear).unwrap();
+        let event_b = replica_b.send(MVRegister::Write("b")).unwrap();
+        assert_eq!(replica_a.query(Read::new()), HashSet::default());
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+
+        let result = set_from_slice(&["b"]);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+    }
+
+    #[test]
+    fn shared_mv_register() {
+        let (mut replica_a, mut replica_b) = twins::<MVRegister<String>>();
+        let (mut shared_a, mut shared_b) = twins::<SharedMVRegister<String>>();
+
+        for (a, b) in [("a", "b"), ("c", "c"), ("d", "e")] {
+            let event_a = replica_a.send(MVRegister::Write(a.to_string())).unwrap();
+            let event_b = replica_b.send(MVRegister::Write(b.to_string())).unwrap();
+            replica_a.receive(event_b);
+            replica_b.receive(event_a);
+
+            let event_a = shared_a
+                .send(SharedMVRegister::write_shared(a.to_string()))
+                .unwrap();
+            let event_b = shared_b
+                .send(SharedMVRegister::write_shared(b.to_string()))
+                .unwrap();
+            shared_a.receive(event_b);
+            shared_b.receive(event_a);
+
+            let value = replica_a.query(Read::new());
+            assert_eq!(shared_a.query(Read::new()), value);
+            assert_eq!(shared_b.query(Read::new()), value);
+        }
+
+        shared_a.send(SharedMVRegister::Clear).unwrap();
+        assert_eq!(shared_a.query(Read::new()), HashSet::default());
+    }
+
+    #[test]
+    fn read_resolved_max() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet::<MVRegister<u32>>();
+
+        let event_a = replica_a.send(MVRegister::Write(3)).unwrap();
+        let event_b = replica_b.send(MVRegister::Write(7)).unwrap();
+        let event_c = replica_c.send(MVRegister::Write(5)).unwrap();
+
+        replica_a.receive(event_c.clone());
+        replica_a.receive(event_b.clone());
+        replica_b.receive(event_a.clone());
+        replica_b.receive(event_c);
+        replica_c.receive(event_b);
+        replica_c.receive(event_a);
+
+        let max = |values: Vec<u32>| values.into_iter().max();
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), set_from_slice(&[3, 5, 7]));
+            assert_eq!(replica.query(ReadResolved(max)), Some(7));
+        }
+
+        // A single write needs no resolution
+        let event = replica_a.send(MVRegister::Write(1)).unwrap();
+        replica_b.receive(event.clone());
+        replica_c.receive(event);
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(ReadResolved(max)), Some(1));
+        }
+    }
 }
@@ -0,0 +1,224 @@
+use std::{
+    convert::Infallible,
+    fmt::Debug,
+    ops::{Add, AddAssign},
+};
+
+#[cfg(feature = "test_utils")]
+use deepsize::DeepSizeOf;
+use moirai_protocol::{
+    crdt::{
+        eval::Eval,
+        pure_crdt::PureCRDT,
+        query::{QueryOperation, Read},
+        redundancy::RedundancyRelation,
+    },
+    event::{tag::Tag, tagged_op::TaggedOp},
+    state::{
+        stable_state::IsStableState,
+        unstable_state::{IsUnstableCore, IsUnstablePrune},
+    },
+    utils::intern_str::{InternalizeOp, Interner},
+};
+
+use crate::policy::{fold_lww, stabilize_lww};
+
+/// Numeric register whose value can be written or adjusted by a delta.
+///
+/// The value is resolved in two steps:
+/// 1. A write overwrites the writes and adjustments it causally follows, even if it then loses
+///    against a concurrent write. Among concurrent writes, the greatest `(lamport, replica id)` wins.
+/// 2. The adjustments that no write overwrote are added to the winning write, or to zero if there
+///    is none. Adding being commutative, their order does not matter.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub enum PNRegister<T> {
+    Write(T),
+    Adjust(T),
+}
+
+fn write<T>(op: &PNRegister<T>) -> Option<&T> {
+    match op {
+        PNRegister::Write(v) => Some(v),
+        PNRegister::Adjust(_) => None,
+    }
+}
+
+/// Stable writes and adjustments, folded into a single value.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub struct PNStable<T>(T);
+
+impl<T> PureCRDT for PNRegister<T>
+where
+    T: Add<Output = T> + AddAssign + Default + Copy + Debug + PartialEq,
+{
+    type Value = T;
+    type StableState = PNStable<T>;
+    type Rejection = Infallible;
+
+    fn redundant_itself<'a>(
+        _new_tagged_op: &TaggedOp<Self>,
+        _stable: &Self::StableState,
+        _unstable: impl Iterator<Item = &'a TaggedOp<Self>>,
+    ) -> bool
+    where
+        Self: 'a,
+    {
+        false
+    }
+
+    fn redundant_by_when_redundant(
+        _old_op: &Self,
+        _old_tag: Option<&Tag>,
+        _is_conc: bool,
+        _new_tagged_op: &TaggedOp<Self>,
+    ) -> bool {
+        false
+    }
+
+    fn redundant_by_when_not_redundant(
+        _old_op: &Self,
+        _old_tag: Option<&Tag>,
+        is_conc: bool,
+        new_tagged_op: &TaggedOp<Self>,
+    ) -> bool {
+        !is_conc && matches!(new_tagged_op.op(), PNRegister::Write(_))
+    }
+
+    /// The winning write is added to the stable adjustments, which are all concurrent with it.
+    fn stabilize(
+        tagged_op: &TaggedOp<Self>,
+        stable: &mut Self::StableState,
+        unstable: &mut impl IsUnstablePrune<Self>,
+    ) {
+        if let Some(v) = stabilize_lww(tagged_op, unstable, write) {
+            stable.0 += *v;
+        }
+    }
+}
+
+impl<T, U> Eval<Read<<Self as PureCRDT>::Value>, U> for PNRegister<T>
+where
+    T: Add<Output = T> + AddAssign + Default + Copy + Debug + PartialEq,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        _q: Read<<Self as PureCRDT>::Value>,
+        stable: &Self::StableState,
+        unstable: &U,
+    ) -> <Read<<Self as PureCRDT>::Value> as QueryOperation>::Response {
+        let mut value = stable.0;
+        let winner = fold_lww(unstable.iter(), write, |op| {
+            if let PNRegister::Adjust(delta) = op {
+                value += *delta;
+            }
+        });
+        // The stable state only holds what is concurrent with an unstable write
+        if let Some(v) = winner {
+            value += *v;
+        }
+        value
+    }
+}
+
+impl<T> IsStableState<PNRegister<T>> for PNStable<T>
+where
+    T: Add<Output = T> + AddAssign + Default + Copy + Debug + PartialEq,
+{
+    fn is_default(&self) -> bool {
+        self.0 == T::default()
+    }
+
+    fn apply(&mut self, value: PNRegister<T>) {
+        match value {
+            PNRegister::Adjust(delta) => self.0 += delta,
+            PNRegister::Write(_) => {
+                unreachable!("writes are stabilized in `PNRegister::stabilize`")
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.0 = T::default();
+    }
+
+    fn prune_redundant_ops(
+        &mut self,
+        _rdnt: RedundancyRelation<PNRegister<T>>,
+        tagged_op: &TaggedOp<PNRegister<T>>,
+    ) {
+        // Every new operation causally follows the stable ones.
+        if let PNRegister::Write(_) = tagged_op.op() {
+            self.0 = T::default();
+        }
+    }
+}
+
+impl<T> InternalizeOp for PNRegister<T> {
+    fn internalize(self, _interner: &Interner) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::{crdt::query::Read, replica::IsReplica};
+
+    use crate::{
+        register::pn_register::PNRegister,
+        utils::membership::{triplet, twins},
+    };
+
+    #[test]
+    fn adjust_concurrent_with_write_is_applied() {
+        let (mut replica_a, mut replica_b) = twins::<PNRegister<i64>>();
+
+        let event = replica_a.send(PNRegister::Adjust(5)).unwrap();
+        replica_b.receive(event);
+
+        let event_a = replica_a.send(PNRegister::Write(10)).unwrap();
+        let event_b = replica_b.send(PNRegister::Adjust(-3)).unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+
+        // The first adjustment is overwritten, the concurrent one applies after the write
+        assert_eq!(replica_a.query(Read::new()), 7);
+        assert_eq!(replica_b.query(Read::new()), 7);
+
+        let event = replica_b.send(PNRegister::Write(1)).unwrap();
+        replica_a.receive(event);
+
+        assert_eq!(replica_a.query(Read::new()), 1);
+        assert_eq!(replica_b.query(Read::new()), 1);
+    }
+
+    #[test]
+    fn concurrent_writes_and_adjust() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet::<PNRegister<i64>>();
+
+        let event_a = replica_a.send(PNRegister::Write(10)).unwrap();
+        let event_b = replica_b.send(PNRegister::Write(20)).unwrap();
+        let event_c = replica_c.send(PNRegister::Adjust(2)).unwrap();
+
+        replica_a.receive(event_b.clone());
+        replica_a.receive(event_c.clone());
+        replica_b.receive(event_c);
+        replica_b.receive(event_a.clone());
+        replica_c.receive(event_a);
+        replica_c.receive(event_b);
+
+        // Same Lamport clock: the write of the greatest replica id wins
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), 22);
+        }
+
+        // Adjustments after the stable write keep adding to it
+        let event = replica_c.send(PNRegister::Adjust(1)).unwrap();
+        replica_a.receive(event.clone());
+        replica_b.receive(event);
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), 23);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::{convert::Infallible, fmt::Debug};
+use std::{convert::Infallible, fmt::Debug, marker::PhantomData, rc::Rc};
 
 #[cfg(feature = "test_utils")]
 use deepsize::DeepSizeOf;
@@ -20,6 +20,17 @@ pub enum TORegister<V> {
     Write(V),
 }
 
+/// Register whose values are shared by the operations, the log and the reads instead of
+/// being copied: cloning an operation or reading the register only bumps a reference count.
+/// Meant for large values, such as names or documents.
+pub type SharedTORegister<V> = TORegister<Rc<V>>;
+
+impl<V> SharedTORegister<V> {
+    pub fn write_shared(value: V) -> Self {
+        TORegister::Write(Rc::new(value))
+    }
+}
+
 impl<V> PureCRDT for TORegister<V>
 where
     V: Debug + Default + PartialOrd + Ord + Clone,
@@ -80,6 +91,55 @@ where
     }
 }
 
+/// The value read, along with the concurrent values it won against (highest first).
+/// The shadowed values are only reported until a later write supersedes them.
+pub struct ReadWithShadowed<V>(PhantomData<V>);
+
+impl<V> ReadWithShadowed<V> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<V> Default for ReadWithShadowed<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> QueryOperation for ReadWithShadowed<V> {
+    type Response = (V, Vec<V>);
+}
+
+impl<V, U> Eval<ReadWithShadowed<V>, U> for TORegister<V>
+where
+    V: Debug + Default + PartialOrd + Ord + Clone,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        _q: ReadWithShadowed<V>,
+        stable: &<TORegister<V> as PureCRDT>::StableState,
+        unstable: &U,
+    ) -> (V, Vec<V>) {
+        // Writes are only pruned by the ones that causally follow them, so the log
+        // still holds every concurrent write beaten by the winner.
+        let mut winner = V::default();
+        let mut values = Vec::new();
+        for o in stable.iter().chain(unstable.iter().map(|t| t.op())) {
+            if let TORegister::Write(v) = o {
+                if v > &winner {
+                    winner = v.clone();
+                }
+                values.push(v.clone());
+            }
+        }
+        values.retain(|v| v != &winner);
+        values.sort_unstable_by(|a, b| b.cmp(a));
+        values.dedup();
+        (winner, values)
+    }
+}
+
 impl<V> InternalizeOp for TORegister<V> {
     fn internalize(self, _interner: &Interner) -> Self {
         self
@@ -90,8 +150,11 @@ impl<V> InternalizeOp for TORegister<V> {
 mod tests {
     use moirai_protocol::{crdt::query::Read, replica::IsReplica};
 
+    use std::rc::Rc;
+
     use crate::{
-        register::to_register::TORegister,
+        model::class_diagram::Visibility,
+        register::to_register::{ReadWithShadowed, SharedTORegister, TORegister},
         utils::membership::{triplet, twins},
     };
 
@@ -192,4 +255,92 @@ mod tests {
         assert_eq!(replica_a.query(Read::new()), 4);
         assert_eq!(replica_a.query(Read::new()), replica_b.query(Read::new()));
     }
+
+    #[test]
+    fn shared_to_register() {
+        let (mut replica_a, mut replica_b) = twins::<TORegister<String>>();
+        let (mut shared_a, mut shared_b) = twins::<SharedTORegister<String>>();
+
+        for (a, b) in [("abc", "abd"), ("x", "w"), ("zz", "z")] {
+            let event_a = replica_a.send(TORegister::Write(a.to_string())).unwrap();
+            let event_b = replica_b.send(TORegister::Write(b.to_string())).unwrap();
+            replica_a.receive(event_b);
+            replica_b.receive(event_a);
+
+            let event_a = shared_a
+                .send(SharedTORegister::write_shared(a.to_string()))
+                .unwrap();
+            let event_b = shared_b
+                .send(SharedTORegister::write_shared(b.to_string()))
+                .unwrap();
+            shared_a.receive(event_b);
+            shared_b.receive(event_a);
+
+            let value = replica_a.query(Read::new());
+            assert_eq!(*shared_a.query(Read::new()), value);
+            assert_eq!(*shared_b.query(Read::new()), value);
+        }
+
+        // The value read is the one written, not a copy
+        let value = Rc::new("zzz".to_string());
+        shared_a.send(TORegister::Write(Rc::clone(&value))).unwrap();
+        assert!(Rc::ptr_eq(&shared_a.query(Read::new()), &value));
+    }
+
+    #[test]
+    fn read_with_shadowed_visibility() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet::<TORegister<Visibility>>();
+
+        assert_eq!(
+            replica_a.query(ReadWithShadowed::new()),
+            (Visibility::default(), vec![])
+        );
+
+        let event_a = replica_a
+            .send(TORegister::Write(Visibility::Protected))
+            .unwrap();
+        let event_b = replica_b
+            .send(TORegister::Write(Visibility::Private))
+            .unwrap();
+        let event_c = replica_c
+            .send(TORegister::Write(Visibility::Package))
+            .unwrap();
+
+        replica_a.receive(event_b.clone());
+        replica_a.receive(event_c.clone());
+        replica_b.receive(event_a.clone());
+        replica_b.receive(event_c);
+        replica_c.receive(event_a);
+        replica_c.receive(event_b);
+
+        let expected = (
+            Visibility::Private,
+            vec![Visibility::Package, Visibility::Protected],
+        );
+        assert_eq!(replica_a.query(ReadWithShadowed::new()), expected);
+        assert_eq!(replica_b.query(ReadWithShadowed::new()), expected);
+        assert_eq!(replica_c.query(ReadWithShadowed::new()), expected);
+        assert_eq!(replica_a.query(Read::new()), Visibility::Private);
+
+        // A write that has seen the others shadows nothing
+        let event = replica_a
+            .send(TORegister::Write(Visibility::Public))
+            .unwrap();
+        replica_b.receive(event.clone());
+        replica_c.receive(event);
+        assert_eq!(
+            replica_b.query(ReadWithShadowed::new()),
+            (Visibility::Public, vec![])
+        );
+    }
+
+    #[test]
+    fn read_with_shadowed_agrees_with_read() {
+        let (mut replica_a, _) = twins::<TORegister<i32>>();
+
+        // A write below the default value loses against it
+        replica_a.send(TORegister::Write(-5)).unwrap();
+        assert_eq!(replica_a.query(Read::new()), 0);
+        assert_eq!(replica_a.query(ReadWithShadowed::new()), (0, vec![-5]));
+    }
 }
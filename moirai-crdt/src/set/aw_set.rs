@@ -1,10 +1,14 @@
-use std::{convert::Infallible, fmt::Debug, hash::Hash};
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+};
 
 #[cfg(feature = "test_utils")]
 use deepsize::DeepSizeOf;
 #[cfg(feature = "fuzz")]
 use moirai_fuzz::op_generator::OpGenerator;
 use moirai_protocol::{
+    clock::version_vector::{Seq, Version},
     crdt::{
         eval::Eval,
         pure_crdt::PureCRDT,
@@ -12,25 +16,94 @@ use moirai_protocol::{
         redundancy::RedundancyRelation,
     },
     event::{tag::Tag, tagged_op::TaggedOp},
+    replica::ReplicaIdx,
     state::{
         stable_state::IsStableState,
-        unstable_state::{CausalReplay, IsUnstableCore},
+        unstable_state::{CausalReplay, IsUnstableCore, IsUnstablePrune},
     },
     utils::intern_str::{InternalizeOp, Interner},
 };
 #[cfg(feature = "fuzz")]
 use rand::{Rng, RngExt};
 
-use crate::HashSet;
 #[cfg(feature = "fuzz")]
 use crate::set::SetConfig;
+use crate::{HashMap, HashSet, undo::Invertible};
 
+/// Add-wins set.
+///
+/// Removes leave no tombstone: a remove discards the adds it observed and is not stored
+/// itself, so the log only keeps the adds of live elements, stable or not. An add
+/// concurrent with a remove was not observed by it, so it is kept and wins.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
 pub enum AWSet<V> {
     Add(V),
     Remove(V),
     Clear,
+    /// Remove the elements whose adds are dominated by the given version.
+    /// Adds that are not covered by the version, including concurrent ones, are kept.
+    /// The version must have been delivered by the issuing replica, it is rejected otherwise.
+    ClearSince(Version),
+}
+
+#[derive(Clone, Debug)]
+pub enum AWSetRejection {
+    /// The version of a `ClearSince` counts events the replica has not delivered.
+    UnobservedVersion(Version),
+}
+
+impl Display for AWSetRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AWSetRejection::UnobservedVersion(version) => {
+                write!(f, "Version {version} was not observed by the replica")
+            }
+        }
+    }
+}
+
+/// Origin and counter of an add.
+type Dot = (ReplicaIdx, Seq);
+
+/// Stable elements along with the add that introduced them, so that a later `ClearSince`
+/// can still tell whether it covers them.
+/// An element costs one dot on top of itself: the adds of an element stabilized
+/// concurrently with the first one are kept apart, in `concurrent`.
+#[derive(Debug, Clone)]
+pub struct AWSetStable<V> {
+    elements: HashMap<V, Dot>,
+    concurrent: HashMap<V, Vec<Dot>>,
+}
+
+impl<V> Default for AWSetStable<V> {
+    fn default() -> Self {
+        Self {
+            elements: HashMap::default(),
+            concurrent: HashMap::default(),
+        }
+    }
+}
+
+impl<V> AWSetStable<V>
+where
+    V: Eq + Hash,
+{
+    pub fn contains(&self, value: &V) -> bool {
+        self.elements.contains_key(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &V> {
+        self.elements.keys()
+    }
 }
 
 impl<V> PureCRDT for AWSet<V>
@@ -38,8 +111,8 @@ where
     V: Debug + Clone + Eq + Hash,
 {
     type Value = HashSet<V>;
-    type StableState = HashSet<V>;
-    type Rejection = Infallible;
+    type StableState = AWSetStable<V>;
+    type Rejection = AWSetRejection;
 
     fn redundant_itself<'a>(
         new_tagged_op: &TaggedOp<Self>,
@@ -49,12 +122,15 @@ where
     where
         Self: 'a,
     {
-        matches!(new_tagged_op.op(), AWSet::Clear | AWSet::Remove(_))
+        matches!(
+            new_tagged_op.op(),
+            AWSet::Clear | AWSet::Remove(_) | AWSet::ClearSince(_)
+        )
     }
 
     fn redundant_by_when_redundant(
         old_op: &Self,
-        _old_tag: Option<&Tag>,
+        old_tag: Option<&Tag>,
         is_conc: bool,
         new_tagged_op: &TaggedOp<Self>,
     ) -> bool {
@@ -62,8 +138,12 @@ where
             && match (old_op, new_tagged_op.op()) {
                 (AWSet::Add(v1), AWSet::Add(v2)) | (AWSet::Add(v1), AWSet::Remove(v2)) => v1 == v2,
                 (_, AWSet::Clear) => true,
+                (AWSet::Add(_), AWSet::ClearSince(version)) => {
+                    old_tag.is_some_and(|tag| tag.id().is_predecessor_of(version))
+                }
                 (AWSet::Remove(_), _) => unreachable!(),
                 (AWSet::Clear, _) => unreachable!(),
+                (AWSet::ClearSince(_), _) => unreachable!(),
             }
     }
 
@@ -75,6 +155,34 @@ where
     ) -> bool {
         Self::redundant_by_when_redundant(old_op, old_tag, is_conc, new_tagged_op)
     }
+
+    /// Adds are moved to the stable state with their dot, so that a later
+    /// `ClearSince` can still tell whether it covers them.
+    fn stabilize(
+        tagged_op: &TaggedOp<Self>,
+        stable: &mut Self::StableState,
+        unstable: &mut impl IsUnstablePrune<Self>,
+    ) {
+        if let AWSet::Add(v) = tagged_op.op() {
+            let dot = (tagged_op.id().idx(), tagged_op.id().seq());
+            if stable.elements.contains_key(v) {
+                stable.concurrent.entry(v.clone()).or_default().push(dot);
+            } else {
+                stable.elements.insert(v.clone(), dot);
+            }
+            unstable.remove(tagged_op.id());
+        }
+    }
+
+    /// A `ClearSince` may only cover events the replica has delivered.
+    fn is_enabled_at(op: &Self, delivered: &Version) -> Result<(), Self::Rejection> {
+        match op {
+            AWSet::ClearSince(version) if !delivered.includes(version) => {
+                Err(AWSetRejection::UnobservedVersion(version.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 impl<V, U> Eval<Read<<Self as PureCRDT>::Value>, U> for AWSet<V>
@@ -87,7 +195,7 @@ where
         stable: &<AWSet<V> as PureCRDT>::StableState,
         unstable: &U,
     ) -> <Read<<Self as PureCRDT>::Value> as QueryOperation>::Response {
-        let mut set = stable.clone();
+        let mut set: HashSet<V> = stable.iter().cloned().collect();
         for o in unstable.iter() {
             if let AWSet::Add(v) = o.op() {
                 set.insert(v.clone());
@@ -114,28 +222,96 @@ where
     }
 }
 
+/// Live elements in the half-open interval `[lo, hi)`, sorted.
+pub struct Range<V> {
+    pub lo: V,
+    pub hi: V,
+}
+
+impl<V> Range<V> {
+    pub fn new(lo: V, hi: V) -> Self {
+        Self { lo, hi }
+    }
+
+    fn contains(&self, value: &V) -> bool
+    where
+        V: Ord,
+    {
+        self.lo <= *value && *value < self.hi
+    }
+}
+
+impl<V> QueryOperation for Range<V> {
+    type Response = Vec<V>;
+}
+
+impl<V, U> Eval<Range<V>, U> for AWSet<V>
+where
+    V: Debug + Clone + Eq + Hash + Ord,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        q: Range<V>,
+        stable: &<AWSet<V> as PureCRDT>::StableState,
+        unstable: &U,
+    ) -> <Range<V> as QueryOperation>::Response {
+        let mut elements: Vec<V> = stable.iter().filter(|v| q.contains(v)).cloned().collect();
+        for o in unstable.iter() {
+            if let AWSet::Add(v) = o.op()
+                && q.contains(v)
+            {
+                elements.push(v.clone());
+            }
+        }
+        // An element may be added by several stable or unstable operations
+        elements.sort_unstable();
+        elements.dedup();
+        elements
+    }
+}
+
+/// Undoing a remove re-adds the element, even if it was not present.
+impl<V> Invertible for AWSet<V>
+where
+    V: Debug + Clone + Hash + Eq,
+{
+    fn inverse(&self) -> Option<Self> {
+        match self {
+            AWSet::Add(v) => Some(AWSet::Remove(v.clone())),
+            AWSet::Remove(v) => Some(AWSet::Add(v.clone())),
+            AWSet::Clear | AWSet::ClearSince(_) => None,
+        }
+    }
+}
+
 impl<V> InternalizeOp for AWSet<V> {
-    fn internalize(self, _interner: &Interner) -> Self {
-        self
+    fn internalize(self, interner: &Interner) -> Self {
+        match self {
+            AWSet::ClearSince(version) => AWSet::ClearSince(version.internalize(interner)),
+            op => op,
+        }
     }
 }
 
-impl<V> IsStableState<AWSet<V>> for HashSet<V>
+impl<V> IsStableState<AWSet<V>> for AWSetStable<V>
 where
     V: Clone + Eq + Hash + Debug,
 {
     fn is_default(&self) -> bool {
-        self.is_empty()
+        self.elements.is_empty()
     }
 
-    fn apply(&mut self, value: AWSet<V>) {
-        if let AWSet::Add(v) = value {
-            self.insert(v);
-        }
+    fn apply(&mut self, _value: AWSet<V>) {
+        unreachable!("adds are stabilized with their id in `AWSet::stabilize`")
     }
 
     fn clear(&mut self) {
-        self.clear();
+        self.elements.clear();
+        self.concurrent.clear();
+    }
+
+    fn num_entries(&self) -> usize {
+        self.elements.len() + self.concurrent.values().map(Vec::len).sum::<usize>()
     }
 
     fn prune_redundant_ops(
@@ -145,11 +321,32 @@ where
     ) {
         match tagged_op.op() {
             AWSet::Add(v) | AWSet::Remove(v) => {
-                self.remove(v);
+                self.elements.remove(v);
+                self.concurrent.remove(v);
             }
             AWSet::Clear => {
                 self.clear();
             }
+            AWSet::ClearSince(version) => {
+                let is_covered = |(idx, seq): &Dot| *seq <= version.seq_by_idx(*idx);
+                let concurrent = &mut self.concurrent;
+                self.elements.retain(|v, dot| {
+                    let Some(mut others) = concurrent.remove(v) else {
+                        return !is_covered(&*dot);
+                    };
+                    others.retain(|other| !is_covered(other));
+                    if is_covered(&*dot) {
+                        match others.pop() {
+                            Some(other) => *dot = other,
+                            None => return false,
+                        }
+                    }
+                    if !others.is_empty() {
+                        concurrent.insert(v.clone(), others);
+                    }
+                    true
+                });
+            }
         }
     }
 }
@@ -160,7 +357,7 @@ impl OpGenerator for AWSet<usize> {
 
     fn generate(
         rng: &mut impl Rng,
-        config: &Self::Config,
+        config: &mut Self::Config,
         _stable: &<Self as PureCRDT>::StableState,
         _unstable: &impl CausalReplay<Self>,
     ) -> Self {
@@ -186,17 +383,74 @@ impl OpGenerator for AWSet<usize> {
 #[cfg(test)]
 mod tests {
     use moirai_protocol::{
+        broadcast::tcsb::IsTcsbTest,
         crdt::query::{Contains, Read},
         replica::IsReplica,
-        state::po_log::VecLog,
+        state::{log::IsLog, po_log::VecLog},
     };
 
+    use proptest::prelude::*;
+
     use crate::{
         HashSet,
-        set::aw_set::AWSet,
-        utils::{membership::twins_log, set_from_slice},
+        set::aw_set::{AWSet, AWSetRejection, Range},
+        utils::{
+            convergence,
+            membership::{triplet_log, twins_log},
+            set_from_slice,
+        },
     };
 
+    #[test]
+    fn events_keep_dependencies() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet_log::<VecLog<AWSet<&str>>>();
+
+        let event_a = replica_a.send(AWSet::Add("a")).unwrap();
+        replica_b.receive(event_a.clone());
+        let event_b = replica_b.send(AWSet::Remove("a")).unwrap();
+        assert_eq!(
+            event_b
+                .event()
+                .version()
+                .iter()
+                .filter(|(_, seq)| *seq > 0)
+                .count(),
+            2
+        );
+
+        // The remove waits for the add it observed
+        replica_c.receive(event_b);
+        assert_eq!(replica_c.tcsb().pending().len(), 1);
+        replica_c.receive(event_a);
+        assert!(replica_c.tcsb().pending().is_empty());
+        assert_eq!(replica_c.query(Read::new()), HashSet::default());
+    }
+
+    #[test]
+    fn range_query() {
+        let (mut replica_a, mut replica_b) = twins_log::<VecLog<AWSet<u32>>>();
+
+        for key in [30, 10, 20] {
+            let event = replica_a.send(AWSet::Add(key)).unwrap();
+            replica_b.receive(event);
+        }
+        let event_a = replica_a.send(AWSet::Add(25)).unwrap();
+        let event_b = replica_b.send(AWSet::Add(25)).unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+        for key in [15, 40] {
+            let event = replica_b.send(AWSet::Add(key)).unwrap();
+            replica_a.receive(event);
+        }
+        let event = replica_a.send(AWSet::Remove(20)).unwrap();
+        replica_b.receive(event);
+
+        assert_eq!(replica_a.query(Range::new(10, 30)), vec![10, 15, 25]);
+        assert_eq!(replica_b.query(Range::new(10, 30)), vec![10, 15, 25]);
+        assert_eq!(replica_a.query(Range::new(26, 41)), vec![30, 40]);
+        assert!(replica_b.query(Range::new(30, 30)).is_empty());
+    }
+
     #[test]
     fn simple_aw_set() {
         let (mut replica_a, mut replica_b) = twins_log::<VecLog<AWSet<&str>>>();
@@ -294,6 +548,60 @@ mod tests {
         assert_eq!(replica_b.query(Read::new()), result);
     }
 
+    #[test]
+    fn fork_delays_stability() {
+        let (mut replica_a, mut replica_b) = twins_log::<VecLog<AWSet<&str>>>();
+
+        let event = replica_a.send(AWSet::Add("x")).unwrap();
+        replica_b.receive(event);
+        let mut replica_c = replica_a.clone_as("c".to_string());
+
+        // `b` adds "x" again, concurrently with its removal by the fork
+        let event_b = replica_b.send(AWSet::Add("x")).unwrap();
+        replica_a.receive(event_b.clone());
+        let event_a = replica_a.send(AWSet::Add("z")).unwrap();
+        replica_b.receive(event_a.clone());
+        // Both `a` and `b` delivered the new add, which is not stable as `c` did not:
+        // at most the first add is
+        for replica in [&replica_a, &replica_b] {
+            assert!(replica.tcsb().last_stable_version().sum() <= 1);
+        }
+
+        let event_c = replica_c.send(AWSet::Remove("x")).unwrap();
+        replica_a.receive(event_c.clone());
+        replica_b.receive(event_c);
+        replica_c.receive(event_b);
+        replica_c.receive(event_a);
+
+        let result = set_from_slice(&["x", "z"]);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+        assert_eq!(replica_c.query(Read::new()), result);
+    }
+
+    #[test]
+    fn pull_waits_for_concurrent_clear() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet_log::<VecLog<AWSet<&str>>>();
+
+        let event_a = replica_a.send(AWSet::Add("x")).unwrap();
+        replica_c.receive(event_a.clone());
+        let event_b = replica_b.send(AWSet::Clear).unwrap();
+        replica_b.receive(event_a);
+
+        // `b` has delivered the add, but `c` has not delivered the clear `b` issued before:
+        // the pull does not make the add stable at `c`
+        let _ = replica_c.pull(replica_b.since());
+        assert_eq!(replica_c.tcsb().last_stable_version().sum(), 0);
+
+        replica_c.receive(event_b.clone());
+        replica_a.receive(event_b);
+
+        let result = set_from_slice(&["x"]);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+        assert_eq!(replica_c.query(Read::new()), result);
+    }
+
     #[test]
     fn concurrent_add_aw_set() {
         let (mut replica_a, mut replica_b) = twins_log::<VecLog<AWSet<&str>>>();
@@ -386,6 +694,199 @@ mod tests {
         assert_eq!(replica_b.query(Read::new()), set_from_slice(&[]));
     }
 
+    #[test]
+    fn add_remove_churn_stays_bounded() {
+        let (mut replica_a, mut replica_b) = twins_log::<VecLog<AWSet<&str>>>();
+
+        for i in 0..10_000 {
+            let event = replica_a.send(AWSet::Add("k")).unwrap();
+            replica_b.receive(event);
+            let event = replica_a.send(AWSet::Remove("k")).unwrap();
+            replica_b.receive(event);
+
+            if i % 100 == 0 {
+                // Acknowledge the events of replica a to let them stabilize
+                let event = replica_b.send(AWSet::Remove("k")).unwrap();
+                replica_a.receive(event);
+
+                for replica in [&replica_a, &replica_b] {
+                    let stats = replica.state().prune_stats();
+                    assert_eq!(stats.live_events + stats.tombstones, 0);
+                    assert_eq!(stats.stable_ops, 0);
+                }
+                assert!(replica_a.tcsb().outbox_len() <= 2);
+            }
+        }
+
+        // `b` adds "v" concurrently with an add and a remove of "v" by `a`
+        let late = replica_b.send(AWSet::Add("v")).unwrap();
+        let event = replica_a.send(AWSet::Add("v")).unwrap();
+        replica_b.receive(event);
+        let event = replica_a.send(AWSet::Remove("v")).unwrap();
+        replica_b.receive(event);
+
+        // The remove is stable at `b` and left nothing behind at `a`
+        assert_eq!(
+            replica_b.tcsb().last_stable_version().sum(),
+            replica_a.tcsb().matrix_clock().origin_version().sum()
+        );
+        let stats = replica_a.state().prune_stats();
+        assert_eq!(stats.live_events + stats.tombstones + stats.stable_ops, 0);
+
+        // The concurrent add arrives late and still wins
+        replica_a.receive(late);
+        for replica in [&replica_a, &replica_b] {
+            assert_eq!(replica.query(Read::new()), set_from_slice(&["v"]));
+        }
+
+        let event = replica_a.send(AWSet::Add("k")).unwrap();
+        replica_b.receive(event);
+        let event = replica_b.send(AWSet::Add("l")).unwrap();
+        replica_a.receive(event);
+
+        let stats = replica_a.state().prune_stats();
+        assert_eq!(stats.live_events + stats.stable_ops, 3);
+        assert_eq!(
+            replica_a.query(Read::new()),
+            set_from_slice(&["k", "l", "v"])
+        );
+    }
+
+    #[test]
+    fn clear_since_aw_set() {
+        let (mut replica_a, mut replica_b) = twins_log::<VecLog<AWSet<&str>>>();
+
+        let event = replica_a.send(AWSet::Add("a")).unwrap();
+        replica_b.receive(event);
+        let event = replica_a.send(AWSet::Add("b")).unwrap();
+        replica_b.receive(event);
+
+        let clock = replica_a.tcsb().matrix_clock().origin_version().clone();
+
+        let event = replica_a.send(AWSet::Add("c")).unwrap();
+        replica_b.receive(event);
+
+        // The add of "a" by B is concurrent with the clear, hence not covered by the clock
+        let event_a = replica_a.send(AWSet::ClearSince(clock)).unwrap();
+        let event_b = replica_b.send(AWSet::Add("a")).unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+
+        let result = set_from_slice(&["a", "c"]);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+    }
+
+    #[test]
+    fn clear_since_stable_aw_set() {
+        let (mut replica_a, mut replica_b) = twins_log::<VecLog<AWSet<&str>>>();
+
+        let event = replica_a.send(AWSet::Add("a")).unwrap();
+        replica_b.receive(event);
+        let event = replica_b.send(AWSet::Add("b")).unwrap();
+        replica_a.receive(event);
+
+        let clock = replica_b.tcsb().matrix_clock().origin_version().clone();
+
+        let event = replica_b.send(AWSet::Add("c")).unwrap();
+        replica_a.receive(event);
+        let event = replica_a.send(AWSet::Add("a")).unwrap();
+        replica_b.receive(event);
+
+        // "a" was added again after the clock
+        let event = replica_b.send(AWSet::ClearSince(clock)).unwrap();
+        replica_a.receive(event);
+
+        let result = set_from_slice(&["a", "c"]);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+        assert!(!replica_a.query(Contains("b")));
+    }
+
+    #[test]
+    fn clear_since_unobserved_version() {
+        let (mut replica_a, mut replica_b) = twins_log::<VecLog<AWSet<&str>>>();
+
+        let event = replica_a.send(AWSet::Add("a")).unwrap();
+        replica_b.receive(event);
+
+        // The clock counts an event `a` has not issued yet
+        let mut clock = replica_a.tcsb().matrix_clock().origin_version().clone();
+        clock.increment();
+        assert!(matches!(
+            replica_a.send(AWSet::ClearSince(clock)),
+            Err(AWSetRejection::UnobservedVersion(_))
+        ));
+
+        assert_eq!(replica_a.query(Read::new()), set_from_slice(&["a"]));
+        assert_eq!(replica_a.tcsb().matrix_clock().origin_version().sum(), 1);
+    }
+
+    #[cfg(feature = "fuzz")]
+    #[test]
+    fn partition_heals_aw_set() {
+        use moirai_fuzz::{
+            config::{FuzzerConfig, ReachabilitySchedule, RunConfig},
+            fuzzer::fuzzer,
+            shrink::reproduces,
+        };
+
+        let full = vec![vec![true; 4]; 4];
+        // {0, 1} and {2, 3} cannot communicate
+        let split = (0..4)
+            .map(|i| (0..4).map(|j| (i < 2) == (j < 2)).collect())
+            .collect();
+        let schedule =
+            ReachabilitySchedule::new(vec![(0, full.clone()), (100, split), (300, full)]);
+
+        // No final merge: replicas must converge on their own once the partition heals
+        let run = RunConfig::new(0.0, 4, 400, None, Some([7; 32]), false, false)
+            .with_reachability_schedule(schedule);
+
+        // The same run stopped before the heal fails: the partition did split the replicas
+        let mut partitioned = run.clone();
+        partitioned.num_operations = 300;
+        assert!(reproduces::<VecLog<AWSet<usize>>>(
+            &partitioned,
+            &Default::default(),
+            false,
+            |a, b| a == b
+        ));
+
+        let config = FuzzerConfig::<VecLog<AWSet<usize>>>::new(
+            "aw_set_partition",
+            vec![run],
+            false,
+            |a, b| a == b,
+            false,
+        );
+
+        fuzzer::<VecLog<AWSet<usize>>>(config);
+    }
+
+    #[cfg(feature = "fuzz")]
+    #[test]
+    fn faulty_network_aw_set() {
+        use moirai_fuzz::{
+            config::{FuzzerConfig, RunConfig},
+            fuzzer::fuzzer,
+        };
+
+        // Half of the deliveries come with a duplicate, and messages may be delayed by 10 ops
+        let run = RunConfig::new(0.1, 4, 500, None, Some([3; 32]), false, false)
+            .with_faulty_network(0.5, 10);
+
+        let config = FuzzerConfig::<VecLog<AWSet<usize>>>::new(
+            "aw_set_faulty_network",
+            vec![run],
+            true,
+            |a, b| a == b,
+            false,
+        );
+
+        fuzzer::<VecLog<AWSet<usize>>>(config);
+    }
+
     #[cfg(feature = "fuzz")]
     #[test]
     #[ignore]
@@ -416,4 +917,21 @@ mod tests {
 
         fuzzer::<VecLog<AWSet<usize>>>(config);
     }
+
+    proptest! {
+        #[test]
+        fn converges_under_random_schedules(
+            schedule in convergence::schedule(
+                prop_oneof![
+                    4 => (0..5u8).prop_map(AWSet::Add),
+                    4 => (0..5u8).prop_map(AWSet::Remove),
+                    1 => Just(AWSet::Clear),
+                ],
+                40,
+            )
+        ) {
+            let values = convergence::run(schedule);
+            prop_assert!(values.iter().all(|v| *v == values[0]), "{values:?}");
+        }
+    }
 }
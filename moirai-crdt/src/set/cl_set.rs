@@ -0,0 +1,441 @@
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+#[cfg(feature = "test_utils")]
+use deepsize::DeepSizeOf;
+#[cfg(feature = "fuzz")]
+use moirai_fuzz::op_generator::OpGenerator;
+use moirai_protocol::{
+    crdt::{
+        eval::Eval,
+        pure_crdt::PureCRDT,
+        query::{Contains, QueryOperation, Read},
+        redundancy::RedundancyRelation,
+    },
+    event::{tag::Tag, tagged_op::TaggedOp},
+    state::{
+        stable_state::IsStableState,
+        unstable_state::{CausalReplay, IsUnstableCore},
+    },
+    utils::intern_str::{InternalizeOp, Interner},
+};
+#[cfg(feature = "fuzz")]
+use rand::{Rng, RngExt};
+
+#[cfg(feature = "fuzz")]
+use crate::set::SetConfig;
+use crate::{HashMap, HashSet};
+
+/// Causal-length set.
+///
+/// Each element has a causal length that only grows: an element is present if its
+/// length is odd. Adding an absent element or removing a present one increments the
+/// length, and concurrent operations are merged by keeping the greatest length.
+/// The log keeps at most the concurrent operations of greatest length per element,
+/// however many times it was added and removed.
+///
+/// Operations carry the causal length the issuing replica observed for the element,
+/// read with [`CausalLength`]. When an add and a remove are concurrent, the operation
+/// reaching the greatest length wins. If both observed the same length, only one of them
+/// changes it: a remove of a present element wins, as does an add of an absent one.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub enum CLSet<V> {
+    Add(V, usize),
+    Remove(V, usize),
+}
+
+impl<V> CLSet<V> {
+    pub fn element(&self) -> &V {
+        match self {
+            CLSet::Add(v, _) | CLSet::Remove(v, _) => v,
+        }
+    }
+
+    /// Causal length of the element once the operation is applied.
+    pub fn length(&self) -> usize {
+        match self {
+            CLSet::Add(_, l) if l % 2 == 0 => l + 1,
+            CLSet::Remove(_, l) if l % 2 == 1 => l + 1,
+            CLSet::Add(_, l) | CLSet::Remove(_, l) => *l,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CLSetRejection {
+    /// The operation was built from an outdated causal length.
+    StaleLength { observed: usize, current: usize },
+}
+
+impl Display for CLSetRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CLSetRejection::StaleLength { observed, current } => write!(
+                f,
+                "Observed causal length {observed} is stale, the current one is {current}"
+            ),
+        }
+    }
+}
+
+/// Causal length of the stable elements.
+#[derive(Debug, Clone)]
+pub struct CLSetStable<V> {
+    lengths: HashMap<V, usize>,
+}
+
+impl<V> Default for CLSetStable<V> {
+    fn default() -> Self {
+        Self {
+            lengths: HashMap::default(),
+        }
+    }
+}
+
+impl<V> IsStableState<CLSet<V>> for CLSetStable<V>
+where
+    V: Clone + Eq + Hash + Debug,
+{
+    fn is_default(&self) -> bool {
+        self.lengths.is_empty()
+    }
+
+    fn apply(&mut self, value: CLSet<V>) {
+        let length = value.length();
+        let entry = self.lengths.entry(value.element().clone()).or_default();
+        *entry = (*entry).max(length);
+    }
+
+    fn clear(&mut self) {
+        self.lengths.clear();
+    }
+
+    fn num_entries(&self) -> usize {
+        self.lengths.len()
+    }
+
+    /// The new operation reaches a greater length than the stable one.
+    fn prune_redundant_ops(
+        &mut self,
+        _rdnt: RedundancyRelation<CLSet<V>>,
+        tagged_op: &TaggedOp<CLSet<V>>,
+    ) {
+        self.lengths.remove(tagged_op.op().element());
+    }
+}
+
+/// Greatest causal length of an element among the stable and unstable operations.
+fn causal_length<'a, V>(
+    v: &V,
+    stable: &CLSetStable<V>,
+    unstable: impl Iterator<Item = &'a TaggedOp<CLSet<V>>>,
+) -> usize
+where
+    V: Eq + Hash + 'a,
+{
+    unstable
+        .filter(|t| t.op().element() == v)
+        .map(|t| t.op().length())
+        .chain(stable.lengths.get(v).copied())
+        .max()
+        .unwrap_or(0)
+}
+
+impl<V> PureCRDT for CLSet<V>
+where
+    V: Debug + Clone + Eq + Hash,
+{
+    type Value = HashSet<V>;
+    type StableState = CLSetStable<V>;
+    type Rejection = CLSetRejection;
+
+    /// A redundant operation does not reach a greater length, it prunes nothing.
+    const DISABLE_R_WHEN_R: bool = true;
+
+    fn redundant_itself<'a>(
+        new_tagged_op: &TaggedOp<Self>,
+        stable: &Self::StableState,
+        unstable: impl Iterator<Item = &'a TaggedOp<Self>>,
+    ) -> bool
+    where
+        Self: 'a,
+    {
+        let op = new_tagged_op.op();
+        op.length() <= causal_length(op.element(), stable, unstable)
+    }
+
+    fn redundant_by_when_not_redundant(
+        old_op: &Self,
+        _old_tag: Option<&Tag>,
+        _is_conc: bool,
+        new_tagged_op: &TaggedOp<Self>,
+    ) -> bool {
+        old_op.element() == new_tagged_op.op().element()
+    }
+
+    fn is_enabled(
+        op: &Self,
+        stable: &Self::StableState,
+        unstable: &impl CausalReplay<Self>,
+    ) -> Result<(), Self::Rejection> {
+        let (CLSet::Add(v, observed) | CLSet::Remove(v, observed)) = op;
+        let current = causal_length(v, stable, unstable.iter());
+        if *observed != current {
+            return Err(CLSetRejection::StaleLength {
+                observed: *observed,
+                current,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<V> InternalizeOp for CLSet<V> {
+    fn internalize(self, _interner: &Interner) -> Self {
+        self
+    }
+}
+
+/// Causal length of an element, `0` if it was never added.
+pub struct CausalLength<V>(pub V);
+
+impl<V> QueryOperation for CausalLength<V> {
+    type Response = usize;
+}
+
+impl<V, U> Eval<CausalLength<V>, U> for CLSet<V>
+where
+    V: Debug + Clone + Eq + Hash,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        q: CausalLength<V>,
+        stable: &<CLSet<V> as PureCRDT>::StableState,
+        unstable: &U,
+    ) -> <CausalLength<V> as QueryOperation>::Response {
+        causal_length(&q.0, stable, unstable.iter())
+    }
+}
+
+impl<V, U> Eval<Read<<Self as PureCRDT>::Value>, U> for CLSet<V>
+where
+    V: Debug + Clone + Eq + Hash,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        _q: Read<<Self as PureCRDT>::Value>,
+        stable: &<CLSet<V> as PureCRDT>::StableState,
+        unstable: &U,
+    ) -> <Read<<Self as PureCRDT>::Value> as QueryOperation>::Response {
+        let mut lengths: HashMap<&V, usize> = stable.lengths.iter().map(|(v, l)| (v, *l)).collect();
+        for t in unstable.iter() {
+            let entry = lengths.entry(t.op().element()).or_default();
+            *entry = (*entry).max(t.op().length());
+        }
+        lengths
+            .into_iter()
+            .filter(|(_, l)| l % 2 == 1)
+            .map(|(v, _)| v.clone())
+            .collect()
+    }
+}
+
+impl<V, U> Eval<Contains<V>, U> for CLSet<V>
+where
+    V: Debug + Clone + Eq + Hash,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        q: Contains<V>,
+        stable: &<CLSet<V> as PureCRDT>::StableState,
+        unstable: &U,
+    ) -> <Contains<V> as QueryOperation>::Response {
+        causal_length(&q.0, stable, unstable.iter()) % 2 == 1
+    }
+}
+
+#[cfg(feature = "fuzz")]
+impl OpGenerator for CLSet<usize> {
+    type Config = SetConfig;
+
+    fn generate(
+        rng: &mut impl Rng,
+        config: &mut Self::Config,
+        stable: &<Self as PureCRDT>::StableState,
+        unstable: &impl CausalReplay<Self>,
+    ) -> Self {
+        let value = rng.random_range(0..config.max_elements);
+        let length = causal_length(&value, stable, unstable.iter());
+        if rng.random_bool(0.6) {
+            CLSet::Add(value, length)
+        } else {
+            CLSet::Remove(value, length)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::{
+        crdt::query::{Contains, Read},
+        replica::IsReplica,
+        state::{log::IsLog, po_log::VecLog},
+    };
+
+    use crate::{
+        HashSet,
+        set::{
+            aw_set::AWSet,
+            cl_set::{CLSet, CausalLength},
+        },
+        utils::{
+            membership::{triplet, twins},
+            set_from_slice,
+        },
+    };
+
+    #[test]
+    fn add_remove_cl_set() {
+        let (mut replica_a, mut replica_b) = twins::<CLSet<&str>>();
+
+        let event = replica_a.send(CLSet::Add("a", 0)).unwrap();
+        replica_b.receive(event);
+        let event = replica_b.send(CLSet::Add("b", 0)).unwrap();
+        replica_a.receive(event);
+        let event = replica_a.send(CLSet::Remove("a", 1)).unwrap();
+        replica_b.receive(event);
+
+        let result = set_from_slice(&["b"]);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+        assert_eq!(replica_b.query(CausalLength("a")), 2);
+        assert!(!replica_b.query(Contains("a")));
+
+        // The length observed by the operation must be the current one
+        assert!(replica_b.send(CLSet::Add("a", 0)).is_err());
+        let event = replica_b.send(CLSet::Add("a", 2)).unwrap();
+        replica_a.receive(event);
+        assert!(replica_a.query(Contains("a")));
+        assert_eq!(replica_a.query(CausalLength("a")), 3);
+    }
+
+    #[test]
+    fn concurrent_add_remove_cl_set() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet::<CLSet<&str>>();
+
+        let event = replica_a.send(CLSet::Add("a", 0)).unwrap();
+        replica_b.receive(event.clone());
+        replica_c.receive(event);
+
+        // Same observed length: the add is a no-op and the remove wins
+        let event_a = replica_a.send(CLSet::Remove("a", 1)).unwrap();
+        let event_b = replica_b.send(CLSet::Add("a", 1)).unwrap();
+        replica_a.receive(event_b.clone());
+        replica_b.receive(event_a.clone());
+        assert_eq!(replica_a.query(Read::new()), HashSet::default());
+        assert_eq!(replica_b.query(Read::new()), HashSet::default());
+
+        // A longer concurrent history wins over the remove
+        let event_c_1 = replica_c.send(CLSet::Remove("a", 1)).unwrap();
+        let event_c_2 = replica_c.send(CLSet::Add("a", 2)).unwrap();
+        for replica in [&mut replica_a, &mut replica_b] {
+            replica.receive(event_c_1.clone());
+            replica.receive(event_c_2.clone());
+        }
+        replica_c.receive(event_b);
+        replica_c.receive(event_a);
+
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), set_from_slice(&["a"]));
+            assert_eq!(replica.query(CausalLength("a")), 3);
+        }
+    }
+
+    #[test]
+    fn concurrent_adds_cl_set() {
+        let (mut replica_a, mut replica_b) = twins::<CLSet<&str>>();
+
+        // As in an add-wins set, concurrent adds of an absent element keep it
+        let event_a = replica_a.send(CLSet::Add("a", 0)).unwrap();
+        let event_b_1 = replica_b.send(CLSet::Add("a", 0)).unwrap();
+        replica_a.receive(event_b_1);
+        replica_b.receive(event_a);
+        assert_eq!(replica_a.query(Read::new()), set_from_slice(&["a"]));
+        assert_eq!(replica_b.query(Read::new()), set_from_slice(&["a"]));
+
+        let event = replica_b.send(CLSet::Remove("a", 1)).unwrap();
+        replica_a.receive(event);
+        assert_eq!(replica_a.query(Read::new()), HashSet::default());
+        assert_eq!(replica_b.query(Read::new()), HashSet::default());
+    }
+
+    #[test]
+    fn size_under_churn() {
+        let (mut cl_a, mut cl_b) = twins::<CLSet<usize>>();
+        let (mut aw_a, mut aw_b) = twins::<AWSet<usize>>();
+
+        for i in 0..1_000 {
+            let v = i % 10;
+            let length = cl_a.query(CausalLength(v));
+            let (op, aw_op) = if length % 2 == 0 {
+                (CLSet::Add(v, length), AWSet::Add(v))
+            } else {
+                (CLSet::Remove(v, length), AWSet::Remove(v))
+            };
+            let event = cl_a.send(op).unwrap();
+            cl_b.receive(event);
+            let event = aw_a.send(aw_op).unwrap();
+            aw_b.receive(event);
+
+            if i % 100 == 99 {
+                // Acknowledge the events of replica a to let them stabilize
+                let length = cl_b.query(CausalLength(0));
+                let op = if length % 2 == 0 {
+                    CLSet::Add(0, length)
+                } else {
+                    CLSet::Remove(0, length)
+                };
+                let event = cl_b.send(op.clone()).unwrap();
+                cl_a.receive(event);
+                let aw_op = match op {
+                    CLSet::Add(v, _) => AWSet::Add(v),
+                    CLSet::Remove(v, _) => AWSet::Remove(v),
+                };
+                let event = aw_b.send(aw_op).unwrap();
+                aw_a.receive(event);
+
+                let cl = cl_a.state().prune_stats();
+                let aw = aw_a.state().prune_stats();
+                // At most one entry per element, whatever the number of cycles.
+                // Unlike the add-wins set, absent elements keep their length.
+                assert!(cl.live_events + cl.stable_ops <= 10);
+                assert!(aw.live_events + aw.stable_ops <= cl.live_events + cl.stable_ops);
+            }
+        }
+
+        assert_eq!(cl_a.query(Read::new()), aw_a.query(Read::new()));
+        assert_eq!(cl_a.query(Read::new()), cl_b.query(Read::new()));
+        assert_eq!(cl_a.query(CausalLength(1)), 100);
+    }
+
+    #[cfg(feature = "fuzz")]
+    #[test]
+    #[ignore]
+    fn fuzz_cl_set() {
+        use moirai_fuzz::{
+            config::{FuzzerConfig, RunConfig},
+            fuzzer::fuzzer,
+        };
+
+        let run = RunConfig::new(0.4, 8, 10_000, None, None, false, false);
+        let runs = vec![run.clone(); 10];
+
+        let config =
+            FuzzerConfig::<VecLog<CLSet<usize>>>::new("cl_set", runs, true, |a, b| a == b, false);
+
+        fuzzer::<VecLog<CLSet<usize>>>(config);
+    }
+}
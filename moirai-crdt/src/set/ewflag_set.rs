@@ -7,7 +7,11 @@ use moirai_protocol::{
         query::{QueryOperation, Read},
     },
     event::Event,
-    state::{effect_context::EffectContext, log::IsLog, po_log::VecLog},
+    state::{
+        effect_context::EffectContext,
+        log::{IsLog, PruneStats},
+        po_log::VecLog,
+    },
     utils::intern_str::{InternalizeOp, Interner},
 };
 
@@ -79,6 +83,10 @@ where
     fn is_default(&self) -> bool {
         self.inner.is_default()
     }
+
+    fn prune_stats(&self) -> PruneStats {
+        self.inner.prune_stats()
+    }
 }
 
 impl<V> EvalNested<Read<HashSet<V>>> for EWFlagSetLog<V>
@@ -0,0 +1,285 @@
+use std::{convert::Infallible, fmt::Debug, hash::Hash};
+
+#[cfg(feature = "test_utils")]
+use deepsize::DeepSizeOf;
+#[cfg(feature = "fuzz")]
+use moirai_fuzz::op_generator::OpGenerator;
+use moirai_protocol::{
+    crdt::{
+        eval::Eval,
+        pure_crdt::{PureCRDT, StableFold},
+        query::{Contains, QueryOperation, Read},
+        redundancy::RedundancyRelation,
+    },
+    event::tagged_op::TaggedOp,
+    state::{
+        stable_state::IsStableState,
+        unstable_state::{CausalReplay, IsUnstableCore},
+    },
+    utils::intern_str::{InternalizeOp, Interner},
+};
+#[cfg(feature = "fuzz")]
+use rand::Rng;
+
+use crate::HashSet;
+#[cfg(feature = "fuzz")]
+use crate::set::SetConfig;
+
+/// Grow-only set: the value is the union of the added elements.
+/// No operation is ever redundant, and stable adds are merged into a plain set.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub enum GSet<V> {
+    Add(V),
+}
+
+#[derive(Clone, Debug)]
+pub struct GSetStable<V> {
+    elements: HashSet<V>,
+}
+
+impl<V> Default for GSetStable<V> {
+    fn default() -> Self {
+        Self {
+            elements: HashSet::default(),
+        }
+    }
+}
+
+impl<V> IsStableState<GSet<V>> for GSetStable<V>
+where
+    V: Clone + Eq + Hash + Debug,
+{
+    fn is_default(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    fn apply(&mut self, value: GSet<V>) {
+        let GSet::Add(v) = value;
+        self.elements.insert(v);
+    }
+
+    fn clear(&mut self) {
+        self.elements.clear();
+    }
+
+    fn num_entries(&self) -> usize {
+        self.elements.len()
+    }
+
+    fn prune_redundant_ops(
+        &mut self,
+        _rdnt: RedundancyRelation<GSet<V>>,
+        _tagged_op: &TaggedOp<GSet<V>>,
+    ) {
+    }
+}
+
+impl<V> PureCRDT for GSet<V>
+where
+    V: Debug + Clone + Hash + Eq,
+{
+    type Value = HashSet<V>;
+    type StableState = GSetStable<V>;
+    type Rejection = Infallible;
+
+    const DISABLE_R_WHEN_R: bool = true;
+    const DISABLE_R_WHEN_NOT_R: bool = true;
+}
+
+impl<V> StableFold for GSet<V> where V: Debug + Clone + Hash + Eq {}
+
+impl<V> InternalizeOp for GSet<V> {
+    fn internalize(self, _interner: &Interner) -> Self {
+        self
+    }
+}
+
+impl<V, U> Eval<Read<<Self as PureCRDT>::Value>, U> for GSet<V>
+where
+    V: Debug + Clone + Eq + Hash,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        _q: Read<<Self as PureCRDT>::Value>,
+        stable: &<GSet<V> as PureCRDT>::StableState,
+        unstable: &U,
+    ) -> <Read<<Self as PureCRDT>::Value> as QueryOperation>::Response {
+        let mut set = stable.elements.clone();
+        for GSet::Add(v) in unstable.iter().map(|t| t.op()) {
+            set.insert(v.clone());
+        }
+        set
+    }
+}
+
+impl<V, U> Eval<Contains<V>, U> for GSet<V>
+where
+    V: Debug + Clone + Eq + Hash,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        q: Contains<V>,
+        stable: &<GSet<V> as PureCRDT>::StableState,
+        unstable: &U,
+    ) -> <Contains<V> as QueryOperation>::Response {
+        stable.elements.contains(&q.0)
+            || unstable
+                .iter()
+                .any(|t| matches!(t.op(), GSet::Add(v) if v == &q.0))
+    }
+}
+
+#[cfg(feature = "fuzz")]
+impl OpGenerator for GSet<String> {
+    type Config = SetConfig;
+
+    fn generate(
+        rng: &mut impl Rng,
+        config: &mut Self::Config,
+        _stable: &<Self as PureCRDT>::StableState,
+        _unstable: &impl CausalReplay<Self>,
+    ) -> Self {
+        let letters: Vec<String> = (0..config.max_elements).map(|i| format!("{i}")).collect();
+        let choice = rand::seq::IteratorRandom::choose(letters.iter(), rng)
+            .unwrap()
+            .clone();
+        GSet::Add(choice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::{
+        crdt::query::{Contains, Read},
+        replica::IsReplica,
+        state::{log::IsLog, po_log::VecLog},
+    };
+
+    use crate::{
+        set::{aw_set::AWSet, g_set::GSet},
+        utils::{
+            membership::{triplet, twins, twins_log},
+            set_from_slice,
+        },
+    };
+
+    #[test]
+    fn g_set_from_iter() {
+        let elements = ["a", "b", "c", "b"];
+
+        let (mut replica_a, mut replica_b) = twins::<GSet<&str>>();
+        replica_a.extend(elements.map(GSet::Add));
+        replica_b.receive_batch(replica_a.pull(replica_b.since()));
+
+        let result = set_from_slice(&elements);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+
+        let log: VecLog<GSet<&str>> = elements.into_iter().map(GSet::Add).collect();
+        assert_eq!(log.eval(Read::new()), result);
+    }
+
+    #[test]
+    fn add_g_set() {
+        let (mut replica_a, mut replica_b) = twins::<GSet<&str>>();
+
+        let event = replica_a.send(GSet::Add("a")).unwrap();
+        replica_b.receive(event);
+        let event = replica_b.send(GSet::Add("b")).unwrap();
+        replica_a.receive(event);
+        let event = replica_b.send(GSet::Add("a")).unwrap();
+        replica_a.receive(event);
+
+        let result = set_from_slice(&["a", "b"]);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+        assert!(replica_a.query(Contains("a")));
+        assert!(!replica_a.query(Contains("c")));
+    }
+
+    #[test]
+    fn concurrent_adds_converge() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet::<GSet<&str>>();
+
+        let event_a = replica_a.send(GSet::Add("a")).unwrap();
+        let event_b = replica_b.send(GSet::Add("b")).unwrap();
+        let event_c = replica_c.send(GSet::Add("a")).unwrap();
+        for (replica, events) in [
+            (&mut replica_a, [&event_b, &event_c]),
+            (&mut replica_b, [&event_c, &event_a]),
+            (&mut replica_c, [&event_a, &event_b]),
+        ] {
+            for event in events {
+                replica.receive(event.clone());
+            }
+        }
+
+        let result = set_from_slice(&["a", "b"]);
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), result);
+        }
+
+        // Once stable, the adds are merged into the stable set
+        let event = replica_a.send(GSet::Add("c")).unwrap();
+        replica_b.receive(event.clone());
+        replica_c.receive(event);
+        let event = replica_b.send(GSet::Add("d")).unwrap();
+        replica_a.receive(event.clone());
+        replica_c.receive(event);
+
+        let result = set_from_slice(&["a", "b", "c", "d"]);
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), result);
+        }
+    }
+
+    #[test]
+    fn size_under_pure_adds() {
+        let (mut g_a, mut g_b) = twins_log::<VecLog<GSet<usize>>>();
+        let (mut aw_a, mut aw_b) = twins_log::<VecLog<AWSet<usize>>>();
+
+        for i in 0..1_000 {
+            let event = g_a.send(GSet::Add(i % 50)).unwrap();
+            g_b.receive(event);
+            let event = aw_a.send(AWSet::Add(i % 50)).unwrap();
+            aw_b.receive(event);
+
+            if i % 100 == 99 {
+                // Acknowledge the events of replica a to let them stabilize
+                let event = g_b.send(GSet::Add(0)).unwrap();
+                g_a.receive(event);
+                let event = aw_b.send(AWSet::Add(0)).unwrap();
+                aw_a.receive(event);
+
+                let g = g_a.state().prune_stats();
+                let aw = aw_a.state().prune_stats();
+                // A stable grow-only set keeps one entry per distinct element
+                assert!(g.stable_ops <= 50);
+                assert_eq!(g.tombstones, 0);
+                assert!(g.live_events + g.stable_ops <= aw.live_events + aw.stable_ops + 1);
+            }
+        }
+
+        assert_eq!(g_a.query(Read::new()), aw_a.query(Read::new()));
+    }
+
+    #[cfg(feature = "fuzz")]
+    #[test]
+    #[ignore]
+    fn fuzz_g_set() {
+        use moirai_fuzz::{
+            config::{FuzzerConfig, RunConfig},
+            fuzzer::fuzzer,
+        };
+        use moirai_protocol::state::po_log::VecLog;
+
+        let run = RunConfig::new(0.4, 8, 10_000, None, None, false, false);
+        let runs = vec![run.clone(); 10];
+
+        let config =
+            FuzzerConfig::<VecLog<GSet<String>>>::new("g_set", runs, true, |a, b| a == b, false);
+
+        fuzzer::<VecLog<GSet<String>>>(config);
+    }
+}
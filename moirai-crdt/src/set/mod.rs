@@ -1,7 +1,11 @@
 pub mod aw_set;
+pub mod cl_set;
 pub mod ewflag_set;
+pub mod g_set;
 pub mod rw_set;
+pub mod tp_set;
 
+#[derive(Clone)]
 pub struct SetConfig {
     pub max_elements: usize,
 }
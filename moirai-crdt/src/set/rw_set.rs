@@ -31,6 +31,9 @@ pub enum RWSet<V> {
     Add(V),
     Remove(V),
     Clear,
+    /// Clear that also drops the removes it sees: unlike [`RWSet::Clear`], an element
+    /// removed before the clear is re-added by an add concurrent with the clear.
+    ClearExceptLater,
 }
 
 // TODO: maybe two hashsets is better?
@@ -50,7 +53,7 @@ where
             RWSet::Remove(_) => {
                 self.1.push(value);
             }
-            RWSet::Clear => unreachable!(),
+            RWSet::Clear | RWSet::ClearExceptLater => unreachable!(),
         }
     }
 
@@ -59,6 +62,10 @@ where
         self.1.clear();
     }
 
+    fn num_entries(&self) -> usize {
+        self.0.len() + self.1.len()
+    }
+
     fn prune_redundant_ops(
         &mut self,
         _rdnt: RedundancyRelation<RWSet<V>>,
@@ -77,6 +84,10 @@ where
             RWSet::Clear => {
                 self.0.clear();
             }
+            RWSet::ClearExceptLater => {
+                self.0.clear();
+                self.1.clear();
+            }
         }
     }
 }
@@ -97,7 +108,7 @@ where
     where
         Self: 'a,
     {
-        matches!(new_tagged_op.op(), RWSet::Clear)
+        matches!(new_tagged_op.op(), RWSet::Clear | RWSet::ClearExceptLater)
     }
 
     fn redundant_by_when_redundant(
@@ -116,7 +127,10 @@ where
                 // otherwise it would fail to preserve the remove against concurrent add.
                 (RWSet::Add(_), RWSet::Clear) => true,
                 (RWSet::Remove(_), RWSet::Clear) => false,
-                (RWSet::Clear, _) => unreachable!(),
+                // Dropping the prior removes lets concurrent adds win over them.
+                (RWSet::Add(_), RWSet::ClearExceptLater)
+                | (RWSet::Remove(_), RWSet::ClearExceptLater) => true,
+                (RWSet::Clear, _) | (RWSet::ClearExceptLater, _) => unreachable!(),
             }
     }
 
@@ -129,6 +143,11 @@ where
         Self::redundant_by_when_redundant(old_op, old_tag, is_conc, new_tagged_op)
     }
 
+    /// A remove is kept to win over concurrent adds of the element.
+    fn is_tombstone(op: &Self) -> bool {
+        matches!(op, RWSet::Remove(_))
+    }
+
     fn stabilize<'a>(
         tagged_op: &TaggedOp<Self>,
         stable: &mut Self::StableState,
@@ -160,7 +179,7 @@ where
                     unstable.remove(tagged_op.id());
                 }
             }
-            RWSet::Clear => unreachable!(),
+            RWSet::Clear | RWSet::ClearExceptLater => unreachable!(),
         }
     }
 }
@@ -200,7 +219,7 @@ where
                     set.remove(v);
                     removed.insert(v);
                 }
-                RWSet::Clear => unreachable!(),
+                RWSet::Clear | RWSet::ClearExceptLater => unreachable!(),
             }
         }
 
@@ -249,7 +268,7 @@ impl OpGenerator for RWSet<String> {
 
     fn generate(
         rng: &mut impl Rng,
-        config: &Self::Config,
+        config: &mut Self::Config,
         _stable: &<Self as PureCRDT>::StableState,
         _unstable: &impl CausalReplay<Self>,
     ) -> Self {
@@ -317,6 +336,55 @@ mod tests {
         assert_eq!(replica_b.query(Contains("a")), false);
     }
 
+    #[test]
+    fn clear_except_later_lets_concurrent_add_win_over_prior_remove() {
+        // Same history as `clear_preserves_prior_remove_against_concurrent_add`:
+        // plain `Clear` keeps the remove, which wins over the add, whereas
+        // `ClearExceptLater` prunes it and lets the add through
+        for (clear, expected) in [(RWSet::Clear, vec![]), (RWSet::ClearExceptLater, vec!["a"])] {
+            let (mut replica_a, mut replica_b) = twins::<RWSet<&str>>();
+
+            let remove = replica_a.send(RWSet::Remove("a")).unwrap();
+            let clear = replica_a.send(clear).unwrap();
+            let add = replica_b.send(RWSet::Add("a")).unwrap();
+
+            replica_a.receive(add);
+            replica_b.receive(remove);
+            replica_b.receive(clear);
+
+            let result = set_from_slice(&expected);
+            assert_eq!(replica_a.query(Read::new()), result);
+            assert_eq!(replica_b.query(Read::new()), result);
+            assert_eq!(replica_a.query(Contains("a")), !expected.is_empty());
+            assert_eq!(replica_b.query(Contains("a")), !expected.is_empty());
+        }
+    }
+
+    #[test]
+    fn clear_except_later_keeps_concurrent_remove() {
+        let (mut replica_a, mut replica_b) = twins::<RWSet<&str>>();
+
+        let event = replica_a.send(RWSet::Add("a")).unwrap();
+        replica_b.receive(event);
+        let event = replica_a.send(RWSet::Add("b")).unwrap();
+        replica_b.receive(event);
+
+        let clear = replica_a.send(RWSet::ClearExceptLater).unwrap();
+        let add = replica_a.send(RWSet::Add("a")).unwrap();
+        let remove = replica_b.send(RWSet::Remove("a")).unwrap();
+        let add_c = replica_b.send(RWSet::Add("c")).unwrap();
+
+        replica_a.receive(remove);
+        replica_a.receive(add_c);
+        replica_b.receive(clear);
+        replica_b.receive(add);
+
+        // The remove concurrent with the clear still wins over the add after it
+        let result = set_from_slice(&["c"]);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+    }
+
     // Note: Following tests are reproduction of same simulation in Figure 18 of the “Pure Operation-Based CRDTs” paper.
 
     #[test]
@@ -438,6 +506,36 @@ mod tests {
         assert_eq!(replica_b.query(Read::new()), set_from_slice(&[]));
     }
 
+    #[test]
+    fn prune_stats_tombstones_dropped_once_stable() {
+        use moirai_protocol::state::log::IsLog;
+
+        let (mut replica_a, mut replica_b) = twins::<RWSet<&str>>();
+
+        for v in ["a", "b", "c", "d"] {
+            let event = replica_a.send(RWSet::Add(v)).unwrap();
+            replica_b.receive(event);
+            let event = replica_a.send(RWSet::Remove(v)).unwrap();
+            replica_b.receive(event);
+        }
+
+        // Each remove pruned its add, but is kept against a concurrent add
+        let before = replica_a.state().prune_stats();
+        assert_eq!(before.tombstones, 4);
+        assert_eq!(before.live_events, 0);
+
+        // Replica b acknowledges the removes: they become stable and are dropped.
+        // Both replicas have seen the add of b, so it is stable too
+        let event = replica_b.send(RWSet::Add("e")).unwrap();
+        replica_a.receive(event);
+
+        let after = replica_a.state().prune_stats();
+        assert_eq!(after.tombstones, 0);
+        assert_eq!(after.live_events, 0);
+        assert_eq!(after.stable_ops, 1);
+        assert!(after.bytes_estimate < before.bytes_estimate);
+    }
+
     #[cfg(feature = "fuzz")]
     #[test]
     #[ignore]
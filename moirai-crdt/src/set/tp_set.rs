@@ -0,0 +1,340 @@
+use std::{convert::Infallible, fmt::Debug, hash::Hash};
+
+#[cfg(feature = "test_utils")]
+use deepsize::DeepSizeOf;
+#[cfg(feature = "fuzz")]
+use moirai_fuzz::op_generator::OpGenerator;
+use moirai_protocol::{
+    crdt::{
+        eval::Eval,
+        pure_crdt::PureCRDT,
+        query::{Contains, QueryOperation, Read},
+        redundancy::RedundancyRelation,
+    },
+    event::{tag::Tag, tagged_op::TaggedOp},
+    state::{
+        stable_state::IsStableState,
+        unstable_state::{CausalReplay, IsUnstableCore},
+    },
+    utils::intern_str::{InternalizeOp, Interner},
+};
+#[cfg(feature = "fuzz")]
+use rand::Rng;
+
+use crate::HashSet;
+#[cfg(feature = "fuzz")]
+use crate::set::SetConfig;
+
+/// Two-phase set: a removed element is tombstoned forever,
+/// any later or concurrent add of the same element is ignored.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub enum TPSet<V> {
+    Add(V),
+    Remove(V),
+}
+
+#[derive(Clone, Debug)]
+pub struct TPSetStable<V> {
+    added: HashSet<V>,
+    tombstones: HashSet<V>,
+}
+
+impl<V> Default for TPSetStable<V> {
+    fn default() -> Self {
+        Self {
+            added: HashSet::default(),
+            tombstones: HashSet::default(),
+        }
+    }
+}
+
+impl<V> IsStableState<TPSet<V>> for TPSetStable<V>
+where
+    V: Clone + Eq + Hash + Debug,
+{
+    fn is_default(&self) -> bool {
+        self.added.is_empty() && self.tombstones.is_empty()
+    }
+
+    fn apply(&mut self, value: TPSet<V>) {
+        match value {
+            TPSet::Add(v) => {
+                if !self.tombstones.contains(&v) {
+                    self.added.insert(v);
+                }
+            }
+            TPSet::Remove(v) => {
+                self.added.remove(&v);
+                self.tombstones.insert(v);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.added.clear();
+        self.tombstones.clear();
+    }
+
+    fn num_entries(&self) -> usize {
+        self.added.len() + self.tombstones.len()
+    }
+
+    fn prune_redundant_ops(
+        &mut self,
+        _rdnt: RedundancyRelation<TPSet<V>>,
+        tagged_op: &TaggedOp<TPSet<V>>,
+    ) {
+        if let TPSet::Remove(v) = tagged_op.op() {
+            self.added.remove(v);
+        }
+    }
+}
+
+impl<V> PureCRDT for TPSet<V>
+where
+    V: Debug + Clone + Hash + Eq,
+{
+    type Value = HashSet<V>;
+    type StableState = TPSetStable<V>;
+    type Rejection = Infallible;
+
+    /// An add of a removed element, or a second remove of the same element, has no effect.
+    fn redundant_itself<'a>(
+        new_tagged_op: &TaggedOp<Self>,
+        stable: &Self::StableState,
+        mut unstable: impl Iterator<Item = &'a TaggedOp<Self>>,
+    ) -> bool
+    where
+        Self: 'a,
+    {
+        let (TPSet::Add(v) | TPSet::Remove(v)) = new_tagged_op.op();
+        stable.tombstones.contains(v)
+            || unstable.any(|t| matches!(t.op(), TPSet::Remove(v2) if v == v2))
+    }
+
+    fn redundant_by_when_redundant(
+        old_op: &Self,
+        _old_tag: Option<&Tag>,
+        is_conc: bool,
+        new_tagged_op: &TaggedOp<Self>,
+    ) -> bool {
+        match (old_op, new_tagged_op.op()) {
+            // A remove wins over every add of the element, even concurrent ones
+            (TPSet::Add(v1), TPSet::Remove(v2)) => v1 == v2,
+            (TPSet::Add(v1), TPSet::Add(v2)) => !is_conc && v1 == v2,
+            (TPSet::Remove(_), _) => false,
+        }
+    }
+
+    fn redundant_by_when_not_redundant(
+        old_op: &Self,
+        old_tag: Option<&Tag>,
+        is_conc: bool,
+        new_tagged_op: &TaggedOp<Self>,
+    ) -> bool {
+        Self::redundant_by_when_redundant(old_op, old_tag, is_conc, new_tagged_op)
+    }
+
+    fn is_tombstone(op: &Self) -> bool {
+        matches!(op, TPSet::Remove(_))
+    }
+}
+
+impl<V> InternalizeOp for TPSet<V> {
+    fn internalize(self, _interner: &Interner) -> Self {
+        self
+    }
+}
+
+impl<V, U> Eval<Read<<Self as PureCRDT>::Value>, U> for TPSet<V>
+where
+    V: Debug + Clone + Eq + Hash,
+    U: IsUnstableCore<Self>,
+{
+    fn execute_query(
+        _q: Read<<Self as PureCRDT>::Value>,
+        stable: &<TPSet<V> as PureCRDT>::StableState,
+        unstable: &U,
+    ) -> <Read<<Self as PureCRDT>::Value> as QueryOperation>::Response {
+        let mut set = stable.added.clone();
+        let mut removed = HashSet::default();
+
+        for o in unstable.iter().map(|t| t.op()) {
+            match o {
+                TPSet::Add(v) => {
+                    set.insert(v.clone());
+                }
+                TPSet::Remove(v) => {
+                    removed.insert(v);
+                }
+            }
+        }
+
+        set.retain(|v| !removed.contains(v) && !stable.tombstones.contains(v));
+        set
+    }
+}
+
+impl<V, U> Eval<Contains<V>, U> for TPSet<V>
+where
+    V: Debug + Clone + Eq + Hash,
+    U: IsUnstableCore<Self>,
+{
+    /// Semantics: there exists an 'add' for the element, and the element was never removed.
+    fn execute_query(
+        q: Contains<V>,
+        stable: &<TPSet<V> as PureCRDT>::StableState,
+        unstable: &U,
+    ) -> <Contains<V> as QueryOperation>::Response {
+        if stable.tombstones.contains(&q.0) {
+            return false;
+        }
+        let mut present = stable.added.contains(&q.0);
+        for op in unstable.iter().map(|t| t.op()) {
+            match op {
+                TPSet::Add(v) if v == &q.0 => present = true,
+                TPSet::Remove(v) if v == &q.0 => return false,
+                _ => {}
+            }
+        }
+        present
+    }
+}
+
+#[cfg(feature = "fuzz")]
+impl OpGenerator for TPSet<String> {
+    type Config = SetConfig;
+
+    fn generate(
+        rng: &mut impl Rng,
+        config: &mut Self::Config,
+        _stable: &<Self as PureCRDT>::StableState,
+        _unstable: &impl CausalReplay<Self>,
+    ) -> Self {
+        let letters: Vec<String> = (0..config.max_elements).map(|i| format!("{i}")).collect();
+        let choice = rand::seq::IteratorRandom::choose(letters.iter(), rng)
+            .unwrap()
+            .clone();
+        // Removes are final: keep them rarer than adds
+        if rng.next_u32().is_multiple_of(4) {
+            TPSet::Remove(choice)
+        } else {
+            TPSet::Add(choice)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::{
+        crdt::query::{Contains, Read},
+        replica::IsReplica,
+    };
+
+    use crate::{
+        set::tp_set::TPSet,
+        utils::{
+            membership::{triplet, twins},
+            set_from_slice,
+        },
+    };
+
+    #[test]
+    fn add_remove_tp_set() {
+        let (mut replica_a, mut replica_b) = twins::<TPSet<&str>>();
+
+        let event = replica_a.send(TPSet::Add("a")).unwrap();
+        replica_b.receive(event);
+        let event = replica_b.send(TPSet::Add("b")).unwrap();
+        replica_a.receive(event);
+
+        let result = set_from_slice(&["a", "b"]);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+
+        let event = replica_a.send(TPSet::Remove("a")).unwrap();
+        replica_b.receive(event);
+
+        let result = set_from_slice(&["b"]);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+    }
+
+    #[test]
+    fn remove_then_add_stays_removed() {
+        let (mut replica_a, mut replica_b) = twins::<TPSet<&str>>();
+
+        let event = replica_a.send(TPSet::Add("a")).unwrap();
+        replica_b.receive(event);
+        let event = replica_a.send(TPSet::Remove("a")).unwrap();
+        replica_b.receive(event);
+        let event = replica_b.send(TPSet::Add("a")).unwrap();
+        replica_a.receive(event);
+
+        let result = set_from_slice(&[]);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+        assert!(!replica_a.query(Contains("a")));
+        assert!(!replica_b.query(Contains("a")));
+    }
+
+    #[test]
+    fn concurrent_add_remove_removed_wins() {
+        let (mut replica_a, mut replica_b, mut replica_c) = triplet::<TPSet<&str>>();
+
+        let event_a = replica_a.send(TPSet::Add("a")).unwrap();
+        let event_b = replica_b.send(TPSet::Remove("a")).unwrap();
+
+        replica_a.receive(event_b.clone());
+        replica_b.receive(event_a.clone());
+        // Opposite delivery order
+        replica_c.receive(event_b);
+        replica_c.receive(event_a);
+
+        let result = set_from_slice(&[]);
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), result);
+            assert!(!replica.query(Contains("a")));
+        }
+
+        // Removed permanently, even once the remove is stable
+        let event_c = replica_c.send(TPSet::Add("a")).unwrap();
+        let event_a = replica_a.send(TPSet::Add("b")).unwrap();
+        let event_b = replica_b.send(TPSet::Add("a")).unwrap();
+        for (replica, events) in [
+            (&mut replica_a, [&event_b, &event_c]),
+            (&mut replica_b, [&event_a, &event_c]),
+            (&mut replica_c, [&event_a, &event_b]),
+        ] {
+            for event in events {
+                replica.receive(event.clone());
+            }
+        }
+
+        let result = set_from_slice(&["b"]);
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), result);
+            assert!(!replica.query(Contains("a")));
+        }
+    }
+
+    #[cfg(feature = "fuzz")]
+    #[test]
+    #[ignore]
+    fn fuzz_tp_set() {
+        use moirai_fuzz::{
+            config::{FuzzerConfig, RunConfig},
+            fuzzer::fuzzer,
+        };
+        use moirai_protocol::state::po_log::VecLog;
+
+        let run = RunConfig::new(0.4, 4, 10, None, None, false, false);
+        let runs = vec![run.clone(); 10_000];
+
+        let config =
+            FuzzerConfig::<VecLog<TPSet<String>>>::new("tp_set", runs, true, |a, b| a == b, false);
+
+        fuzzer::<VecLog<TPSet<String>>>(config);
+    }
+}
@@ -0,0 +1,174 @@
+//! Per-replica undo/redo.
+//!
+//! Undoing an operation does not rewrite history: the inverse of the operation is
+//! broadcast as a new event, so that it is ordered like any other operation with
+//! respect to concurrent ones. E.g., undoing an `AWSet::Add` removes the element
+//! only for the adds it observed, and a concurrent add by another replica survives.
+
+use moirai_protocol::{
+    broadcast::message::EventMessage, crdt::pure_crdt::PureCRDT, replica::IsReplica,
+    state::log::IsLog,
+};
+
+/// Operations that can be compensated by another operation.
+pub trait Invertible: PureCRDT + Clone {
+    /// Operation cancelling the effect of `self`, or `None` if there is none,
+    /// e.g., for a reset that loses the previous value.
+    fn inverse(&self) -> Option<Self>;
+}
+
+/// Undo and redo stacks of the operations issued by one replica.
+#[derive(Debug, Clone)]
+pub struct UndoLog<O> {
+    undo: Vec<O>,
+    redo: Vec<O>,
+}
+
+impl<O> Default for UndoLog<O> {
+    fn default() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+}
+
+impl<O> UndoLog<O>
+where
+    O: Invertible,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Send `op` through `replica` and record it as the last undoable operation.
+    /// A new operation discards the redo stack. An operation without inverse
+    /// also discards the undo stack, as the operations before it can no longer be undone.
+    pub fn send<L, R>(&mut self, replica: &mut R, op: O) -> Result<EventMessage<O>, L::Rejection>
+    where
+        L: IsLog<Op = O>,
+        R: IsReplica<L>,
+    {
+        let message = replica.send(op.clone())?;
+        self.redo.clear();
+        if op.inverse().is_some() {
+            self.undo.push(op);
+        } else {
+            self.undo.clear();
+        }
+        Ok(message)
+    }
+
+    /// Send the inverse of the last recorded operation, if any.
+    /// If the inverse is rejected, the operation stays on the undo stack.
+    pub fn undo<L, R>(&mut self, replica: &mut R) -> Option<Result<EventMessage<O>, L::Rejection>>
+    where
+        L: IsLog<Op = O>,
+        R: IsReplica<L>,
+    {
+        let op = self.undo.pop()?;
+        let inverse = op
+            .inverse()
+            .expect("only invertible operations are recorded");
+        let result = replica.send(inverse);
+        if result.is_ok() {
+            self.redo.push(op);
+        } else {
+            self.undo.push(op);
+        }
+        Some(result)
+    }
+
+    /// Send again the last undone operation, if any.
+    pub fn redo<L, R>(&mut self, replica: &mut R) -> Option<Result<EventMessage<O>, L::Rejection>>
+    where
+        L: IsLog<Op = O>,
+        R: IsReplica<L>,
+    {
+        let op = self.redo.pop()?;
+        let result = replica.send(op.clone());
+        if result.is_ok() {
+            self.undo.push(op);
+        } else {
+            self.redo.push(op);
+        }
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::{crdt::query::Read, replica::IsReplica};
+
+    use crate::{
+        counter::resettable_counter::Counter,
+        set::aw_set::AWSet,
+        undo::UndoLog,
+        utils::{membership::twins, set_from_slice},
+    };
+
+    #[test]
+    fn undo_redo_counter() {
+        let (mut replica_a, mut replica_b) = twins::<Counter<i32>>();
+        let mut undo_a = UndoLog::new();
+
+        let event = undo_a.send(&mut replica_a, Counter::Inc(5)).unwrap();
+        replica_b.receive(event);
+        let event = replica_b.send(Counter::Inc(2)).unwrap();
+        replica_a.receive(event);
+        assert_eq!(replica_a.query(Read::new()), 7);
+
+        // Only the increment of replica a is undone
+        let event = undo_a.undo(&mut replica_a).unwrap().unwrap();
+        replica_b.receive(event);
+        assert_eq!(replica_a.query(Read::new()), 2);
+        assert_eq!(replica_b.query(Read::new()), 2);
+        assert!(undo_a.undo(&mut replica_a).is_none());
+
+        let event = undo_a.redo(&mut replica_a).unwrap().unwrap();
+        replica_b.receive(event);
+        assert_eq!(replica_a.query(Read::new()), 7);
+        assert_eq!(replica_b.query(Read::new()), 7);
+
+        // A reset cannot be undone, nor what precedes it
+        undo_a.send(&mut replica_a, Counter::Reset).unwrap();
+        assert!(!undo_a.can_undo());
+        assert!(!undo_a.can_redo());
+    }
+
+    #[test]
+    fn undo_set_add() {
+        let (mut replica_a, mut replica_b) = twins::<AWSet<&str>>();
+        let mut undo_a = UndoLog::new();
+
+        let event = undo_a.send(&mut replica_a, AWSet::Add("a")).unwrap();
+        replica_b.receive(event);
+        let event = undo_a.send(&mut replica_a, AWSet::Add("b")).unwrap();
+        replica_b.receive(event);
+
+        // The undo is concurrent with an add of the same element by replica b
+        let event_a = undo_a.undo(&mut replica_a).unwrap().unwrap();
+        let event_b = replica_b.send(AWSet::Add("b")).unwrap();
+        replica_a.receive(event_b);
+        replica_b.receive(event_a);
+
+        let result = set_from_slice(&["a", "b"]);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+
+        let event = undo_a.undo(&mut replica_a).unwrap().unwrap();
+        replica_b.receive(event);
+
+        let result = set_from_slice(&["b"]);
+        assert_eq!(replica_a.query(Read::new()), result);
+        assert_eq!(replica_b.query(Read::new()), result);
+    }
+}
@@ -0,0 +1,85 @@
+//! Property-based convergence checks: replicas send random operations and receive each
+//! other's messages in a random order. When a property fails, proptest shrinks the
+//! schedule down to a minimal counterexample.
+
+use moirai_protocol::{
+    broadcast::message::EventMessage,
+    crdt::{eval::EvalNested, pure_crdt::PureCRDT, query::Read},
+    replica::IsReplica,
+    state::{log::IsLog, po_log::VecLog},
+    utils::intern_str::InternalizeOp,
+};
+use proptest::{prelude::*, sample::Index};
+
+use crate::utils::membership::triplet;
+
+pub const REPLICAS: usize = 3;
+
+#[derive(Debug, Clone)]
+pub enum Step<O> {
+    /// The replica sends the operation to the others.
+    Send(usize, O),
+    /// The replica receives one of the messages still in flight towards it.
+    Deliver(usize, Index),
+}
+
+/// Schedules of up to `max_len` steps, sending operations drawn from `op`.
+pub fn schedule<O>(
+    op: impl Strategy<Value = O> + 'static,
+    max_len: usize,
+) -> impl Strategy<Value = Vec<Step<O>>>
+where
+    O: std::fmt::Debug + Clone + 'static,
+{
+    let step = prop_oneof![
+        (0..REPLICAS, op).prop_map(|(replica, op)| Step::Send(replica, op)),
+        (0..REPLICAS, any::<Index>()).prop_map(|(replica, idx)| Step::Deliver(replica, idx)),
+    ];
+    prop::collection::vec(step, 0..max_len)
+}
+
+/// Runs the schedule, delivers the messages still in flight, and returns the value read
+/// by each replica.
+pub fn run<O>(schedule: Vec<Step<O>>) -> Vec<O::Value>
+where
+    O: PureCRDT + Clone + InternalizeOp,
+    VecLog<O>: IsLog<Op = O> + EvalNested<Read<O::Value>>,
+{
+    let (replica_a, replica_b, replica_c) = triplet::<O>();
+    let mut replicas = [replica_a, replica_b, replica_c];
+    let mut in_flight: [Vec<EventMessage<O>>; REPLICAS] = Default::default();
+
+    for step in schedule {
+        match step {
+            Step::Send(replica, op) => {
+                // A rejected operation is not broadcast
+                let Ok(message) = replicas[replica].send(op) else {
+                    continue;
+                };
+                for (other, messages) in in_flight.iter_mut().enumerate() {
+                    if other != replica {
+                        messages.push(message.clone());
+                    }
+                }
+            }
+            Step::Deliver(replica, idx) => {
+                let messages = &mut in_flight[replica];
+                if !messages.is_empty() {
+                    let message = messages.remove(idx.index(messages.len()));
+                    replicas[replica].receive(message);
+                }
+            }
+        }
+    }
+
+    for (replica, messages) in replicas.iter_mut().zip(in_flight) {
+        for message in messages {
+            replica.receive(message);
+        }
+    }
+
+    replicas
+        .iter()
+        .map(|replica| replica.query(Read::new()))
+        .collect()
+}
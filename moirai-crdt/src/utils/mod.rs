@@ -1,5 +1,7 @@
 use crate::HashSet;
 
+#[cfg(test)]
+pub mod convergence;
 pub mod membership;
 
 pub fn set_from_slice<T: Eq + std::hash::Hash + Clone>(slice: &[T]) -> HashSet<T> {
@@ -1,8 +1,11 @@
 use moirai_protocol::state::log::IsLog;
+use serde::{Deserialize, Serialize};
+
+use crate::{invariant::Invariant, op_generator::OpGeneratorNested};
 
 pub struct FuzzerConfig<'a, L>
 where
-    L: IsLog,
+    L: IsLog + OpGeneratorNested,
 {
     /// Name of the simulation, used for logging
     pub name: &'a str,
@@ -13,11 +16,15 @@ where
     pub compare: fn(&L::Value, &L::Value) -> bool,
     /// Whether to save the execution results to a JSON file in bench-results/
     pub save_execution: bool,
+    /// Properties checked on the replicas during each run, see [`Invariant`]
+    pub invariants: Vec<Box<dyn Invariant<L>>>,
+    /// Configuration of the operation generator, each run starts from a copy of it
+    pub op_config: L::Config,
 }
 
 impl<'a, L> FuzzerConfig<'a, L>
 where
-    L: IsLog,
+    L: IsLog + OpGeneratorNested,
 {
     pub fn new(
         name: &'a str,
@@ -36,8 +43,21 @@ where
             final_merge,
             compare,
             save_execution,
+            invariants: Vec::new(),
+            op_config: L::Config::default(),
         }
     }
+
+    pub fn with_invariant(mut self, invariant: impl Invariant<L> + 'static) -> Self {
+        self.invariants.push(Box::new(invariant));
+        self
+    }
+
+    /// Generate the operations with `op_config`, e.g., to skew the distribution of operation kinds.
+    pub fn with_op_config(mut self, op_config: L::Config) -> Self {
+        self.op_config = op_config;
+        self
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -50,12 +70,24 @@ pub struct RunConfig {
     pub num_operations: usize,
     /// Optional reachability matrix to define which replicas can communicate with each other
     pub reachability: Option<Vec<Vec<bool>>>,
+    /// Optional reachability matrices changing over the run, takes precedence over `reachability`
+    pub reachability_schedule: Option<ReachabilitySchedule>,
     /// Seed for the random number generator
     pub seed: Option<[u8; 32]>,
     /// Whether to generate an execution graph in GraphViz format
     pub generate_execution_graph: bool,
     /// Whether to disable stability (stabilize() will never be called)
     pub disable_stability: bool,
+    /// Probability, for each delivered message, of also delivering again a past message
+    pub duplication_rate: f64,
+    /// Maximum number of operations by which a message may be delayed, 0 for in-order delivery
+    pub reorder_window: usize,
+    /// Whether to check, after each operation, that online replicas with the same clock have the same value
+    pub check_intermediate: bool,
+    /// Whether to record every issued event, to report the events missing from diverging replicas
+    pub witness_graph: bool,
+    /// Sorted indices of the operations that are generated but not issued, e.g., by the shrinker
+    pub skipped_ops: Vec<usize>,
 }
 
 impl RunConfig {
@@ -81,32 +113,159 @@ impl RunConfig {
             "Number of operations must be greater than 0"
         );
         if let Some(matrix) = &reachability {
-            assert!(
-                matrix.len() == num_replicas as usize,
-                "Reachability matrix must have size equal to number of replicas"
-            );
-            for row in matrix {
-                assert!(
-                    row.len() == num_replicas as usize,
-                    "Each row in reachability matrix must have size equal to number of replicas"
-                );
-            }
-            // Ensure that a process is always reachable to itself
-            for (i, item) in matrix.iter().enumerate().take(num_replicas as usize) {
-                assert!(
-                    item[i],
-                    "Each replica must be reachable to itself in the reachability matrix"
-                );
-            }
+            check_reachability(matrix, num_replicas);
         }
         Self {
             churn_rate,
             num_replicas,
             num_operations,
             reachability,
+            reachability_schedule: None,
             seed,
             generate_execution_graph,
             disable_stability,
+            duplication_rate: 0.0,
+            reorder_window: 0,
+            check_intermediate: false,
+            witness_graph: false,
+            skipped_ops: Vec::new(),
+        }
+    }
+
+    /// Make the network duplicate and reorder messages, to exercise idempotence and causal buffering.
+    pub fn with_faulty_network(mut self, duplication_rate: f64, reorder_window: usize) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&duplication_rate),
+            "Duplication rate must be between 0 and 1"
+        );
+        self.duplication_rate = duplication_rate;
+        self.reorder_window = reorder_window;
+        self
+    }
+
+    /// Check convergence during the run, not only at the end: as soon as two online replicas
+    /// have delivered the same events, their values must be equal.
+    ///
+    /// The final merge can hide a transient divergence, e.g., when the value depends on the
+    /// delivery order until the events are stable. With this check, a CRDT with such a bug
+    /// fails at the first operation after which it diverges, which also makes the failing
+    /// execution much shorter to inspect.
+    pub fn with_intermediate_checks(mut self) -> Self {
+        self.check_intermediate = true;
+        self
+    }
+
+    /// On divergence, report the events each replica is missing compared to a record of all
+    /// the issued events, e.g., "replica 3 never applied (2:5)".
+    pub fn with_witness_graph(mut self) -> Self {
+        self.witness_graph = true;
+        self
+    }
+
+    /// Generate the operations at the given indices without issuing them.
+    pub fn with_skipped_ops(mut self, mut skipped_ops: Vec<usize>) -> Self {
+        skipped_ops.sort_unstable();
+        skipped_ops.dedup();
+        assert!(
+            skipped_ops
+                .last()
+                .is_none_or(|last| *last < self.num_operations),
+            "Skipped operations must be less than the number of operations"
+        );
+        self.skipped_ops = skipped_ops;
+        self
+    }
+
+    pub fn is_skipped(&self, op_index: usize) -> bool {
+        self.skipped_ops.binary_search(&op_index).is_ok()
+    }
+
+    /// Number of operations actually issued, i.e., not skipped.
+    pub fn num_issued_operations(&self) -> usize {
+        self.num_operations - self.skipped_ops.len()
+    }
+
+    /// Make the reachability between replicas change over the run, e.g., to simulate a partition that heals.
+    pub fn with_reachability_schedule(mut self, schedule: ReachabilitySchedule) -> Self {
+        for (_, matrix) in &schedule.segments {
+            check_reachability(matrix, self.num_replicas);
         }
+        self.reachability_schedule = Some(schedule);
+        self
+    }
+
+    /// Reachability matrix in effect when issuing the operation `op_index`, if any.
+    pub fn reachability_at(&self, op_index: usize) -> Option<&Vec<Vec<bool>>> {
+        match &self.reachability_schedule {
+            Some(schedule) => schedule.matrix_at(op_index),
+            None => self.reachability.as_ref(),
+        }
+    }
+}
+
+fn check_reachability(matrix: &[Vec<bool>], num_replicas: u8) {
+    assert!(
+        matrix.len() == num_replicas as usize,
+        "Reachability matrix must have size equal to number of replicas"
+    );
+    for row in matrix {
+        assert!(
+            row.len() == num_replicas as usize,
+            "Each row in reachability matrix must have size equal to number of replicas"
+        );
+    }
+    // Ensure that a process is always reachable to itself
+    for (i, item) in matrix.iter().enumerate().take(num_replicas as usize) {
+        assert!(
+            item[i],
+            "Each replica must be reachable to itself in the reachability matrix"
+        );
+    }
+}
+
+/// Sequence of reachability matrices, each one applying from its starting operation index
+/// until the start of the next one. Before the first segment, all replicas are reachable.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReachabilitySchedule {
+    segments: Vec<(usize, Vec<Vec<bool>>)>,
+}
+
+impl ReachabilitySchedule {
+    pub fn new(segments: Vec<(usize, Vec<Vec<bool>>)>) -> Self {
+        assert!(
+            segments.windows(2).all(|w| w[0].0 < w[1].0),
+            "Reachability segments must be sorted by strictly increasing start operation"
+        );
+        Self { segments }
+    }
+
+    pub fn matrix_at(&self, op_index: usize) -> Option<&Vec<Vec<bool>>> {
+        self.segments
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= op_index)
+            .map(|(_, matrix)| matrix)
+    }
+
+    pub fn segments(&self) -> &[(usize, Vec<Vec<bool>>)] {
+        &self.segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_segments() {
+        let full = vec![vec![true; 2]; 2];
+        let split = vec![vec![true, false], vec![false, true]];
+        let schedule = ReachabilitySchedule::new(vec![(10, split.clone()), (20, full.clone())]);
+
+        assert_eq!(schedule.matrix_at(0), None);
+        assert_eq!(schedule.matrix_at(10), Some(&split));
+        assert_eq!(schedule.matrix_at(19), Some(&split));
+        assert_eq!(schedule.matrix_at(20), Some(&full));
+        assert_eq!(schedule.matrix_at(1_000), Some(&full));
     }
 }
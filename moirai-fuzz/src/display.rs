@@ -40,6 +40,32 @@ pub fn display_config_table(run_config: &RunConfig, final_merge: bool) -> Table
         &format!("{:.1}%", run_config.churn_rate * 100.0),
     ]);
 
+    if run_config.duplication_rate > 0.0 || run_config.reorder_window > 0 {
+        config_table.add_row(vec![
+            "Duplication rate",
+            &format!("{:.1}%", run_config.duplication_rate * 100.0),
+        ]);
+        config_table.add_row(vec![
+            "Reorder window",
+            &format!("{} ops", run_config.reorder_window),
+        ]);
+    }
+
+    if !run_config.skipped_ops.is_empty() {
+        config_table.add_row(vec![
+            "Skipped operations",
+            &format!("{}", Int::from(run_config.skipped_ops.len() as i32)),
+        ]);
+    }
+
+    if run_config.check_intermediate {
+        config_table.add_row(vec!["Intermediate checks", "Yes"]);
+    }
+
+    if run_config.witness_graph {
+        config_table.add_row(vec!["Witness graph", "Yes"]);
+    }
+
     config_table.add_row(vec!["Final merge", if final_merge { "Yes" } else { "No" }]);
 
     if let Some(seed) = run_config.seed {
@@ -114,6 +140,10 @@ pub fn display_run_results(run_number: usize, results: &RunResults) -> Table {
         ]);
     }
 
+    if let Some(score) = results.concurrency_score {
+        results_table.add_row(vec!["Concurrency score", &format!("{:.3}", score)]);
+    }
+
     results_table.add_row(vec![
         "Total deliver time per replica (ms)",
         &format!(
@@ -140,6 +170,19 @@ pub fn display_run_results(run_number: usize, results: &RunResults) -> Table {
         ),
     ]);
 
+    results_table.add_row(vec![
+        "Receive call latency p50/p95/p99 per replica (µs)",
+        &format!(
+            "[{}]",
+            results
+                .receive_latency_us_per_replica
+                .iter()
+                .map(|[p50, p95, p99]| format!("{p50}/{p95}/{p99}"))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+    ]);
+
     results_table.add_row(vec![
         "Avg effect time (ms)",
         &format!("{}", results.avg_effect_ms),
@@ -65,12 +65,10 @@ where
         )
     }
 
-    pub fn inter_replica_concurrency_ratio(&self) -> f64 {
+    /// Events in topological order, with `reachable[i][j] == true` iff the `j`-th event
+    /// is reachable from the `i`-th one.
+    fn reachability(&self) -> (Vec<NodeIndex>, Vec<FixedBitSet>) {
         let node_count = self.graph.node_count();
-        if node_count < 2 {
-            return 0.0;
-        }
-
         let topo = toposort(&self.graph, None).expect("graph must be acyclic");
 
         // Map NodeIndex -> dense integer position [0..n)
@@ -96,6 +94,17 @@ where
             }
         }
 
+        (topo, reachable)
+    }
+
+    pub fn inter_replica_concurrency_ratio(&self) -> f64 {
+        let node_count = self.graph.node_count();
+        if node_count < 2 {
+            return 0.0;
+        }
+
+        let (topo, reachable) = self.reachability();
+
         let mut concurrent_pairs = 0usize;
         let mut total_inter_replica_pairs = 0usize;
 
@@ -126,6 +135,24 @@ where
         }
     }
 
+    /// Average number of events concurrent with each event, i.e., `2 * concurrent pairs / n`.
+    /// A sequential history scores 0, while `n` mutually concurrent events score `n - 1`.
+    pub fn concurrency_score(&self) -> f64 {
+        let node_count = self.graph.node_count();
+        if node_count < 2 {
+            return 0.0;
+        }
+
+        let (_, reachable) = self.reachability();
+
+        let concurrent_pairs = (0..node_count)
+            .flat_map(|i| ((i + 1)..node_count).map(move |j| (i, j)))
+            .filter(|&(i, j)| !reachable[i][j] && !reachable[j][i])
+            .count();
+
+        2.0 * concurrent_pairs as f64 / node_count as f64
+    }
+
     pub fn internal(&self) -> &Dag<TaggedOp<O>, ()> {
         &self.graph
     }
@@ -178,8 +205,55 @@ fn transitive_reduce_dag<N>(graph: &mut Dag<N, ()>) {
 #[cfg(test)]
 mod tests {
     use daggy::Dag;
+    use moirai_protocol::{
+        broadcast::tcsb::{IsTcsb, Tcsb},
+        utils::intern_str::{InternalizeOp, Interner},
+    };
+
+    use super::{ExecutionGraph, transitive_reduce_dag};
+
+    #[derive(Clone, Debug)]
+    struct Op;
+
+    impl InternalizeOp for Op {
+        fn internalize(self, _interner: &Interner) -> Self {
+            self
+        }
+    }
+
+    fn tcsb(id: &str, members: &[&str]) -> Tcsb<Op> {
+        let mut interner = Interner::new();
+        let (idx, _) = interner.intern(id);
+        for member in members {
+            interner.intern(member);
+        }
+        Tcsb::new(idx, interner)
+    }
+
+    #[test]
+    fn concurrency_score_sequential_history() {
+        let mut tcsb_a = tcsb("a", &["a", "b"]);
+        let mut graph = ExecutionGraph::new();
+        for _ in 0..5 {
+            graph.append(tcsb_a.send(Op).event());
+        }
+
+        assert_eq!(graph.concurrency_score(), 0.0);
+    }
 
-    use super::transitive_reduce_dag;
+    #[test]
+    fn concurrency_score_fan_out() {
+        let members = ["a", "b", "c", "d", "e"];
+        let mut graph = ExecutionGraph::new();
+        for id in members {
+            let mut replica = tcsb(id, &members);
+            graph.append(replica.send(Op).event());
+        }
+
+        // Each event is concurrent with the 4 others
+        assert_eq!(graph.concurrency_score(), 4.0);
+        assert_eq!(graph.inter_replica_concurrency_ratio(), 1.0);
+    }
 
     #[test]
     fn transitive_reduction_removes_direct_shortcut() {
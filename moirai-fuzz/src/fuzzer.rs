@@ -39,7 +39,13 @@ where
 
         info!("{}", config_table);
 
-        let run_data = runner::<L>(run_config, config.final_merge, config.compare);
+        let run_data = runner::<L>(
+            run_config,
+            config.op_config.clone(),
+            config.final_merge,
+            config.compare,
+            &config.invariants,
+        );
         let results = run_results(&run_data);
 
         debug!("Run {} completed", run_idx + 1);
@@ -89,12 +95,20 @@ pub struct RunResults {
     pub total_effect_ms_per_replica: Vec<u128>,
     /// Average time per effect() call (in milliseconds), across all replicas
     pub avg_effect_ms: f64,
+    /// p50, p95 and p99 of the time taken by a receive call that delivered events (in microseconds), for each replica. Index i = replica i
+    #[serde(default)]
+    pub receive_latency_us_per_replica: Vec<[u128; 3]>,
     /// Execution graph in GraphViz DOT format (if generated)
     pub execution_graph_dot: Option<String>,
     /// Inter-replica concurrency ratio (if execution graph was generated)
     pub inter_replica_concurrency_ratio: Option<f64>,
+    /// Average number of concurrent events per event (if execution graph was generated)
+    pub concurrency_score: Option<f64>,
     /// Seed
     pub used_seed: String,
+    /// Number of events delivered by each replica at convergence
+    #[serde(default)]
+    pub delivered_events: usize,
 }
 
 fn run_results(run_data: &RunData) -> RunResults {
@@ -128,6 +142,18 @@ fn run_results(run_data: &RunData) -> RunResults {
         vec
     };
 
+    let receive_latency_us_per_replica = {
+        let mut vec = vec![[0u128; 3]; run_data.config.num_replicas as usize];
+        for (idx, histogram) in run_data.receive_latency_per_replica.iter() {
+            vec[idx.0] = [0.5, 0.95, 0.99].map(|p| {
+                histogram
+                    .percentile(p)
+                    .map_or(0, |duration| duration.as_micros())
+            });
+        }
+        vec
+    };
+
     let avg_effect_ms = run_data
         .total_time_in_effect_per_replica
         .values()
@@ -142,9 +168,12 @@ fn run_results(run_data: &RunData) -> RunResults {
         total_deliver_ms_per_replica,
         total_effect_ms_per_replica,
         avg_effect_ms,
+        receive_latency_us_per_replica,
         execution_graph_dot: run_data.execution_graph_dot.clone(),
         inter_replica_concurrency_ratio: run_data.inter_replica_concurrency_ratio,
+        concurrency_score: run_data.concurrency_score,
         used_seed: seed_to_hex(&run_data.used_seed),
+        delivered_events: run_data.num_delivered_events,
     }
 }
 
@@ -0,0 +1,32 @@
+use moirai_protocol::{broadcast::tcsb::Tcsb, replica::Replica, state::log::IsLog};
+
+use crate::metrics::MetricsLog;
+
+/// Replica as run by the fuzzer. It answers the same queries as `L`.
+pub type FuzzReplica<L> = Replica<MetricsLog<L>, Tcsb<<L as IsLog>::Op>>;
+
+/// Property of a CRDT that must hold on every replica during a run, beyond convergence,
+/// e.g., a bounded counter never goes negative.
+///
+/// Invariants are checked on every online replica after each operation of the run, once the
+/// events of the step are delivered, and on every replica after the final merge. A violation
+/// fails the run with a panic naming the replica, the step and the message of the invariant:
+///
+/// ```text
+/// Invariant violated by replica 1 after operation 42: counter is 1001
+/// ```
+///
+/// Closures taking the replica implement the trait.
+pub trait Invariant<L: IsLog> {
+    fn check(&self, replica: &FuzzReplica<L>) -> Result<(), String>;
+}
+
+impl<L, F> Invariant<L> for F
+where
+    L: IsLog,
+    F: Fn(&FuzzReplica<L>) -> Result<(), String>,
+{
+    fn check(&self, replica: &FuzzReplica<L>) -> Result<(), String> {
+        self(replica)
+    }
+}
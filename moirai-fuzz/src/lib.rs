@@ -2,11 +2,18 @@ pub mod config;
 mod display;
 pub mod execution_graph;
 pub mod fuzzer;
+pub mod invariant;
 pub mod metrics;
+pub mod op_config;
 pub mod op_generator;
+pub mod op_weaver;
 mod runner;
 mod serialize;
+pub mod shrink;
 mod utils;
 pub mod value_generator;
+pub mod witness;
+
+pub use serialize::{ExecutionRecord, RunParameters, RunRecord, export_csv};
 
 type HashMap<K, V> = rustc_hash::FxHashMap<K, V>;
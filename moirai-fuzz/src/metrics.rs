@@ -8,7 +8,10 @@ use moirai_protocol::{
     clock::version_vector::Version,
     crdt::{eval::EvalNested, query::QueryOperation},
     event::Event,
-    state::{effect_context::EffectContext, log::IsLog},
+    state::{
+        effect_context::EffectContext,
+        log::{IsLog, PruneStats},
+    },
 };
 use rand::Rng;
 
@@ -58,6 +61,7 @@ impl<L: IsLog> IsLog for MetricsLog<L> {
     type Value = L::Value;
     type Op = L::Op;
     type Rejection = L::Rejection;
+    const NEEDS_CAUSAL_DEPS: bool = L::NEEDS_CAUSAL_DEPS;
 
     fn new() -> Self {
         Self::new(L::new())
@@ -71,6 +75,10 @@ impl<L: IsLog> IsLog for MetricsLog<L> {
         self.inner.is_enabled(op)
     }
 
+    fn is_enabled_at(&self, op: &Self::Op, delivered: &Version) -> Result<(), Self::Rejection> {
+        self.inner.is_enabled_at(op, delivered)
+    }
+
     fn effect(&mut self, event: Event<Self::Op>, ctx: &mut EffectContext<'_>) {
         let start = Instant::now();
         self.inner.effect(event, ctx);
@@ -92,6 +100,10 @@ impl<L: IsLog> IsLog for MetricsLog<L> {
     fn is_default(&self) -> bool {
         self.inner.is_default()
     }
+
+    fn prune_stats(&self) -> PruneStats {
+        self.inner.prune_stats()
+    }
 }
 
 // Déléguer EvalNested à l'inner log
@@ -105,11 +117,71 @@ where
     }
 }
 
+/// Histogram of durations in power-of-two buckets of nanoseconds.
+#[derive(Clone, Debug)]
+pub struct LatencyHistogram {
+    /// `buckets[i]` counts the durations in `[2^i, 2^(i+1))` nanoseconds, 0 being in the first one
+    buckets: [usize; 64],
+    count: usize,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self {
+            buckets: [0; 64],
+            count: 0,
+        }
+    }
+
+    /// Record `n` samples of `duration`.
+    pub fn record_n(&mut self, duration: Duration, n: usize) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        let bucket = nanos.checked_ilog2().unwrap_or(0) as usize;
+        self.buckets[bucket] += n;
+        self.count += n;
+    }
+
+    pub fn record(&mut self, duration: Duration) {
+        self.record_n(duration, 1);
+    }
+
+    /// Number of recorded samples
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Upper bound of the bucket holding the `p`-th percentile, e.g., `0.99`,
+    /// or `None` if the histogram is empty.
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((p * self.count as f64).ceil() as usize).clamp(1, self.count);
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let upper = 1u64.checked_shl(bucket as u32 + 1).unwrap_or(u64::MAX);
+                return Some(Duration::from_nanos(upper));
+            }
+        }
+        unreachable!()
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<L> OpGeneratorNested for MetricsLog<L>
 where
     L: IsLog + OpGeneratorNested,
 {
-    fn generate(&self, rng: &mut impl Rng) -> Self::Op {
-        self.inner.generate(rng)
+    type Config = L::Config;
+
+    fn generate(&self, rng: &mut impl Rng, config: &mut Self::Config) -> Self::Op {
+        self.inner.generate(rng, config)
     }
 }
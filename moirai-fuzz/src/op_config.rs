@@ -0,0 +1,190 @@
+use rand::{
+    Rng, RngExt,
+    distr::{Distribution, weighted::WeightedIndex},
+};
+
+/// Distribution of the kinds of operations produced by an `OpGenerator`.
+///
+/// `K` is a lightweight enum of the operation kinds of a CRDT (e.g., insert/delete),
+/// the generator then fills in the operation arguments.
+#[derive(Debug, Clone, Default)]
+pub enum OpConfig<K> {
+    /// Every available kind is equally likely.
+    #[default]
+    Uniform,
+    /// Each kind is drawn proportionally to its weight.
+    /// Kinds that are not listed are never drawn.
+    Weighted(Vec<(K, f64)>),
+    /// The next kind depends on the previously drawn one.
+    Markov(MarkovChain<K>),
+}
+
+#[derive(Debug, Clone)]
+pub struct MarkovChain<K> {
+    kinds: Vec<K>,
+    /// `transitions[i][j]` is the weight of drawing `kinds[j]` right after `kinds[i]`.
+    transitions: Vec<Vec<f64>>,
+    /// Index of the last drawn kind
+    last: Option<usize>,
+}
+
+impl<K> MarkovChain<K> {
+    pub fn new(kinds: Vec<K>, transitions: Vec<Vec<f64>>) -> Self {
+        assert_eq!(
+            kinds.len(),
+            transitions.len(),
+            "Transition matrix must have one row per operation kind"
+        );
+        for row in &transitions {
+            assert_eq!(
+                row.len(),
+                kinds.len(),
+                "Transition matrix must have one column per operation kind"
+            );
+        }
+        Self {
+            kinds,
+            transitions,
+            last: None,
+        }
+    }
+}
+
+impl<K> OpConfig<K>
+where
+    K: Clone + PartialEq,
+{
+    /// Draw an operation kind among the `available` ones, i.e., the kinds that are enabled in the current state.
+    /// Falls back to a uniform draw if none of the available kinds has a positive weight.
+    pub fn sample(&mut self, rng: &mut impl Rng, available: &[K]) -> K {
+        assert!(
+            !available.is_empty(),
+            "At least one operation kind must be available"
+        );
+        let weights: Vec<f64> = match &*self {
+            OpConfig::Uniform => vec![1.0; available.len()],
+            OpConfig::Weighted(weights) => available
+                .iter()
+                .map(|k| {
+                    weights
+                        .iter()
+                        .find(|(kind, _)| kind == k)
+                        .map_or(0.0, |(_, w)| *w)
+                })
+                .collect(),
+            OpConfig::Markov(chain) => available
+                .iter()
+                .map(|k| {
+                    let Some(j) = chain.kinds.iter().position(|kind| kind == k) else {
+                        return 0.0;
+                    };
+                    chain.last.map_or(1.0, |i| chain.transitions[i][j])
+                })
+                .collect(),
+        };
+
+        let kind = match WeightedIndex::new(&weights) {
+            Ok(dist) => available[dist.sample(rng)].clone(),
+            Err(_) => available[rng.random_range(0..available.len())].clone(),
+        };
+
+        if let OpConfig::Markov(chain) = self {
+            chain.last = chain.kinds.iter().position(|k| k == &kind);
+        }
+        kind
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Kind {
+        Read,
+        Insert,
+        Delete,
+    }
+
+    const ALL: [Kind; 3] = [Kind::Read, Kind::Insert, Kind::Delete];
+    const SAMPLES: usize = 100_000;
+
+    fn frequencies(config: &mut OpConfig<Kind>, rng: &mut ChaCha8Rng) -> [f64; 3] {
+        let mut counts = [0usize; 3];
+        for _ in 0..SAMPLES {
+            let kind = config.sample(rng, &ALL);
+            counts[ALL.iter().position(|k| *k == kind).unwrap()] += 1;
+        }
+        counts.map(|c| c as f64 / SAMPLES as f64)
+    }
+
+    #[test]
+    fn weighted_distribution() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut config = OpConfig::Weighted(vec![
+            (Kind::Read, 0.8),
+            (Kind::Insert, 0.15),
+            (Kind::Delete, 0.05),
+        ]);
+
+        let freq = frequencies(&mut config, &mut rng);
+        for (observed, expected) in freq.iter().zip([0.8, 0.15, 0.05]) {
+            assert!(
+                (observed - expected).abs() < 0.01,
+                "observed {observed}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn weighted_only_available_kinds() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut config = OpConfig::Weighted(vec![(Kind::Read, 1.0), (Kind::Delete, 1.0)]);
+
+        for _ in 0..100 {
+            assert_eq!(
+                config.sample(&mut rng, &[Kind::Insert, Kind::Delete]),
+                Kind::Delete
+            );
+            // No available kind has a positive weight: uniform fallback
+            assert_eq!(config.sample(&mut rng, &[Kind::Insert]), Kind::Insert);
+        }
+    }
+
+    #[test]
+    fn markov_distribution() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        // Inserts come in bursts, deletes always follow a read
+        let mut config = OpConfig::Markov(MarkovChain::new(
+            ALL.to_vec(),
+            vec![
+                vec![0.5, 0.0, 0.5],
+                vec![0.1, 0.9, 0.0],
+                vec![0.5, 0.5, 0.0],
+            ],
+        ));
+
+        let mut previous = config.sample(&mut rng, &ALL);
+        let mut after_insert = 0usize;
+        let mut insert_after_insert = 0usize;
+        for _ in 0..SAMPLES {
+            let kind = config.sample(&mut rng, &ALL);
+            if previous == Kind::Delete {
+                assert_ne!(kind, Kind::Delete);
+            }
+            if previous == Kind::Insert {
+                assert_ne!(kind, Kind::Delete);
+                after_insert += 1;
+                if kind == Kind::Insert {
+                    insert_after_insert += 1;
+                }
+            }
+            previous = kind;
+        }
+        let ratio = insert_after_insert as f64 / after_insert as f64;
+        assert!((ratio - 0.9).abs() < 0.01, "observed {ratio}");
+    }
+}
@@ -14,26 +14,36 @@ use moirai_protocol::{
 use rand::Rng;
 
 pub trait OpGenerator: PureCRDT {
-    type Config: Default;
+    type Config: Default + Clone;
 
     fn generate(
         rng: &mut impl Rng,
-        config: &Self::Config,
+        config: &mut Self::Config,
         stable: &Self::StableState,
         unstable: &impl CausalReplay<Self>,
     ) -> Self;
 }
 
 pub trait OpGeneratorNested: IsLog {
-    fn generate(&self, rng: &mut impl Rng) -> Self::Op;
+    /// Configuration of the generator, owned by the run and passed to every generation,
+    /// e.g., to skew the distribution of operation kinds.
+    type Config: Default + Clone;
+
+    fn generate(&self, rng: &mut impl Rng, config: &mut Self::Config) -> Self::Op;
 }
 
 impl<O> OpGeneratorNested for GraphLog<O>
 where
     O: PureCRDT + Clone + OpGenerator + DeepSizeOf,
 {
-    fn generate(&self, rng: &mut impl Rng) -> <GraphLog<O> as IsLog>::Op {
-        self.with_stable(|stable| O::generate(rng, &O::Config::default(), stable, self.unstable()))
+    type Config = O::Config;
+
+    fn generate(
+        &self,
+        rng: &mut impl Rng,
+        config: &mut Self::Config,
+    ) -> <GraphLog<O> as IsLog>::Op {
+        self.with_stable(|stable| O::generate(rng, config, stable, self.unstable()))
     }
 }
 
@@ -42,15 +52,10 @@ where
     O: PureCRDT + Clone + OpGenerator + DeepSizeOf,
     U: IsUnstableState<O> + Default + Debug + DeepSizeOf,
 {
-    fn generate(&self, rng: &mut impl Rng) -> Self::Op {
-        self.with_stable(|stable| {
-            O::generate(
-                rng,
-                &<O as OpGenerator>::Config::default(),
-                stable,
-                self.unstable(),
-            )
-        })
+    type Config = O::Config;
+
+    fn generate(&self, rng: &mut impl Rng, config: &mut Self::Config) -> Self::Op {
+        self.with_stable(|stable| O::generate(rng, config, stable, self.unstable()))
     }
 }
 
@@ -58,7 +63,9 @@ impl<L> OpGeneratorNested for CachedLog<L>
 where
     L: OpGeneratorNested,
 {
-    fn generate(&self, rng: &mut impl Rng) -> Self::Op {
-        self.inner().generate(rng)
+    type Config = L::Config;
+
+    fn generate(&self, rng: &mut impl Rng, config: &mut Self::Config) -> Self::Op {
+        self.inner().generate(rng, config)
     }
 }
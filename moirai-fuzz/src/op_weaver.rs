@@ -0,0 +1,138 @@
+use log::info;
+use moirai_protocol::{
+    broadcast::{message::EventMessage, tcsb::Tcsb},
+    crdt::{eval::EvalNested, query::Read},
+    replica::IsReplica,
+    state::log::IsLog,
+    utils::intern_str::InternalizeOp,
+};
+use rand::{RngExt, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::utils::{boostrap::bootstrap_n, format::seed_to_hex};
+
+/// Fixed operations whose delivery orders are explored by [`op_weaver`].
+pub struct EventGraphConfig<'a, L>
+where
+    L: IsLog,
+{
+    /// Name of the exploration, used for logging
+    pub name: &'a str,
+    pub num_replicas: u8,
+    /// Operations with the index of the replica issuing them, in the order they are issued
+    pub operations: Vec<(usize, L::Op)>,
+    /// Number of interleavings to explore
+    pub num_interleavings: usize,
+    /// Seed of the first interleaving, the next ones follow from it
+    pub seed: [u8; 32],
+    /// Comparison function to check if the replicas converge
+    pub compare: fn(&L::Value, &L::Value) -> bool,
+}
+
+impl<'a, L> EventGraphConfig<'a, L>
+where
+    L: IsLog,
+{
+    pub fn new(
+        name: &'a str,
+        num_replicas: u8,
+        operations: Vec<(usize, L::Op)>,
+        num_interleavings: usize,
+        seed: [u8; 32],
+        compare: fn(&L::Value, &L::Value) -> bool,
+    ) -> Self {
+        assert!(
+            operations
+                .iter()
+                .all(|(origin, _)| *origin < num_replicas as usize),
+            "Every operation must be issued by one of the {num_replicas} replicas"
+        );
+        Self {
+            name,
+            num_replicas,
+            operations,
+            num_interleavings,
+            seed,
+            compare,
+        }
+    }
+}
+
+/// Issue the operations of `config` under many delivery orders, and check that the replicas
+/// converge once every message is delivered.
+///
+/// In each interleaving, replicas issue the operations in the given order, while messages are
+/// delivered to each other replica at random points, possibly out of causal order.
+/// An operation rejected by its replica at that point is skipped.
+///
+/// # Panics
+/// If the replicas diverge, with the steps of the diverging interleaving.
+pub fn op_weaver<L>(config: EventGraphConfig<L>)
+where
+    L: IsLog + EvalNested<Read<<L as IsLog>::Value>>,
+    <L as IsLog>::Op: InternalizeOp,
+{
+    info!(
+        "🧶 Weaving {} operations of {} in {} interleavings, seed {}",
+        config.operations.len(),
+        config.name,
+        config.num_interleavings,
+        seed_to_hex(&config.seed)
+    );
+    let mut rng = ChaCha8Rng::from_seed(config.seed);
+
+    for interleaving in 0..config.num_interleavings {
+        let mut replicas = bootstrap_n::<L, Tcsb<L::Op>>(config.num_replicas);
+        let mut next_op = 0;
+        // Messages not delivered yet, with their destination
+        let mut in_flight: Vec<(usize, EventMessage<L::Op>)> = Vec::new();
+        let mut steps: Vec<String> = Vec::new();
+
+        while next_op < config.operations.len() || !in_flight.is_empty() {
+            let issue =
+                next_op < config.operations.len() && (in_flight.is_empty() || rng.random_bool(0.5));
+            if issue {
+                let (origin, op) = &config.operations[next_op];
+                next_op += 1;
+                match replicas[*origin].send(op.clone()) {
+                    Ok(message) => {
+                        steps.push(format!(
+                            "{} issues {op:?} as {}",
+                            replicas[*origin].id(),
+                            message.event().id()
+                        ));
+                        for dest in (0..replicas.len()).filter(|dest| dest != origin) {
+                            in_flight.push((dest, message.clone()));
+                        }
+                    }
+                    Err(rejection) => steps.push(format!(
+                        "{} rejects {op:?}: {rejection}",
+                        replicas[*origin].id()
+                    )),
+                }
+            } else {
+                let (dest, message) = in_flight.swap_remove(rng.random_range(0..in_flight.len()));
+                steps.push(format!(
+                    "{} receives {}",
+                    replicas[dest].id(),
+                    message.event().id()
+                ));
+                replicas[dest].receive(message);
+            }
+        }
+
+        let first_value = replicas[0].query(Read::new());
+        for r in replicas.iter().skip(1) {
+            let value = r.query(Read::new());
+            if !(config.compare)(&first_value, &value) {
+                panic!(
+                    "[{}] Interleaving {interleaving} diverged: {first_value:?} at {} vs {value:?} at {}\nSteps:\n{}",
+                    config.name,
+                    replicas[0].id(),
+                    r.id(),
+                    steps.join("\n")
+                );
+            }
+        }
+    }
+}
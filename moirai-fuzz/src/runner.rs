@@ -6,9 +6,13 @@ use std::time::{Duration, Instant};
 use indicatif::{ProgressBar, ProgressStyle};
 use log::{debug, info, warn};
 use moirai_protocol::{
-    broadcast::tcsb::{IsTcsbTest, Tcsb},
+    broadcast::{
+        message::EventMessage,
+        tcsb::{IsTcsbTest, Tcsb},
+    },
+    clock::version_vector::Version,
     crdt::{eval::EvalNested, query::Read},
-    replica::{IsReplica, ReplicaIdx},
+    replica::{IsReplica, Replica, ReplicaIdx},
     state::log::IsLog,
     utils::intern_str::InternalizeOp,
 };
@@ -19,12 +23,14 @@ use crate::{
     HashMap,
     config::RunConfig,
     execution_graph::ExecutionGraph,
-    metrics::{MetricsLog, set_disable_stability},
+    invariant::{FuzzReplica, Invariant},
+    metrics::{LatencyHistogram, MetricsLog, set_disable_stability},
     op_generator::OpGeneratorNested,
     utils::{
         boostrap::bootstrap_n,
         format::{clean_dot_output, format_string_ellipsis, seed_to_hex},
     },
+    witness::Witness,
 };
 
 /// Internal structure to hold run results before serialization
@@ -35,20 +41,28 @@ pub struct RunData {
     pub used_seed: [u8; 32],
     /// Final value observed after convergence
     pub first_value: String,
+    /// Number of events delivered by each replica at convergence
+    pub num_delivered_events: usize,
     /// Total time taken to deliver all ops, per replica
     pub total_time_to_deliver_per_replica: HashMap<ReplicaIdx, Duration>,
+    /// Time taken by each call to `receive` or `receive_batch` that delivered events, per replica
+    pub receive_latency_per_replica: HashMap<ReplicaIdx, LatencyHistogram>,
     /// Total time spent in effect() per replica
     pub total_time_in_effect_per_replica: HashMap<ReplicaIdx, Duration>,
     /// Execution graph in DOT format (if generated)
     pub execution_graph_dot: Option<String>,
     /// Inter-replica concurrency ratio (if execution graph was generated)
     pub inter_replica_concurrency_ratio: Option<f64>,
+    /// Average number of concurrent events per event (if execution graph was generated)
+    pub concurrency_score: Option<f64>,
 }
 
 pub fn runner<L>(
     config: RunConfig,
+    mut op_config: L::Config,
     final_merge: bool,
     compare: fn(&L::Value, &L::Value) -> bool,
+    invariants: &[Box<dyn Invariant<L>>],
 ) -> RunData
 where
     L: IsLog + OpGeneratorNested + EvalNested<Read<<L as IsLog>::Value>>,
@@ -68,13 +82,16 @@ where
     let mut rng = ChaCha8Rng::from_seed(used_seed);
 
     let mut replicas = bootstrap_n::<MetricsLog<L>, Tcsb<L::Op>>(config.num_replicas);
-    let reachability = config.reachability.clone().unwrap_or_else(|| {
-        vec![vec![true; config.num_replicas.into()]; config.num_replicas.into()]
-    });
+    let full_reachability =
+        vec![vec![true; config.num_replicas.into()]; config.num_replicas.into()];
     // `online[i]` indicates whether replica i is online.
     let mut online = vec![true; config.num_replicas.into()];
     let mut count_ops = 0;
-    let mut total_time_to_deliver_per_replica: HashMap<ReplicaIdx, Duration> = HashMap::default();
+    let mut delivery_times = DeliveryTimes::default();
+    // Messages sent so far with their sender, for the network to duplicate them
+    let mut sent: Vec<(usize, EventMessage<L::Op>)> = Vec::new();
+    // Messages delayed by the network: operation count at which they are due, recipient, message
+    let mut delayed: Vec<(usize, usize, EventMessage<L::Op>)> = Vec::new();
 
     // Create execution graph if requested
     let mut execution_graph: Option<ExecutionGraph<L::Op>> = if config.generate_execution_graph {
@@ -83,6 +100,8 @@ where
         None
     };
 
+    let mut witness = config.witness_graph.then(Witness::new);
+
     // Create a progress bar with indicatif
     let pb = ProgressBar::new(config.num_operations as u64);
     pb.set_style(
@@ -97,6 +116,10 @@ where
 
     // Main loop
     while count_ops < config.num_operations {
+        // The reachability may change over the run
+        let reachability = config
+            .reachability_at(count_ops)
+            .unwrap_or(&full_reachability);
         // Randomly select a replica
         let replica_idx = (0..config.num_replicas).choose(&mut rng).unwrap() as usize;
         // Determine if the replica is online based on churn rate
@@ -111,36 +134,59 @@ where
                 {
                     let since = replicas[replica_idx].since();
                     let batch = replicas[other_idx].pull(since);
-                    timed(
+                    timed_receive(
+                        &mut replicas[replica_idx],
                         ReplicaIdx(replica_idx),
-                        &mut total_time_to_deliver_per_replica,
-                        || replicas[replica_idx].receive_batch(batch),
+                        &mut delivery_times,
+                        |replica| replica.receive_batch(batch),
                     );
                 }
             }
         }
 
+        // Deliver the delayed messages that are due, if their recipient is still online
+        let (due, pending): (Vec<_>, Vec<_>) = delayed
+            .into_iter()
+            .partition(|(due_at, _, _)| *due_at <= count_ops);
+        delayed = pending;
+        for (_, other_idx, msg) in due {
+            if online[other_idx] {
+                timed_receive(
+                    &mut replicas[other_idx],
+                    ReplicaIdx(other_idx),
+                    &mut delivery_times,
+                    |replica| replica.receive(msg),
+                );
+            }
+        }
+
         // Send the operation
-        let op = replicas[replica_idx].state().generate(&mut rng);
+        let op = replicas[replica_idx]
+            .state()
+            .generate(&mut rng, &mut op_config);
         count_ops += 1;
 
         // Update progress bar
         pb.inc(1);
 
-        let msg = timed(
+        // A skipped operation is still generated, to disturb the rest of the run as little as possible
+        if config.is_skipped(count_ops - 1) {
+            continue;
+        }
+
+        let (msg, _) = timed(
+            &mut replicas[replica_idx],
             ReplicaIdx(replica_idx),
-            &mut total_time_to_deliver_per_replica,
-            || {
-                replicas[replica_idx]
-                    .send(op.clone())
-                    .unwrap_or_else(|err| {
-                        panic!(
-                            "Failed to send operation from replica {}: {:?}. Error: {}",
-                            replicas[replica_idx].id(),
-                            op,
-                            err
-                        )
-                    })
+            &mut delivery_times,
+            |replica| {
+                replica.send(op.clone()).unwrap_or_else(|err| {
+                    panic!(
+                        "Failed to send operation from replica {}: {:?}. Error: {}",
+                        replica.id(),
+                        op,
+                        err
+                    )
+                })
             },
         );
 
@@ -149,6 +195,9 @@ where
             let event = msg.event().clone();
             graph.append(&event);
         }
+        if let Some(ref mut witness) = witness {
+            witness.record(msg.event());
+        }
 
         if online[replica_idx] {
             for other_idx in 0..config.num_replicas.into() {
@@ -156,18 +205,90 @@ where
                     && online[other_idx]
                     && reachability[replica_idx][other_idx]
                 {
-                    timed(
-                        ReplicaIdx(other_idx),
-                        &mut total_time_to_deliver_per_replica,
-                        || replicas[other_idx].receive(msg.clone()),
+                    let delay = if config.reorder_window > 0 {
+                        rng.random_range(0..=config.reorder_window)
+                    } else {
+                        0
+                    };
+                    if delay > 0 {
+                        delayed.push((count_ops + delay, other_idx, msg.clone()));
+                    } else {
+                        timed_receive(
+                            &mut replicas[other_idx],
+                            ReplicaIdx(other_idx),
+                            &mut delivery_times,
+                            |replica| replica.receive(msg.clone()),
+                        );
+                    }
+                    if config.duplication_rate > 0.0 && rng.random_bool(config.duplication_rate) {
+                        let duplicate = sent
+                            .iter()
+                            .filter(|(from, _)| {
+                                *from != other_idx && reachability[*from][other_idx]
+                            })
+                            .choose(&mut rng);
+                        if let Some((_, duplicate)) = duplicate {
+                            timed_receive(
+                                &mut replicas[other_idx],
+                                ReplicaIdx(other_idx),
+                                &mut delivery_times,
+                                |replica| replica.receive(duplicate.clone()),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        if config.duplication_rate > 0.0 {
+            sent.push((replica_idx, msg));
+        }
+
+        if config.check_intermediate {
+            for (i, j) in (0..config.num_replicas.into())
+                .flat_map(|i| (i + 1..config.num_replicas.into()).map(move |j| (i, j)))
+                .filter(|(i, j)| online[*i] && online[*j])
+            {
+                let clock_i = replicas[i].tcsb().matrix_clock().origin_version();
+                let clock_j = replicas[j].tcsb().matrix_clock().origin_version();
+                if !clock_i.iter().eq(clock_j.iter()) {
+                    continue;
+                }
+                let value_i = replicas[i].query(Read::new());
+                let value_j = replicas[j].query(Read::new());
+                if !compare(&value_i, &value_j) {
+                    pb.finish_and_clear();
+                    if let Some(ref graph) = execution_graph {
+                        warn!(
+                            "Execution graph at divergence:\n{}",
+                            clean_dot_output(&graph.to_dot())
+                        );
+                    }
+                    panic!(
+                        "Replicas {i} and {j} diverged after operation {count_ops} with the same clock {clock_i}: {value_i:?} vs {value_j:?}"
                     );
                 }
             }
         }
+
+        let online_replicas = replicas
+            .iter()
+            .zip(&online)
+            .filter_map(|(replica, online)| online.then_some(replica));
+        check_invariants(online_replicas, invariants, count_ops);
     }
 
     pb.finish_with_message("All operations completed ✓");
 
+    // The network eventually delivers the messages it delayed
+    for (_, other_idx, msg) in delayed {
+        timed_receive(
+            &mut replicas[other_idx],
+            ReplicaIdx(other_idx),
+            &mut delivery_times,
+            |replica| replica.receive(msg),
+        );
+    }
+
     // Final convergence phase
     if final_merge {
         let total_merges = (config.num_replicas as usize) * (config.num_replicas as usize - 1);
@@ -188,10 +309,11 @@ where
                     let since = replicas[i].since();
                     let msg = replicas[j].pull(since);
 
-                    timed(
+                    timed_receive(
+                        &mut replicas[i],
                         ReplicaIdx(i),
-                        &mut total_time_to_deliver_per_replica,
-                        || replicas[i].receive_batch(msg),
+                        &mut delivery_times,
+                        |replica| replica.receive_batch(msg),
                     );
 
                     merge_pb.inc(1);
@@ -200,22 +322,29 @@ where
         }
         let event_count = replicas[0].tcsb().matrix_clock().origin_version().sum();
         assert_eq!(
-            event_count, config.num_operations,
+            event_count,
+            config.num_issued_operations(),
             "Replica 0 has a different number of events after final merge: {} vs {}",
-            event_count, config.num_operations
+            event_count,
+            config.num_issued_operations()
         );
         for replica in &replicas {
             let replica_event_count = replica.tcsb().matrix_clock().origin_version().sum();
             if replica_event_count != event_count {
                 panic!(
-                    "Replica {} has a different number of events after final merge: {} vs {}",
+                    "Replica {} has a different number of events after final merge: {} vs {}{}",
                     replica.id(),
                     replica_event_count,
-                    event_count
+                    event_count,
+                    witness_report(
+                        witness.as_ref(),
+                        &[(replica.id(), replica.tcsb().matrix_clock().origin_version())]
+                    )
                 );
             }
         }
         merge_pb.finish_with_message("Convergence completed ✓");
+        check_invariants(replicas.iter(), invariants, count_ops);
     }
 
     // Check convergence
@@ -239,9 +368,19 @@ where
         if num_delivered_events != replica_delivered_events {
             check_pb.finish_and_clear();
             panic!(
-                "Replica {} and {} have delivered a different number of events: {num_delivered_events} vs {replica_delivered_events}",
+                "Replica {} and {} have delivered a different number of events: {num_delivered_events} vs {replica_delivered_events}{}",
                 replicas[0].id(),
-                r.id()
+                r.id(),
+                witness_report(
+                    witness.as_ref(),
+                    &[
+                        (
+                            replicas[0].id(),
+                            replicas[0].tcsb().matrix_clock().origin_version()
+                        ),
+                        (r.id(), r.tcsb().matrix_clock().origin_version()),
+                    ]
+                )
             );
         }
         let value = r.query(Read::new());
@@ -253,7 +392,19 @@ where
                     clean_dot_output(&graph.to_dot())
                 );
             }
-            panic!("Replicas 0 and {idx} diverged: {val} vs {value:?}");
+            panic!(
+                "Replicas 0 and {idx} diverged: {val} vs {value:?}{}",
+                witness_report(
+                    witness.as_ref(),
+                    &[
+                        (
+                            replicas[0].id(),
+                            replicas[0].tcsb().matrix_clock().origin_version()
+                        ),
+                        (r.id(), r.tcsb().matrix_clock().origin_version()),
+                    ]
+                )
+            );
         }
         check_pb.inc(1);
     }
@@ -270,6 +421,7 @@ where
     // Generate DOT format for the execution graph if it was created
     let mut execution_graph_dot = None;
     let mut inter_replica_concurrency_ratio = None;
+    let mut concurrency_score = None;
 
     if let Some(graph) = execution_graph.as_mut() {
         let graph_pb = ProgressBar::new_spinner();
@@ -282,6 +434,7 @@ where
             ratio
         ));
         inter_replica_concurrency_ratio = Some(ratio);
+        concurrency_score = Some(graph.concurrency_score());
 
         let output = graph.to_dot();
         execution_graph_dot = Some(clean_dot_output(&output));
@@ -292,23 +445,152 @@ where
         config,
         used_seed,
         first_value: val,
-        total_time_to_deliver_per_replica,
+        num_delivered_events,
+        total_time_to_deliver_per_replica: delivery_times.total,
+        receive_latency_per_replica: delivery_times.latency,
         total_time_in_effect_per_replica,
         execution_graph_dot,
         inter_replica_concurrency_ratio,
+        concurrency_score,
     }
 }
 
-fn timed<F, R>(replica_idx: ReplicaIdx, recorder: &mut HashMap<ReplicaIdx, Duration>, f: F) -> R
+/// Time spent by each replica to deliver events: in total, and per receive call.
+#[derive(Default)]
+struct DeliveryTimes {
+    total: HashMap<ReplicaIdx, Duration>,
+    latency: HashMap<ReplicaIdx, LatencyHistogram>,
+}
+
+/// Run `f` on a replica and add its duration to the replica's total.
+fn timed<L, F, R>(
+    replica: &mut Replica<L, Tcsb<L::Op>>,
+    replica_idx: ReplicaIdx,
+    times: &mut DeliveryTimes,
+    f: F,
+) -> (R, Duration)
 where
-    F: FnOnce() -> R,
+    L: IsLog,
+    L::Op: InternalizeOp,
+    F: FnOnce(&mut Replica<L, Tcsb<L::Op>>) -> R,
 {
     let start = Instant::now();
-    let result = f();
+    let result = f(replica);
     let elapsed = start.elapsed();
-    recorder
-        .entry(replica_idx)
-        .and_modify(|d| *d += elapsed)
-        .or_insert(elapsed);
-    result
+    *times.total.entry(replica_idx).or_default() += elapsed;
+    (result, elapsed)
+}
+
+/// Like [`timed`] for a receive call, which also records the call's latency if it delivered events.
+/// A call that delivers a whole batch counts once.
+fn timed_receive<L, F>(
+    replica: &mut Replica<L, Tcsb<L::Op>>,
+    replica_idx: ReplicaIdx,
+    times: &mut DeliveryTimes,
+    f: F,
+) where
+    L: IsLog,
+    L::Op: InternalizeOp,
+    F: FnOnce(&mut Replica<L, Tcsb<L::Op>>),
+{
+    let delivered_before = replica.num_delivered_events();
+    let ((), elapsed) = timed(replica, replica_idx, times, f);
+    if replica.num_delivered_events() > delivered_before {
+        times
+            .latency
+            .entry(replica_idx)
+            .or_default()
+            .record(elapsed);
+    }
+}
+
+/// # Panics
+/// On the first violated invariant.
+fn check_invariants<'a, L>(
+    replicas: impl Iterator<Item = &'a FuzzReplica<L>>,
+    invariants: &[Box<dyn Invariant<L>>],
+    count_ops: usize,
+) where
+    L: IsLog + 'a,
+    L::Op: InternalizeOp,
+{
+    if invariants.is_empty() {
+        return;
+    }
+    for replica in replicas {
+        for invariant in invariants {
+            if let Err(message) = invariant.check(replica) {
+                panic!(
+                    "Invariant violated by replica {} after operation {count_ops}: {message}",
+                    replica.id()
+                );
+            }
+        }
+    }
+}
+
+/// Events missing from each replica compared to the witness, if it is enabled.
+fn witness_report(witness: Option<&Witness>, clocks: &[(&str, &Version)]) -> String {
+    let Some(witness) = witness else {
+        return String::new();
+    };
+    clocks
+        .iter()
+        .map(|(id, clock)| format!("\nReplica {id} {}", witness.diff(clock)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::{
+        state::{po_log::VecLog, unstable_state::CausalReplay},
+        utils::test_ops::Inc,
+    };
+    use rand::Rng;
+
+    use super::*;
+    use crate::op_generator::OpGenerator;
+
+    impl OpGenerator for Inc {
+        type Config = ();
+
+        fn generate(
+            _rng: &mut impl Rng,
+            _config: &mut Self::Config,
+            _stable: &Vec<Self>,
+            _unstable: &impl CausalReplay<Self>,
+        ) -> Self {
+            Inc(1)
+        }
+    }
+
+    #[test]
+    fn latency_counts_receive_calls() {
+        let config = RunConfig::new(0.3, 3, 50, None, Some([7; 32]), false, false);
+        let run_data = runner::<VecLog<Inc>>(config, (), true, |a, b| a == b, &[]);
+
+        assert_eq!(run_data.num_delivered_events, 50);
+        assert_eq!(run_data.receive_latency_per_replica.len(), 3);
+        for histogram in run_data.receive_latency_per_replica.values() {
+            // Sending is not timed, and a call counts once however many events it delivered
+            assert!(histogram.count() > 0);
+            assert!(histogram.count() < run_data.num_delivered_events);
+            assert!(histogram.percentile(0.5) <= histogram.percentile(0.99));
+        }
+    }
+
+    #[test]
+    fn invariant_holds_on_every_step() {
+        let config = RunConfig::new(0.3, 3, 200, None, Some([3; 32]), false, false);
+        let at_most_1000: Box<dyn Invariant<VecLog<Inc>>> =
+            Box::new(|replica: &FuzzReplica<VecLog<Inc>>| {
+                let value = replica.query(Read::new());
+                if value > 1000 {
+                    return Err(format!("counter is {value}"));
+                }
+                Ok(())
+            });
+        let run_data = runner::<VecLog<Inc>>(config, (), true, |a, b| a == b, &[at_most_1000]);
+        assert_eq!(run_data.num_delivered_events, 200);
+    }
 }
@@ -1,11 +1,15 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
 
 use chrono::Local;
 use log::info;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    config::RunConfig,
+    config::{ReachabilitySchedule, RunConfig},
     fuzzer::{ExecutionSummary, RunResults},
     utils::git::{get_git_branch, get_git_commit},
 };
@@ -47,6 +51,7 @@ pub struct RunParameters {
     pub churn_rate: f64,
     pub disable_stability: bool,
     pub reachability: Option<Vec<Vec<bool>>>,
+    pub reachability_schedule: Option<ReachabilitySchedule>,
 }
 
 /// Save execution record with multiple runs to JSON file in bench-results/ directory
@@ -99,6 +104,7 @@ pub fn save_execution_record(
                 churn_rate: data.1.churn_rate,
                 disable_stability: data.1.disable_stability,
                 reachability: data.1.reachability.clone(),
+                reachability_schedule: data.1.reachability_schedule.clone(),
             },
             results: RunResults {
                 final_state: data.0.final_state,
@@ -107,9 +113,12 @@ pub fn save_execution_record(
                 total_deliver_ms_per_replica: data.0.total_deliver_ms_per_replica,
                 total_effect_ms_per_replica: data.0.total_effect_ms_per_replica,
                 avg_effect_ms: data.0.avg_effect_ms,
+                receive_latency_us_per_replica: data.0.receive_latency_us_per_replica,
                 execution_graph_dot: data.0.execution_graph_dot,
                 inter_replica_concurrency_ratio: data.0.inter_replica_concurrency_ratio,
+                concurrency_score: data.0.concurrency_score,
                 used_seed: data.0.used_seed,
+                delivered_events: data.0.delivered_events,
             },
         })
         .collect();
@@ -133,3 +142,110 @@ pub fn save_execution_record(
 
     Ok(())
 }
+
+/// Columns of the CSV export, one row per run.
+const CSV_HEADER: &str = "run_number,seed,num_replicas,num_operations,churn_rate,\
+avg_throughput_ops_per_sec,avg_time_per_op_ms,mean_deliver_ms,max_deliver_ms,\
+mean_effect_ms,max_effect_ms,delivered_events,all_delivered";
+
+/// Export runs to a CSV file, e.g., to load them in a spreadsheet or a dataframe.
+/// The per-replica times are summarized by their mean and maximum.
+/// `all_delivered` tells whether every generated operation was delivered by the end of the run.
+/// Convergence itself is not a column: a run whose replicas diverge fails instead of being recorded.
+pub fn export_csv(records: &[RunRecord], path: impl AsRef<Path>) -> std::io::Result<()> {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for record in records {
+        let params = &record.parameters;
+        let results = &record.results;
+        let (mean_deliver_ms, max_deliver_ms) = mean_max(&results.total_deliver_ms_per_replica);
+        let (mean_effect_ms, max_effect_ms) = mean_max(&results.total_effect_ms_per_replica);
+        let all_delivered = results.delivered_events == params.num_operations;
+        writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            record.run_number,
+            results.used_seed,
+            params.num_replicas,
+            params.num_operations,
+            params.churn_rate,
+            results.avg_throughput_ops_per_sec,
+            results.avg_time_per_op_ms,
+            mean_deliver_ms,
+            max_deliver_ms,
+            mean_effect_ms,
+            max_effect_ms,
+            results.delivered_events,
+            all_delivered,
+        )
+        .expect("writing to a String cannot fail");
+    }
+    fs::write(path, csv)
+}
+
+fn mean_max(values: &[u128]) -> (f64, u128) {
+    if values.is_empty() {
+        return (0.0, 0);
+    }
+    let mean = values.iter().sum::<u128>() as f64 / values.len() as f64;
+    (mean, values.iter().copied().max().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_record(run_number: usize, delivered_events: usize) -> RunRecord {
+        RunRecord {
+            run_number,
+            parameters: RunParameters {
+                num_replicas: 2,
+                num_operations: 10,
+                churn_rate: 0.5,
+                disable_stability: false,
+                reachability: None,
+                reachability_schedule: None,
+            },
+            results: RunResults {
+                final_state: "{}".to_string(),
+                avg_time_per_op_ms: 0.5,
+                avg_throughput_ops_per_sec: 2000.0,
+                total_deliver_ms_per_replica: vec![4, 6],
+                total_effect_ms_per_replica: vec![1, 3],
+                avg_effect_ms: 2.0,
+                receive_latency_us_per_replica: vec![[1, 2, 4], [1, 4, 8]],
+                execution_graph_dot: None,
+                inter_replica_concurrency_ratio: None,
+                concurrency_score: None,
+                used_seed: format!("{run_number:064x}"),
+                delivered_events,
+            },
+        }
+    }
+
+    #[test]
+    fn export_two_runs() {
+        let path = std::env::temp_dir().join(format!("moirai-export-{}.csv", std::process::id()));
+        export_csv(&[run_record(1, 10), run_record(2, 7)], &path).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let header: Vec<&str> = lines[0].split(',').collect();
+        assert_eq!(header.len(), 13);
+        assert_eq!(header[0], "run_number");
+        assert_eq!(header[12], "all_delivered");
+
+        let row: Vec<&str> = lines[1].split(',').collect();
+        assert_eq!(row.len(), header.len());
+        assert_eq!(row[0], "1");
+        assert_eq!(row[7], "5");
+        assert_eq!(row[8], "6");
+        assert_eq!(row[12], "true");
+        let row: Vec<&str> = lines[2].split(',').collect();
+        assert_eq!(row[0], "2");
+        assert_eq!(row[11], "7");
+        assert_eq!(row[12], "false");
+    }
+}
@@ -0,0 +1,182 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use log::info;
+use moirai_protocol::{
+    crdt::{eval::EvalNested, query::Read},
+    state::log::IsLog,
+    utils::intern_str::InternalizeOp,
+};
+
+use crate::{config::RunConfig, op_generator::OpGeneratorNested, runner::runner};
+
+/// Whether the run fails, e.g., because the replicas diverge.
+/// The run must have a seed to be reproducible.
+pub fn reproduces<L>(
+    config: &RunConfig,
+    op_config: &L::Config,
+    final_merge: bool,
+    compare: fn(&L::Value, &L::Value) -> bool,
+) -> bool
+where
+    L: IsLog + OpGeneratorNested + EvalNested<Read<<L as IsLog>::Value>>,
+    <L as IsLog>::Op: InternalizeOp,
+{
+    assert!(
+        config.seed.is_some(),
+        "A run without seed is not reproducible"
+    );
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        runner::<L>(config.clone(), op_config.clone(), final_merge, compare, &[])
+    }))
+    .is_err()
+}
+
+/// Minimize a failing run, like a delta debugger.
+///
+/// The number of operations is first cut down to the shortest failing prefix. Then chunks of
+/// operations, halving in size, are skipped as long as the run still fails. The returned
+/// configuration keeps the seed and still fails.
+///
+/// The panic hook is silenced while shrinking, as every failing attempt panics.
+/// Only the first run, which must fail, reports its panic.
+pub fn shrink<L>(
+    config: RunConfig,
+    op_config: &L::Config,
+    final_merge: bool,
+    compare: fn(&L::Value, &L::Value) -> bool,
+) -> RunConfig
+where
+    L: IsLog + OpGeneratorNested + EvalNested<Read<<L as IsLog>::Value>>,
+    <L as IsLog>::Op: InternalizeOp,
+{
+    let fails = |config: &RunConfig| reproduces::<L>(config, op_config, final_merge, compare);
+    assert!(fails(&config), "The run to shrink must fail");
+
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let mut best = config;
+
+    // Shortest failing prefix
+    let mut step = best.num_operations / 2;
+    while step > 0 {
+        let num_operations = best.num_operations - step;
+        let skipped_ops = best
+            .skipped_ops
+            .iter()
+            .copied()
+            .filter(|op| *op < num_operations)
+            .collect();
+        let candidate = RunConfig {
+            num_operations,
+            ..best.clone()
+        }
+        .with_skipped_ops(skipped_ops);
+        if candidate.num_issued_operations() > 0 && fails(&candidate) {
+            best = candidate;
+        } else {
+            step /= 2;
+        }
+    }
+
+    // Skip chunks of operations
+    let mut chunk = best.num_operations / 2;
+    while chunk > 0 {
+        for start in (0..best.num_operations).step_by(chunk) {
+            let end = (start + chunk).min(best.num_operations);
+            if (start..end).all(|op| best.is_skipped(op)) {
+                continue;
+            }
+            let mut skipped_ops = best.skipped_ops.clone();
+            skipped_ops.extend(start..end);
+            let candidate = best.clone().with_skipped_ops(skipped_ops);
+            if candidate.num_issued_operations() > 0 && fails(&candidate) {
+                best = candidate;
+            }
+        }
+        chunk /= 2;
+    }
+
+    panic::set_hook(hook);
+    info!(
+        "Shrunk to {} issued operations out of {}",
+        best.num_issued_operations(),
+        best.num_operations
+    );
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use deepsize::DeepSizeOf;
+    use moirai_protocol::{
+        crdt::{eval::Eval, pure_crdt::PureCRDT},
+        state::{
+            po_log::VecLog,
+            unstable_state::{CausalReplay, IsUnstableCore},
+        },
+        utils::intern_str::Interner,
+    };
+    use rand::{Rng, RngExt};
+
+    use super::*;
+    use crate::op_generator::OpGenerator;
+
+    /// Broken register whose value is the last operation delivered, so concurrent
+    /// operations delivered in different orders make the replicas diverge.
+    #[derive(Clone, Debug, DeepSizeOf)]
+    struct LastDelivered(u32);
+
+    impl PureCRDT for LastDelivered {
+        type Value = u32;
+        type StableState = Vec<Self>;
+        type Rejection = Infallible;
+    }
+
+    impl InternalizeOp for LastDelivered {
+        fn internalize(self, _interner: &Interner) -> Self {
+            self
+        }
+    }
+
+    impl<U: IsUnstableCore<LastDelivered>> Eval<Read<u32>, U> for LastDelivered {
+        fn execute_query(_q: Read<u32>, stable: &Vec<LastDelivered>, unstable: &U) -> u32 {
+            unstable
+                .iter()
+                .last()
+                .map(|t| t.op())
+                .or(stable.last())
+                .map_or(0, |op| op.0)
+        }
+    }
+
+    impl OpGenerator for LastDelivered {
+        type Config = ();
+
+        fn generate(
+            rng: &mut impl Rng,
+            _config: &mut Self::Config,
+            _stable: &Vec<Self>,
+            _unstable: &impl CausalReplay<Self>,
+        ) -> Self {
+            LastDelivered(rng.random_range(1..1_000))
+        }
+    }
+
+    #[test]
+    fn shrink_broken_register() {
+        type Log = VecLog<LastDelivered>;
+        let compare = |a: &u32, b: &u32| a == b;
+
+        let failing = (0..32u8)
+            .map(|i| RunConfig::new(0.3, 3, 200, None, Some([i; 32]), false, false))
+            .find(|config| reproduces::<Log>(config, &(), true, compare))
+            .expect("the broken register diverges");
+
+        let shrunk = shrink::<Log>(failing, &(), true, compare);
+
+        assert!(reproduces::<Log>(&shrunk, &(), true, compare));
+        assert!(shrunk.num_issued_operations() <= 20);
+    }
+}
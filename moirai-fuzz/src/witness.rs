@@ -0,0 +1,157 @@
+use std::fmt::Display;
+
+use moirai_protocol::{
+    clock::version_vector::Version,
+    event::{Event, id::EventId},
+};
+
+/// Reference record of every event issued during a run, with its causal dependencies.
+///
+/// When replicas diverge, the events a replica has delivered are compared to the witness,
+/// to tell which events it is missing instead of only which replicas disagree.
+#[derive(Debug, Default)]
+pub struct Witness {
+    events: Vec<(EventId, Version)>,
+}
+
+impl Witness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record<O>(&mut self, event: &Event<O>) {
+        self.events
+            .push((event.id().clone(), event.version().clone()));
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Events of the witness that are not delivered in the given replica clock.
+    pub fn diff(&self, delivered: &Version) -> WitnessDiff {
+        let is_delivered = |id: &EventId| delivered.seq_by_idx(id.idx()) >= id.seq();
+        let mut diff = WitnessDiff::default();
+        for (id, version) in &self.events {
+            if is_delivered(id) {
+                continue;
+            }
+            if version.dependencies().all(|dep| is_delivered(&dep)) {
+                diff.never_applied.push(id.clone());
+            } else {
+                diff.blocked.push(id.clone());
+            }
+        }
+        diff
+    }
+}
+
+/// Events missing from a replica, compared to the [`Witness`].
+#[derive(Debug, Default, PartialEq)]
+pub struct WitnessDiff {
+    /// Events whose dependencies were all delivered: the replica dropped them.
+    pub never_applied: Vec<EventId>,
+    /// Events waiting, directly or not, on one of the events above.
+    pub blocked: Vec<EventId>,
+}
+
+impl WitnessDiff {
+    pub fn is_empty(&self) -> bool {
+        self.never_applied.is_empty() && self.blocked.is_empty()
+    }
+}
+
+impl Display for WitnessDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "applied every event");
+        }
+        let list = |ids: &[EventId]| {
+            ids.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        write!(f, "never applied {}", list(&self.never_applied))?;
+        if !self.blocked.is_empty() {
+            write!(f, " (blocking {})", list(&self.blocked))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moirai_protocol::{
+        broadcast::tcsb::{IsTcsb, IsTcsbTest, Tcsb},
+        utils::intern_str::{InternalizeOp, Interner},
+    };
+
+    use super::*;
+
+    #[derive(Clone, Debug)]
+    struct Op;
+
+    impl InternalizeOp for Op {
+        fn internalize(self, _interner: &Interner) -> Self {
+            self
+        }
+    }
+
+    fn tcsb(id: &str, members: &[&str]) -> Tcsb<Op> {
+        let mut interner = Interner::new();
+        for member in members {
+            interner.intern(member);
+        }
+        let (idx, _) = interner.intern(id);
+        Tcsb::new(idx, interner)
+    }
+
+    #[test]
+    fn witness_names_dropped_event() {
+        let members = ["a", "b", "c"];
+        let mut tcsb_a = tcsb("a", &members);
+        let mut tcsb_b = tcsb("b", &members);
+        let mut tcsb_c = tcsb("c", &members);
+        let mut witness = Witness::new();
+
+        let mut dropped = None;
+        for i in 0..4 {
+            let msg = tcsb_a.send(Op);
+            witness.record(msg.event());
+            tcsb_b.receive(msg.clone());
+            while tcsb_b.next_causally_ready().is_some() {}
+            // The network loses the second event of a on its way to c
+            if i == 1 {
+                dropped = Some(msg.event().id().clone());
+            } else {
+                tcsb_c.receive(msg);
+            }
+        }
+        let msg = tcsb_b.send(Op);
+        witness.record(msg.event());
+        tcsb_c.receive(msg);
+        while tcsb_c.next_causally_ready().is_some() {}
+
+        assert_eq!(witness.len(), 5);
+        assert!(
+            witness
+                .diff(tcsb_b.matrix_clock().origin_version())
+                .is_empty()
+        );
+
+        let diff = witness.diff(tcsb_c.matrix_clock().origin_version());
+        let dropped = dropped.unwrap();
+        assert_eq!(diff.never_applied, vec![dropped.clone()]);
+        // The next events of a and the one of b, which depends on them, wait for it
+        assert_eq!(diff.blocked.len(), 3);
+        assert!(
+            diff.to_string()
+                .starts_with(&format!("never applied {dropped}"))
+        );
+    }
+}
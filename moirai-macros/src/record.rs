@@ -148,6 +148,14 @@ macro_rules! record {
                     }
                 }
 
+                fn prune_stats(&self) -> $crate::moirai_protocol::state::log::PruneStats {
+                    let mut stats = $crate::moirai_protocol::state::log::PruneStats::default();
+                    $(
+                        stats += self.$field.prune_stats();
+                    )*
+                    stats
+                }
+
             }
 
             impl $crate::moirai_protocol::crdt::eval::EvalNested<$crate::moirai_protocol::crdt::query::Read<<Self as $crate::moirai_protocol::state::log::IsLog>::Value>> for [<$name Log>]
@@ -174,6 +182,39 @@ macro_rules! record {
                 }
             }
 
+            $crate::__record_to_json!([<$name Value>] { $($field),* });
+
+            $(
+                /// Query routed to a single field of the record, the other fields are not evaluated.
+                #[derive(Debug, Clone)]
+                pub struct [<$name $field:camel Query>]<Q> {
+                    pub nested_query: Q,
+                }
+
+                impl<Q> [<$name $field:camel Query>]<Q> {
+                    pub fn new(nested_query: Q) -> Self {
+                        Self { nested_query }
+                    }
+                }
+
+                impl<Q> $crate::moirai_protocol::crdt::query::QueryOperation for [<$name $field:camel Query>]<Q>
+                where
+                    Q: $crate::moirai_protocol::crdt::query::QueryOperation,
+                {
+                    type Response = Q::Response;
+                }
+
+                impl<Q> $crate::moirai_protocol::crdt::eval::EvalNested<[<$name $field:camel Query>]<Q>> for [<$name Log>]
+                where
+                    Q: $crate::moirai_protocol::crdt::query::QueryOperation,
+                    $T: $crate::moirai_protocol::crdt::eval::EvalNested<Q>,
+                {
+                    fn execute_query(&self, q: [<$name $field:camel Query>]<Q>) -> Q::Response {
+                        $crate::moirai_protocol::crdt::eval::EvalNested::execute_query(&self.$field, q.nested_query)
+                    }
+                }
+            )*
+
             /// Possible rejections when trying to apply an operation to the record, containing the rejections of all fields
             /// or an "AlreadyInitialized" rejection if trying to apply a "New" operation to an initialized record.
             #[derive(Debug)]
@@ -197,3 +238,32 @@ macro_rules! record {
         }
     };
 }
+
+/// JSON rendering of the value of a record, as an object keyed by field name.
+/// Only implemented with the `json` feature.
+#[cfg(feature = "json")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __record_to_json {
+    ($value:ident { $($field:ident),* }) => {
+        impl $crate::moirai_protocol::crdt::to_json::ToJson for $value {
+            fn to_json(&self) -> $crate::moirai_protocol::serde_json::Value {
+                let mut object = $crate::moirai_protocol::serde_json::Map::new();
+                $(
+                    object.insert(
+                        stringify!($field).to_string(),
+                        $crate::moirai_protocol::crdt::to_json::ToJson::to_json(&self.$field),
+                    );
+                )*
+                $crate::moirai_protocol::serde_json::Value::Object(object)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "json"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __record_to_json {
+    ($value:ident { $($field:ident),* }) => {};
+}
@@ -1,3 +1,7 @@
+// A macro to define a union type, holding the value of one variant or the conflict of concurrent variants.
+// Once a parent removes the union, e.g. a map removing its key, a child left with its default state
+// reads as unset rather than as the default value of its variant.
+
 #[macro_export]
 macro_rules! union {
     (
@@ -6,6 +10,7 @@ macro_rules! union {
         $crate::paste::paste! {
             /// List of variant names, used in the `Choose` operation to select a variant
             #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+            #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
             pub enum [<$union Variant>] {
                 $(
                     $variant,
@@ -14,6 +19,7 @@ macro_rules! union {
 
             /// Set of Union CRDT operations
             #[derive(Clone, Debug)]
+            #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
             pub enum $union {
                 $(
                     $variant($ty),
@@ -107,6 +113,18 @@ macro_rules! union {
                     }
                 }
 
+                fn __moirai_child_prune_stats(
+                    child: &[<$union Child>],
+                ) -> $crate::moirai_protocol::state::log::PruneStats {
+                    match child {
+                        $(
+                            [<$union Child>]::$variant(log) => {
+                                <$log as $crate::moirai_protocol::state::log::IsLog>::prune_stats(log)
+                            }
+                        )*
+                    }
+                }
+
                 fn __moirai_reset_child(
                     child: &mut [<$union Child>],
                     version: &$crate::moirai_protocol::clock::version_vector::Version,
@@ -316,13 +334,19 @@ macro_rules! union {
                 fn redundant_by_parent(&mut self, version: &$crate::moirai_protocol::clock::version_vector::Version, conservative: bool) {
                     match &mut self.child {
                         [<$union Container>]::Unset => {}
-                        [<$union Container>]::Value(union_child) => match union_child.as_mut() {
-                            $(
-                                [<$union Child>]::$variant(log) => {
-                                    log.redundant_by_parent(version, conservative);
-                                }
-                            )*
-                        },
+                        [<$union Container>]::Value(union_child) => {
+                            match union_child.as_mut() {
+                                $(
+                                    [<$union Child>]::$variant(log) => {
+                                        log.redundant_by_parent(version, conservative);
+                                    }
+                                )*
+                            }
+                            // A removed value reads as absent, not as the default of its variant
+                            if Self::__moirai_child_is_default(union_child) {
+                                self.child = [<$union Container>]::Unset;
+                            }
+                        }
                         [<$union Container>]::Conflicts(union_childs) => {
                             for union_child in union_childs {
                                 match union_child {
@@ -347,6 +371,17 @@ macro_rules! union {
                             .all(Self::__moirai_child_is_default),
                     }
                 }
+
+                fn prune_stats(&self) -> $crate::moirai_protocol::state::log::PruneStats {
+                    match &self.child {
+                        [<$union Container>]::Unset => Default::default(),
+                        [<$union Container>]::Value(child) => Self::__moirai_child_prune_stats(child.as_ref()),
+                        [<$union Container>]::Conflicts(children) => children
+                            .iter()
+                            .map(Self::__moirai_child_prune_stats)
+                            .sum(),
+                    }
+                }
             }
 
             impl $crate::moirai_protocol::crdt::eval::EvalNested<$crate::moirai_protocol::crdt::query::Read<<Self as $crate::moirai_protocol::state::log::IsLog>::Value>> for [<$union Log>] {
@@ -0,0 +1,92 @@
+//! Epidemic anti-entropy built on `since`/`pull`.
+//!
+//! Instead of merging every pair of replicas, each replica pulls the events it misses
+//! from a few random peers per round, so that a cluster converges in a logarithmic
+//! number of rounds with a linear number of exchanges per round.
+
+use rand::{Rng, RngExt};
+
+use crate::{replica::IsReplica, state::log::IsLog};
+
+/// Run one gossip round: every replica pulls from `fanout` distinct peers chosen at random.
+/// Events pulled during the round are forwarded by the next pulls of the same round.
+pub fn gossip_round<L, R>(replicas: &mut [R], fanout: usize, rng: &mut impl Rng)
+where
+    L: IsLog,
+    R: IsReplica<L>,
+{
+    for i in 0..replicas.len() {
+        for peer in random_peers(replicas.len(), i, fanout, rng) {
+            let since = replicas[i].since();
+            let batch = replicas[peer].pull(since);
+            replicas[i].receive_batch(batch);
+        }
+    }
+}
+
+/// Up to `fanout` distinct indices in `0..n`, excluding `me`.
+fn random_peers(n: usize, me: usize, fanout: usize, rng: &mut impl Rng) -> Vec<usize> {
+    let mut peers: Vec<usize> = (0..n).filter(|&j| j != me).collect();
+    let fanout = fanout.min(peers.len());
+    // Partial Fisher-Yates shuffle
+    for k in 0..fanout {
+        let l = rng.random_range(k..peers.len());
+        peers.swap(k, l);
+    }
+    peers.truncate(fanout);
+    peers
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::{
+        broadcast::tcsb::Tcsb, replica::Replica, state::po_log::VecLog, utils::test_ops::Inc,
+    };
+
+    type IncReplica = Replica<VecLog<Inc>, Tcsb<Inc>>;
+
+    #[test]
+    fn random_peers_are_distinct() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..100 {
+            let mut peers = random_peers(8, 5, 3, &mut rng);
+            assert_eq!(peers.len(), 3);
+            assert!(!peers.contains(&5));
+            peers.sort();
+            peers.dedup();
+            assert_eq!(peers.len(), 3);
+        }
+        assert_eq!(random_peers(3, 0, 5, &mut rng).len(), 2);
+    }
+
+    #[test]
+    fn gossip_converges() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let members = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let mut replicas: Vec<IncReplica> = members
+            .iter()
+            .map(|id| Replica::bootstrap(id.to_string(), &members))
+            .collect();
+        for replica in replicas.iter_mut() {
+            replica.send(Inc(1)).unwrap();
+        }
+
+        let mut rounds = 0;
+        while replicas
+            .iter()
+            .any(|replica| replica.num_delivered_events() < members.len())
+        {
+            gossip_round(&mut replicas, 2, &mut rng);
+            rounds += 1;
+            assert!(rounds <= 8, "no convergence after {rounds} rounds");
+        }
+        assert!(
+            replicas
+                .iter()
+                .all(|replica| replica.num_delivered_events() == members.len())
+        );
+    }
+}
@@ -3,7 +3,11 @@ use std::fmt::{Debug, Display};
 #[cfg(feature = "test_utils")]
 use deepsize::DeepSizeOf;
 
-use crate::{clock::version_vector::Version, event::Event, replica::ReplicaId};
+use crate::{
+    clock::version_vector::{Version, VersionDelta},
+    event::{Event, id::EventId, lamport::Lamport},
+    replica::ReplicaId,
+};
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
@@ -32,6 +36,28 @@ impl<O> Batch<O> {
     pub fn origin_id(&self) -> &ReplicaId {
         self.version.origin_id()
     }
+
+    /// Encode the batch for the wire: the batch version is used as the base clock
+    /// and each event only keeps the entries of its version that differ from it.
+    pub fn encode(self) -> EncodedBatch<O> {
+        let events = self
+            .events
+            .into_iter()
+            .map(|event| {
+                let delta = event.version().delta(&self.version);
+                EncodedEvent {
+                    id: event.id().clone(),
+                    lamport: *event.lamport(),
+                    op: event.into_op(),
+                    delta,
+                }
+            })
+            .collect();
+        EncodedBatch {
+            base: self.version,
+            events,
+        }
+    }
 }
 
 impl<O> Display for Batch<O>
@@ -51,3 +77,143 @@ where
         write!(f, "], version: {} }}", self.version)
     }
 }
+
+/// Delta-compressed representation of a [`Batch`].
+/// The full version of every event is rebuilt from the base clock on [`EncodedBatch::decode`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub struct EncodedBatch<O> {
+    base: Version,
+    events: Vec<EncodedEvent<O>>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+struct EncodedEvent<O> {
+    id: EventId,
+    lamport: Lamport,
+    op: O,
+    delta: VersionDelta,
+}
+
+impl<O> EncodedBatch<O> {
+    pub fn decode(self) -> Batch<O> {
+        let events = self
+            .events
+            .into_iter()
+            .map(|e| {
+                let version = Version::from_delta(&self.base, &e.delta);
+                Event::new(e.id, e.lamport, e.op, version)
+            })
+            .collect();
+        Batch::new(events, self.base)
+    }
+
+    pub fn base(&self) -> &Version {
+        &self.base
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn origin_id(&self) -> &ReplicaId {
+        self.base.origin_id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{RngExt, SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::{replica::ReplicaIdx, utils::intern_str::Interner};
+
+    fn random_version(
+        rng: &mut StdRng,
+        interner: &Interner,
+        origin_idx: ReplicaIdx,
+        max_seq: usize,
+    ) -> Version {
+        let mut version = Version::new(origin_idx, interner.resolver().clone());
+        for i in 0..interner.resolver().len() {
+            // Leave some entries untouched so that deltas are actually sparse
+            if rng.random_bool(0.5) {
+                version.set_by_idx(ReplicaIdx(i), rng.random_range(0..max_seq));
+            }
+        }
+        version.set_by_idx(origin_idx, rng.random_range(1..max_seq));
+        version
+    }
+
+    #[test]
+    fn encode_decode_random_batches() {
+        let mut rng = StdRng::seed_from_u64(42);
+
+        for _ in 0..200 {
+            let mut interner = Interner::new();
+            let num_replicas = rng.random_range(1..8);
+            for i in 0..num_replicas {
+                interner.intern(&i.to_string());
+            }
+
+            let mut events = Vec::new();
+            for _ in 0..rng.random_range(0..20) {
+                // Grow the view mid-batch so that versions have different lengths
+                if rng.random_bool(0.1) {
+                    let n = interner.resolver().len();
+                    interner.intern(&n.to_string());
+                }
+                let n = interner.resolver().len();
+                let origin_idx = ReplicaIdx(rng.random_range(0..n));
+                let version = random_version(&mut rng, &interner, origin_idx, 50);
+                let id = EventId::new(
+                    origin_idx,
+                    version.origin_seq(),
+                    interner.resolver().clone(),
+                );
+                let op: u32 = rng.random();
+                events.push(Event::new(id, Lamport::from(&version), op, version));
+            }
+
+            let origin_idx = ReplicaIdx(rng.random_range(0..num_replicas));
+            let base = random_version(&mut rng, &interner, origin_idx, 50);
+            let batch = Batch::new(events, base);
+
+            let decoded = batch.clone().encode().decode();
+
+            assert_eq!(decoded.version(), batch.version());
+            assert_eq!(decoded.events().len(), batch.events().len());
+            for (a, b) in decoded.events().iter().zip(batch.events().iter()) {
+                assert_eq!(a.id(), b.id());
+                assert_eq!(a.lamport(), b.lamport());
+                assert_eq!(a.op(), b.op());
+                assert_eq!(a.version(), b.version());
+            }
+        }
+    }
+
+    #[test]
+    fn encode_only_keeps_differing_entries() {
+        let mut interner = Interner::new();
+        let a = interner.intern("a").0;
+        let b = interner.intern("b").0;
+        let _c = interner.intern("c").0;
+
+        let mut base = Version::new(a, interner.resolver().clone());
+        base.set_by_idx(a, 3);
+        base.set_by_idx(b, 2);
+
+        let mut version = Version::new(b, interner.resolver().clone());
+        version.set_by_idx(a, 3);
+        version.set_by_idx(b, 3);
+
+        let delta = version.delta(&base);
+        assert_eq!(delta.num_entries(), 1);
+        assert_eq!(Version::from_delta(&base, &delta), version);
+    }
+}
@@ -4,8 +4,13 @@ use std::marker::PhantomData;
 use deepsize::DeepSizeOf;
 
 use crate::{
-    broadcast::{batch::Batch, since::Since},
-    event::Event,
+    HashSet,
+    broadcast::{
+        batch::{Batch, EncodedBatch},
+        since::Since,
+    },
+    clock::version_vector::Version,
+    event::{Event, id::EventId},
     utils::intern_str::Resolver,
 };
 
@@ -43,7 +48,8 @@ pub struct Message<O, K = kind::Any> {
 #[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
 pub enum Payload<O> {
     Event(Event<O>),
-    Batch(Batch<O>),
+    /// Batches travel delta-compressed and are decoded on reception.
+    Batch(EncodedBatch<O>),
     Since(Since),
 }
 
@@ -82,18 +88,37 @@ impl<O> Message<O, kind::Event> {
             _ => unreachable!("EventMessage is expected to hold an event payload"),
         }
     }
+
+    /// Same message, with the dependencies of the event elided (see [`Event::elide_dependencies`]).
+    pub fn without_dependencies(mut self) -> Self {
+        match &mut self.payload {
+            Payload::Event(event) => event.elide_dependencies(),
+            _ => unreachable!("EventMessage is expected to hold an event payload"),
+        }
+        self
+    }
 }
 
 impl<O> Message<O, kind::Batch> {
     pub fn new(batch: Batch<O>, resolver: Resolver) -> Self {
         Self {
-            payload: Payload::Batch(batch),
+            payload: Payload::Batch(batch.encode()),
             resolver,
             _kind: PhantomData,
         }
     }
 
-    pub fn batch(&self) -> &Batch<O> {
+    /// Decoded copy of the batch. Prefer [`Self::encoded_batch`], or [`Self::into_batch`]
+    /// to decode the batch without cloning it.
+    #[deprecated(note = "the batch is stored encoded: use `encoded_batch` or `into_batch`")]
+    pub fn batch(&self) -> Batch<O>
+    where
+        O: Clone,
+    {
+        self.encoded_batch().clone().decode()
+    }
+
+    pub fn encoded_batch(&self) -> &EncodedBatch<O> {
         match &self.payload {
             Payload::Batch(batch) => batch,
             _ => unreachable!("BatchMessage is expected to hold a batch payload"),
@@ -102,14 +127,14 @@ impl<O> Message<O, kind::Batch> {
 
     pub fn into_batch(self) -> Batch<O> {
         match self.payload {
-            Payload::Batch(batch) => batch,
+            Payload::Batch(batch) => batch.decode(),
             _ => unreachable!("BatchMessage is expected to hold a batch payload"),
         }
     }
 
     pub fn into_parts(self) -> (Batch<O>, Resolver) {
         match self.payload {
-            Payload::Batch(batch) => (batch, self.resolver),
+            Payload::Batch(batch) => (batch.decode(), self.resolver),
             _ => unreachable!("BatchMessage is expected to hold a batch payload"),
         }
     }
@@ -124,6 +149,15 @@ impl<O> Message<O, kind::Since> {
         }
     }
 
+    /// Request on behalf of a replica whose version and undelivered events are known,
+    /// e.g., by a relay that does not hold the replica itself.
+    /// `version` must be expressed with the indices of the requesting replica.
+    #[allow(clippy::mutable_key_type)]
+    pub fn from_clock(version: Version, except: HashSet<EventId>) -> Self {
+        let resolver = version.resolver().clone();
+        Self::new(Since::new(version, except), resolver)
+    }
+
     pub fn since(&self) -> &Since {
         match &self.payload {
             Payload::Since(since) => since,
@@ -1,3 +1,5 @@
+#[cfg(feature = "test_utils")]
+pub mod anti_entropy;
 pub mod batch;
 pub mod message;
 pub mod since;
@@ -1,10 +1,14 @@
-use std::{cmp::Ordering, collections::BTreeMap, fmt::Debug};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, VecDeque},
+    fmt::{Debug, Display},
+    io,
+    rc::Rc,
+};
 
 #[cfg(feature = "test_utils")]
 use deepsize::DeepSizeOf;
 
-#[cfg(feature = "test_utils")]
-use crate::replica::ReplicaIdOwned;
 use crate::{
     HashMap, HashSet,
     broadcast::{
@@ -13,9 +17,9 @@ use crate::{
         since::Since,
     },
     clock::{matrix_clock::MatrixClock, version_vector::Version},
-    event::{Event, id::EventId, lamport::Lamport},
-    replica::ReplicaIdx,
-    utils::intern_str::{InternalizeOp, Interner},
+    event::{Event, MalformedEvent, id::EventId, lamport::Lamport},
+    replica::{ReplicaId, ReplicaIdOwned, ReplicaIdx},
+    utils::intern_str::{InternalizeOp, Interner, Resolver},
 };
 
 /// Services of the Tagged Causal Stable Broadcast communication protocol
@@ -36,6 +40,151 @@ pub trait IsTcsb<O> {
     fn next_causally_ready(&mut self) -> Option<Event<O>>;
     /// Return the new stable version if it has advanced
     fn is_stable(&mut self) -> Option<&Version>;
+    /// Return the version delivered by the local replica.
+    fn version(&self) -> &Version;
+}
+
+#[derive(Clone, Debug)]
+pub enum MergeError {
+    /// Both instances must know exactly the same replicas to be merged.
+    MembersMismatch {
+        local: Vec<ReplicaIdOwned>,
+        other: Vec<ReplicaIdOwned>,
+    },
+    /// Events that `other` has delivered but no longer holds, as they were stable there,
+    /// and that the local replica has not received.
+    PrunedEvents(Vec<EventId>),
+    /// `other` issues events under the identity of the local replica, e.g., a copy that
+    /// evolved offline without being forked with [`Tcsb::fork`]: its events cannot be told
+    /// apart from the local ones.
+    SameOrigin(ReplicaIdOwned),
+}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeError::MembersMismatch { local, other } => {
+                write!(f, "Cannot merge views {local:?} and {other:?}")
+            }
+            MergeError::PrunedEvents(ids) => {
+                write!(f, "Cannot merge events {ids:?} pruned from the other view")
+            }
+            MergeError::SameOrigin(id) => {
+                write!(f, "Cannot merge another view issuing events as {id}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+/// Error of [`Tcsb::try_receive`] and [`Tcsb::try_receive_batch`]: the message is rejected
+/// before touching the view.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReceiveError {
+    /// The origin of the event is not in the view the message was sent with.
+    UnknownOrigin { idx: ReplicaIdx, view_len: usize },
+    /// The metadata of the event `id` is inconsistent.
+    MalformedEvent { id: EventId, reason: MalformedEvent },
+}
+
+impl Display for ReceiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReceiveError::UnknownOrigin { idx, view_len } => write!(
+                f,
+                "Event from unknown origin {} in a view of {view_len} replicas",
+                idx.0
+            ),
+            ReceiveError::MalformedEvent { id, reason } => {
+                write!(f, "Malformed event {id}: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReceiveError {}
+
+impl ReceiveError {
+    /// Check `event` against the `view` it was sent with.
+    fn validate<O>(event: &Event<O>, view: &Resolver) -> Result<(), Self> {
+        event.validate_against(view).map_err(|reason| match reason {
+            MalformedEvent::OriginOutOfView { idx, view_len } => {
+                ReceiveError::UnknownOrigin { idx, view_len }
+            }
+            reason => ReceiveError::MalformedEvent {
+                id: EventId::new(event.id().idx(), event.id().seq(), view.clone()),
+                reason,
+            },
+        })
+    }
+}
+
+/// Error of [`Tcsb::try_pull`]: some of the requested events are stable and were discarded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PullError {
+    /// The events of `replica` after `since` up to `stable` are no longer kept.
+    HistoryDiscarded {
+        replica: ReplicaIdOwned,
+        since: usize,
+        stable: usize,
+    },
+}
+
+impl Display for PullError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PullError::HistoryDiscarded {
+                replica,
+                since,
+                stable,
+            } => write!(
+                f,
+                "Events {}..={stable} of {replica} are stable and were discarded, \
+                 start from a snapshot of the state instead",
+                since + 1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PullError {}
+
+/// Result of [`Tcsb::try_send`].
+#[derive(Debug)]
+pub enum SendOutcome<O> {
+    /// The operation was turned into an event to broadcast.
+    Sent(EventMessage<O>),
+    /// Too many events are not stable yet: the operation was not sent.
+    Backpressure {
+        unstable: usize,
+        max_unstable: usize,
+    },
+}
+
+/// Events held by a replica, see [`Tcsb::dump_all`].
+#[derive(Clone, Debug)]
+pub struct Dump<O> {
+    /// Version of the stable events: only the stable state of the log holds them.
+    pub stable_version: Version,
+    /// Stable events no delivered event depends on, see [`Tcsb::frontier`].
+    pub stable_frontier: Vec<EventId>,
+    /// Every unstable event, delivered or not, in a causal order.
+    pub events: Vec<Event<O>>,
+    /// Version of the events delivered by the replica.
+    pub version: Version,
+}
+
+/// Order of the events returned by [`Tcsb::pull_ordered`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PullOrder {
+    /// Replica by replica, each in sequence order, as [`IsTcsb::pull`].
+    #[default]
+    Replica,
+    /// By Lamport timestamp, ties broken by replica id then sequence number, so that
+    /// earlier edits are delivered first when streaming a large backlog.
+    /// An event always has a greater timestamp than its causal predecessors.
+    Lamport,
 }
 
 #[derive(Debug)]
@@ -45,6 +194,9 @@ pub struct Tcsb<O> {
     /// Received events not yet causally ready.
     /// It contains only events from other replicas than the local one.
     inbox: HashMap<EventId, Event<O>>,
+    /// Events received by `receive_many`, sorted in a causal order.
+    /// They are delivered before the inbox is scanned, or moved to the inbox if not causally ready.
+    ordered: VecDeque<Event<O>>,
     /// Events waiting to be broadcast.
     /// It contains events from all replicas, including the local one.
     /// Organized by replica index and then by sequence number for efficient range queries.
@@ -58,6 +210,31 @@ pub struct Tcsb<O> {
     interner: Interner,
     /// The indices of the columns that were updated in the last matrix clock update, used for efficient stable version computation.
     last_updated_columns: Vec<ReplicaIdx>,
+    /// Number of unstable events above which `try_send` refuses new operations.
+    max_unstable: Option<usize>,
+    /// Delivered events that no other delivered event depends on, at most one per replica.
+    frontier: Vec<EventId>,
+    /// Received events it rejects are not delivered, see [`Tcsb::set_receive_filter`].
+    receive_filter: Option<ReceiveFilter<O>>,
+}
+
+/// Predicate accepting the received events to deliver, see [`Tcsb::set_receive_filter`].
+pub type EventFilter<O> = dyn Fn(&Event<O>) -> bool;
+
+/// Predicate on the received events, shared with the forks of the Tcsb.
+struct ReceiveFilter<O>(Rc<EventFilter<O>>);
+
+impl<O> Debug for ReceiveFilter<O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReceiveFilter").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "test_utils")]
+impl<O> DeepSizeOf for ReceiveFilter<O> {
+    fn deep_size_of_children(&self, _context: &mut deepsize::Context) -> usize {
+        0
+    }
 }
 
 impl<O> IsTcsb<O> for Tcsb<O>
@@ -68,62 +245,63 @@ where
         let resolver = interner.resolver();
         Self {
             inbox: HashMap::default(),
+            ordered: VecDeque::new(),
             outbox: HashMap::default(),
             matrix_clock: MatrixClock::new(replica_idx, resolver.clone()),
             last_stable_version: Version::new(replica_idx, resolver.clone()),
             interner,
             replica_idx,
             last_updated_columns: Vec::new(),
+            max_unstable: None,
+            frontier: Vec::new(),
+            receive_filter: None,
         }
     }
 
+    /// Invalid messages are dropped, see [`Tcsb::try_receive`].
     fn receive(&mut self, message: EventMessage<O>) {
-        // TODO: do the checks before internalizing (i.e, before adding new replicas to the matrix clock)
-        let event = self.internalize_event(message);
-        self.record(event);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("receive").entered();
+        if let Err(_err) = self.try_receive(message) {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("Dropped message: {_err}");
+        }
     }
 
+    /// Invalid messages are dropped, see [`Tcsb::try_receive_batch`].
     fn receive_batch(&mut self, message: BatchMessage<O>) {
-        let batch = self.internalize_batch(message);
-        for event in batch.into_events() {
-            self.record(event);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("receive_batch").entered();
+        if let Err(_err) = self.try_receive_batch(message) {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("Dropped batch: {_err}");
         }
     }
 
     fn send(&mut self, op: O) -> EventMessage<O> {
-        let seq = self.matrix_clock.origin_version_mut().increment();
-        let version = self.matrix_clock.origin_version();
-        let lamport = Lamport::from(version);
-        let event_id = EventId::new(self.replica_idx, seq, self.interner.resolver().clone());
-        let event = Event::new(event_id, lamport, op, version.clone());
-        self.outbox
-            .entry(event.id().idx())
-            .or_default()
-            .insert(event.id().seq(), event.clone());
+        let event = self.send_local(op);
         EventMessage::new(event, self.interner.resolver().clone())
     }
 
+    /// Events rejected by the receive filter are skipped, see [`Tcsb::set_receive_filter`].
     fn next_causally_ready(&mut self) -> Option<Event<O>> {
-        let maybe_event = self
-            .inbox
-            .values()
-            .find(|e| self.is_causally_ready(e))
-            .cloned();
-        if let Some(event) = maybe_event {
-            self.inbox.remove(event.id()).unwrap();
-            self.matrix_clock.origin_version_mut().join(event.version());
-            self.last_updated_columns = self
-                .matrix_clock
-                .set_by_idx_incremental(event.id().idx(), event.version().clone());
-            return Some(event);
+        while let Some(event) = self.pop_causally_ready() {
+            let accepted = self
+                .receive_filter
+                .as_ref()
+                .is_none_or(|filter| (filter.0)(&event));
+            if accepted {
+                return Some(event);
+            }
         }
         None
     }
 
     fn is_stable(&mut self) -> Option<&Version> {
+        let updated_columns = std::mem::take(&mut self.last_updated_columns);
         let lsv = self
             .matrix_clock
-            .column_wise_min_incremental(&self.last_stable_version, &self.last_updated_columns);
+            .column_wise_min_incremental(&self.last_stable_version, &updated_columns);
         if lsv == self.last_stable_version {
             None
         } else {
@@ -133,29 +311,15 @@ where
         }
     }
 
+    fn version(&self) -> &Version {
+        self.matrix_clock.origin_version()
+    }
+
     /// # Performance
     /// `O(m log m + k log k)` where `m` is the number of replicas and `k` is the number of events returned.
     fn pull(&mut self, since: SinceMessage) -> BatchMessage<O> {
         let since = self.internalize_since(since);
-        let mut events = Vec::new();
-
-        // Iterate over replicas in the version vector and only fetch needed ranges
-        for (replica_idx, req_seq) in since.version().iter() {
-            // Skip events originating from the requesting replica itself
-            if replica_idx == since.version().origin_idx() {
-                continue;
-            }
-
-            if let Some(events_by_seq) = self.outbox.get(&replica_idx) {
-                // Range query: get all events with sequence > req_seq
-                for (_, event) in events_by_seq.range((req_seq + 1)..) {
-                    if !since.except().contains(event.id()) {
-                        events.push(event.clone());
-                    }
-                }
-            }
-        }
-
+        let events = self.events_since(since).collect();
         let batch = Batch::new(events, self.matrix_clock.origin_version().clone());
         BatchMessage::new(batch, self.interner.resolver().clone())
     }
@@ -173,6 +337,673 @@ impl<O> Tcsb<O>
 where
     O: Debug + Clone + InternalizeOp,
 {
+    /// Receive a message, checking its event against the view it was sent with first,
+    /// so that a misconfigured or malicious peer cannot make the replica panic.
+    pub fn try_receive(&mut self, message: EventMessage<O>) -> Result<(), ReceiveError> {
+        ReceiveError::validate(message.event(), message.resolver())?;
+        let event = self.internalize_event(message);
+        self.record(event);
+        Ok(())
+    }
+
+    /// Receive a batch, checking each of its events against the view it was sent with first.
+    /// The whole batch is rejected if any of them is malformed.
+    pub fn try_receive_batch(&mut self, message: BatchMessage<O>) -> Result<(), ReceiveError> {
+        let batch = self.internalize_batch(message)?;
+        for event in batch.into_events() {
+            self.record(event);
+        }
+        Ok(())
+    }
+
+    /// Whether the local replica is the only member of its view.
+    pub fn is_solo(&self) -> bool {
+        self.interner.resolver().len() == 1
+    }
+
+    /// Create and record the event of a local operation, without the message to broadcast it.
+    pub fn send_local(&mut self, op: O) -> Event<O> {
+        let seq = self.matrix_clock.increment_origin();
+        let version = self.matrix_clock.origin_version();
+        let lamport = Lamport::from(version);
+        let event_id = EventId::new(self.replica_idx, seq, self.interner.resolver().clone());
+        let event = Event::new(event_id, lamport, op, version.clone());
+        self.outbox
+            .entry(event.id().idx())
+            .or_default()
+            .insert(event.id().seq(), event.clone());
+        self.advance_frontier(event.id(), event.version());
+        event
+    }
+
+    /// Same as [`IsTcsb::pull`], failing instead of returning an incomplete batch when `since`
+    /// predates the stable version.
+    ///
+    /// Events are dropped from the outbox as soon as they are stable: every member of the view
+    /// has delivered them, so none of them pulls them again. A replica that is not counted by
+    /// the stability, e.g., one joining the view late, may still ask for them.
+    pub fn try_pull(&mut self, since: SinceMessage) -> Result<BatchMessage<O>, PullError> {
+        let since = self.internalize_since(since);
+        let origin_idx = since.version().origin_idx();
+        if let Some((idx, stable)) = self
+            .last_stable_version
+            .iter()
+            .find(|(idx, seq)| *idx != origin_idx && since.version().seq_by_idx(*idx) < *seq)
+        {
+            return Err(PullError::HistoryDiscarded {
+                replica: self.interner.resolve(idx).unwrap().to_string(),
+                since: since.version().seq_by_idx(idx),
+                stable,
+            });
+        }
+        let events = self.events_since(since).collect();
+        let batch = Batch::new(events, self.matrix_clock.origin_version().clone());
+        Ok(BatchMessage::new(batch, self.interner.resolver().clone()))
+    }
+
+    /// Events of [`IsTcsb::pull`], in the same order, cloned one at a time as the iterator
+    /// is consumed, e.g., to stream them to a transport without buffering the whole batch.
+    pub fn pull_iter(&mut self, since: SinceMessage) -> impl Iterator<Item = Event<O>> + '_ {
+        let since = self.internalize_since(since);
+        self.events_since(since)
+    }
+
+    /// Same as [`IsTcsb::pull`], with the events in the given order.
+    pub fn pull_ordered(&mut self, since: SinceMessage, order: PullOrder) -> BatchMessage<O> {
+        let since = self.internalize_since(since);
+        let mut events: Vec<Event<O>> = self.events_since(since).collect();
+        if order == PullOrder::Lamport {
+            events.sort_by(|e1, e2| {
+                (e1.lamport(), e1.id().origin_id(), e1.id().seq()).cmp(&(
+                    e2.lamport(),
+                    e2.id().origin_id(),
+                    e2.id().seq(),
+                ))
+            });
+        }
+        let batch = Batch::new(events, self.matrix_clock.origin_version().clone());
+        BatchMessage::new(batch, self.interner.resolver().clone())
+    }
+
+    /// # Performance
+    /// `O(m)` to start where `m` is the number of replicas, then `O(log k)` per event.
+    fn events_since(&self, since: Since) -> impl Iterator<Item = Event<O>> + '_ {
+        // Only fetch the needed ranges, skipping the events of the requesting replica itself
+        let origin_idx = since.version().origin_idx();
+        let ranges: Vec<(ReplicaIdx, usize)> = since
+            .version()
+            .iter()
+            .filter(|(replica_idx, _)| *replica_idx != origin_idx)
+            .collect();
+        ranges
+            .into_iter()
+            .filter_map(move |(replica_idx, req_seq)| {
+                // Range query: get all events with sequence > req_seq
+                let events_by_seq = self.outbox.get(&replica_idx)?;
+                Some(events_by_seq.range((req_seq + 1)..))
+            })
+            .flatten()
+            .filter(move |(_, event)| !since.except().contains(event.id()))
+            .map(|(_, event)| event.clone())
+    }
+
+    /// Record again an event read from a write-ahead log (see [`crate::persistence`]).
+    /// The replicas of its resolver are learnt in order, so that its indices are the local ones.
+    /// A local event is created anew and returned to be delivered.
+    pub(crate) fn restore(&mut self, event: Event<O>) -> io::Result<Option<Event<O>>> {
+        let resolver = event.version().resolver().clone();
+        for (i, id) in resolver.into_vec().iter().enumerate() {
+            let (idx, is_new) = self.interner.intern(id);
+            if is_new {
+                self.matrix_clock.add_replica(idx);
+            }
+            if idx != ReplicaIdx(i) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("replica {id} is not at the same index in the log"),
+                ));
+            }
+        }
+
+        if event.id().idx() == self.replica_idx {
+            let seq = event.id().seq();
+            let local = self.send_local(event.into_op());
+            if local.id().seq() != seq {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("local event {seq} replayed as {}", local.id().seq()),
+                ));
+            }
+            return Ok(Some(local));
+        }
+
+        let resolver = self.interner.resolver().clone();
+        let id = EventId::new(event.id().idx(), event.id().seq(), resolver.clone());
+        let mut version = Version::new(event.id().idx(), resolver);
+        for (idx, seq) in event.version().iter() {
+            version.set_by_idx(idx, seq);
+        }
+        let lamport = *event.lamport();
+        self.record(Event::new(id, lamport, event.into_op(), version));
+        Ok(None)
+    }
+
+    /// Intern the given replica IDs ahead of time, so that receiving their first events
+    /// does not have to grow the interner and the matrix clock.
+    /// Like the members given at bootstrap, they are then waited for by the stability.
+    pub fn prewarm(&mut self, members: &[&ReplicaId]) {
+        for member in members {
+            let (idx, is_new) = self.interner.intern(member);
+            if is_new {
+                self.matrix_clock.add_replica(idx);
+            }
+        }
+    }
+
+    /// Set the number of unstable events above which [`Tcsb::try_send`] reports backpressure.
+    /// `None` disables the limit.
+    pub fn set_max_unstable(&mut self, max_unstable: Option<usize>) {
+        self.max_unstable = max_unstable;
+    }
+
+    /// Only deliver the received events accepted by `filter`, e.g., to ignore the operations
+    /// of an origin that is not trusted with some keys. Local events are not filtered.
+    ///
+    /// A rejected event is still marked as delivered, so that the events depending on it are
+    /// delivered and the stability advances, and it is still sent to the replicas pulling
+    /// from this one. Its operation is never applied, though, while the other replicas apply it:
+    /// the replicas diverge, by design. The filter should be a policy every replica applies.
+    /// The rejected events are not persisted, so a replica with a write-ahead log refuses
+    /// a filter, see [`Replica::set_receive_filter`](crate::replica::Replica::set_receive_filter).
+    pub fn set_receive_filter(&mut self, filter: Box<EventFilter<O>>) {
+        self.receive_filter = Some(ReceiveFilter(Rc::from(filter)));
+    }
+
+    pub fn clear_receive_filter(&mut self) {
+        self.receive_filter = None;
+    }
+
+    pub fn max_unstable(&self) -> Option<usize> {
+        self.max_unstable
+    }
+
+    pub(crate) fn stable_version(&self) -> &Version {
+        &self.last_stable_version
+    }
+
+    pub(crate) fn resolver(&self) -> &Resolver {
+        self.interner.resolver()
+    }
+
+    /// Replicas in the local view, the local one included, indexed as in the events.
+    /// The local replica comes first, then the others in the order they were learnt.
+    pub fn view(&self) -> &Resolver {
+        self.interner.resolver()
+    }
+
+    /// Causal frontier of the delivered events: those no other delivered event depends on,
+    /// sorted by replica id. The join of their versions is the current version, so they identify
+    /// it as an external reference, e.g., for optimistic concurrency.
+    ///
+    /// Unlike the heads of an event graph, the frontier is kept once its events are stable.
+    pub fn frontier(&self) -> Vec<EventId> {
+        let mut frontier = self.frontier.clone();
+        frontier.sort_by(|id1, id2| id1.origin_id().cmp(id2.origin_id()));
+        frontier
+    }
+
+    /// Ids of the replicas in the local view, the local one included.
+    pub fn view_members(&self) -> Vec<ReplicaIdOwned> {
+        self.interner.resolver().into_vec()
+    }
+
+    /// Number of received events waiting for their dependencies to be delivered.
+    pub fn num_pending(&self) -> usize {
+        self.inbox.len() + self.ordered.len()
+    }
+
+    /// Number of received events, local ones included, that are not stable yet.
+    pub fn num_unstable(&self) -> usize {
+        self.outbox
+            .values()
+            .map(|events_by_seq| events_by_seq.len())
+            .sum()
+    }
+
+    /// Same as `send`, unless `max_unstable` events are already waiting for stability,
+    /// in which case the operation is not sent and the application should throttle.
+    pub fn try_send(&mut self, op: O) -> SendOutcome<O> {
+        let unstable = self.num_unstable();
+        match self.max_unstable {
+            Some(max_unstable) if unstable >= max_unstable => SendOutcome::Backpressure {
+                unstable,
+                max_unstable,
+            },
+            _ => SendOutcome::Sent(self.send(op)),
+        }
+    }
+
+    /// Receive several batches at once, e.g. the answers of several peers to a pull.
+    ///
+    /// The events are sorted up front in a causal order, so that `next_causally_ready` delivers
+    /// them in a single pass instead of scanning the inbox for each of them.
+    /// The delivered events are the same as receiving the batches one by one.
+    /// Invalid batches are dropped, see [`Tcsb::try_receive_batch`].
+    pub fn receive_many(&mut self, messages: impl IntoIterator<Item = BatchMessage<O>>) {
+        let mut events = Vec::new();
+        for message in messages {
+            let batch = match self.internalize_batch(message) {
+                Ok(batch) => batch,
+                Err(_err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("Dropped batch: {_err}");
+                    continue;
+                }
+            };
+            events.extend(
+                batch
+                    .into_events()
+                    .into_iter()
+                    .filter(|event| self.is_valid(event)),
+            );
+        }
+        // The version of an event sums to more than the version of any of its dependencies
+        events.sort_by_key(|event| (event.version().sum(), event.id().idx(), event.id().seq()));
+        events.dedup_by(|a, b| a.id() == b.id());
+        for event in events {
+            self.outbox
+                .entry(event.id().idx())
+                .or_default()
+                .insert(event.id().seq(), event.clone());
+            self.ordered.push_back(event);
+        }
+    }
+
+    /// Every event held by the replica, delivered or not, with what a fresh replica needs to
+    /// start from them, see [`Tcsb::load_dump`].
+    ///
+    /// Unlike a pull, it does not depend on what the requester knows, e.g. to inspect a replica
+    /// or to feed a fresh one.
+    ///
+    /// Stable events are pruned from the broadcast layer: their effects only remain in the stable
+    /// state of the log, which must be handed over along the dump.
+    pub fn dump_all(&self) -> Dump<O> {
+        let mut events: Vec<Event<O>> = self
+            .outbox
+            .values()
+            .flat_map(|events_by_seq| events_by_seq.values())
+            .cloned()
+            .collect();
+        events.sort_by_key(|event| (event.version().sum(), event.id().idx(), event.id().seq()));
+        let stable_frontier = self
+            .frontier
+            .iter()
+            .filter(|head| head.seq() <= self.last_stable_version.seq_by_idx(head.idx()))
+            .cloned()
+            .collect();
+        Dump {
+            stable_version: self.last_stable_version.clone(),
+            stable_frontier,
+            events,
+            version: self.matrix_clock.origin_version().clone(),
+        }
+    }
+
+    /// Start from the dump of another replica, whose stable events are already folded into the
+    /// state the local replica was bootstrapped with. The events of the dump are then delivered
+    /// through the usual causal delivery.
+    ///
+    /// Every member is known to have delivered the stable events, so the matrix clock starts
+    /// from the stable version. Its replicas are added to the view if they are not in it.
+    ///
+    /// # Panics
+    /// If the local replica already received events, or issued any of the dumped ones.
+    pub fn load_dump(&mut self, dump: Dump<O>) {
+        assert!(
+            self.matrix_clock.origin_version().sum() == 0 && self.inbox.is_empty(),
+            "A dump can only be loaded by a fresh replica"
+        );
+        let Dump {
+            stable_version,
+            stable_frontier,
+            events,
+            version,
+        } = dump;
+
+        let mut stable: Vec<(ReplicaIdx, usize)> = Vec::new();
+        for (their_idx, seq) in stable_version.iter() {
+            let id = stable_version.resolver().resolve(their_idx).unwrap();
+            let (idx, is_new) = self.interner.intern(id);
+            if is_new {
+                self.matrix_clock.add_replica(idx);
+            }
+            stable.push((idx, seq));
+        }
+        let resolver = self.interner.resolver().clone();
+        let stable_from = |origin_idx: ReplicaIdx| {
+            let mut version = Version::new(origin_idx, resolver.clone());
+            for (idx, seq) in &stable {
+                version.set_by_idx(*idx, *seq);
+            }
+            version
+        };
+        for idx in (0..self.interner.len()).map(ReplicaIdx) {
+            self.matrix_clock
+                .set_by_idx_incremental(idx, stable_from(idx));
+        }
+        self.last_stable_version = stable_from(self.replica_idx);
+        self.frontier = stable_frontier
+            .iter()
+            .map(|head| {
+                let idx = self.interner.get(head.origin_id()).unwrap();
+                EventId::new(idx, head.seq(), resolver.clone())
+            })
+            .collect();
+
+        let resolver = version.resolver().clone();
+        let batch = Batch::new(events, version);
+        self.receive_many([BatchMessage::new(batch, resolver)]);
+    }
+
+    /// Integrate every event known by `other` but not yet received by `self`,
+    /// e.g., to merge back a fork that evolved offline.
+    /// Events are then delivered through the usual causal delivery.
+    ///
+    /// # Errors
+    /// Both instances must share the same member set, under different identities.
+    /// Every event delivered by `other` that `self` lacks must still be in the outbox of `other`,
+    /// which keeps the events it sent or received until they are stable.
+    /// The events `other` received but did not deliver yet are integrated as well.
+    pub fn merge(&mut self, other: &Tcsb<O>) -> Result<(), MergeError> {
+        let mut local = self.interner.resolver().into_vec();
+        let mut remote = other.interner.resolver().into_vec();
+        local.sort();
+        remote.sort();
+        if local != remote {
+            return Err(MergeError::MembersMismatch {
+                local,
+                other: remote,
+            });
+        }
+
+        let local_id = self.interner.resolve(self.replica_idx).unwrap();
+        if other.interner.resolve(other.replica_idx) == Some(local_id) {
+            return Err(MergeError::SameOrigin(local_id.to_string()));
+        }
+
+        let pruned: Vec<EventId> = self
+            .missing_from(other.matrix_clock.origin_version())
+            .into_iter()
+            .filter(|id| {
+                let idx = other.interner.get(id.origin_id()).unwrap();
+                !other
+                    .outbox
+                    .get(&idx)
+                    .is_some_and(|events_by_seq| events_by_seq.contains_key(&id.seq()))
+            })
+            .collect();
+        if !pruned.is_empty() {
+            return Err(MergeError::PrunedEvents(pruned));
+        }
+
+        let version = self.matrix_clock.origin_version();
+        let events: Vec<Event<O>> = other
+            .outbox
+            .values()
+            .flat_map(|events_by_seq| events_by_seq.values())
+            .filter(|event| {
+                let idx = self.interner.get(event.id().origin_id()).unwrap();
+                idx != self.replica_idx && event.id().seq() > version.seq_by_idx(idx)
+            })
+            .cloned()
+            .collect();
+
+        let batch = Batch::new(events, other.matrix_clock.origin_version().clone());
+        self.receive_batch(BatchMessage::new(batch, other.interner.resolver().clone()));
+        Ok(())
+    }
+
+    /// Copy of the local view under the identity `new_id`, added to the view.
+    /// The copy has received everything the local replica has, and issues its own events,
+    /// concurrent with the local ones.
+    ///
+    /// The local replica adds `new_id` to its view too, as having delivered its events so far.
+    /// Its next messages announce the fork to the other members, before they can learn that
+    /// it delivered any later event: no member can stabilize an event the fork has not
+    /// delivered, and is concurrent with the operations of the fork. In turn, the stability
+    /// waits for the fork like for any other member.
+    ///
+    /// The interner is rebuilt rather than shared, so that the copy does not grow
+    /// the view of the local replica when it learns new members.
+    ///
+    /// # Panics
+    /// If `new_id` is already in the view.
+    pub fn fork(&mut self, new_id: &ReplicaId) -> Self {
+        let (new_idx, is_new) = self.interner.intern(new_id);
+        assert!(is_new, "Replica {new_id} is already in the view");
+        self.matrix_clock.add_replica(new_idx);
+        let mut row = Version::new(new_idx, self.interner.resolver().clone());
+        for (idx, seq) in self.matrix_clock.origin_version().iter() {
+            row.set_by_idx(idx, seq);
+        }
+        self.matrix_clock.set_by_idx_incremental(new_idx, row);
+
+        let mut interner = Interner::new();
+        for member in self.interner.resolver().into_vec() {
+            interner.intern(&member);
+        }
+        let resolver = interner.resolver().clone();
+
+        let rekey_version = |origin_idx: ReplicaIdx, version: &Version| {
+            let mut rekeyed = Version::new(origin_idx, resolver.clone());
+            for (idx, seq) in version.iter() {
+                rekeyed.set_by_idx(idx, seq);
+            }
+            rekeyed
+        };
+        let rekey_event = |event: &Event<O>| {
+            let id = EventId::new(event.id().idx(), event.id().seq(), resolver.clone());
+            let version = rekey_version(event.id().idx(), event.version());
+            Event::new(id, *event.lamport(), event.op().clone(), version)
+        };
+
+        let mut fork = Self::new(new_idx, interner);
+        for idx in (0..self.interner.len()).map(ReplicaIdx) {
+            let row = self.matrix_clock.version_by_idx(idx).unwrap();
+            fork.matrix_clock.set_by_idx(idx, rekey_version(idx, row));
+        }
+        fork.last_stable_version = rekey_version(new_idx, &self.last_stable_version);
+        fork.inbox = self
+            .inbox
+            .values()
+            .map(|event| {
+                let event = rekey_event(event);
+                (event.id().clone(), event)
+            })
+            .collect();
+        fork.ordered = self.ordered.iter().map(rekey_event).collect();
+        fork.outbox = self
+            .outbox
+            .iter()
+            .map(|(idx, events_by_seq)| {
+                let events_by_seq = events_by_seq
+                    .iter()
+                    .map(|(seq, event)| (*seq, rekey_event(event)))
+                    .collect();
+                (*idx, events_by_seq)
+            })
+            .collect();
+        fork.max_unstable = self.max_unstable;
+        fork.receive_filter = self
+            .receive_filter
+            .as_ref()
+            .map(|filter| ReceiveFilter(Rc::clone(&filter.0)));
+        fork.frontier = self
+            .frontier
+            .iter()
+            .map(|id| EventId::new(id.idx(), id.seq(), resolver.clone()))
+            .collect();
+        fork
+    }
+
+    /// Events included in `their_version` that have not been received locally,
+    /// i.e., neither delivered nor waiting in the inbox.
+    ///
+    /// `their_version` may come from another replica: its entries are matched by replica id.
+    /// Entries of replicas unknown to the local replica are ignored.
+    pub fn missing_from(&self, their_version: &Version) -> Vec<EventId> {
+        let local = self.matrix_clock.origin_version();
+        let mut missing = Vec::new();
+        for (their_idx, their_seq) in their_version.iter() {
+            let Some(idx) = their_version
+                .resolver()
+                .resolve(their_idx)
+                .and_then(|id| self.interner.get(id))
+            else {
+                continue;
+            };
+            for seq in (local.seq_by_idx(idx) + 1)..=their_seq {
+                let id = EventId::new(idx, seq, self.interner.resolver().clone());
+                if !self.inbox.contains_key(&id) {
+                    missing.push(id);
+                }
+            }
+        }
+        missing.sort();
+        missing
+    }
+
+    /// Whether the event was delivered locally or waits in the inbox.
+    ///
+    /// `id` may come from another replica: it is matched by replica id.
+    pub fn has_received(&self, id: &EventId) -> bool {
+        let Some(idx) = self.interner.get(id.origin_id()) else {
+            return false;
+        };
+        self.matrix_clock.origin_version().seq_by_idx(idx) >= id.seq()
+            || self.inbox.contains_key(&EventId::new(
+                idx,
+                id.seq(),
+                self.interner.resolver().clone(),
+            ))
+    }
+
+    /// Replicas known to have delivered the event, i.e., whose row of the matrix clock includes it.
+    ///
+    /// The knowledge of the local replica lags behind: a peer is only known to have delivered
+    /// an event once an event it issued afterwards is delivered locally.
+    pub fn acked_by(&self, id: &EventId) -> HashSet<ReplicaIdOwned> {
+        let Some(origin) = self.interner.get(id.origin_id()) else {
+            return HashSet::default();
+        };
+        (0..self.interner.resolver().len())
+            .map(ReplicaIdx)
+            .filter(|idx| {
+                self.matrix_clock
+                    .version_by_idx(*idx)
+                    .is_some_and(|row| row.seq_by_idx(origin) >= id.seq())
+            })
+            .filter_map(|idx| self.interner.resolve(idx).map(ToString::to_string))
+            .collect()
+    }
+
+    /// Whether every member of the view has delivered the event: it no longer needs to be sent.
+    pub fn fully_acked(&self, id: &EventId) -> bool {
+        self.acked_by(id).len() == self.interner.resolver().len()
+    }
+
+    /// Events received locally, delivered or waiting in the inbox,
+    /// that are not included in `their_version`. Dual of [`Tcsb::missing_from`].
+    pub fn extra_beyond(&self, their_version: &Version) -> Vec<EventId> {
+        let by_id: HashMap<&ReplicaId, usize> = their_version
+            .iter()
+            .filter_map(|(idx, seq)| their_version.resolver().resolve(idx).map(|id| (id, seq)))
+            .collect();
+        let their_seq = |idx: ReplicaIdx| {
+            self.interner
+                .resolve(idx)
+                .and_then(|id| by_id.get(id).copied())
+                .unwrap_or(0)
+        };
+        let mut extra: Vec<EventId> = self
+            .matrix_clock
+            .origin_version()
+            .iter()
+            .flat_map(|(idx, seq)| {
+                ((their_seq(idx) + 1)..=seq)
+                    .map(move |s| EventId::new(idx, s, self.interner.resolver().clone()))
+            })
+            .chain(
+                self.inbox
+                    .keys()
+                    .filter(|id| id.seq() > their_seq(id.idx()))
+                    .cloned(),
+            )
+            .collect();
+        extra.sort();
+        extra
+    }
+
+    /// Events waiting in the inbox for their dependencies, each with the
+    /// first dependency that has not been delivered yet.
+    ///
+    /// Events that are causally ready but not yet delivered are not listed.
+    pub fn pending(&self) -> Vec<(EventId, EventId)> {
+        let mut pending: Vec<(EventId, EventId)> = self
+            .inbox
+            .values()
+            .filter_map(|event| {
+                self.missing_dependency(event)
+                    .map(|dep| (event.id().clone(), dep))
+            })
+            .collect();
+        pending.sort();
+        pending
+    }
+
+    /// First dependency of `event` not delivered locally, if any.
+    fn missing_dependency(&self, event: &Event<O>) -> Option<EventId> {
+        let version = self.matrix_clock.origin_version();
+        event.version().iter().find_map(|(idx, event_seq)| {
+            // The event itself is part of its version
+            let event_seq = if idx == event.id().idx() {
+                event_seq - 1
+            } else {
+                event_seq
+            };
+            let local_seq = version.seq_by_idx(idx);
+            (local_seq < event_seq)
+                .then(|| EventId::new(idx, local_seq + 1, self.interner.resolver().clone()))
+        })
+    }
+
+    /// Next causally ready event, marked as delivered whether the receive filter accepts it or not.
+    fn pop_causally_ready(&mut self) -> Option<Event<O>> {
+        while let Some(event) = self.ordered.pop_front() {
+            // The same event may have been received from several peers
+            if self.is_duplicate(&event) {
+                continue;
+            }
+            if self.is_causally_ready(&event) {
+                self.inbox.remove(event.id());
+                self.mark_delivered(&event);
+                return Some(event);
+            }
+            self.inbox.insert(event.id().clone(), event);
+        }
+        let maybe_event = self
+            .inbox
+            .values()
+            .find(|e| self.is_causally_ready(e))
+            .cloned();
+        if let Some(event) = maybe_event {
+            self.inbox.remove(event.id()).unwrap();
+            self.mark_delivered(&event);
+            return Some(event);
+        }
+        None
+    }
+
     /// Record a received event in the inbox and outbox if it is valid.
     fn record(&mut self, event: Event<O>) {
         if self.is_valid(&event) {
@@ -184,6 +1015,53 @@ where
         }
     }
 
+    /// Update the matrix clock with an event about to be delivered.
+    fn mark_delivered(&mut self, event: &Event<O>) {
+        self.matrix_clock.join_origin(event.version());
+        let updated = self
+            .matrix_clock
+            .set_by_idx_incremental(event.id().idx(), event.version().clone());
+        self.extend_updated_columns(updated);
+        self.advance_frontier(event.id(), event.version());
+    }
+
+    /// Record that the replica `idx` has delivered `version`, as the clock of one of its pulls
+    /// or batches tells. It is how the replicas learn what the others have delivered from the
+    /// events whose dependencies were elided.
+    /// The entry of `idx` itself is left to the delivery of its events, as it tells which of
+    /// them are duplicates.
+    ///
+    /// The acknowledgement is ignored until the local replica has delivered every event `idx`
+    /// issued up to `version`: those may be concurrent with the events of `version`, which are
+    /// then not stable yet.
+    fn acknowledge(&mut self, idx: ReplicaIdx, version: &Version) {
+        if idx == self.replica_idx
+            || self.matrix_clock.origin_version().seq_by_idx(idx) < version.seq_by_idx(idx)
+        {
+            return;
+        }
+        let mut acknowledged = version.clone();
+        acknowledged.set_by_idx(idx, 0);
+        let updated = self.matrix_clock.set_by_idx_incremental(idx, acknowledged);
+        self.extend_updated_columns(updated);
+    }
+
+    /// Columns to recompute at the next stability check.
+    fn extend_updated_columns(&mut self, columns: Vec<ReplicaIdx>) {
+        for column in columns {
+            if !self.last_updated_columns.contains(&column) {
+                self.last_updated_columns.push(column);
+            }
+        }
+    }
+
+    /// Replace the events of the frontier that the event `id` of `version` depends on by `id`.
+    fn advance_frontier(&mut self, id: &EventId, version: &Version) {
+        self.frontier
+            .retain(|head| version.seq_by_idx(head.idx()) < head.seq());
+        self.frontier.push(id.clone());
+    }
+
     fn is_valid(&self, event: &Event<O>) -> bool {
         // TODO: reject events from unknown replicas (?)
 
@@ -234,7 +1112,13 @@ where
 
     /// Return `true` if the event is not greater than the last stable version
     /// i.e., if the sending replica needs a state transfer.
+    ///
+    /// The version of an event whose dependencies were elided tells nothing of what its origin
+    /// delivered, it is only stale if its own counter is stable.
     fn is_stale(&self, event: &Event<O>) -> bool {
+        if event.has_no_dependencies() {
+            return event.id().seq() <= self.last_stable_version.seq_by_idx(event.id().idx());
+        }
         match event.version().partial_cmp(&self.last_stable_version) {
             Some(Ordering::Less) | Some(Ordering::Equal) | None => true,
             Some(Ordering::Greater) => false,
@@ -317,11 +1201,15 @@ where
             })
             .collect();
 
+        self.acknowledge(from, &version);
         Since::new(version, except)
     }
 
-    fn internalize_batch(&mut self, message: BatchMessage<O>) -> Batch<O> {
+    fn internalize_batch(&mut self, message: BatchMessage<O>) -> Result<Batch<O>, ReceiveError> {
         let (batch, resolver) = message.into_parts();
+        for event in batch.events() {
+            ReceiveError::validate(event, &resolver)?;
+        }
         // Intern the batch origin ID
         let (from, is_new) = self.interner.intern(batch.origin_id());
 
@@ -365,7 +1253,8 @@ where
             events.push(e);
         }
 
-        Batch::new(events, version)
+        self.acknowledge(from, &version);
+        Ok(Batch::new(events, version))
     }
 }
 
@@ -423,13 +1312,637 @@ where
     }
 
     fn outbox_len(&self) -> usize {
-        self.outbox
-            .values()
-            .map(|events_by_seq| events_by_seq.len())
-            .sum()
+        self.num_unstable()
     }
 
     fn interner(&self) -> &Interner {
         &self.interner
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Op(u8);
+
+    impl InternalizeOp for Op {
+        fn internalize(self, _interner: &Interner) -> Self {
+            self
+        }
+    }
+
+    fn tcsb(id: &str, members: &[&str]) -> Tcsb<Op> {
+        let mut interner = Interner::new();
+        let (idx, _) = interner.intern(id);
+        for member in members {
+            interner.intern(member);
+        }
+        Tcsb::new(idx, interner)
+    }
+
+    fn deliver_all(tcsb: &mut Tcsb<Op>) -> Vec<Op> {
+        let mut ops = Vec::new();
+        while let Some(event) = tcsb.next_causally_ready() {
+            ops.push(event.op().clone());
+        }
+        ops
+    }
+
+    #[test]
+    fn receive_unknown_origin() {
+        let mut tcsb_a = tcsb("a", &["a", "b"]);
+        let resolver = tcsb_a.interner.resolver().clone();
+        let message = || {
+            let mut version = Version::new(ReplicaIdx(5), resolver.clone());
+            version.set_by_idx(ReplicaIdx(5), 1);
+            let id = EventId::new(ReplicaIdx(5), 1, resolver.clone());
+            let event = Event::new(id, Lamport::new(1), Op(1), version);
+            EventMessage::new(event, resolver.clone())
+        };
+
+        assert_eq!(
+            tcsb_a.try_receive(message()),
+            Err(ReceiveError::UnknownOrigin {
+                idx: ReplicaIdx(5),
+                view_len: 2
+            })
+        );
+        tcsb_a.receive(message());
+        assert!(deliver_all(&mut tcsb_a).is_empty());
+        assert_eq!(tcsb_a.interner.resolver().len(), 2);
+    }
+
+    #[test]
+    fn malformed_event_in_batch_is_rejected() {
+        let mut tcsb_a = tcsb("a", &["a", "b"]);
+        let resolver = tcsb_a.interner.resolver().clone();
+
+        // The clock of the event does not count the event itself
+        let version = Version::new(ReplicaIdx(1), resolver.clone());
+        let id = EventId::new(ReplicaIdx(1), 1, resolver.clone());
+        let event = Event::new(id, Lamport::new(1), Op(1), version.clone());
+        let batch = Batch::new(vec![event], version);
+        let message = || BatchMessage::new(batch.clone(), resolver.clone());
+
+        match tcsb_a.try_receive_batch(message()) {
+            Err(ReceiveError::MalformedEvent { id, reason }) => {
+                assert_eq!((id.origin_id(), id.seq()), ("b", 1));
+                assert_eq!(reason, MalformedEvent::SeqMismatch { id: 1, clock: 0 });
+            }
+            other => panic!("expected a malformed event, got {other:?}"),
+        }
+        tcsb_a.receive_batch(message());
+        assert!(deliver_all(&mut tcsb_a).is_empty());
+        assert_eq!(tcsb_a.num_pending(), 0);
+    }
+
+    #[test]
+    fn merge_fork() {
+        let mut tcsb_a = tcsb("a", &["a", "b"]);
+        let mut tcsb_b = tcsb("b", &["a", "b"]);
+
+        // Common history before the fork
+        let msg = tcsb_a.send(Op(1));
+        tcsb_b.receive(msg);
+        assert_eq!(deliver_all(&mut tcsb_b), vec![Op(1)]);
+
+        // Both sides work offline
+        tcsb_a.send(Op(2));
+        tcsb_b.send(Op(3));
+        tcsb_b.send(Op(4));
+
+        tcsb_a.merge(&tcsb_b).unwrap();
+        tcsb_b.merge(&tcsb_a).unwrap();
+
+        assert_eq!(deliver_all(&mut tcsb_a), vec![Op(3), Op(4)]);
+        assert_eq!(deliver_all(&mut tcsb_b), vec![Op(2)]);
+
+        let version_a = tcsb_a.matrix_clock.origin_version();
+        let version_b = tcsb_b.matrix_clock.origin_version();
+        for idx in [ReplicaIdx(0), ReplicaIdx(1)] {
+            assert_eq!(version_a.seq_by_idx(idx), version_b.seq_by_idx(idx));
+        }
+        assert_eq!(version_a.sum(), 4);
+        assert!(tcsb_a.inbox.is_empty() && tcsb_b.inbox.is_empty());
+
+        // Both outboxes hold the whole history, ready to be pulled by either side
+        let outbox = |tcsb: &Tcsb<Op>| {
+            let mut ops: Vec<Op> = IsTcsbTest::outbox(tcsb).map(|e| e.op().clone()).collect();
+            ops.sort_by_key(|op| op.0);
+            ops
+        };
+        assert_eq!(outbox(&tcsb_a), vec![Op(1), Op(2), Op(3), Op(4)]);
+        assert_eq!(outbox(&tcsb_b), outbox(&tcsb_a));
+
+        // Merging again is a no-op
+        tcsb_a.merge(&tcsb_b).unwrap();
+        assert!(deliver_all(&mut tcsb_a).is_empty());
+    }
+
+    #[test]
+    fn merge_fork_after_stabilization() {
+        let mut tcsb_a = tcsb("a", &["a", "b"]);
+        let mut tcsb_b = tcsb("b", &["a", "b"]);
+        // Copy of "b" taken before it received anything
+        let mut stale_b = tcsb("b", &["a", "b"]);
+
+        let msg = tcsb_a.send(Op(1));
+        tcsb_b.receive(msg);
+        assert_eq!(deliver_all(&mut tcsb_b), vec![Op(1)]);
+        let msg = tcsb_b.send(Op(2));
+        tcsb_a.receive(msg);
+        assert_eq!(deliver_all(&mut tcsb_a), vec![Op(2)]);
+        // Both events are stable at "a" and dropped from its outbox
+        assert!(tcsb_a.is_stable().is_some());
+
+        match stale_b.merge(&tcsb_a) {
+            Err(MergeError::PrunedEvents(ids)) => {
+                assert_eq!(dots(ids), vec![("a".to_string(), 1), ("b".to_string(), 1)]);
+            }
+            other => panic!("expected pruned events, got {other:?}"),
+        }
+        assert!(deliver_all(&mut stale_b).is_empty());
+    }
+
+    fn dots(ids: Vec<EventId>) -> Vec<(String, usize)> {
+        ids.iter()
+            .map(|id| (id.origin_id().to_string(), id.seq()))
+            .collect()
+    }
+
+    #[test]
+    fn missing_and_extra_events() {
+        let mut tcsb_a = tcsb("a", &["a", "b"]);
+        let mut tcsb_b = tcsb("b", &["a", "b"]);
+
+        let msg_1 = tcsb_a.send(Op(1));
+        let _msg_2 = tcsb_a.send(Op(2));
+        let msg_3 = tcsb_a.send(Op(3));
+        tcsb_b.receive(msg_1);
+        assert_eq!(deliver_all(&mut tcsb_b), vec![Op(1)]);
+        tcsb_b.send(Op(4));
+        // Not causally ready: stays in the inbox
+        tcsb_b.receive(msg_3);
+        assert!(deliver_all(&mut tcsb_b).is_empty());
+
+        let version_a = tcsb_a.matrix_clock.origin_version().clone();
+        let version_b = tcsb_b.matrix_clock.origin_version().clone();
+
+        assert_eq!(
+            dots(tcsb_b.missing_from(&version_a)),
+            vec![("a".to_string(), 2)]
+        );
+        assert_eq!(
+            dots(tcsb_b.extra_beyond(&version_a)),
+            vec![("b".to_string(), 1)]
+        );
+        assert_eq!(
+            dots(tcsb_a.missing_from(&version_b)),
+            vec![("b".to_string(), 1)]
+        );
+        assert_eq!(
+            dots(tcsb_a.extra_beyond(&version_b)),
+            vec![("a".to_string(), 2), ("a".to_string(), 3)]
+        );
+        assert!(tcsb_a.missing_from(&version_a).is_empty());
+        assert!(tcsb_a.extra_beyond(&version_a).is_empty());
+    }
+
+    #[test]
+    fn pending_until_dependency_delivered() {
+        let mut tcsb_a = tcsb("a", &["a", "b", "c"]);
+        let mut tcsb_b = tcsb("b", &["a", "b", "c"]);
+        let mut tcsb_c = tcsb("c", &["a", "b", "c"]);
+
+        let msg_1 = tcsb_a.send(Op(1));
+        tcsb_b.receive(msg_1.clone());
+        assert_eq!(deliver_all(&mut tcsb_b), vec![Op(1)]);
+        let msg_2 = tcsb_b.send(Op(2));
+
+        // Delivered out of causal order: waits for the event of a
+        tcsb_c.receive(msg_2);
+        assert!(deliver_all(&mut tcsb_c).is_empty());
+        let pending: Vec<_> = tcsb_c
+            .pending()
+            .into_iter()
+            .map(|(id, dep)| dots(vec![id, dep]))
+            .collect();
+        assert_eq!(
+            pending,
+            vec![vec![("b".to_string(), 1), ("a".to_string(), 1)]]
+        );
+
+        tcsb_c.receive(msg_1);
+        assert_eq!(deliver_all(&mut tcsb_c), vec![Op(1), Op(2)]);
+        assert!(tcsb_c.pending().is_empty());
+    }
+
+    #[test]
+    fn receive_many_in_causal_order() {
+        let members = ["a", "b", "c", "d"];
+        let mut tcsb_a = tcsb("a", &members);
+        let mut tcsb_b = tcsb("b", &members);
+        let mut tcsb_c = tcsb("c", &members);
+        let mut tcsb_d = tcsb("d", &members);
+
+        tcsb_a.send(Op(1));
+        tcsb_a.send(Op(2));
+        let batch = tcsb_a.pull(tcsb_b.since());
+        tcsb_b.receive_batch(batch);
+        assert_eq!(deliver_all(&mut tcsb_b), vec![Op(1), Op(2)]);
+        tcsb_b.send(Op(3));
+        tcsb_a.send(Op(4));
+
+        // The batch of b depends on the one of a, and both contain the first events of a
+        let batches = vec![tcsb_b.pull(tcsb_c.since()), tcsb_a.pull(tcsb_c.since())];
+        tcsb_c.receive_many(batches);
+        let mut delivered = deliver_all(&mut tcsb_c);
+        assert_eq!(delivered.len(), 4);
+        assert!(
+            delivered.iter().position(|op| *op == Op(3))
+                > delivered.iter().position(|op| *op == Op(2))
+        );
+        assert!(tcsb_c.inbox.is_empty());
+
+        // Same result as receiving the batches one by one
+        tcsb_d.receive_batch(tcsb_b.pull(tcsb_d.since()));
+        tcsb_d.receive_batch(tcsb_a.pull(tcsb_d.since()));
+        let mut one_by_one = deliver_all(&mut tcsb_d);
+        one_by_one.sort_by_key(|op| op.0);
+        delivered.sort_by_key(|op| op.0);
+        assert_eq!(delivered, one_by_one);
+        assert_eq!(
+            tcsb_c.matrix_clock.origin_version().sum(),
+            tcsb_d.matrix_clock.origin_version().sum()
+        );
+    }
+
+    #[test]
+    fn try_send_backpressure() {
+        let mut tcsb_a = tcsb("a", &["a", "b"]);
+        let mut tcsb_b = tcsb("b", &["a", "b"]);
+        tcsb_a.set_max_unstable(Some(2));
+
+        let mut messages = Vec::new();
+        for i in 0..2 {
+            match tcsb_a.try_send(Op(i)) {
+                SendOutcome::Sent(message) => messages.push(message),
+                SendOutcome::Backpressure { .. } => panic!("unexpected backpressure"),
+            }
+        }
+        assert!(matches!(
+            tcsb_a.try_send(Op(2)),
+            SendOutcome::Backpressure {
+                unstable: 2,
+                max_unstable: 2
+            }
+        ));
+
+        // Once b acknowledges the events, they become stable and `a` can send again
+        for message in messages {
+            tcsb_b.receive(message);
+        }
+        assert_eq!(deliver_all(&mut tcsb_b), vec![Op(0), Op(1)]);
+        tcsb_a.receive(tcsb_b.send(Op(3)));
+        assert_eq!(deliver_all(&mut tcsb_a), vec![Op(3)]);
+        assert!(tcsb_a.is_stable().is_some());
+
+        assert_eq!(tcsb_a.num_unstable(), 0);
+        assert!(matches!(tcsb_a.try_send(Op(4)), SendOutcome::Sent(_)));
+    }
+
+    #[test]
+    fn pull_from_clock() {
+        let mut tcsb_a = tcsb("a", &["a", "b", "c"]);
+        let mut tcsb_b = tcsb("b", &["a", "b", "c"]);
+        let mut tcsb_c = tcsb("c", &["a", "b", "c"]);
+
+        let msg_1 = tcsb_a.send(Op(1));
+        tcsb_a.send(Op(2));
+        let msg_3 = tcsb_a.send(Op(3));
+        tcsb_c.receive_batch(tcsb_a.pull(tcsb_c.since()));
+        deliver_all(&mut tcsb_c);
+        tcsb_c.send(Op(4));
+        tcsb_b.receive(msg_1);
+        deliver_all(&mut tcsb_b);
+        tcsb_b.receive(msg_3);
+        assert!(deliver_all(&mut tcsb_b).is_empty());
+
+        // A relay only knows the clock of b and the events b has not delivered yet
+        let version = tcsb_b.matrix_clock.origin_version().clone();
+        #[allow(clippy::mutable_key_type)]
+        let except = tcsb_b.inbox.keys().cloned().collect();
+        let by_relay = tcsb_c.pull(SinceMessage::from_clock(version, except));
+        let by_peer = tcsb_c.pull(tcsb_b.since());
+
+        let ids = |message: BatchMessage<Op>| {
+            let mut ids: Vec<EventId> = message
+                .into_batch()
+                .into_events()
+                .into_iter()
+                .map(|event| event.id().clone())
+                .collect();
+            ids.sort();
+            dots(ids)
+        };
+        let expected = vec![("a".to_string(), 2), ("c".to_string(), 1)];
+        assert_eq!(ids(by_relay), expected);
+        assert_eq!(ids(by_peer), expected);
+    }
+
+    #[test]
+    fn pull_iter_matches_pull() {
+        let mut tcsb_a = tcsb("a", &["a", "b", "c"]);
+        let mut tcsb_b = tcsb("b", &["a", "b", "c"]);
+        let tcsb_c = tcsb("c", &["a", "b", "c"]);
+
+        for i in 0..5 {
+            tcsb_a.send(Op(i));
+        }
+        let msg = tcsb_b.send(Op(10));
+        tcsb_a.receive(msg);
+        deliver_all(&mut tcsb_a);
+        tcsb_a.send(Op(5));
+
+        let ids = |events: Vec<Event<Op>>| -> Vec<EventId> {
+            events.into_iter().map(|event| event.id().clone()).collect()
+        };
+        let eager = ids(tcsb_a.pull(tcsb_c.since()).into_batch().into_events());
+        let lazy = ids(tcsb_a.pull_iter(tcsb_c.since()).collect());
+        assert_eq!(eager.len(), 7);
+        assert_eq!(lazy, eager);
+
+        // Events already known or excluded by the requester are skipped alike
+        let eager = ids(tcsb_a.pull(tcsb_b.since()).into_batch().into_events());
+        let lazy = ids(tcsb_a.pull_iter(tcsb_b.since()).collect());
+        assert_eq!(eager.len(), 6);
+        assert_eq!(lazy, eager);
+    }
+
+    #[test]
+    fn pull_in_lamport_order() {
+        let members = ["a", "b", "c"];
+        let mut tcsb_a = tcsb("a", &members);
+        let mut tcsb_b = tcsb("b", &members);
+        let tcsb_c = tcsb("c", &members);
+
+        // a runs ahead, then b catches up and keeps editing concurrently with a
+        for i in 0..4 {
+            tcsb_a.send(Op(i));
+        }
+        tcsb_b.send(Op(10));
+        tcsb_b.receive_batch(tcsb_a.pull(tcsb_b.since()));
+        deliver_all(&mut tcsb_b);
+        for i in 11..14 {
+            tcsb_b.send(Op(i));
+        }
+        tcsb_a.send(Op(4));
+        tcsb_a.receive_batch(tcsb_b.pull(tcsb_a.since()));
+        deliver_all(&mut tcsb_a);
+        tcsb_a.send(Op(5));
+
+        let events = tcsb_a
+            .pull_ordered(tcsb_c.since(), PullOrder::Lamport)
+            .into_batch()
+            .into_events();
+        assert_eq!(events.len(), 10);
+        for (i, event) in events.iter().enumerate() {
+            // Every causal predecessor in the batch comes first
+            for later in &events[i + 1..] {
+                assert!(!later.id().is_predecessor_of(event.version()));
+            }
+        }
+        assert!(events.is_sorted_by_key(|event| *event.lamport()));
+        // The first event of b, concurrent with the ones of a, is not delivered last
+        assert_eq!(events[1].op(), &Op(10));
+
+        let default = tcsb_a.pull_ordered(tcsb_c.since(), PullOrder::default());
+        let pulled = tcsb_a.pull(tcsb_c.since());
+        let ops = |message: BatchMessage<Op>| -> Vec<Op> {
+            message
+                .into_batch()
+                .into_events()
+                .into_iter()
+                .map(|event| event.op().clone())
+                .collect()
+        };
+        assert_eq!(ops(default), ops(pulled));
+    }
+
+    #[test]
+    fn view_lists_bootstrap_members() {
+        let members = ["a", "b", "c"];
+        let tcsb_a = tcsb("a", &members);
+        assert!(tcsb_a.view().members().eq(members));
+        assert_eq!(tcsb_a.view().len(), 3);
+
+        // The local replica comes first
+        let mut tcsb_b = tcsb("b", &members);
+        assert!(tcsb_b.view().members().eq(["b", "a", "c"]));
+        for member in members {
+            let idx = tcsb_b.view().index_of(member).unwrap();
+            assert_eq!(tcsb_b.view().resolve(idx), Some(member));
+        }
+        assert_eq!(tcsb_b.view().index_of("d"), None);
+
+        // A replica learnt from its events joins the view
+        let mut tcsb_d = tcsb("d", &["a", "b", "c", "d"]);
+        tcsb_b.receive(tcsb_d.send(Op(1)));
+        assert_eq!(tcsb_b.view().index_of("d"), Some(ReplicaIdx(3)));
+    }
+
+    #[test]
+    fn merge_different_members() {
+        let mut tcsb_a = tcsb("a", &["a", "b"]);
+        let tcsb_c = tcsb("c", &["a", "c"]);
+
+        assert!(matches!(
+            tcsb_a.merge(&tcsb_c),
+            Err(MergeError::MembersMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn dump_all_to_fresh_replica() {
+        let members = ["a", "b", "c"];
+        let mut tcsb_a = tcsb("a", &members);
+        let mut tcsb_b = tcsb("b", &members);
+        let mut tcsb_c = tcsb("c", &members);
+
+        for i in 0..3 {
+            tcsb_b.receive(tcsb_a.send(Op(i)));
+            deliver_all(&mut tcsb_b);
+            tcsb_a.receive(tcsb_b.send(Op(10 + i)));
+            deliver_all(&mut tcsb_a);
+        }
+        // Not delivered by a yet, but held by it
+        let missed = [tcsb_b.send(Op(20)), tcsb_b.send(Op(21))];
+        tcsb_a.receive(tcsb_b.send(Op(22)));
+        assert!(deliver_all(&mut tcsb_a).is_empty());
+
+        let dump = tcsb_a.dump_all();
+        assert_eq!(dump.events.len(), 7);
+        tcsb_c.load_dump(dump);
+        let mut delivered = deliver_all(&mut tcsb_c);
+        delivered.sort_by_key(|op| op.0);
+        assert_eq!(delivered, vec![Op(0), Op(1), Op(2), Op(10), Op(11), Op(12)]);
+        assert_eq!(
+            tcsb_c.matrix_clock.origin_version().sum(),
+            tcsb_a.matrix_clock.origin_version().sum()
+        );
+
+        // The event waiting for its dependencies is delivered with them, as by a
+        for msg in missed {
+            tcsb_a.receive(msg.clone());
+            tcsb_c.receive(msg);
+        }
+        assert_eq!(deliver_all(&mut tcsb_a), vec![Op(20), Op(21), Op(22)]);
+        assert_eq!(deliver_all(&mut tcsb_c), vec![Op(20), Op(21), Op(22)]);
+        assert_eq!(
+            tcsb_c.matrix_clock.origin_version().sum(),
+            tcsb_a.matrix_clock.origin_version().sum()
+        );
+    }
+
+    #[test]
+    fn acked_after_full_sync() {
+        let members = ["a", "b", "c"];
+        let mut tcsb_a = tcsb("a", &members);
+        let mut tcsb_b = tcsb("b", &members);
+        let mut tcsb_c = tcsb("c", &members);
+
+        let msg = tcsb_a.send(Op(1));
+        let id = msg.event().id().clone();
+        let acked = |ids: &[&str]| ids.iter().map(ToString::to_string).collect::<HashSet<_>>();
+        assert_eq!(tcsb_a.acked_by(&id), acked(&["a"]));
+
+        tcsb_b.receive(msg.clone());
+        tcsb_c.receive(msg);
+        deliver_all(&mut tcsb_b);
+        deliver_all(&mut tcsb_c);
+        // b only knows that a and itself delivered the event
+        assert_eq!(tcsb_b.acked_by(&id), acked(&["a", "b"]));
+        assert!(!tcsb_b.fully_acked(&id));
+
+        tcsb_a.receive(tcsb_b.send(Op(2)));
+        tcsb_a.receive(tcsb_c.send(Op(3)));
+        deliver_all(&mut tcsb_a);
+        assert_eq!(tcsb_a.acked_by(&id), acked(&members));
+        assert!(tcsb_a.fully_acked(&id));
+    }
+
+    #[test]
+    fn frontier_after_concurrent_sends() {
+        let mut tcsb_a = tcsb("a", &["a", "b"]);
+        let mut tcsb_b = tcsb("b", &["a", "b"]);
+        let head = |id: &str, seq: usize| vec![(id.to_string(), seq)];
+        assert!(tcsb_a.frontier().is_empty());
+
+        let msg_1 = tcsb_a.send(Op(1));
+        let msg_2 = tcsb_a.send(Op(2));
+        assert_eq!(dots(tcsb_a.frontier()), head("a", 2));
+
+        // b depends on the first event of a only, so it is concurrent with the second one
+        tcsb_b.receive(msg_1);
+        deliver_all(&mut tcsb_b);
+        let msg_3 = tcsb_b.send(Op(3));
+        assert_eq!(dots(tcsb_b.frontier()), head("b", 1));
+
+        tcsb_a.receive(msg_3);
+        tcsb_b.receive(msg_2);
+        deliver_all(&mut tcsb_a);
+        deliver_all(&mut tcsb_b);
+        let both = vec![("a".to_string(), 2), ("b".to_string(), 1)];
+        assert_eq!(dots(tcsb_a.frontier()), both);
+        assert_eq!(dots(tcsb_b.frontier()), both);
+
+        tcsb_b.receive(tcsb_a.send(Op(4)));
+        deliver_all(&mut tcsb_b);
+        assert_eq!(dots(tcsb_a.frontier()), head("a", 3));
+        assert_eq!(dots(tcsb_b.frontier()), head("a", 3));
+    }
+
+    #[test]
+    fn dump_all_once_stable() {
+        let members = ["a", "b", "c"];
+        let mut tcsb_a = tcsb("a", &members);
+        let mut tcsb_b = tcsb("b", &members);
+        let mut tcsb_c = tcsb("c", &members);
+
+        // Concurrent with the event of a
+        let msg_2 = tcsb_b.send(Op(2));
+        let msg_1 = tcsb_a.send(Op(1));
+        tcsb_b.receive(msg_1.clone());
+        tcsb_c.receive(msg_1);
+        deliver_all(&mut tcsb_b);
+        deliver_all(&mut tcsb_c);
+        // The pulls tell a that b and c delivered its event
+        let since = tcsb_a.since();
+        tcsb_a.receive_batch(tcsb_c.pull(since));
+        let since = tcsb_a.since();
+        tcsb_a.receive_batch(tcsb_b.pull(since));
+        assert_eq!(deliver_all(&mut tcsb_a), vec![Op(2)]);
+        assert!(tcsb_a.is_stable().is_some());
+        assert_eq!(tcsb_a.num_unstable(), 1);
+
+        let dump = tcsb_a.dump_all();
+        assert_eq!(dump.stable_version.sum(), 1);
+        assert_eq!(dump.events.len(), 1);
+        assert_eq!(
+            dots(dump.stable_frontier.clone()),
+            vec![("a".to_string(), 1)]
+        );
+
+        // A fresh replica only delivers the unstable event
+        let mut tcsb_d = tcsb("d", &["a", "b", "c", "d"]);
+        tcsb_d.load_dump(dump);
+        assert_eq!(deliver_all(&mut tcsb_d), vec![Op(2)]);
+        assert_eq!(tcsb_d.version().sum(), 2);
+        assert_eq!(tcsb_d.stable_version().sum(), 1);
+        assert_eq!(dots(tcsb_d.frontier()), dots(tcsb_a.frontier()));
+
+        // and the events depending on the stable one
+        tcsb_c.receive(msg_2);
+        deliver_all(&mut tcsb_c);
+        tcsb_d.receive(tcsb_c.send(Op(3)));
+        assert_eq!(deliver_all(&mut tcsb_d), vec![Op(3)]);
+    }
+
+    #[test]
+    fn stable_events_are_discarded() {
+        let mut tcsb_a = tcsb("a", &["a", "b"]);
+        let mut tcsb_b = tcsb("b", &["a", "b"]);
+
+        for _ in 0..1_000 {
+            tcsb_b.receive(tcsb_a.send(Op(1)));
+            deliver_all(&mut tcsb_b);
+            tcsb_b.is_stable();
+            tcsb_a.receive(tcsb_b.send(Op(2)));
+            deliver_all(&mut tcsb_a);
+            tcsb_a.is_stable();
+            // Only the events not known to be delivered by the other replica are kept
+            assert!(tcsb_a.num_unstable() <= 2);
+            assert!(tcsb_b.num_unstable() <= 2);
+        }
+
+        assert!(tcsb_a.try_pull(tcsb_b.since()).is_ok());
+        // A late replica asks for events that were discarded
+        let tcsb_c = tcsb("c", &["a", "b", "c"]);
+        let err = tcsb_a.try_pull(tcsb_c.since()).unwrap_err();
+        assert_eq!(
+            err,
+            PullError::HistoryDiscarded {
+                replica: "a".to_string(),
+                since: 0,
+                stable: 1_000,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Events 1..=1000 of a are stable and were discarded, \
+             start from a snapshot of the state instead"
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use std::fmt::{Debug, Display, Formatter};
+use std::{
+    fmt::{Debug, Display, Formatter},
+    ops::{Deref, DerefMut},
+};
 
 #[cfg(feature = "test_utils")]
 use deepsize::DeepSizeOf;
@@ -27,13 +30,21 @@ impl ReplicaMap {
     }
 }
 
+/// Minimum of a column of the matrix clock, with the number of rows holding it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+struct ColumnMin {
+    seq: Seq,
+    count: usize,
+}
+
 /// A matrix clock is a generalization in 2 dimensions of a vector clock. It is a square matrix of positive integers.
 /// Each row represents the last vector clock known by the local replica from each member of the view.
 /// The column-wise maximum is the clock of the local replica. The column-wise minimum is the stable version vector (SVV).
 #[derive(Debug, PartialEq)]
 #[cfg_attr(
     feature = "serde",
-    derive(Serialize, Deserialize, Tsify),
+    derive(Serialize, Tsify),
     tsify(into_wasm_abi, from_wasm_abi)
 )]
 #[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
@@ -41,6 +52,39 @@ pub struct MatrixClock {
     entries: ReplicaMap,
     origin_idx: ReplicaIdx,
     resolver: Resolver,
+    /// Running minimum of each column, updated as the rows advance.
+    /// Not serialized: it is rebuilt from the rows on deserialization.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    column_mins: Vec<ColumnMin>,
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for MatrixClock {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Fields {
+            entries: ReplicaMap,
+            origin_idx: ReplicaIdx,
+            resolver: Resolver,
+        }
+
+        let Fields {
+            entries,
+            origin_idx,
+            resolver,
+        } = Fields::deserialize(deserializer)?;
+        let len = entries.0.len();
+        let mut matrix = Self {
+            entries,
+            origin_idx,
+            resolver,
+            column_mins: vec![ColumnMin { seq: 0, count: 0 }; len],
+        };
+        for col_idx in 0..len {
+            matrix.rescan_column(ReplicaIdx(col_idx));
+        }
+        Ok(matrix)
+    }
 }
 
 impl MatrixClock {
@@ -50,10 +94,18 @@ impl MatrixClock {
             entries.push(Version::new(ReplicaIdx(i), resolver.clone()));
         }
 
+        let column_mins = vec![
+            ColumnMin {
+                seq: 0,
+                count: entries.len(),
+            };
+            entries.len()
+        ];
         let matrix = Self {
             entries: ReplicaMap(entries),
             origin_idx,
             resolver,
+            column_mins,
         };
         debug_assert!(matrix.is_valid());
         matrix
@@ -63,34 +115,122 @@ impl MatrixClock {
         self.entries.get(self.origin_idx).unwrap()
     }
 
-    pub fn origin_version_mut(&mut self) -> &mut Version {
-        self.entries.get_mut(self.origin_idx).unwrap()
+    /// Mutable access to the origin clock.
+    ///
+    /// # Complexity
+    /// The returned guard rescans every column when dropped: `O(n^2)`.
+    #[deprecated(
+        note = "use `increment_origin` or `join_origin`, which keep the running minimums up to date"
+    )]
+    pub fn origin_version_mut(&mut self) -> OriginVersionMut<'_> {
+        OriginVersionMut { matrix: self }
+    }
+
+    /// Increment the origin's entry of the origin clock, for a new local event.
+    pub fn increment_origin(&mut self) -> Seq {
+        let seq = self.origin_version().origin_seq() + 1;
+        self.advance(self.origin_idx, self.origin_idx, seq);
+        seq
+    }
+
+    /// Merge the version of a delivered event into the origin clock.
+    pub fn join_origin(&mut self, version: &Version) {
+        for (col_idx, seq) in version.iter() {
+            self.advance(self.origin_idx, col_idx, seq);
+        }
     }
 
     pub fn version_by_idx(&self, idx: ReplicaIdx) -> Option<&Version> {
         self.entries.get(idx)
     }
 
+    /// Replace a row, which may go backward.
+    ///
+    /// # Complexity
+    /// Rescans every column: `O(n^2)`. Use [`MatrixClock::set_by_idx_incremental`] for a row that advances.
     pub fn set_by_idx(&mut self, idx: ReplicaIdx, version: Version) {
         *self.entries.get_mut(idx).unwrap() = version;
+        for col_idx in 0..self.column_mins.len() {
+            self.rescan_column(ReplicaIdx(col_idx));
+        }
     }
 
+    /// Advance a row to `version`, and return the columns that changed.
+    ///
+    /// # Complexity
+    /// `O(n)`, plus an `O(n)` rescan of each column whose last row at the minimum advances:
+    /// `O(n^2)` in the worst case, when the row was the only one at the minimum of every column.
     pub fn set_by_idx_incremental(&mut self, idx: ReplicaIdx, version: Version) -> Vec<ReplicaIdx> {
-        let entry = self.entries.get_mut(idx).unwrap();
         let mut updated_columns = Vec::new();
         for (col_idx, seq) in version.iter() {
-            if seq > entry.seq_by_idx(col_idx) {
-                entry.set_by_idx(col_idx, seq);
+            if self.advance(idx, col_idx, seq) {
                 updated_columns.push(col_idx);
             }
         }
         updated_columns
     }
 
+    /// # Complexity
+    /// `O(n)`
     pub fn add_replica(&mut self, idx: ReplicaIdx) {
         debug_assert!(idx.0 == self.entries.0.len());
         let version = Version::new(idx, self.resolver.clone());
         self.entries.0.push(version);
+        // The new row is 0 in every column
+        for min in self.column_mins.iter_mut() {
+            if min.seq == 0 {
+                min.count += 1;
+            } else {
+                *min = ColumnMin { seq: 0, count: 1 };
+            }
+        }
+        self.column_mins.push(ColumnMin {
+            seq: 0,
+            count: self.entries.0.len(),
+        });
+    }
+
+    /// Raise the entry `col_idx` of the row `row_idx` to `seq`, keeping the column minimum.
+    /// Returns whether the entry changed.
+    ///
+    /// # Complexity
+    /// `O(1)`, or `O(n)` when the last row at the minimum of the column advances.
+    fn advance(&mut self, row_idx: ReplicaIdx, col_idx: ReplicaIdx, seq: Seq) -> bool {
+        let entry = self.entries.get_mut(row_idx).unwrap();
+        let old = entry.seq_by_idx(col_idx);
+        if seq <= old {
+            return false;
+        }
+        entry.set_by_idx(col_idx, seq);
+        let min = &mut self.column_mins[col_idx.0];
+        if old == min.seq {
+            min.count -= 1;
+            if min.count == 0 {
+                self.rescan_column(col_idx);
+            }
+        }
+        true
+    }
+
+    /// # Complexity
+    /// `O(n)`
+    fn rescan_column(&mut self, col_idx: ReplicaIdx) {
+        let mut min = ColumnMin {
+            seq: Seq::MAX,
+            count: 0,
+        };
+        for ver in self.entries.0.iter() {
+            let entry = ver.seq_by_idx(col_idx);
+            if entry < min.seq {
+                min = ColumnMin {
+                    seq: entry,
+                    count: 1,
+                };
+            } else if entry == min.seq {
+                min.count += 1;
+            }
+        }
+        self.column_mins[col_idx.0] = min;
     }
 
     /// At each node i, the Stable Version Vector at i (SVVi) is the pointwise minimum of all version vectors in the LTM.
@@ -111,9 +251,12 @@ impl MatrixClock {
         min_clock
     }
 
-    /// Incremental SVV recomputation that only rescans columns whose value can advance
-    /// relative to the provided `last_svv`. It stops a column scan early as soon as a value
-    /// less than or equal to the previous minimum is found, since the minimum then cannot grow.
+    /// Incremental SVV recomputation that only updates the columns whose value can advance
+    /// relative to the provided `last_svv`, from the running minimum of each column.
+    ///
+    /// # Complexity
+    /// `O(n)` to copy `last_svv`, then `O(1)` per updated column: the columns are rescanned
+    /// when the rows advance, see [`MatrixClock::set_by_idx_incremental`].
     pub fn column_wise_min_incremental(
         &self,
         last_svv: &Version,
@@ -122,19 +265,7 @@ impl MatrixClock {
         let mut svv = last_svv.clone();
 
         for col_idx in updated_columns {
-            let mut min_value = Seq::MAX;
-            for ver in self.entries.0.iter() {
-                let entry = ver.seq_by_idx(*col_idx);
-                if entry == svv.seq_by_idx(*col_idx) {
-                    // Cannot advance this column's minimum
-                    min_value = entry;
-                    break;
-                }
-                if entry < min_value {
-                    min_value = entry;
-                }
-            }
-            svv.set_by_idx(*col_idx, min_value);
+            svv.set_by_idx(*col_idx, self.column_mins[col_idx.0].seq);
         }
 
         svv
@@ -181,6 +312,7 @@ impl MatrixClock {
     /// - is square
     /// - no clock i has an entry j greater than the entry j of clock j
     /// - every entry i of the origin clock is equal or greater than the entry i of the clock i
+    /// - the running minimum of each column matches the column
     ///
     /// Returns true if the matrix clock is valid
     /// # Complexity
@@ -190,7 +322,23 @@ impl MatrixClock {
         let diagonal = self.diagonal();
         let dominate = self.dominate();
 
-        is_square && diagonal && dominate
+        is_square && diagonal && dominate && self.mins_match()
+    }
+
+    /// Check that the running minimum of each column matches the matrix
+    /// # Complexity
+    /// `O(n^2)`
+    fn mins_match(&self) -> bool {
+        self.column_mins.len() == self.entries.0.len()
+            && self.column_mins.iter().enumerate().all(|(col_idx, min)| {
+                let column = self
+                    .entries
+                    .0
+                    .iter()
+                    .map(|ver| ver.seq_by_idx(ReplicaIdx(col_idx)));
+                column.clone().min() == Some(min.seq)
+                    && column.filter(|seq| *seq == min.seq).count() == min.count
+            })
     }
 
     #[cfg(test)]
@@ -204,6 +352,34 @@ impl MatrixClock {
     }
 }
 
+/// Origin clock borrowed by the deprecated [`MatrixClock::origin_version_mut`].
+/// The running minimum of each column is rebuilt when it is dropped.
+pub struct OriginVersionMut<'a> {
+    matrix: &'a mut MatrixClock,
+}
+
+impl Deref for OriginVersionMut<'_> {
+    type Target = Version;
+
+    fn deref(&self) -> &Version {
+        self.matrix.origin_version()
+    }
+}
+
+impl DerefMut for OriginVersionMut<'_> {
+    fn deref_mut(&mut self) -> &mut Version {
+        self.matrix.entries.get_mut(self.matrix.origin_idx).unwrap()
+    }
+}
+
+impl Drop for OriginVersionMut<'_> {
+    fn drop(&mut self) {
+        for col_idx in 0..self.matrix.column_mins.len() {
+            self.matrix.rescan_column(ReplicaIdx(col_idx));
+        }
+    }
+}
+
 impl Display for MatrixClock {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "{{")?;
@@ -217,6 +393,8 @@ impl Display for MatrixClock {
 
 #[cfg(test)]
 mod tests {
+    use rand::{RngExt, SeedableRng, rngs::StdRng};
+
     use crate::{
         clock::{matrix_clock::MatrixClock, version_vector::Version},
         replica::ReplicaIdx,
@@ -284,4 +462,137 @@ mod tests {
             Version::build(resolver.clone(), ReplicaIdx(0), &[3, 10, 0])
         );
     }
+
+    #[test]
+    #[allow(deprecated)]
+    fn incremental_matches_full_recomputation() {
+        const N: usize = 64;
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut interner = Interner::new();
+        for i in 0..N {
+            interner.intern(&format!("r{i}"));
+        }
+        let resolver = interner.resolver();
+        let mut mc = MatrixClock::new(ReplicaIdx(0), resolver.clone());
+        let mut svv = mc.column_wise_min();
+        // Delivered version of each replica
+        let mut clocks: Vec<Version> = (0..N)
+            .map(|i| Version::new(ReplicaIdx(i), resolver.clone()))
+            .collect();
+
+        for _ in 0..2_000 {
+            // A replica issues an event after learning the clock of another one
+            let sender = rng.random_range(0..N);
+            let other = clocks[rng.random_range(0..N)].clone();
+            if other.origin_seq() > 0 {
+                clocks[sender].join(&other);
+            }
+            clocks[sender].increment();
+            let version = clocks[sender].clone();
+
+            mc.join_origin(&version);
+            let updated = mc.set_by_idx_incremental(ReplicaIdx(sender), version);
+            svv = mc.column_wise_min_incremental(&svv, &updated);
+            assert_eq!(svv, mc.column_wise_min());
+        }
+        assert!(mc.is_valid());
+        assert!(svv.sum() > 0);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn origin_version_mut_keeps_the_minimums() {
+        let mut interner = Interner::new();
+        interner.intern("A");
+        interner.intern("B");
+        let resolver = interner.resolver();
+        let mut mc = MatrixClock::build(resolver.clone(), ReplicaIdx(0), &[&[1, 3], &[1, 3]]);
+        let svv = mc.column_wise_min();
+
+        // The running minimums are rebuilt once the origin clock is released
+        mc.origin_version_mut().increment();
+        assert!(mc.is_valid());
+        mc.set_by_idx_incremental(
+            ReplicaIdx(1),
+            Version::build(resolver.clone(), ReplicaIdx(1), &[2, 3]),
+        );
+        assert!(mc.is_valid());
+        assert_eq!(
+            mc.column_wise_min_incremental(&svv, &[ReplicaIdx(0)]),
+            Version::build(resolver.clone(), ReplicaIdx(0), &[2, 3])
+        );
+    }
+
+    /// Cost of the stable version with 64 replicas, from a scan of the updated columns as before
+    /// the running minimums, and from the running minimums, the rows being updated in both cases.
+    /// Run with `cargo test --release -p moirai-protocol stable_version_cost -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn stable_version_cost_64_replicas() {
+        use std::time::Instant;
+
+        use crate::clock::version_vector::Seq;
+
+        const N: usize = 64;
+        const EVENTS: u32 = 200_000;
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut interner = Interner::new();
+        for i in 0..N {
+            interner.intern(&format!("r{i}"));
+        }
+        let resolver = interner.resolver();
+        let mut clocks: Vec<Version> = (0..N)
+            .map(|i| Version::new(ReplicaIdx(i), resolver.clone()))
+            .collect();
+        let events: Vec<(usize, Version)> = (0..EVENTS)
+            .map(|_| {
+                let sender = rng.random_range(0..N);
+                let other = clocks[rng.random_range(0..N)].clone();
+                if other.origin_seq() > 0 {
+                    clocks[sender].join(&other);
+                }
+                clocks[sender].increment();
+                (sender, clocks[sender].clone())
+            })
+            .collect();
+
+        // Scan of each updated column, stopping at the previous minimum
+        let mut mc = MatrixClock::new(ReplicaIdx(0), resolver.clone());
+        let mut svv = Version::new(ReplicaIdx(0), resolver.clone());
+        let start = Instant::now();
+        for (sender, version) in &events {
+            let row = &mut mc.entries.0[*sender];
+            let mut updated = Vec::new();
+            for (col_idx, seq) in version.iter() {
+                if seq > row.seq_by_idx(col_idx) {
+                    row.set_by_idx(col_idx, seq);
+                    updated.push(col_idx);
+                }
+            }
+            for col_idx in updated {
+                let mut min = Seq::MAX;
+                for row in mc.entries.0.iter() {
+                    let seq = row.seq_by_idx(col_idx);
+                    min = min.min(seq);
+                    if seq == svv.seq_by_idx(col_idx) {
+                        break;
+                    }
+                }
+                svv.set_by_idx(col_idx, min);
+            }
+        }
+        println!("scan: {:?} per event", start.elapsed() / EVENTS);
+        let scanned = svv;
+
+        let mut mc = MatrixClock::new(ReplicaIdx(0), resolver.clone());
+        let mut svv = Version::new(ReplicaIdx(0), resolver.clone());
+        let start = Instant::now();
+        for (sender, version) in events {
+            let updated = mc.set_by_idx_incremental(ReplicaIdx(sender), version);
+            svv = mc.column_wise_min_incremental(&svv, &updated);
+        }
+        println!("running minimums: {:?} per event", start.elapsed() / EVENTS);
+
+        assert_eq!(svv, scanned);
+    }
 }
@@ -14,62 +14,183 @@ use tsify::Tsify;
 use crate::{
     event::id::EventId,
     replica::{ReplicaId, ReplicaIdx},
-    utils::intern_str::Resolver,
+    utils::intern_str::{InternalizeOp, Interner, Resolver},
 };
 
 /// Sequence number
 pub type Seq = usize;
 
+/// Views smaller than this always use dense entries.
+const SPARSE_MIN_LEN: usize = 16;
+
+/// Entries of a version.
+///
+/// An event usually depends on few members of a large view, so its version starts as the sorted
+/// list of its non-zero entries. It becomes a dense vector once it is no longer smaller that way.
+/// Both representations behave identically.
 #[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
-#[derive(Debug, Clone, PartialEq)]
-struct VersionEntries(Vec<Seq>);
+#[derive(Debug, Clone)]
+enum VersionEntries {
+    Dense(Vec<Seq>),
+    Sparse {
+        /// Length of the equivalent dense vector
+        len: usize,
+        /// Non-zero entries, sorted by index
+        entries: Vec<(ReplicaIdx, Seq)>,
+    },
+}
 
 impl VersionEntries {
+    fn new(len: usize) -> Self {
+        if len < SPARSE_MIN_LEN {
+            Self::Dense(vec![0; len])
+        } else {
+            Self::Sparse {
+                len,
+                entries: Vec::new(),
+            }
+        }
+    }
+
     fn get(&self, idx: ReplicaIdx) -> Seq {
-        *self.0.get(idx.0).unwrap_or(&0)
+        match self {
+            Self::Dense(entries) => *entries.get(idx.0).unwrap_or(&0),
+            Self::Sparse { entries, .. } => entries
+                .binary_search_by_key(&idx, |(i, _)| *i)
+                .map_or(0, |pos| entries[pos].1),
+        }
     }
 
-    fn get_mut(&mut self, idx: ReplicaIdx) -> &mut Seq {
+    /// # Complexity
+    /// Runs in `O(1)` time complexity for dense entries, `O(k)` for `k` sparse entries
+    fn set(&mut self, idx: ReplicaIdx, seq: Seq) {
         self.fill_to(idx.0 + 1);
-        self.0.get_mut(idx.0).unwrap()
+        match self {
+            Self::Dense(entries) => entries[idx.0] = seq,
+            Self::Sparse { entries, .. } => {
+                match (entries.binary_search_by_key(&idx, |(i, _)| *i), seq) {
+                    (Ok(pos), 0) => {
+                        entries.remove(pos);
+                    }
+                    (Ok(pos), seq) => entries[pos].1 = seq,
+                    (Err(_), 0) => {}
+                    (Err(pos), seq) => entries.insert(pos, (idx, seq)),
+                }
+                self.densify_if_larger();
+            }
+        }
+    }
+
+    fn increment(&mut self, idx: ReplicaIdx) -> Seq {
+        let seq = self.get(idx) + 1;
+        self.set(idx, seq);
+        seq
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Dense(entries) => entries.len(),
+            Self::Sparse { len, .. } => *len,
+        }
+    }
+
+    fn fill_to(&mut self, new_len: usize) {
+        match self {
+            Self::Dense(entries) => {
+                if new_len > entries.len() {
+                    entries.resize(new_len, 0);
+                }
+            }
+            Self::Sparse { len, .. } => *len = new_len.max(*len),
+        }
     }
 
-    fn fill_to(&mut self, len: usize) {
-        if len > self.0.len() {
-            self.0.resize(len, 0);
+    /// A sparse entry takes the room of two dense ones.
+    fn densify_if_larger(&mut self) {
+        if let Self::Sparse { len, entries } = self
+            && entries.len() * 2 >= *len
+        {
+            let mut dense = vec![0; *len];
+            for (idx, seq) in entries.iter() {
+                dense[idx.0] = *seq;
+            }
+            *self = Self::Dense(dense);
         }
     }
 
     /// # Complexity
     /// Runs in `O(n)` time complexity with `n` being the number of members in the view
     fn join(&mut self, other: &Self) {
-        self.fill_to(other.0.len());
-        self.0
-            .iter_mut()
-            .zip(other.0.iter().chain(std::iter::repeat(&0)))
-            .for_each(|(a, b)| {
-                if *a < *b {
-                    *a = *b;
+        self.fill_to(other.len());
+        match (&mut *self, other) {
+            (Self::Dense(a), Self::Dense(b)) => a
+                .iter_mut()
+                .zip(b.iter().chain(std::iter::repeat(&0)))
+                .for_each(|(a, b)| {
+                    if *a < *b {
+                        *a = *b;
+                    }
+                }),
+            _ => {
+                for (idx, seq) in other.non_zero() {
+                    if self.get(idx) < seq {
+                        self.set(idx, seq);
+                    }
                 }
-            });
+            }
+        }
     }
 
     /// # Complexity
     /// Runs in `O(n)` time complexity with `n` being the number of members in the view
     fn meet(&mut self, other: &Self) {
-        self.fill_to(other.0.len());
-        self.0
-            .iter_mut()
-            .zip(other.0.iter().chain(std::iter::repeat(&0)))
-            .for_each(|(a, b)| {
-                if *a > *b {
-                    *a = *b;
+        self.fill_to(other.len());
+        match self {
+            Self::Dense(entries) => {
+                for (i, a) in entries.iter_mut().enumerate() {
+                    *a = (*a).min(other.get(ReplicaIdx(i)));
                 }
-            });
+            }
+            Self::Sparse { entries, .. } => {
+                for (idx, a) in entries.iter_mut() {
+                    *a = (*a).min(other.get(*idx));
+                }
+                entries.retain(|(_, seq)| *seq > 0);
+            }
+        }
+    }
+
+    /// Every entry, zeros included, in index order.
+    fn iter(&self) -> impl Iterator<Item = (ReplicaIdx, Seq)> + '_ {
+        (0..self.len()).map(|i| (ReplicaIdx(i), self.get(ReplicaIdx(i))))
+    }
+
+    /// Non-zero entries, in index order.
+    fn non_zero(&self) -> Box<dyn Iterator<Item = (ReplicaIdx, Seq)> + '_> {
+        match self {
+            Self::Dense(entries) => Box::new(
+                entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, seq)| **seq > 0)
+                    .map(|(i, seq)| (ReplicaIdx(i), *seq)),
+            ),
+            Self::Sparse { entries, .. } => Box::new(entries.iter().copied()),
+        }
     }
+}
 
-    fn values(&self) -> impl Iterator<Item = &Seq> {
-        self.0.iter()
+/// Entries are equal when they hold the same values, whatever their representation.
+impl PartialEq for VersionEntries {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.non_zero().eq(other.non_zero())
+    }
+}
+
+impl Hash for VersionEntries {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        self.non_zero().for_each(|entry| entry.hash(state));
     }
 }
 
@@ -89,15 +210,14 @@ pub struct Version {
 impl Hash for Version {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.origin_idx.hash(state);
-        self.entries.0.hash(state);
+        self.entries.hash(state);
     }
 }
 
 impl Version {
     pub fn new(origin_idx: ReplicaIdx, resolver: Resolver) -> Self {
-        let entries = vec![0; resolver.len()];
         Self {
-            entries: VersionEntries(entries),
+            entries: VersionEntries::new(resolver.len()),
             origin_idx,
             resolver,
         }
@@ -106,11 +226,9 @@ impl Version {
     /// Increment the origin's entry.
     ///
     /// # Complexity
-    /// Runs in `O(1)` time complexity
+    /// Runs in `O(1)` time complexity, `O(k)` for a sparse version of `k` entries
     pub fn increment(&mut self) -> usize {
-        let seq = self.entries.get_mut(self.origin_idx);
-        *seq += 1;
-        *seq
+        self.entries.increment(self.origin_idx)
     }
 
     /// Merge two clocks that share the same view.
@@ -127,22 +245,39 @@ impl Version {
         self.entries.join(&other.entries);
     }
 
+    /// Whether the event of `self` causally follows the event of `other`.
+    /// Equal versions belong to the same event, which neither dominates nor precedes itself.
+    pub fn dominates(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Greater)
+    }
+
+    /// Whether the event of `self` causally precedes the event of `other`.
+    pub fn happens_before(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Less)
+    }
+
+    /// Whether the events of `self` and `other` are causally unrelated.
+    pub fn concurrent_with(&self, other: &Self) -> bool {
+        self.partial_cmp(other).is_none()
+    }
+
+    /// Whether every entry of `other` is at most the matching entry of `self`.
+    /// Unlike `partial_cmp`, it does not assume that both versions belong to events.
+    pub fn includes(&self, other: &Self) -> bool {
+        other
+            .entries
+            .non_zero()
+            .all(|(idx, seq)| self.entries.get(idx) >= seq)
+    }
+
     pub fn iter<'a>(&'a self) -> impl Iterator<Item = (ReplicaIdx, Seq)> + 'a {
-        self.entries
-            .0
-            .iter()
-            .enumerate()
-            .map(|(i, v)| (ReplicaIdx(i), *v))
+        self.entries.iter()
     }
 
     pub fn dependencies<'a>(&'a self) -> impl Iterator<Item = EventId> + 'a {
-        self.iter().filter_map(|(idx, seq)| {
-            if seq > 0 {
-                let seq = if idx == self.origin_idx { seq - 1 } else { seq };
-                Some(EventId::new(idx, seq, self.resolver.clone()))
-            } else {
-                None
-            }
+        self.entries.non_zero().map(|(idx, seq)| {
+            let seq = if idx == self.origin_idx { seq - 1 } else { seq };
+            EventId::new(idx, seq, self.resolver.clone())
         })
     }
 
@@ -155,11 +290,11 @@ impl Version {
     }
 
     pub fn sum(&self) -> usize {
-        self.entries.values().sum()
+        self.entries.non_zero().map(|(_, seq)| seq).sum()
     }
 
     pub fn set_by_idx(&mut self, idx: ReplicaIdx, value: Seq) {
-        *self.entries.get_mut(idx) = value;
+        self.entries.set(idx, value);
     }
 
     pub fn len(&self) -> usize {
@@ -186,6 +321,48 @@ impl Version {
         &self.resolver
     }
 
+    /// Encode this version as the sparse list of entries that differ from `base`.
+    ///
+    /// # Complexity
+    /// Runs in `O(n)` time complexity with `n` being the number of members in the view
+    pub fn delta(&self, base: &Version) -> VersionDelta {
+        debug_assert!(
+            self.resolver == base.resolver,
+            "Computing a delta between versions with different views"
+        );
+        let entries = self
+            .iter()
+            .filter(|(idx, seq)| base.seq_by_idx(*idx) != *seq)
+            .collect();
+        VersionDelta {
+            origin_idx: self.origin_idx,
+            len: self.entries.len(),
+            entries,
+        }
+    }
+
+    /// Rebuild the version encoded by `delta` on top of `base`.
+    /// The result is identical to the version the delta was computed from.
+    ///
+    /// # Complexity
+    /// Runs in `O(n)` time complexity with `n` being the number of members in the view
+    pub fn from_delta(base: &Version, delta: &VersionDelta) -> Self {
+        let mut entries = VersionEntries::new(delta.len);
+        for (idx, seq) in base.entries.non_zero() {
+            if idx.0 < delta.len {
+                entries.set(idx, seq);
+            }
+        }
+        for (idx, seq) in delta.entries.iter() {
+            entries.set(*idx, *seq);
+        }
+        Self {
+            entries,
+            origin_idx: delta.origin_idx,
+            resolver: base.resolver.clone(),
+        }
+    }
+
     #[cfg(test)]
     pub(in crate::clock) fn build(
         resolver: Resolver,
@@ -200,6 +377,28 @@ impl Version {
     }
 }
 
+/// Sparse encoding of a [`Version`] relative to a base version.
+/// Only the entries that differ from the base are stored.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub struct VersionDelta {
+    origin_idx: ReplicaIdx,
+    /// Number of entries of the encoded version, which may be smaller than the base's.
+    len: usize,
+    entries: Vec<(ReplicaIdx, Seq)>,
+}
+
+impl VersionDelta {
+    pub fn origin_idx(&self) -> ReplicaIdx {
+        self.origin_idx
+    }
+
+    /// Number of entries that differ from the base version.
+    pub fn num_entries(&self) -> usize {
+        self.entries.len()
+    }
+}
+
 impl From<&Version> for EventId {
     fn from(version: &Version) -> Self {
         if version.origin_seq() == 0 {
@@ -213,6 +412,23 @@ impl From<&Version> for EventId {
     }
 }
 
+/// Versions embedded in operations must be translated to the local indices.
+impl InternalizeOp for Version {
+    fn internalize(self, interner: &Interner) -> Self {
+        let translate = |idx: ReplicaIdx| {
+            let id = self.resolver.resolve(idx).unwrap();
+            interner.get(id).unwrap_or_else(|| {
+                panic!("Cannot translate embedded Version for unknown replica {id}")
+            })
+        };
+        let mut version = Version::new(translate(self.origin_idx), interner.resolver().clone());
+        for (idx, seq) in self.iter() {
+            version.set_by_idx(translate(idx), seq);
+        }
+        version
+    }
+}
+
 impl Display for Version {
     fn fmt(&self, _f: &mut std::fmt::Formatter) -> std::fmt::Result {
         // ["a":1,"b":2,"c":3]@a
@@ -243,21 +459,14 @@ impl Version {
     fn compare_non_origin_entries(&self, other: &Self) -> (bool, bool) {
         let mut self_greater = false;
         let mut other_greater = false;
-        let l = self.entries.0.len().max(other.entries.0.len());
+        let l = self.entries.len().max(other.entries.len());
 
-        for (idx, (a, b)) in self
-            .entries
-            .values()
-            .chain(std::iter::repeat(&0))
-            .zip(other.entries.values().chain(std::iter::repeat(&0)))
-            .take(l)
-            .enumerate()
-        {
+        for idx in (0..l).map(ReplicaIdx) {
             // On ignore les entrées des origines (déjà vérifiées)
-            if ReplicaIdx(idx) == self.origin_idx || ReplicaIdx(idx) == other.origin_idx {
+            if idx == self.origin_idx || idx == other.origin_idx {
                 continue;
             }
-            match a.cmp(b) {
+            match self.entries.get(idx).cmp(&other.entries.get(idx)) {
                 Ordering::Greater => self_greater = true,
                 Ordering::Less => other_greater = true,
                 Ordering::Equal => {}
@@ -402,4 +611,139 @@ mod tests {
 
         assert_eq!(v1.partial_cmp(&v2), Some(Ordering::Equal));
     }
+
+    #[test]
+    fn causality_relations() {
+        let mut interner = Interner::new();
+        let a = interner.intern("a").0;
+        let b = interner.intern("b").0;
+        let c = interner.intern("c").0;
+        let resolver = interner.resolver().clone();
+        let v = |origin, values: &[usize]| Version::build(resolver.clone(), origin, values);
+
+        // (left, right, left dominates, left happens before, concurrent)
+        let table = [
+            (v(a, &[1, 0, 0]), v(a, &[2, 0, 0]), false, true, false),
+            (v(a, &[2, 0, 0]), v(a, &[1, 0, 0]), true, false, false),
+            (v(a, &[1, 0, 0]), v(b, &[0, 1, 0]), false, false, true),
+            (v(a, &[1, 0, 0]), v(b, &[1, 1, 0]), false, true, false),
+            (v(c, &[1, 1, 1]), v(b, &[0, 1, 0]), true, false, false),
+            (v(c, &[1, 0, 1]), v(b, &[0, 1, 0]), false, false, true),
+            (v(c, &[2, 1, 1]), v(a, &[2, 0, 0]), true, false, false),
+            (v(b, &[2, 2, 0]), v(c, &[1, 1, 1]), false, false, true),
+        ];
+
+        for (left, right, dominates, happens_before, concurrent) in table {
+            assert_eq!(
+                left.dominates(&right),
+                dominates,
+                "{left} dominates {right}"
+            );
+            assert_eq!(
+                left.happens_before(&right),
+                happens_before,
+                "{left} -> {right}"
+            );
+            assert_eq!(
+                left.concurrent_with(&right),
+                concurrent,
+                "{left} || {right}"
+            );
+            // Exactly one relation holds between distinct events
+            let relations = [dominates, happens_before, concurrent];
+            assert_eq!(relations.iter().filter(|r| **r).count(), 1);
+            // The relations are consistent with their converse
+            assert_eq!(right.dominates(&left), happens_before);
+            assert_eq!(right.happens_before(&left), dominates);
+            assert_eq!(right.concurrent_with(&left), concurrent);
+        }
+    }
+
+    fn sparse_and_dense(len: usize, values: &[(usize, Seq)]) -> (Version, Version) {
+        let mut interner = Interner::new();
+        for i in 0..len {
+            interner.intern(&format!("r{i}"));
+        }
+        let resolver = interner.resolver().clone();
+        let mut dense = Version {
+            entries: VersionEntries::Dense(vec![0; len]),
+            origin_idx: ReplicaIdx(0),
+            resolver: resolver.clone(),
+        };
+        let mut sparse = Version::new(ReplicaIdx(0), resolver);
+        for (idx, seq) in values {
+            dense.set_by_idx(ReplicaIdx(*idx), *seq);
+            sparse.set_by_idx(ReplicaIdx(*idx), *seq);
+        }
+        (sparse, dense)
+    }
+
+    #[test]
+    fn sparse_and_dense_compare_equal() {
+        let (sparse, dense) = sparse_and_dense(64, &[(0, 3), (5, 1), (63, 2)]);
+        assert!(matches!(sparse.entries, VersionEntries::Sparse { .. }));
+        assert_eq!(sparse, dense);
+        assert_eq!(sparse.partial_cmp(&dense), Some(Ordering::Equal));
+        assert_eq!(sparse.sum(), 6);
+        assert_eq!(sparse.seq_by_idx(ReplicaIdx(5)), 1);
+        assert_eq!(sparse.seq_by_idx(ReplicaIdx(6)), 0);
+        assert!(sparse.iter().eq(dense.iter()));
+        assert!(sparse.dependencies().eq(dense.dependencies()));
+
+        let hash = |v: &Version| {
+            let mut hasher = std::hash::DefaultHasher::new();
+            v.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&sparse), hash(&dense));
+
+        // Joins and meets give the same result whatever the representation
+        let (other_sparse, other_dense) = sparse_and_dense(64, &[(0, 4), (5, 4), (7, 2)]);
+        let mut joined = sparse.clone();
+        joined.join(&other_dense);
+        let mut expected = dense.clone();
+        expected.join(&other_sparse);
+        assert_eq!(joined, expected);
+        assert_eq!(joined.seq_by_idx(ReplicaIdx(5)), 4);
+
+        let mut met = sparse.clone();
+        met.meet(&other_dense);
+        let mut expected = dense.clone();
+        expected.meet(&other_sparse);
+        assert_eq!(met, expected);
+        assert_eq!(met.sum(), 4);
+    }
+
+    #[cfg(feature = "test_utils")]
+    #[test]
+    fn sparse_becomes_dense_when_larger() {
+        let values: Vec<_> = (0..32).map(|i| (i, 1)).collect();
+        let (sparse, dense) = sparse_and_dense(64, &values[..15]);
+        assert!(matches!(sparse.entries, VersionEntries::Sparse { .. }));
+        assert!(sparse.deep_size_of() < dense.deep_size_of());
+
+        let (sparse, dense) = sparse_and_dense(64, &values);
+        assert!(matches!(sparse.entries, VersionEntries::Dense(_)));
+        assert_eq!(sparse, dense);
+    }
+
+    /// Memory of the versions of 64 members whose events each depend on a few others.
+    #[cfg(feature = "test_utils")]
+    #[test]
+    fn sparse_memory_64_members() {
+        let (mut sparse, mut dense) = (0, 0);
+        for event in 0..1_000 {
+            let values: Vec<_> = (0..4)
+                .map(|i| ((event * 7 + i * 13) % 64, event + 1))
+                .collect();
+            let (s, d) = sparse_and_dense(64, &values);
+            sparse += s.entries.deep_size_of();
+            dense += d.entries.deep_size_of();
+        }
+        // 4 entries out of 64 take less than a quarter of the dense memory
+        assert!(
+            sparse * 4 < dense,
+            "sparse: {sparse} bytes, dense: {dense} bytes"
+        );
+    }
 }
@@ -0,0 +1,332 @@
+//! Compressed wire format of the batches, for bandwidth-constrained links.
+//!
+//! A batch is written in a compact binary form, in which the operations are encoded
+//! with their [`OpCodec`], and then compressed with zstd. The ids of the replicas of the
+//! view are written first, so that the decoded batch has its own resolver, with the
+//! same indices: it can be received with
+//! `BatchMessage::new(batch, batch.version().resolver().clone())`.
+//!
+//! Small batches compress poorly on their own: a [`BatchDictionary`] trained on
+//! typical batches of an application makes them much smaller.
+
+use std::io::{self, Read};
+
+use crate::{
+    broadcast::batch::Batch,
+    clock::version_vector::Version,
+    event::{Event, id::EventId, lamport::Lamport},
+    persistence::{OpCodec, invalid_data, put_u64, take_u64},
+    replica::ReplicaIdx,
+    utils::intern_str::{Interner, Resolver},
+};
+
+const LEVEL: i32 = 3;
+
+/// Largest decompressed batch accepted by default, in bytes.
+/// It bounds the memory that a malicious peer can make a replica allocate.
+pub const MAX_BATCH_SIZE: usize = 64 * 1024 * 1024;
+
+/// Compress a batch.
+pub fn encode_batch<O: OpCodec>(batch: &Batch<O>) -> Vec<u8> {
+    zstd::bulk::compress(&to_bytes(batch), LEVEL).expect("in-memory compression cannot fail")
+}
+
+/// Decompress a batch written by [`encode_batch`].
+///
+/// # Errors
+/// If the batch is malformed or decompresses to more than [`MAX_BATCH_SIZE`] bytes.
+pub fn decode_batch<O: OpCodec>(bytes: &[u8]) -> io::Result<Batch<O>> {
+    let mut raw = Vec::new();
+    zstd::Decoder::new(bytes)?
+        .take(MAX_BATCH_SIZE as u64 + 1)
+        .read_to_end(&mut raw)?;
+    if raw.len() > MAX_BATCH_SIZE {
+        return Err(invalid_data("batch too large"));
+    }
+    from_bytes(&raw)
+}
+
+/// zstd dictionary shared by the replicas to compress small batches.
+#[derive(Clone, Debug)]
+pub struct BatchDictionary {
+    raw: Vec<u8>,
+    max_batch_size: usize,
+}
+
+impl BatchDictionary {
+    /// Train a dictionary of at most `max_size` bytes on sample batches.
+    ///
+    /// # Errors
+    /// If there are too few samples to train on.
+    pub fn train<O: OpCodec>(samples: &[Batch<O>], max_size: usize) -> io::Result<Self> {
+        let samples: Vec<Vec<u8>> = samples.iter().map(to_bytes).collect();
+        Ok(Self::from_bytes(zstd::dict::from_samples(
+            &samples, max_size,
+        )?))
+    }
+
+    /// Dictionary trained by another replica, see [`BatchDictionary::as_bytes`].
+    pub fn from_bytes(raw: Vec<u8>) -> Self {
+        Self {
+            raw,
+            max_batch_size: MAX_BATCH_SIZE,
+        }
+    }
+
+    /// Largest decompressed batch accepted by [`BatchDictionary::decode_batch`],
+    /// [`MAX_BATCH_SIZE`] by default.
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size;
+        self
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// Same as [`encode_batch`], with the dictionary.
+    pub fn encode_batch<O: OpCodec>(&self, batch: &Batch<O>) -> io::Result<Vec<u8>> {
+        let raw = to_bytes(batch);
+        let mut compressor = zstd::bulk::Compressor::with_dictionary(LEVEL, &self.raw)?;
+        let mut bytes = Vec::new();
+        put_u64(&mut bytes, raw.len());
+        bytes.extend(compressor.compress(&raw)?);
+        Ok(bytes)
+    }
+
+    /// Decompress a batch written by [`BatchDictionary::encode_batch`] with the same dictionary.
+    ///
+    /// # Errors
+    /// If the batch is malformed or announces more than the maximum batch size.
+    pub fn decode_batch<O: OpCodec>(&self, mut bytes: &[u8]) -> io::Result<Batch<O>> {
+        let len = take_u64(&mut bytes).ok_or_else(|| invalid_data("truncated batch"))?;
+        if len > self.max_batch_size {
+            return Err(invalid_data("batch too large"));
+        }
+        let mut decompressor = zstd::bulk::Decompressor::with_dictionary(&self.raw)?;
+        from_bytes(&decompressor.decompress(bytes, len)?)
+    }
+}
+
+fn put_version(buf: &mut Vec<u8>, version: &Version) {
+    put_u64(buf, version.origin_idx().0);
+    let entries: Vec<_> = version.iter().filter(|(_, seq)| *seq > 0).collect();
+    put_u64(buf, entries.len());
+    for (idx, seq) in entries {
+        put_u64(buf, idx.0);
+        put_u64(buf, seq);
+    }
+}
+
+fn to_bytes<O: OpCodec>(batch: &Batch<O>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    let resolver = batch.version().resolver();
+    put_u64(&mut buf, resolver.len());
+    for idx in 0..resolver.len() {
+        let id = resolver.resolve(ReplicaIdx(idx)).unwrap();
+        put_u64(&mut buf, id.len());
+        buf.extend_from_slice(id.as_bytes());
+    }
+    put_version(&mut buf, batch.version());
+    put_u64(&mut buf, batch.events().len());
+    for event in batch.events() {
+        put_u64(&mut buf, event.id().seq());
+        put_u64(&mut buf, event.lamport().val());
+        put_version(&mut buf, event.version());
+        let mut op = Vec::new();
+        event.op().encode(&mut op);
+        put_u64(&mut buf, op.len());
+        buf.extend(op);
+    }
+    buf
+}
+
+fn from_bytes<O: OpCodec>(bytes: &[u8]) -> io::Result<Batch<O>> {
+    let mut reader = Reader { bytes };
+    let mut interner = Interner::new();
+    for _ in 0..reader.u64()? {
+        let len = reader.u64()?;
+        let id = std::str::from_utf8(reader.take(len)?).map_err(invalid_data)?;
+        interner.intern(id);
+    }
+    let resolver = interner.resolver().clone();
+    let base = reader.version(&resolver)?;
+    let mut events = Vec::new();
+    for _ in 0..reader.u64()? {
+        let seq = reader.u64()?;
+        let lamport = Lamport::new(reader.u64()?);
+        let version = reader.version(&resolver)?;
+        let len = reader.u64()?;
+        let op = O::decode(reader.take(len)?).ok_or_else(|| invalid_data("invalid operation"))?;
+        let id = EventId::new(version.origin_idx(), seq, resolver.clone());
+        events.push(Event::new(id, lamport, op, version));
+    }
+    Ok(Batch::new(events, base))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn u64(&mut self) -> io::Result<usize> {
+        take_u64(&mut self.bytes).ok_or_else(|| invalid_data("truncated batch"))
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let (head, rest) = self
+            .bytes
+            .split_at_checked(len)
+            .ok_or_else(|| invalid_data("truncated batch"))?;
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn idx(&mut self, resolver: &Resolver) -> io::Result<ReplicaIdx> {
+        let idx = self.u64()?;
+        if idx >= resolver.len() {
+            return Err(invalid_data("unknown replica"));
+        }
+        Ok(ReplicaIdx(idx))
+    }
+
+    fn version(&mut self, resolver: &Resolver) -> io::Result<Version> {
+        let mut version = Version::new(self.idx(resolver)?, resolver.clone());
+        for _ in 0..self.u64()? {
+            let idx = self.idx(resolver)?;
+            version.set_by_idx(idx, self.u64()?);
+        }
+        Ok(version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        broadcast::tcsb::Tcsb,
+        crdt::query::Read,
+        replica::{IsReplica, Replica},
+        state::po_log::VecLog,
+        utils::test_ops::Inc,
+    };
+
+    type IncReplica = Replica<VecLog<Inc>, Tcsb<Inc>>;
+
+    /// Batch of `n` increments of a and b, as pulled by c.
+    fn counter_batch(n: usize) -> Batch<Inc> {
+        let members = ["a", "b", "c"];
+        let mut replica_a: IncReplica = Replica::bootstrap("a".to_string(), &members);
+        let mut replica_b: IncReplica = Replica::bootstrap("b".to_string(), &members);
+        let replica_c: IncReplica = Replica::bootstrap("c".to_string(), &members);
+        for i in 0..n {
+            let (sender, receiver) = if i % 3 == 0 {
+                (&mut replica_b, &mut replica_a)
+            } else {
+                (&mut replica_a, &mut replica_b)
+            };
+            let event = sender.send(Inc((i % 5) as u8 + 1)).unwrap();
+            receiver.receive(event);
+        }
+        replica_a.pull(replica_c.since()).into_batch()
+    }
+
+    /// Plain JSON rendering of a batch.
+    fn json_baseline(batch: &Batch<Inc>) -> Vec<u8> {
+        let version = |version: &Version| {
+            version
+                .iter()
+                .map(|(idx, seq)| {
+                    (
+                        version.resolver().resolve(idx).unwrap().to_string(),
+                        json!(seq),
+                    )
+                })
+                .collect::<serde_json::Map<_, _>>()
+        };
+        let events: Vec<_> = batch
+            .events()
+            .iter()
+            .map(|event| {
+                json!({
+                    "origin": event.id().origin_id(),
+                    "seq": event.id().seq(),
+                    "lamport": event.lamport().val(),
+                    "version": version(event.version()),
+                    "op": event.op().0,
+                })
+            })
+            .collect();
+        serde_json::to_vec(&json!({ "version": version(batch.version()), "events": events }))
+            .unwrap()
+    }
+
+    fn assert_same_batch(a: &Batch<Inc>, b: &Batch<Inc>) {
+        assert_eq!(a.version().to_string(), b.version().to_string());
+        assert_eq!(a.events().len(), b.events().len());
+        for (a, b) in a.events().iter().zip(b.events()) {
+            assert_eq!(a.id(), b.id());
+            assert_eq!(a.lamport(), b.lamport());
+            assert_eq!(a.op(), b.op());
+            assert_eq!(a.version().to_string(), b.version().to_string());
+        }
+    }
+
+    #[test]
+    fn round_trip_smaller_than_json() {
+        let batch = counter_batch(300);
+        let bytes = encode_batch(&batch);
+        let decoded = decode_batch::<Inc>(&bytes).unwrap();
+        assert_same_batch(&decoded, &batch);
+        assert!(bytes.len() < json_baseline(&batch).len());
+
+        // The decoded batch can be received by a replica
+        let mut replica_c: IncReplica = Replica::bootstrap("c".to_string(), &["a", "b", "c"]);
+        let resolver = decoded.version().resolver().clone();
+        replica_c.receive_batch(crate::broadcast::message::BatchMessage::new(
+            decoded, resolver,
+        ));
+        let expected: usize = (0..300).map(|i| i % 5 + 1).sum();
+        assert_eq!(replica_c.query(Read::new()), expected);
+
+        assert!(decode_batch::<Inc>(&bytes[..bytes.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn dictionary_for_small_batches() {
+        let samples: Vec<_> = (0..100).map(|i| counter_batch(i % 10 + 1)).collect();
+        let dictionary = BatchDictionary::train(&samples, 1024).unwrap();
+        let dictionary = BatchDictionary::from_bytes(dictionary.as_bytes().to_vec());
+
+        let batch = counter_batch(4);
+        let bytes = dictionary.encode_batch(&batch).unwrap();
+        assert_same_batch(&dictionary.decode_batch::<Inc>(&bytes).unwrap(), &batch);
+        assert!(bytes.len() < encode_batch(&batch).len());
+        assert!(bytes.len() < json_baseline(&batch).len());
+    }
+
+    #[test]
+    fn oversized_batches_are_rejected() {
+        let batch = counter_batch(10);
+
+        // A forged length is rejected before any allocation
+        let samples: Vec<_> = (0..100).map(|i| counter_batch(i % 10 + 1)).collect();
+        let dictionary = BatchDictionary::train(&samples, 1024).unwrap();
+        let mut bytes = dictionary.encode_batch(&batch).unwrap();
+        bytes[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        let err = dictionary.decode_batch::<Inc>(&bytes).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let dictionary = dictionary.with_max_batch_size(16);
+        let bytes = dictionary.encode_batch(&batch).unwrap();
+        let err = dictionary.decode_batch::<Inc>(&bytes).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // A small frame that decompresses past the limit
+        let bytes = zstd::bulk::compress(&vec![0; MAX_BATCH_SIZE + 1], LEVEL).unwrap();
+        let err = decode_batch::<Inc>(&bytes).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
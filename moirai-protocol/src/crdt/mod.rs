@@ -3,3 +3,5 @@ pub mod policy;
 pub mod pure_crdt;
 pub mod query;
 pub mod redundancy;
+#[cfg(feature = "json")]
+pub mod to_json;
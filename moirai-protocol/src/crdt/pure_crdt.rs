@@ -24,6 +24,13 @@ pub trait PureCRDT: Debug + Sized {
     const DISABLE_R_WHEN_R: bool = false;
     const DISABLE_R_WHEN_NOT_R: bool = false;
     const DISABLE_STABILIZE: bool = false;
+    /// Whether the events of the CRDT must carry their full causal dependencies.
+    /// If `false`, the broadcast events only carry the counter of their origin, which is
+    /// only correct if the redundancy rules and the evaluation ignore concurrency.
+    /// Such events are delivered in the order of their origin only. The other replicas then
+    /// learn what the origin has delivered from the clocks of its pulls and batches instead,
+    /// so that stability advances with anti-entropy.
+    const NEEDS_CAUSAL_DEPS: bool = true;
 
     fn redundant_itself<'a>(
         _new_tagged_op: &TaggedOp<Self>,
@@ -54,6 +61,13 @@ pub trait PureCRDT: Debug + Sized {
         false
     }
 
+    /// Whether `new_tagged_op`, which is not redundant itself, can make logged operations redundant.
+    /// If not, the log is not scanned with `redundant_by_when_not_redundant`, as with
+    /// `DISABLE_R_WHEN_NOT_R` but for this operation only.
+    fn prunes_when_not_redundant(_new_tagged_op: &TaggedOp<Self>) -> bool {
+        true
+    }
+
     fn stabilize(
         _tagged_op: &TaggedOp<Self>,
         _stable: &mut Self::StableState,
@@ -78,6 +92,12 @@ pub trait PureCRDT: Debug + Sized {
         CausalReset::Prune
     }
 
+    /// Whether a stored operation is only kept to mask concurrent operations
+    /// (e.g., a remove), rather than to contribute to the value.
+    fn is_tombstone(_op: &Self) -> bool {
+        false
+    }
+
     /// `is_enabled` can inspect the state to determine if the operation violates any precondition.
     fn is_enabled(
         _op: &Self,
@@ -86,4 +106,16 @@ pub trait PureCRDT: Debug + Sized {
     ) -> Result<(), Self::Rejection> {
         Ok(())
     }
+
+    /// `is_enabled_at` checks the operation against the version `delivered` by the replica
+    /// issuing it, e.g., for an operation addressing the events it observed.
+    /// Only called for the operations of the log at the root of the replica.
+    fn is_enabled_at(_op: &Self, _delivered: &Version) -> Result<(), Self::Rejection> {
+        Ok(())
+    }
 }
+
+/// CRDT whose stable state applies any sequence of its operations: folding them into the
+/// stable state gives the same value as delivering them, as no operation makes another
+/// redundant (e.g., a grow-only set or a counter).
+pub trait StableFold: PureCRDT {}
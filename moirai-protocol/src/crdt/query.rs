@@ -25,6 +25,39 @@ impl<V> Default for Read<V> {
     }
 }
 
+impl<V> Clone for Read<V> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+/// Read of a keyed container as a list of entries sorted by key,
+/// so that two converged replicas return identical outputs.
+#[derive(Debug)]
+pub struct ReadSorted<K, V>(std::marker::PhantomData<(K, V)>);
+
+impl<K, V> QueryOperation for ReadSorted<K, V> {
+    type Response = Vec<(K, V)>;
+}
+
+impl<K, V> ReadSorted<K, V> {
+    pub fn new() -> Self {
+        Self(std::marker::PhantomData)
+    }
+}
+
+impl<K, V> Default for ReadSorted<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Clone for ReadSorted<K, V> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
 pub struct ReadId;
 
 impl QueryOperation for ReadId {
@@ -0,0 +1,166 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    hash::BuildHasher,
+    rc::Rc,
+};
+
+use serde_json::{Map, Number, Value};
+
+/// Rendering of a CRDT value as JSON.
+///
+/// Counters become numbers, flags booleans, sets and lists arrays, and maps objects.
+/// Values of records defined with `record!` become objects keyed by field name, if the
+/// `json` feature of `moirai-macros` is enabled.
+pub trait ToJson {
+    fn to_json(&self) -> Value;
+}
+
+macro_rules! integer_to_json {
+    ($($t:ty),*) => {
+        $(
+            impl ToJson for $t {
+                fn to_json(&self) -> Value {
+                    Value::from(*self)
+                }
+            }
+        )*
+    };
+}
+
+integer_to_json!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+macro_rules! float_to_json {
+    ($($t:ty),*) => {
+        $(
+            /// Non-finite numbers have no JSON representation and become `null`.
+            impl ToJson for $t {
+                fn to_json(&self) -> Value {
+                    Number::from_f64(*self as f64).map_or(Value::Null, Value::Number)
+                }
+            }
+        )*
+    };
+}
+
+float_to_json!(f32, f64);
+
+impl ToJson for bool {
+    fn to_json(&self) -> Value {
+        Value::Bool(*self)
+    }
+}
+
+impl ToJson for char {
+    fn to_json(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Value {
+        Value::String(self.clone())
+    }
+}
+
+impl ToJson for Value {
+    fn to_json(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> Value {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn to_json(&self) -> Value {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Rc<T> {
+    fn to_json(&self) -> Value {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Value {
+        self.as_ref().map_or(Value::Null, ToJson::to_json)
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> Value {
+        Value::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Value {
+        self.as_slice().to_json()
+    }
+}
+
+/// The elements are sorted by their JSON text, so that equal sets render identically.
+impl<T: ToJson, S: BuildHasher> ToJson for HashSet<T, S> {
+    fn to_json(&self) -> Value {
+        let mut elements: Vec<Value> = self.iter().map(ToJson::to_json).collect();
+        elements.sort_by_cached_key(Value::to_string);
+        Value::Array(elements)
+    }
+}
+
+impl<T: ToJson> ToJson for BTreeSet<T> {
+    fn to_json(&self) -> Value {
+        Value::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+impl<K: ToString, V: ToJson, S: BuildHasher> ToJson for HashMap<K, V, S> {
+    fn to_json(&self) -> Value {
+        Value::Object(
+            self.iter()
+                .map(|(k, v)| (k.to_string(), v.to_json()))
+                .collect::<Map<String, Value>>(),
+        )
+    }
+}
+
+impl<K: ToString, V: ToJson> ToJson for BTreeMap<K, V> {
+    fn to_json(&self) -> Value {
+        Value::Object(
+            self.iter()
+                .map(|(k, v)| (k.to_string(), v.to_json()))
+                .collect::<Map<String, Value>>(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn nested_values() {
+        let mut map: HashMap<String, Vec<Option<f64>>> = HashMap::new();
+        map.insert("a".to_string(), vec![Some(1.5), None, Some(f64::NAN)]);
+        map.insert("b".to_string(), vec![]);
+        assert_eq!(map.to_json(), json!({ "a": [1.5, null, null], "b": [] }));
+
+        let set: HashSet<&str> = ["c", "a", "b"].into_iter().collect();
+        assert_eq!(set.to_json(), json!(["a", "b", "c"]));
+        assert_eq!((-7i32).to_json(), json!(-7));
+        assert_eq!(true.to_json(), json!(true));
+    }
+}
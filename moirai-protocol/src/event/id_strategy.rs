@@ -0,0 +1,152 @@
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+use crate::{
+    clock::version_vector::Seq,
+    event::id::EventId,
+    replica::{ReplicaId, ReplicaIdx},
+    utils::intern_str::Resolver,
+};
+
+/// How events are referenced outside of the replicas sharing a view.
+///
+/// Replicas always identify events by their origin and counter, on which the causal
+/// delivery relies. A strategy derives an external id from them, and back: the same
+/// [`EventId`] always gives the same external id, from which [`EventIdStrategy::event_id`]
+/// recovers it. Whether distinct events get distinct external ids depends on the strategy.
+pub trait EventIdStrategy {
+    type Id: Clone + Debug + Eq + Hash;
+
+    fn external_id(id: &EventId) -> Self::Id;
+    /// The event id matching an external id, if its origin is known by the resolver.
+    fn event_id(id: &Self::Id, resolver: &Resolver) -> Option<EventId>;
+}
+
+/// The event id itself, i.e., the origin and counter of the event.
+pub struct CompactIds;
+
+impl EventIdStrategy for CompactIds {
+    type Id = (String, Seq);
+
+    fn external_id(id: &EventId) -> Self::Id {
+        (id.origin_id().to_string(), id.seq())
+    }
+
+    fn event_id((origin, seq): &Self::Id, resolver: &Resolver) -> Option<EventId> {
+        let idx = find_origin(resolver, |id| id == origin.as_str())?;
+        Some(EventId::new(idx, *seq, resolver.clone()))
+    }
+}
+
+/// A UUID (version 8) made of a hash of the origin and of the counter of the event.
+///
+/// It is computed the same way by every replica, whatever their view,
+/// and does not collide as long as the hashes of the replica ids do not.
+pub struct UuidIds;
+
+/// 128-bit identifier, displayed in the hyphenated UUID format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EventUuid(pub u128);
+
+impl Display for EventUuid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let v = self.0;
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            v >> 96,
+            (v >> 80) & 0xffff,
+            (v >> 64) & 0xffff,
+            (v >> 48) & 0xffff,
+            v & 0xffff_ffff_ffff
+        )
+    }
+}
+
+const VERSION_MASK: u128 = 0xf << 76;
+const VERSION_8: u128 = 0x8 << 76;
+const VARIANT_MASK: u128 = 0b11 << 62;
+const VARIANT_RFC: u128 = 0b10 << 62;
+
+impl UuidIds {
+    /// 64-bit FNV-1a, which, unlike the std hasher, is stable across builds.
+    fn hash_origin(origin: &ReplicaId) -> u64 {
+        origin.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    fn origin_bits(origin: &ReplicaId) -> u128 {
+        ((Self::hash_origin(origin) as u128) << 64) & !VERSION_MASK
+    }
+}
+
+impl EventIdStrategy for UuidIds {
+    type Id = EventUuid;
+
+    /// # Panics
+    /// If the counter does not fit in 62 bits.
+    fn external_id(id: &EventId) -> Self::Id {
+        let seq = id.seq() as u128;
+        assert!(
+            seq & VARIANT_MASK == 0,
+            "Counter {seq} too large for a UUID"
+        );
+        EventUuid(Self::origin_bits(id.origin_id()) | VERSION_8 | VARIANT_RFC | seq)
+    }
+
+    fn event_id(id: &Self::Id, resolver: &Resolver) -> Option<EventId> {
+        if id.0 & VERSION_MASK != VERSION_8 || id.0 & VARIANT_MASK != VARIANT_RFC {
+            return None;
+        }
+        let bits = id.0 & !(u128::from(u64::MAX) | VERSION_MASK);
+        let idx = find_origin(resolver, |origin| Self::origin_bits(origin) == bits)?;
+        let seq = (id.0 & u128::from(u64::MAX) & !VARIANT_MASK) as Seq;
+        Some(EventId::new(idx, seq, resolver.clone()))
+    }
+}
+
+fn find_origin(resolver: &Resolver, matches: impl Fn(&ReplicaId) -> bool) -> Option<ReplicaIdx> {
+    (0..resolver.len())
+        .map(ReplicaIdx)
+        .find(|idx| resolver.resolve(*idx).is_some_and(&matches))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::intern_str::Interner;
+
+    #[test]
+    fn uuid_round_trip() {
+        let mut interner = Interner::new();
+        let (idx_a, _) = interner.intern("a");
+        let (idx_b, _) = interner.intern("b");
+        let id_a = EventId::new(idx_a, 3, interner.resolver().clone());
+        let id_b = EventId::new(idx_b, 3, interner.resolver().clone());
+
+        let uuid = UuidIds::external_id(&id_a);
+        assert_ne!(uuid, UuidIds::external_id(&id_b));
+        assert_eq!(uuid.to_string().len(), 36);
+        assert_eq!(uuid.to_string().as_bytes()[14], b'8');
+        assert_eq!(UuidIds::event_id(&uuid, interner.resolver()), Some(id_a));
+
+        // Another view resolves the same UUID to the same event
+        let mut other = Interner::new();
+        other.intern("b");
+        let (idx, _) = other.intern("a");
+        let id = UuidIds::event_id(&uuid, other.resolver()).unwrap();
+        assert_eq!(id.idx(), idx);
+        assert_eq!((id.origin_id(), id.seq()), ("a", 3));
+
+        let compact = CompactIds::external_id(&id_b);
+        assert_eq!(
+            CompactIds::event_id(&compact, other.resolver())
+                .unwrap()
+                .origin_id(),
+            "b"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 pub mod id;
+pub mod id_strategy;
 pub mod lamport;
 pub mod tag;
 pub mod tagged_op;
@@ -9,10 +10,62 @@ use std::fmt::{Debug, Display};
 use deepsize::DeepSizeOf;
 
 use crate::{
-    clock::version_vector::Version,
+    clock::version_vector::{Seq, Version},
     event::{id::EventId, lamport::Lamport},
+    replica::ReplicaIdx,
+    utils::intern_str::Resolver,
 };
 
+/// Error of [`Event::validate_against`]: the metadata of the event is inconsistent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MalformedEvent {
+    /// The origin of the event is not a member of the view.
+    OriginOutOfView { idx: ReplicaIdx, view_len: usize },
+    /// The sequence numbers of a replica start at 1.
+    ZeroSeq,
+    /// The clock of the event belongs to another replica than its id.
+    ClockOriginMismatch { id: ReplicaIdx, clock: ReplicaIdx },
+    /// The clock does not count the event itself.
+    SeqMismatch { id: Seq, clock: Seq },
+    /// The clock depends on an event of a replica outside the view.
+    DependencyOutOfView { idx: ReplicaIdx, seq: Seq },
+    /// The Lamport clock is lower than the number of events in the clock.
+    LamportBehind { lamport: Lamport, clock_sum: usize },
+}
+
+impl Display for MalformedEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MalformedEvent::OriginOutOfView { idx, view_len } => write!(
+                f,
+                "origin {} is out of a view of {view_len} replicas",
+                idx.0
+            ),
+            MalformedEvent::ZeroSeq => write!(f, "sequence number 0"),
+            MalformedEvent::ClockOriginMismatch { id, clock } => write!(
+                f,
+                "the id has origin {} but the clock has origin {}",
+                id.0, clock.0
+            ),
+            MalformedEvent::SeqMismatch { id, clock } => write!(
+                f,
+                "the id has sequence number {id} but the clock counts {clock} events of its origin"
+            ),
+            MalformedEvent::DependencyOutOfView { idx, seq } => write!(
+                f,
+                "the clock depends on event {seq} of replica {}, out of the view",
+                idx.0
+            ),
+            MalformedEvent::LamportBehind { lamport, clock_sum } => write!(
+                f,
+                "Lamport clock {lamport} is behind the {clock_sum} events of the clock"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MalformedEvent {}
+
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
 pub struct Event<O> {
@@ -44,6 +97,10 @@ impl<O> Event<O> {
         &self.op
     }
 
+    pub fn into_op(self) -> O {
+        self.op
+    }
+
     pub fn lamport(&self) -> &Lamport {
         &self.lamport
     }
@@ -51,6 +108,62 @@ impl<O> Event<O> {
     pub fn version(&self) -> &Version {
         &self.version
     }
+
+    /// Check that the metadata of the event is consistent, and fits in the `view` it is delivered in:
+    /// its origin is a member, its clock counts it and only depends on members, and its Lamport
+    /// clock is not behind its clock.
+    pub fn validate_against(&self, view: &Resolver) -> Result<(), MalformedEvent> {
+        let idx = self.id.idx();
+        if idx.0 >= view.len() {
+            return Err(MalformedEvent::OriginOutOfView {
+                idx,
+                view_len: view.len(),
+            });
+        }
+        if self.id.seq() == 0 {
+            return Err(MalformedEvent::ZeroSeq);
+        }
+        if self.version.origin_idx() != idx {
+            return Err(MalformedEvent::ClockOriginMismatch {
+                id: idx,
+                clock: self.version.origin_idx(),
+            });
+        }
+        if self.version.origin_seq() != self.id.seq() {
+            return Err(MalformedEvent::SeqMismatch {
+                id: self.id.seq(),
+                clock: self.version.origin_seq(),
+            });
+        }
+        if let Some((idx, seq)) = self
+            .version
+            .iter()
+            .find(|(idx, seq)| idx.0 >= view.len() && *seq > 0)
+        {
+            return Err(MalformedEvent::DependencyOutOfView { idx, seq });
+        }
+        let clock_sum = self.version.sum();
+        if self.lamport.val() < clock_sum {
+            return Err(MalformedEvent::LamportBehind {
+                lamport: self.lamport,
+                clock_sum,
+            });
+        }
+        Ok(())
+    }
+
+    /// Drop the dependencies of the event, keeping only the counter of its origin.
+    pub fn elide_dependencies(&mut self) {
+        let mut version = Version::new(self.id.idx(), self.version.resolver().clone());
+        version.set_by_idx(self.id.idx(), self.id.seq());
+        self.version = version;
+    }
+
+    /// Whether the version of the event only counts the events of its origin, as when its
+    /// dependencies were elided.
+    pub fn has_no_dependencies(&self) -> bool {
+        self.version.sum() == self.id.seq()
+    }
 }
 
 impl<O> Display for Event<O>
@@ -61,3 +174,61 @@ where
         write!(f, "[{:?}, {}]", self.op, self.version)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::intern_str::Interner;
+
+    fn view() -> Resolver {
+        let mut interner = Interner::new();
+        interner.intern("a");
+        interner.intern("b");
+        interner.resolver().clone()
+    }
+
+    fn event(idx: usize, seq: Seq, lamport: usize, clock: &[usize]) -> Event<()> {
+        let view = view();
+        let id = EventId::new(ReplicaIdx(idx), seq, view.clone());
+        let mut version = Version::new(ReplicaIdx(idx), view);
+        for (i, seq) in clock.iter().enumerate() {
+            version.set_by_idx(ReplicaIdx(i), *seq);
+        }
+        Event::new(id, Lamport::new(lamport), (), version)
+    }
+
+    #[test]
+    fn validate_malformed_events() {
+        let view = view();
+        assert_eq!(event(1, 2, 3, &[1, 2]).validate_against(&view), Ok(()));
+
+        let err = event(1, 3, 3, &[1, 2]).validate_against(&view).unwrap_err();
+        assert_eq!(err, MalformedEvent::SeqMismatch { id: 3, clock: 2 });
+        assert_eq!(
+            err.to_string(),
+            "the id has sequence number 3 but the clock counts 2 events of its origin"
+        );
+
+        assert_eq!(
+            event(1, 2, 5, &[1, 2, 0, 2]).validate_against(&view),
+            Err(MalformedEvent::DependencyOutOfView {
+                idx: ReplicaIdx(3),
+                seq: 2
+            })
+        );
+        assert_eq!(
+            event(2, 1, 1, &[0, 0, 1]).validate_against(&view),
+            Err(MalformedEvent::OriginOutOfView {
+                idx: ReplicaIdx(2),
+                view_len: 2
+            })
+        );
+        assert_eq!(
+            event(0, 1, 2, &[1, 2]).validate_against(&view),
+            Err(MalformedEvent::LamportBehind {
+                lamport: Lamport::new(2),
+                clock_sum: 3
+            })
+        );
+    }
+}
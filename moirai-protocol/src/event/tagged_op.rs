@@ -6,7 +6,7 @@ use deepsize::DeepSizeOf;
 use crate::event::{Event, id::EventId, lamport::Lamport, tag::Tag};
 
 #[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TaggedOp<O> {
     op: O,
     tag: Tag,
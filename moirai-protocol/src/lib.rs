@@ -1,10 +1,16 @@
 pub mod broadcast;
 pub mod clock;
+#[cfg(feature = "compression")]
+pub mod codec;
 pub mod crdt;
 pub mod event;
+pub mod persistence;
 pub mod replica;
 pub mod state;
 pub mod utils;
 
+#[cfg(feature = "json")]
+pub use serde_json;
+
 type HashMap<K, V> = rustc_hash::FxHashMap<K, V>;
 type HashSet<V> = rustc_hash::FxHashSet<V>;
@@ -0,0 +1,391 @@
+//! Write-ahead log of the delivered events.
+//!
+//! A replica opened with [`Replica::with_wal`](crate::replica::Replica::with_wal) appends
+//! every event it delivers, local or remote, and replays them when it is reopened.
+//! The indices of the logged versions are the local ones: the log also records the
+//! replicas in the order the local replica learnt about them, so that they are
+//! interned in the same order on replay.
+
+use std::{
+    fmt::Debug,
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    clock::version_vector::Version,
+    event::{Event, id::EventId, lamport::Lamport},
+    replica::ReplicaIdx,
+    utils::intern_str::Interner,
+};
+
+/// Byte encoding of the operations stored in a write-ahead log.
+pub trait OpCodec: Sized {
+    fn encode(&self, buf: &mut Vec<u8>);
+    /// Decode an operation from the bytes written by `encode`, or `None` if they are invalid.
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+/// Append-only log of the events delivered by a replica.
+pub trait WalLog<O>: Debug {
+    /// Persist `event` before returning.
+    fn append(&mut self, event: &Event<O>) -> io::Result<()>;
+    /// Events of the log, in the order they were appended.
+    fn replay(&mut self) -> io::Result<impl Iterator<Item = Event<O>>>
+    where
+        Self: Sized;
+}
+
+const MEMBER: u8 = 0;
+const EVENT: u8 = 1;
+
+/// Write-ahead log in a file of length-prefixed records.
+///
+/// A record interrupted by a crash is discarded when the file is reopened.
+#[derive(Debug)]
+pub struct FileWal {
+    path: PathBuf,
+    file: File,
+    /// Number of replicas recorded in the log.
+    num_members: usize,
+    /// Whether each record is flushed to the disk before `append` returns.
+    sync: bool,
+}
+
+impl FileWal {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        let (records, len) = records(&bytes);
+        if len < bytes.len() {
+            file.set_len(len as u64)?;
+        }
+        let num_members = records.iter().filter(|(tag, _)| *tag == MEMBER).count();
+        Ok(Self {
+            path,
+            file,
+            num_members,
+            sync: true,
+        })
+    }
+
+    /// Whether each record is flushed to the disk before `append` returns, the default.
+    ///
+    /// Without it, the records survive a crash of the process but not of the system:
+    /// the last events may then be lost, including local ones, whose sequence numbers
+    /// the reopened replica would reuse.
+    pub fn set_sync(&mut self, sync: bool) {
+        self.sync = sync;
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn write_record(&mut self, tag: u8, body: &[u8]) -> io::Result<()> {
+        let mut record = Vec::with_capacity(body.len() + 5);
+        record.extend_from_slice(&(body.len() as u32 + 1).to_le_bytes());
+        record.push(tag);
+        record.extend_from_slice(body);
+        self.file.write_all(&record)
+    }
+}
+
+impl<O> WalLog<O> for FileWal
+where
+    O: OpCodec,
+{
+    fn append(&mut self, event: &Event<O>) -> io::Result<()> {
+        let resolver = event.version().resolver().clone();
+        for idx in self.num_members..resolver.len() {
+            let id = resolver.resolve(ReplicaIdx(idx)).unwrap();
+            self.write_record(MEMBER, id.as_bytes())?;
+        }
+        self.num_members = self.num_members.max(resolver.len());
+
+        let mut body = Vec::new();
+        put_u64(&mut body, event.id().idx().0);
+        put_u64(&mut body, event.id().seq());
+        put_u64(&mut body, event.lamport().val());
+        let entries: Vec<_> = event.version().iter().filter(|(_, seq)| *seq > 0).collect();
+        put_u64(&mut body, entries.len());
+        for (idx, seq) in entries {
+            put_u64(&mut body, idx.0);
+            put_u64(&mut body, seq);
+        }
+        event.op().encode(&mut body);
+        self.write_record(EVENT, &body)?;
+        if self.sync {
+            self.file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn replay(&mut self) -> io::Result<impl Iterator<Item = Event<O>>> {
+        let bytes = std::fs::read(&self.path)?;
+        let (records, _) = records(&bytes);
+        let mut interner = Interner::new();
+        // Learn every replica first, so that all the events share the complete resolver
+        for (_, body) in records.iter().filter(|(tag, _)| *tag == MEMBER) {
+            let id = std::str::from_utf8(body).map_err(invalid_data)?;
+            interner.intern(id);
+        }
+        let resolver = interner.resolver().clone();
+        let mut events = Vec::new();
+        for (_, mut body) in records.into_iter().filter(|(tag, _)| *tag == EVENT) {
+            let mut next = || take_u64(&mut body).ok_or_else(|| invalid_data("truncated event"));
+            let known = |idx: usize| {
+                (idx < resolver.len())
+                    .then_some(ReplicaIdx(idx))
+                    .ok_or_else(|| invalid_data("unknown replica"))
+            };
+            let idx = known(next()?)?;
+            let seq = next()?;
+            let lamport = Lamport::new(next()?);
+            let mut version = Version::new(idx, resolver.clone());
+            for _ in 0..next()? {
+                let entry_idx = known(next()?)?;
+                version.set_by_idx(entry_idx, next()?);
+            }
+            let op = O::decode(body).ok_or_else(|| invalid_data("invalid operation"))?;
+            let id = EventId::new(idx, seq, resolver.clone());
+            events.push(Event::new(id, lamport, op, version));
+        }
+        Ok(events.into_iter())
+    }
+}
+
+/// Complete records of `bytes` with their tag, and the length of the bytes they span.
+fn records(bytes: &[u8]) -> (Vec<(u8, &[u8])>, usize) {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while let Some(len) = bytes.get(offset..offset + 4) {
+        let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+        let Some(record) = bytes.get(offset + 4..offset + 4 + len) else {
+            break;
+        };
+        let Some((&tag, body)) = record.split_first() else {
+            break;
+        };
+        records.push((tag, body));
+        offset += 4 + len;
+    }
+    (records, offset)
+}
+
+pub(crate) fn put_u64(buf: &mut Vec<u8>, value: usize) {
+    buf.extend_from_slice(&(value as u64).to_le_bytes());
+}
+
+pub(crate) fn take_u64(bytes: &mut &[u8]) -> Option<usize> {
+    let (value, rest) = bytes.split_first_chunk::<8>()?;
+    *bytes = rest;
+    Some(u64::from_le_bytes(*value) as usize)
+}
+
+pub(crate) fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, fs, io::Write};
+
+    use super::*;
+    use crate::{
+        broadcast::tcsb::Tcsb,
+        crdt::{eval::Eval, pure_crdt::PureCRDT, query::Read},
+        replica::{IsReplica, Replica},
+        state::{po_log::VecLog, unstable_state::IsUnstableCore},
+        utils::intern_str::{InternalizeOp, Interner},
+    };
+
+    #[derive(Clone, Debug)]
+    struct Add(u8);
+
+    impl PureCRDT for Add {
+        type Value = usize;
+        type StableState = Vec<Self>;
+        type Rejection = Infallible;
+    }
+
+    impl InternalizeOp for Add {
+        fn internalize(self, _interner: &Interner) -> Self {
+            self
+        }
+    }
+
+    impl<U: IsUnstableCore<Add>> Eval<Read<usize>, U> for Add {
+        fn execute_query(_q: Read<usize>, stable: &Vec<Add>, unstable: &U) -> usize {
+            let stable: usize = stable.iter().map(|op| op.0 as usize).sum();
+            let unstable: usize = unstable.iter().map(|t| t.op().0 as usize).sum();
+            stable + unstable
+        }
+    }
+
+    impl OpCodec for Add {
+        fn encode(&self, buf: &mut Vec<u8>) {
+            buf.push(self.0);
+        }
+
+        fn decode(bytes: &[u8]) -> Option<Self> {
+            match bytes {
+                [v] => Some(Add(*v)),
+                _ => None,
+            }
+        }
+    }
+
+    type AddReplica = Replica<VecLog<Add>, Tcsb<Add>>;
+
+    fn wal_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("moirai-{name}-{}.wal", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn since(replica: &AddReplica) -> (String, usize) {
+        let since = replica.since();
+        (
+            since.since().version().to_string(),
+            since.since().except().len(),
+        )
+    }
+
+    #[test]
+    fn reopen_after_crash() {
+        let path = wal_path("reopen");
+        let members = ["a", "b", "c"];
+        let mut replica_b: AddReplica = Replica::bootstrap("b".to_string(), &members);
+        let mut replica_c: AddReplica = Replica::bootstrap("c".to_string(), &members);
+
+        let mut replica_a = AddReplica::with_wal("a".to_string(), &members, &path).unwrap();
+        let event = replica_a.send(Add(1)).unwrap();
+        replica_b.receive(event);
+        let event = replica_b.send(Add(2)).unwrap();
+        replica_a.receive(event.clone());
+        replica_c.receive(event);
+        let event = replica_c.send(Add(3)).unwrap();
+        replica_a.receive(event.clone());
+        replica_b.receive(event);
+        let event = replica_a.send(Add(4)).unwrap();
+        replica_b.receive(event);
+
+        let before = since(&replica_a);
+        assert_eq!(replica_a.query(Read::new()), 10);
+        drop(replica_a);
+
+        let mut replica_a = AddReplica::with_wal("a".to_string(), &members, &path).unwrap();
+        assert_eq!(since(&replica_a), before);
+        assert_eq!(replica_a.query(Read::new()), 10);
+
+        // The reopened replica resumes its sequence
+        let event = replica_a.send(Add(5)).unwrap();
+        replica_b.receive(event);
+        assert_eq!(replica_b.query(Read::new()), 15);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn unknown_replica_index_is_rejected() {
+        let path = wal_path("unknown-index");
+        let mut wal = FileWal::open(&path).unwrap();
+        wal.write_record(MEMBER, b"a").unwrap();
+        // Event 1 of a, whose version has an entry for the unknown replica 3
+        let mut body = Vec::new();
+        for value in [0, 1, 1, 1, 3, 1] {
+            put_u64(&mut body, value);
+        }
+        Add(1).encode(&mut body);
+        wal.write_record(EVENT, &body).unwrap();
+        drop(wal);
+
+        let members = ["a"];
+        let error = AddReplica::with_wal("a".to_string(), &members, &path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reopen_without_sync() {
+        let path = wal_path("unsynced");
+        let members = ["a", "b"];
+
+        let mut replica_a: AddReplica = Replica::builder("a".to_string())
+            .with_members(&members)
+            .with_wal(&path)
+            .with_wal_sync(false)
+            .build()
+            .unwrap();
+        replica_a.send(Add(1)).unwrap();
+        replica_a.send(Add(2)).unwrap();
+        drop(replica_a);
+
+        let replica_a = AddReplica::with_wal("a".to_string(), &members, &path).unwrap();
+        assert_eq!(replica_a.query(Read::new()), 3);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn receive_filter_is_refused() {
+        let path = wal_path("filter");
+        let members = ["a", "b", "c"];
+        let mut replica_b: AddReplica = Replica::bootstrap("b".to_string(), &members);
+        let mut replica_c: AddReplica = Replica::bootstrap("c".to_string(), &members);
+
+        let mut replica_a = AddReplica::with_wal("a".to_string(), &members, &path).unwrap();
+        let filter = replica_a
+            .set_receive_filter(Box::new(|event: &Event<Add>| event.id().origin_id() != "c"));
+        assert_eq!(filter.unwrap_err().kind(), io::ErrorKind::Unsupported);
+
+        let event_c = replica_c.send(Add(1)).unwrap();
+        replica_b.receive(event_c.clone());
+        // Depends on the event of c
+        let event_b = replica_b.send(Add(2)).unwrap();
+        replica_a.receive(event_c);
+        replica_a.receive(event_b);
+        let before = since(&replica_a);
+        drop(replica_a);
+
+        let replica_a = AddReplica::with_wal("a".to_string(), &members, &path).unwrap();
+        assert_eq!(since(&replica_a), before);
+        assert_eq!(replica_a.query(Read::new()), 3);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn interrupted_record_is_discarded() {
+        let path = wal_path("interrupted");
+        let members = ["a", "b"];
+
+        let mut replica_a = AddReplica::with_wal("a".to_string(), &members, &path).unwrap();
+        replica_a.send(Add(1)).unwrap();
+        replica_a.send(Add(2)).unwrap();
+        let before = since(&replica_a);
+        drop(replica_a);
+
+        // Crash in the middle of a record
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[42, 0, 0, 0, EVENT, 1]).unwrap();
+        drop(file);
+
+        let mut replica_a = AddReplica::with_wal("a".to_string(), &members, &path).unwrap();
+        assert_eq!(since(&replica_a), before);
+        replica_a.send(Add(3)).unwrap();
+        drop(replica_a);
+
+        let replica_a = AddReplica::with_wal("a".to_string(), &members, &path).unwrap();
+        assert_eq!(replica_a.query(Read::new()), 6);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,4 +1,9 @@
-use std::fmt::Debug;
+use std::{
+    fmt::{Debug, Display},
+    io,
+    path::Path,
+    sync::mpsc::{Receiver, Sender, channel},
+};
 
 #[cfg(feature = "test_utils")]
 use deepsize::DeepSizeOf;
@@ -8,17 +13,77 @@ use crate::broadcast::tcsb::IsTcsbTest;
 use crate::{
     broadcast::{
         message::{BatchMessage, EventMessage, SinceMessage},
-        tcsb::IsTcsb,
+        tcsb::{Dump, EventFilter, IsTcsb, PullError, SendOutcome, Tcsb},
     },
+    clock::version_vector::Version,
     crdt::{
         eval::{BorrowedRead, EvalNested},
         query::QueryOperation,
     },
-    event::Event,
-    state::{effect_context::EffectContext, log::IsLog, sink::SinkCollector},
-    utils::intern_str::Interner,
+    event::{Event, id::EventId, id_strategy::EventIdStrategy},
+    persistence::{FileWal, OpCodec, WalLog},
+    state::{
+        effect_context::EffectContext,
+        log::{HistoricLog, IsLog, PruneStats, StableSnapshot},
+        sink::SinkCollector,
+    },
+    utils::intern_str::{InternalizeOp, Interner, Resolver},
 };
 
+/// Error of [`Replica::query_at`]: the state at the requested version cannot be rebuilt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotReconstructible;
+
+impl Display for NotReconstructible {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The version is no longer reconstructible")
+    }
+}
+
+impl std::error::Error for NotReconstructible {}
+
+/// Summary of the state of a replica, see [`Replica::stats`].
+#[derive(Clone, Debug)]
+pub struct ReplicaStats {
+    pub id: ReplicaIdOwned,
+    pub view_members: Vec<ReplicaIdOwned>,
+    /// Version of the events delivered by the replica.
+    pub my_clock: Version,
+    /// Entries kept by the log, stable or not (see [`PruneStats`]).
+    pub log_size: usize,
+    /// Number of delivered events that are stable.
+    pub stable_count: usize,
+    /// Number of received events waiting for their dependencies.
+    pub pending_count: usize,
+}
+
+/// What receiving one message did, see [`Replica::receive_with_ack`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeliveryOutcome {
+    /// Events delivered by the call, in delivery order: the received one,
+    /// then the buffered events that were waiting for it.
+    pub applied: Vec<EventId>,
+    /// The event waits in the inbox for missing dependencies, see [`Tcsb::pending`].
+    pub buffered: bool,
+    /// The event had already been delivered or buffered: the message was ignored.
+    pub duplicate: bool,
+}
+
+impl Display for ReplicaStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: members [{}], clock {}, {} log entries, {} stable events, {} pending events",
+            self.id,
+            self.view_members.join(", "),
+            self.my_clock,
+            self.log_size,
+            self.stable_count,
+            self.pending_count
+        )
+    }
+}
+
 pub type ReplicaId = str;
 pub type ReplicaIdOwned = String;
 
@@ -53,6 +118,13 @@ where
     fn read_ref(&self) -> &L::Value
     where
         L: BorrowedRead;
+    /// Inspect the cached materialized value of the replica state without cloning it.
+    fn with_value<R>(&self, f: impl FnOnce(&L::Value) -> R) -> R
+    where
+        L: BorrowedRead,
+    {
+        f(self.read_ref())
+    }
     /// Update the state of the replica with the given operation.
     fn update(&mut self, op: L::Op) -> Result<(), L::Rejection> {
         self.send(op)?;
@@ -62,11 +134,63 @@ where
     fn bootstrap(id: ReplicaIdOwned, members: &[&ReplicaId]) -> Self;
 }
 
-#[derive(Debug)]
-pub struct Replica<L, T> {
+pub struct Replica<L, T>
+where
+    L: IsLog,
+{
     id: ReplicaIdOwned,
     tcsb: T,
     state: L,
+    /// Log where the delivered events are persisted, if any.
+    wal: Option<Box<dyn WalLog<L::Op>>>,
+    /// Error that stopped the persistence of the delivered events, see [`Replica::wal_error`].
+    wal_error: Option<io::Error>,
+    subscriptions: Vec<Box<dyn Subscription<L>>>,
+}
+
+impl<L, T> Debug for Replica<L, T>
+where
+    L: IsLog + Debug,
+    T: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Replica")
+            .field("id", &self.id)
+            .field("tcsb", &self.tcsb)
+            .field("state", &self.state)
+            .field("wal", &self.wal)
+            .field("wal_error", &self.wal_error)
+            .field("subscriptions", &self.subscriptions.len())
+            .finish()
+    }
+}
+
+/// Query re-evaluated after each delivered event, see [`Replica::subscribe`].
+trait Subscription<L> {
+    /// Send the result of the query if it changed. `false` once the receiver is dropped.
+    fn notify(&mut self, state: &L) -> bool;
+}
+
+struct QuerySubscription<Q: QueryOperation> {
+    query: Q,
+    last: Q::Response,
+    sender: Sender<Q::Response>,
+}
+
+impl<L, Q> Subscription<L> for QuerySubscription<Q>
+where
+    L: IsLog + EvalNested<Q>,
+    Q: QueryOperation + Clone,
+    Q::Response: PartialEq + Clone,
+{
+    fn notify(&mut self, state: &L) -> bool {
+        let result = state.eval(self.query.clone());
+        if result == self.last {
+            return true;
+        }
+        self.last = result.clone();
+        self.sender.send(result).is_ok()
+    }
 }
 
 impl<L, T> IsReplica<L> for Replica<L, T>
@@ -81,6 +205,9 @@ where
             id,
             tcsb: T::new(idx.0, interner),
             state: L::new(),
+            wal: None,
+            wal_error: None,
+            subscriptions: Vec::new(),
         }
     }
 
@@ -96,18 +223,21 @@ where
         while let Some(e) = self.tcsb.next_causally_ready() {
             self.deliver(e);
         }
+        self.stabilize();
     }
 
     fn send(&mut self, op: L::Op) -> Result<EventMessage<L::Op>, L::Rejection> {
-        self.state.is_enabled(&op)?;
+        self.state.is_enabled_at(&op, self.tcsb.version())?;
         let op = L::prepare(op);
         let message = self.tcsb.send(op);
         self.deliver(message.event().clone());
-        Ok(message)
+        Ok(Self::broadcast(message))
     }
 
     fn pull(&mut self, since: SinceMessage) -> BatchMessage<L::Op> {
-        self.tcsb.pull(since)
+        let batch = self.tcsb.pull(since);
+        self.stabilize();
+        batch
     }
 
     fn query<Q: QueryOperation>(&self, q: Q) -> Q::Response
@@ -133,21 +263,23 @@ where
     }
 
     fn bootstrap(id: ReplicaIdOwned, members: &[&ReplicaId]) -> Self {
-        assert!(
-            members.contains(&&(*id)),
-            "Bootstrap replica ID {} must be included in members list {:?}",
-            id,
-            members
-        );
-        let mut interner = Interner::new();
-        let (idx, _) = interner.intern(&id);
-        for member in members {
-            interner.intern(member);
-        }
-        Self {
-            id,
-            tcsb: T::new(idx, interner),
-            state: L::new(),
+        Self::bootstrap_with_state(id, members, L::new())
+    }
+}
+
+/// Apply the operations as a batch, e.g., to seed a replica with initial data,
+/// see [`Replica::apply_batch`]. The messages are not returned: the other replicas pull the events.
+///
+/// # Panics
+/// Panics if an operation is not enabled.
+impl<L> Extend<L::Op> for Replica<L, Tcsb<L::Op>>
+where
+    L: IsLog,
+    L::Op: InternalizeOp,
+{
+    fn extend<I: IntoIterator<Item = L::Op>>(&mut self, ops: I) {
+        if let Err(err) = self.apply_batch(ops) {
+            panic!("Replica {} cannot extend its log: {err}", self.id);
         }
     }
 }
@@ -173,22 +305,434 @@ where
             id,
             tcsb: T::new(idx, interner),
             state,
+            wal: None,
+            wal_error: None,
+            subscriptions: Vec::new(),
+        }
+    }
+
+    /// Receive the result of `query` each time it changes, i.e., after a delivered event,
+    /// local or remote, for which it differs from the previous one. The current result is not sent.
+    ///
+    /// The query is evaluated after every delivered event. The subscription ends when the
+    /// receiver is dropped.
+    pub fn subscribe<Q>(&mut self, query: Q) -> Receiver<Q::Response>
+    where
+        L: EvalNested<Q>,
+        Q: QueryOperation + Clone + 'static,
+        Q::Response: PartialEq + Clone + 'static,
+    {
+        let (sender, receiver) = channel();
+        let last = self.state.eval(query.clone());
+        self.subscriptions.push(Box::new(QuerySubscription {
+            query,
+            last,
+            sender,
+        }));
+        receiver
+    }
+
+    /// Message to broadcast for a local event, without its dependencies
+    /// if the log does not need them (see [`IsLog::NEEDS_CAUSAL_DEPS`]).
+    fn broadcast(message: EventMessage<L::Op>) -> EventMessage<L::Op> {
+        if L::NEEDS_CAUSAL_DEPS {
+            message
+        } else {
+            message.without_dependencies()
         }
     }
 
     fn deliver(&mut self, event: Event<L::Op>) {
+        self.apply(event);
+        self.stabilize();
+        self.notify();
+    }
+
+    /// Persist the event and apply it to the state, without stabilizing nor notifying.
+    fn apply(&mut self, event: Event<L::Op>) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("new_event").entered();
+        if let Some(wal) = self.wal.as_mut()
+            && let Err(err) = wal.append(&event)
+        {
+            // The log would miss this event: the following ones are not persisted either
+            self.wal = None;
+            self.wal_error = Some(err);
+        }
         let mut sink = SinkCollector::new();
         let mut ctx = EffectContext::root("root", Some(&mut sink));
 
         self.state.effect(event, &mut ctx);
+    }
+
+    fn notify(&mut self) {
+        let state = &self.state;
+        self.subscriptions
+            .retain_mut(|subscription| subscription.notify(state));
+    }
 
-        let maybe_version = self.tcsb.is_stable();
-        if let Some(version) = maybe_version {
+    /// Stabilize the events that every member has delivered, as far as the replica knows.
+    /// Besides deliveries, pulls and batches tell what the other members have delivered.
+    fn stabilize(&mut self) {
+        if let Some(version) = self.tcsb.is_stable() {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::trace_span!("stabilize").entered();
             self.state.stabilize(version);
         }
     }
 }
 
+impl<L> Replica<L, Tcsb<L::Op>>
+where
+    L: IsLog,
+    L::Op: InternalizeOp,
+{
+    /// Bootstrap a replica whose delivered events are persisted in the write-ahead log at `path`.
+    /// The events already in the log are replayed first, so that a replica reopened after
+    /// a crash resumes where it stopped. It must be bootstrapped with the same members.
+    /// Events received but not delivered yet are not persisted: they are pulled again.
+    ///
+    /// If an event cannot be appended to the log, the replica stops persisting its events,
+    /// see [`Replica::wal_error`].
+    pub fn with_wal(
+        id: ReplicaIdOwned,
+        members: &[&ReplicaId],
+        path: impl AsRef<Path>,
+    ) -> io::Result<Self>
+    where
+        L::Op: OpCodec,
+    {
+        Self::builder(id)
+            .with_members(members)
+            .with_wal(path)
+            .build()
+    }
+
+    /// Configure a replica option by option, see [`ReplicaBuilder`].
+    pub fn builder(id: ReplicaIdOwned) -> ReplicaBuilder<L> {
+        ReplicaBuilder::new(id)
+    }
+
+    /// Evaluate `q` on the state made of the events included in `version` only.
+    ///
+    /// Fails if the log cannot rebuild that state, e.g. if events not included in
+    /// `version` are already stable and folded with the others. Logs that prune redundant
+    /// operations cannot rebuild any, which is checked at compile time, see [`HistoricLog`].
+    pub fn query_at<Q>(&self, q: Q, version: Version) -> Result<Q::Response, NotReconstructible>
+    where
+        Q: QueryOperation,
+        L: HistoricLog + EvalNested<Q>,
+    {
+        let state = self
+            .state
+            .at(&version, self.tcsb.stable_version())
+            .ok_or(NotReconstructible)?;
+        Ok(state.eval(q))
+    }
+
+    /// Same as `receive`, reporting whether the event was delivered, buffered or a duplicate.
+    /// An invalid message is dropped: it is reported as neither buffered nor a duplicate.
+    pub fn receive_with_ack(&mut self, message: EventMessage<L::Op>) -> DeliveryOutcome {
+        let id = message.event().id().clone();
+        let duplicate = self.tcsb.has_received(&id);
+        self.tcsb.receive(message);
+        let mut applied = Vec::new();
+        while let Some(e) = self.tcsb.next_causally_ready() {
+            applied.push(e.id().clone());
+            self.deliver(e);
+        }
+        // The ids come from different views: compare them by replica id
+        let delivered = applied
+            .iter()
+            .any(|applied| applied.origin_id() == id.origin_id() && applied.seq() == id.seq());
+        DeliveryOutcome {
+            buffered: !duplicate && !delivered && self.tcsb.has_received(&id),
+            applied,
+            duplicate,
+        }
+    }
+
+    /// The stable part of the state and every event the replica holds, e.g., to debug a cold
+    /// start: a fresh replica starts from both with [`Replica::bootstrap_from_dump`].
+    /// See [`Tcsb::dump_all`].
+    pub fn dump_all(&self) -> (L, Dump<L::Op>)
+    where
+        L: StableSnapshot,
+    {
+        let stable = self.state.stable_snapshot(self.tcsb.stable_version());
+        (stable, self.tcsb.dump_all())
+    }
+
+    /// Bootstrap a replica from the stable state and the events dumped by another one,
+    /// see [`Replica::dump_all`] and [`Tcsb::load_dump`].
+    /// The state may hold event ids: the members must be given in the same order as to the
+    /// dumping replica, so that they keep their meaning.
+    pub fn bootstrap_from_dump(
+        id: ReplicaIdOwned,
+        members: &[&ReplicaId],
+        stable: L,
+        dump: Dump<L::Op>,
+    ) -> Self {
+        let mut replica = Self::bootstrap_with_state(id, members, stable);
+        replica.tcsb.load_dump(dump);
+        while let Some(e) = replica.tcsb.next_causally_ready() {
+            replica.deliver(e);
+        }
+        replica.stabilize();
+        replica
+    }
+
+    /// See [`Tcsb::try_pull`].
+    pub fn try_pull(&mut self, since: SinceMessage) -> Result<BatchMessage<L::Op>, PullError> {
+        let batch = self.tcsb.try_pull(since);
+        self.stabilize();
+        batch
+    }
+
+    /// Receive several batches at once and deliver their events in a single pass.
+    /// See [`Tcsb::receive_many`].
+    pub fn receive_many(&mut self, messages: impl IntoIterator<Item = BatchMessage<L::Op>>) {
+        self.tcsb.receive_many(messages);
+        while let Some(e) = self.tcsb.next_causally_ready() {
+            self.deliver(e);
+        }
+        self.stabilize();
+    }
+
+    /// See [`Tcsb::prewarm`].
+    pub fn prewarm_resolver(&mut self, members: &[&ReplicaId]) {
+        self.tcsb.prewarm(members);
+    }
+
+    /// See [`Tcsb::view`].
+    pub fn view(&self) -> &Resolver {
+        self.tcsb.view()
+    }
+
+    /// Event matching an id built with the strategy `S`, e.g. received from another system.
+    /// `None` if the origin of the event is not in the view of the replica.
+    pub fn event_id<S: EventIdStrategy>(&self, id: &S::Id) -> Option<EventId> {
+        S::event_id(id, self.tcsb.resolver())
+    }
+
+    /// Summary of the replica, e.g. to monitor a running node.
+    pub fn stats(&self) -> ReplicaStats {
+        let PruneStats {
+            live_events,
+            tombstones,
+            stable_ops,
+            ..
+        } = self.state.prune_stats();
+        ReplicaStats {
+            id: self.id.clone(),
+            view_members: self.tcsb.view_members(),
+            my_clock: self.tcsb.version().clone(),
+            log_size: live_events + tombstones + stable_ops,
+            stable_count: self.tcsb.stable_version().sum(),
+            pending_count: self.tcsb.num_pending(),
+        }
+    }
+
+    /// Error that stopped the persistence of the delivered events in the write-ahead log.
+    ///
+    /// The replica still delivers the events, but a replica reopened from the log resumes from
+    /// the last event persisted: the ones delivered since, local ones included, are lost.
+    /// The replica should thus stop issuing operations and be reopened.
+    pub fn wal_error(&self) -> Option<&io::Error> {
+        self.wal_error.as_ref()
+    }
+
+    /// See [`Tcsb::set_max_unstable`].
+    pub fn set_max_unstable(&mut self, max_unstable: Option<usize>) {
+        self.tcsb.set_max_unstable(max_unstable);
+    }
+
+    /// See [`Tcsb::set_receive_filter`].
+    ///
+    /// # Errors
+    /// If the replica persists its events in a write-ahead log: the rejected events are not
+    /// logged, so a reopened replica would wait for them forever, and the filter is not either.
+    pub fn set_receive_filter(&mut self, filter: Box<EventFilter<L::Op>>) -> io::Result<()> {
+        if self.wal.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "a receive filter cannot be set on a replica with a write-ahead log",
+            ));
+        }
+        self.tcsb.set_receive_filter(filter);
+        Ok(())
+    }
+
+    /// Fork the replica under the identity `new_id`, e.g., to promote a hot spare.
+    /// The fork starts from the same state and issues its own operations, concurrent with
+    /// the ones of `self`. It neither persists its events nor keeps the subscriptions.
+    /// `self` adds the fork to its view, so that the stability waits for it. See [`Tcsb::fork`].
+    pub fn clone_as(&mut self, new_id: ReplicaIdOwned) -> Self
+    where
+        L: Clone,
+    {
+        Self {
+            tcsb: self.tcsb.fork(&new_id),
+            id: new_id,
+            state: self.state.clone(),
+            wal: None,
+            wal_error: None,
+            subscriptions: Vec::new(),
+        }
+    }
+
+    /// Apply a local operation, and return the message to broadcast it if there is any
+    /// other member. A replica alone in its view only skips building the outgoing message:
+    /// the event is the same as with `send`, delivered and stabilized the same way, and
+    /// recorded in the outbox, so that members joining later can pull it.
+    pub fn apply_local(&mut self, op: L::Op) -> Result<Option<EventMessage<L::Op>>, L::Rejection> {
+        if !self.tcsb.is_solo() {
+            return self.send(op).map(Some);
+        }
+        self.state.is_enabled_at(&op, self.tcsb.version())?;
+        let op = L::prepare(op);
+        let event = self.tcsb.send_local(op);
+        self.deliver(event);
+        Ok(None)
+    }
+
+    /// Apply local operations in a single pass, e.g., to seed a replica with initial data.
+    /// Each operation is checked against the state left by the previous ones and recorded
+    /// without building its message: the other replicas pull the events.
+    /// The stability and the subscriptions are only updated once, after the last operation.
+    ///
+    /// # Errors
+    /// Stops at the first operation that is not enabled, the previous ones being applied.
+    pub fn apply_batch(
+        &mut self,
+        ops: impl IntoIterator<Item = L::Op>,
+    ) -> Result<(), L::Rejection> {
+        let result: Result<(), L::Rejection> = ops.into_iter().try_for_each(|op| {
+            self.state.is_enabled_at(&op, self.tcsb.version())?;
+            let op = L::prepare(op);
+            let event = self.tcsb.send_local(op);
+            self.apply(event);
+            Ok(())
+        });
+        self.stabilize();
+        self.notify();
+        result
+    }
+
+    /// Same as `send`, but the operation is neither applied nor sent
+    /// when too many events wait for stability (see [`Tcsb::try_send`]).
+    pub fn try_send(&mut self, op: L::Op) -> Result<SendOutcome<L::Op>, L::Rejection> {
+        self.state.is_enabled_at(&op, self.tcsb.version())?;
+        let op = L::prepare(op);
+        match self.tcsb.try_send(op) {
+            SendOutcome::Sent(message) => {
+                self.deliver(message.event().clone());
+                Ok(SendOutcome::Sent(Self::broadcast(message)))
+            }
+            outcome => Ok(outcome),
+        }
+    }
+}
+
+/// Opened log, the events it already holds, and how to box it once configured.
+type OpenedWal<O> = (FileWal, Vec<Event<O>>, fn(FileWal) -> Box<dyn WalLog<O>>);
+
+/// Options of a [`Replica`] broadcasting its events with a [`Tcsb`].
+///
+/// Without members, the replica is alone in its view.
+pub struct ReplicaBuilder<L: IsLog> {
+    id: ReplicaIdOwned,
+    members: Vec<ReplicaIdOwned>,
+    state: Option<L>,
+    /// Opened log, or the error to report on `build`.
+    wal: Option<io::Result<OpenedWal<L::Op>>>,
+    wal_sync: bool,
+    max_unstable: Option<usize>,
+}
+
+impl<L> ReplicaBuilder<L>
+where
+    L: IsLog,
+    L::Op: InternalizeOp,
+{
+    pub fn new(id: ReplicaIdOwned) -> Self {
+        Self {
+            members: vec![id.clone()],
+            id,
+            state: None,
+            wal: None,
+            wal_sync: true,
+            max_unstable: None,
+        }
+    }
+
+    /// Members of the view. They must include the replica itself.
+    pub fn with_members(mut self, members: &[&ReplicaId]) -> Self {
+        self.members = members.iter().map(|m| m.to_string()).collect();
+        self
+    }
+
+    /// Initial state instead of an empty log, see [`Replica::bootstrap_with_state`].
+    pub fn with_state(mut self, state: L) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Persist the delivered events in the write-ahead log at `path`, see [`Replica::with_wal`].
+    /// The log is read right away; an error is reported by `build`.
+    pub fn with_wal(mut self, path: impl AsRef<Path>) -> Self
+    where
+        L::Op: OpCodec,
+    {
+        let wal = FileWal::open(path).and_then(|mut wal| {
+            let events: Vec<Event<L::Op>> = WalLog::<L::Op>::replay(&mut wal)?.collect();
+            let boxed: fn(FileWal) -> Box<dyn WalLog<L::Op>> = |wal| Box::new(wal);
+            Ok((wal, events, boxed))
+        });
+        self.wal = Some(wal);
+        self
+    }
+
+    /// Whether each event is flushed to the disk before it is delivered, the default.
+    /// See [`FileWal::set_sync`].
+    pub fn with_wal_sync(mut self, sync: bool) -> Self {
+        self.wal_sync = sync;
+        self
+    }
+
+    /// See [`Tcsb::set_max_unstable`].
+    pub fn with_max_unstable(mut self, max_unstable: usize) -> Self {
+        self.max_unstable = Some(max_unstable);
+        self
+    }
+
+    /// # Errors
+    /// If the write-ahead log cannot be read.
+    ///
+    /// # Panics
+    /// If the replica is not one of the members.
+    pub fn build(self) -> io::Result<Replica<L, Tcsb<L::Op>>> {
+        let members: Vec<&ReplicaId> = self.members.iter().map(String::as_str).collect();
+        let state = self.state.unwrap_or_default();
+        let mut replica = Replica::bootstrap_with_state(self.id, &members, state);
+        replica.set_max_unstable(self.max_unstable);
+        if let Some(wal) = self.wal {
+            let (mut wal, events, boxed) = wal?;
+            wal.set_sync(self.wal_sync);
+            for event in events {
+                if let Some(event) = replica.tcsb.restore(event)? {
+                    replica.deliver(event);
+                }
+                while let Some(event) = replica.tcsb.next_causally_ready() {
+                    replica.deliver(event);
+                }
+            }
+            replica.wal = Some(boxed(wal));
+        }
+        Ok(replica)
+    }
+}
+
 #[cfg(feature = "test_utils")]
 impl<L, T> Replica<L, T>
 where
@@ -211,3 +755,675 @@ where
         &mut self.state
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "tracing")]
+    use std::sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    };
+    use std::{collections::BTreeMap, convert::Infallible, io};
+
+    #[cfg(feature = "tracing")]
+    use tracing::{
+        Event as TracingEvent, Metadata, Subscriber,
+        span::{Attributes, Id, Record},
+    };
+
+    use crate::{
+        broadcast::tcsb::{IsTcsb, IsTcsbTest, MergeError, SendOutcome, Tcsb},
+        crdt::{eval::Eval, pure_crdt::PureCRDT, query::Read},
+        event::{
+            Event,
+            id_strategy::{CompactIds, EventIdStrategy, UuidIds},
+        },
+        persistence::WalLog,
+        replica::{DeliveryOutcome, IsReplica, Replica, ReplicaIdx},
+        state::{cache::CachedLog, log::IsLog, po_log::VecLog, unstable_state::IsUnstableCore},
+        utils::{
+            intern_str::{InternalizeOp, Interner},
+            test_ops::{Inc, UnprunedInc},
+        },
+    };
+
+    type IncReplica = Replica<VecLog<Inc>, Tcsb<Inc>>;
+
+    #[test]
+    fn solo_replica_matches_cluster() {
+        let mut solo: IncReplica = Replica::bootstrap("a".to_string(), &["a"]);
+        let mut replica_a: IncReplica = Replica::bootstrap("a".to_string(), &["a", "b"]);
+        for _ in 0..5 {
+            assert!(solo.apply_local(Inc(1)).unwrap().is_none());
+            assert!(replica_a.apply_local(Inc(1)).unwrap().is_some());
+        }
+        assert_eq!(solo.query(Read::new()), replica_a.query(Read::new()));
+
+        // A new member joins and catches up with the history of the solo replica
+        let mut replica_b: IncReplica = Replica::bootstrap("b".to_string(), &["a", "b"]);
+        let event = replica_b.send(Inc(1)).unwrap();
+        solo.receive(event);
+        assert_eq!(solo.tcsb().members().len(), 2);
+        replica_b.receive_batch(solo.pull(replica_b.since()));
+        let event = solo.apply_local(Inc(1)).unwrap().unwrap();
+        replica_b.receive(event);
+
+        assert_eq!(solo.query(Read::new()), 7);
+        assert_eq!(replica_b.query(Read::new()), 7);
+    }
+
+    /// Compare `send` with `apply_local` on a replica alone in its view.
+    /// Run with `cargo test --release -p moirai-protocol send_cost_solo -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn send_cost_solo_replica() {
+        use std::time::Instant;
+
+        const OPS: u32 = 200_000;
+
+        let mut sent: Replica<VecLog<UnprunedInc>, Tcsb<UnprunedInc>> =
+            Replica::bootstrap("a".to_string(), &["a"]);
+        let start = Instant::now();
+        for _ in 0..OPS {
+            sent.send(UnprunedInc).unwrap();
+        }
+        println!("send: {:?} per op", start.elapsed() / OPS);
+
+        let mut applied: Replica<VecLog<UnprunedInc>, Tcsb<UnprunedInc>> =
+            Replica::bootstrap("a".to_string(), &["a"]);
+        let start = Instant::now();
+        for _ in 0..OPS {
+            applied.apply_local(UnprunedInc).unwrap();
+        }
+        println!("apply_local: {:?} per op", start.elapsed() / OPS);
+
+        assert_eq!(sent.query(Read::new()), applied.query(Read::new()));
+    }
+
+    #[test]
+    fn bootstrap_from_dump_once_stable() {
+        let members = ["a", "b"];
+        let mut replica_a: IncReplica = Replica::bootstrap("a".to_string(), &members);
+        let mut replica_b: IncReplica = Replica::bootstrap("b".to_string(), &members);
+        for _ in 0..3 {
+            replica_b.receive(replica_a.send(Inc(1)).unwrap());
+            replica_a.receive(replica_b.send(Inc(2)).unwrap());
+        }
+        // Not delivered by b yet
+        let event = replica_a.send(Inc(5)).unwrap();
+
+        // The stable events are only in the stable state
+        let (stable, dump) = replica_a.dump_all();
+        assert_eq!(stable.eval(Read::new()), 9);
+        assert_eq!(dump.stable_version.sum(), 6);
+        assert_eq!(dump.events.len(), 1);
+        let mut replica_c: IncReplica =
+            Replica::bootstrap_from_dump("c".to_string(), &["a", "b", "c"], stable, dump);
+        assert_eq!(replica_c.query(Read::new()), 14);
+        assert_eq!(replica_c.stats().my_clock.sum(), 7);
+
+        replica_b.receive(event);
+        let event_b = replica_b.send(Inc(1)).unwrap();
+        replica_c.receive(event_b.clone());
+        replica_a.receive(event_b);
+        assert_eq!(replica_a.query(Read::new()), 15);
+        assert_eq!(replica_c.query(Read::new()), 15);
+    }
+
+    #[test]
+    fn clone_as_forks_a_counter() {
+        let mut replica_a: IncReplica = Replica::bootstrap("a".to_string(), &["a", "b"]);
+        let mut replica_b: IncReplica = Replica::bootstrap("b".to_string(), &["a", "b"]);
+        for _ in 0..2 {
+            let event = replica_a.send(Inc(1)).unwrap();
+            replica_b.receive(event);
+        }
+
+        let mut replica_c = replica_a.clone_as("c".to_string());
+        assert_eq!(replica_c.id(), "c");
+        assert_eq!(replica_c.query(Read::new()), 2);
+        // The source knows about the fork, unlike the other members until they hear from the source
+        assert_eq!(replica_a.tcsb().members().len(), 3);
+        assert_eq!(replica_b.tcsb().members().len(), 2);
+        assert_eq!(replica_c.tcsb().members().len(), 3);
+
+        let event_a = replica_a.send(Inc(1)).unwrap();
+        let event_b = replica_b.send(Inc(1)).unwrap();
+        let events_c = [
+            replica_c.send(Inc(1)).unwrap(),
+            replica_c.send(Inc(1)).unwrap(),
+        ];
+        // Concurrent with the last event of the source
+        assert_eq!(
+            events_c[0].event().version().seq_by_idx(ReplicaIdx(0)),
+            event_a.event().version().seq_by_idx(ReplicaIdx(0)) - 1
+        );
+
+        for event in &events_c {
+            replica_a.receive(event.clone());
+            replica_b.receive(event.clone());
+        }
+        replica_a.receive(event_b.clone());
+        replica_b.receive(event_a.clone());
+        replica_c.receive(event_a);
+        replica_c.receive(event_b);
+
+        for replica in [&replica_a, &replica_b, &replica_c] {
+            assert_eq!(replica.query(Read::new()), 6);
+        }
+    }
+
+    #[test]
+    fn merge_rejects_a_copy_under_the_same_identity() {
+        let mut replica_a: IncReplica = Replica::bootstrap("a".to_string(), &["a", "b"]);
+        let mut replica_b: IncReplica = Replica::bootstrap("b".to_string(), &["a", "b"]);
+        // Copy of "a" that keeps its identity instead of being forked with `clone_as`
+        let mut copy_a: IncReplica = Replica::bootstrap("a".to_string(), &["a", "b"]);
+
+        let event = replica_b.send(Inc(1)).unwrap();
+        replica_a.receive(event.clone());
+        copy_a.receive(event);
+        replica_b.receive(replica_a.send(Inc(2)).unwrap());
+        copy_a.send(Inc(3)).unwrap();
+
+        assert!(matches!(
+            replica_a.tcsb.merge(&copy_a.tcsb),
+            Err(MergeError::SameOrigin(id)) if id == "a"
+        ));
+        assert!(replica_a.tcsb.next_causally_ready().is_none());
+
+        // A fork under its own identity is merged back into one linear history
+        let mut replica_c = replica_a.clone_as("c".to_string());
+        let event_c = replica_c.send(Inc(4)).unwrap();
+        let event_a = replica_a.send(Inc(5)).unwrap();
+        replica_a.tcsb.merge(&replica_c.tcsb).unwrap();
+        while let Some(event) = replica_a.tcsb.next_causally_ready() {
+            replica_a.deliver(event);
+        }
+
+        replica_b.receive(event_a);
+        replica_b.receive(event_c);
+        assert_eq!(replica_b.query(Read::new()), 12);
+        assert_eq!(replica_a.query(Read::new()), replica_b.query(Read::new()));
+    }
+
+    #[test]
+    fn receive_with_ack_out_of_order() {
+        let mut replica_a: IncReplica = Replica::bootstrap("a".to_string(), &["a", "b"]);
+        let mut replica_b: IncReplica = Replica::bootstrap("b".to_string(), &["a", "b"]);
+        let events: Vec<_> = (0..3).map(|_| replica_a.send(Inc(1)).unwrap()).collect();
+        let seqs = |outcome: &DeliveryOutcome| -> Vec<usize> {
+            outcome.applied.iter().map(|id| id.seq()).collect()
+        };
+
+        let outcome = replica_b.receive_with_ack(events[2].clone());
+        assert!(outcome.buffered && !outcome.duplicate);
+        assert!(outcome.applied.is_empty());
+        let outcome = replica_b.receive_with_ack(events[2].clone());
+        assert!(outcome.duplicate && !outcome.buffered);
+
+        let outcome = replica_b.receive_with_ack(events[1].clone());
+        assert!(outcome.buffered);
+        // The missing event drains the inbox
+        let outcome = replica_b.receive_with_ack(events[0].clone());
+        assert!(!outcome.buffered && !outcome.duplicate);
+        assert_eq!(seqs(&outcome), vec![1, 2, 3]);
+        assert_eq!(outcome.applied[0].origin_id(), "a");
+        assert_eq!(replica_b.query(Read::new()), 3);
+
+        let outcome = replica_b.receive_with_ack(events[0].clone());
+        assert_eq!(
+            outcome,
+            DeliveryOutcome {
+                duplicate: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn wal_failure_stops_persistence() {
+        #[derive(Debug)]
+        struct FullDisk;
+
+        impl WalLog<Inc> for FullDisk {
+            fn append(&mut self, _event: &Event<Inc>) -> io::Result<()> {
+                Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"))
+            }
+
+            fn replay(&mut self) -> io::Result<impl Iterator<Item = Event<Inc>>> {
+                Ok(std::iter::empty())
+            }
+        }
+
+        let (mut replica_a, mut replica_b): (IncReplica, IncReplica) = (
+            Replica::bootstrap("a".to_string(), &["a", "b"]),
+            Replica::bootstrap("b".to_string(), &["a", "b"]),
+        );
+        replica_a.wal = Some(Box::new(FullDisk));
+        assert!(replica_a.wal_error().is_none());
+
+        let event = replica_a.send(Inc(1)).unwrap();
+        replica_b.receive(event);
+        let error = replica_a.wal_error().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::StorageFull);
+        assert!(replica_a.wal.is_none());
+
+        // The replica keeps delivering in memory
+        let event = replica_b.send(Inc(1)).unwrap();
+        replica_a.receive(event);
+        assert_eq!(replica_a.query(Read::new()), 2);
+    }
+
+    #[test]
+    fn receive_filter_drops_an_origin() {
+        let members = ["a", "b", "c"];
+        let [mut replica_a, mut replica_b, mut replica_c]: [IncReplica; 3] =
+            members.map(|id| Replica::bootstrap(id.to_string(), &members));
+        replica_a
+            .set_receive_filter(Box::new(|event: &Event<Inc>| event.id().origin_id() != "c"))
+            .unwrap();
+
+        let event_c = replica_c.send(Inc(1)).unwrap();
+        replica_b.receive(event_c.clone());
+        // Depends on the rejected event
+        let event_b = replica_b.send(Inc(1)).unwrap();
+        replica_a.receive(event_c);
+        replica_a.receive(event_b);
+        assert_eq!(replica_a.query(Read::new()), 1);
+        assert_eq!(replica_b.query(Read::new()), 2);
+        // The rejected event counts as delivered: it is not pulled again
+        assert!(
+            replica_a
+                .tcsb()
+                .missing_from(replica_b.tcsb().version())
+                .is_empty()
+        );
+
+        let event_a = replica_a.send(Inc(1)).unwrap();
+        replica_b.receive(event_a);
+        assert_eq!(replica_a.query(Read::new()), 2);
+        assert_eq!(replica_b.query(Read::new()), 3);
+    }
+
+    #[test]
+    fn receive_many_converges() {
+        let members = ["a", "b", "c", "d", "e"];
+        let mut peers: Vec<IncReplica> = ["a", "b", "c"]
+            .iter()
+            .map(|id| Replica::bootstrap(id.to_string(), &members))
+            .collect();
+        // Each peer sees part of the history of the previous one
+        for round in 0..20 {
+            for i in 0..peers.len() {
+                peers[i].send(Inc(1)).unwrap();
+                if round % 3 == i {
+                    let prev = (i + peers.len() - 1) % peers.len();
+                    let since = peers[i].since();
+                    let batch = peers[prev].pull(since);
+                    peers[i].receive_batch(batch);
+                }
+            }
+        }
+
+        let mut replica_d: IncReplica = Replica::bootstrap("d".to_string(), &members);
+        let mut replica_e: IncReplica = Replica::bootstrap("e".to_string(), &members);
+        let batches: Vec<_> = peers
+            .iter_mut()
+            .rev()
+            .map(|peer| peer.pull(replica_d.since()))
+            .collect();
+        replica_d.receive_many(batches);
+        for peer in peers.iter_mut().rev() {
+            replica_e.receive_batch(peer.pull(replica_e.since()));
+        }
+
+        assert_eq!(replica_d.query(Read::new()), 60);
+        assert_eq!(replica_d.query(Read::new()), replica_e.query(Read::new()));
+        assert_eq!(
+            replica_d.num_delivered_events(),
+            replica_e.num_delivered_events()
+        );
+    }
+
+    /// Compare `receive_many` with receiving the same small batches one by one,
+    /// the most recent first, so that most events wait for their dependencies.
+    /// Run with `cargo test --release -p moirai-protocol receive_many_cost -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn receive_many_cost() {
+        use std::time::Instant;
+
+        const ROUNDS: usize = 500;
+        const BATCH: usize = 10;
+
+        let members = ["a", "b", "c", "d", "e"];
+        let mut batches = Vec::new();
+        for id in ["a", "b", "c"] {
+            let mut peer: IncReplica = Replica::bootstrap(id.to_string(), &members);
+            // Tracks what was already pulled from the peer, so that each batch is a delta
+            let mut pulled: IncReplica = Replica::bootstrap("e".to_string(), &members);
+            for _ in 0..ROUNDS {
+                for _ in 0..BATCH {
+                    peer.send(Inc(1)).unwrap();
+                }
+                let batch = peer.pull(pulled.since());
+                pulled.receive_batch(batch.clone());
+                batches.push(batch);
+            }
+        }
+        batches.reverse();
+
+        let mut looped: IncReplica = Replica::bootstrap("d".to_string(), &members);
+        let start = Instant::now();
+        for batch in batches.clone() {
+            looped.receive_batch(batch);
+        }
+        println!("receive_batch loop: {:?}", start.elapsed());
+
+        let mut merged: IncReplica = Replica::bootstrap("d".to_string(), &members);
+        let start = Instant::now();
+        merged.receive_many(batches);
+        println!("receive_many: {:?}", start.elapsed());
+
+        assert_eq!(merged.query(Read::new()), 3 * ROUNDS * BATCH);
+        assert_eq!(merged.query(Read::new()), looped.query(Read::new()));
+    }
+
+    #[test]
+    fn event_id_round_trip() {
+        let mut replica_a: IncReplica = Replica::bootstrap("a".to_string(), &["a", "b"]);
+        // Another view, where the indices of the members differ
+        let mut replica_b: IncReplica = Replica::bootstrap("b".to_string(), &["b", "a"]);
+        let outsider: IncReplica = Replica::bootstrap("c".to_string(), &["c"]);
+
+        replica_a.send(Inc(1)).unwrap();
+        let event = replica_a.send(Inc(1)).unwrap();
+        let id = event.event().id().clone();
+        replica_b.receive(event);
+
+        let compact = CompactIds::external_id(&id);
+        assert_eq!(compact, ("a".to_string(), 2));
+        assert_eq!(replica_b.event_id::<CompactIds>(&compact), Some(id.clone()));
+        assert_eq!(outsider.event_id::<CompactIds>(&compact), None);
+
+        let uuid = UuidIds::external_id(&id);
+        let resolved = replica_b.event_id::<UuidIds>(&uuid).unwrap();
+        assert_eq!(resolved, id);
+        assert_eq!(UuidIds::external_id(&resolved), uuid);
+        assert_eq!(replica_a.event_id::<UuidIds>(&uuid), Some(id));
+        assert_eq!(outsider.event_id::<UuidIds>(&uuid), None);
+    }
+
+    #[test]
+    fn prewarm_resolver_interns_members_once() {
+        let members = ["a", "b", "c"];
+        let replica: IncReplica = Replica::bootstrap("a".to_string(), &members);
+        assert_eq!(replica.tcsb().interner().len(), members.len());
+
+        let mut replica_a: IncReplica = Replica::new("a".to_string());
+        assert_eq!(replica_a.tcsb().interner().len(), 1);
+        replica_a.prewarm_resolver(&members);
+        replica_a.prewarm_resolver(&members);
+        assert_eq!(replica_a.tcsb().interner().len(), members.len());
+        assert_eq!(replica_a.tcsb().members().len(), members.len());
+
+        // Receiving from a prewarmed member does not intern it again
+        let mut replica_b: IncReplica = Replica::bootstrap("b".to_string(), &members);
+        replica_a.receive(replica_b.send(Inc(1)).unwrap());
+        assert_eq!(replica_a.tcsb().interner().len(), members.len());
+        assert_eq!(replica_a.query(Read::new()), 1);
+    }
+
+    #[test]
+    fn stats_summary() {
+        let members = ["a", "b", "c"];
+        let mut replica_a: IncReplica = Replica::bootstrap("a".to_string(), &members);
+        let mut replica_b: IncReplica = Replica::bootstrap("b".to_string(), &members);
+        let mut replica_c: IncReplica = Replica::bootstrap("c".to_string(), &members);
+
+        let event_a = replica_a.send(Inc(1)).unwrap();
+        replica_b.receive(event_a.clone());
+        let event_b = replica_b.send(Inc(1)).unwrap();
+        // The event of b waits for the one of a
+        replica_c.receive(event_b.clone());
+
+        let stats = replica_c.stats();
+        assert_eq!(stats.id, "c");
+        assert_eq!(stats.view_members.len(), 3);
+        assert_eq!(stats.pending_count, 1);
+        assert_eq!(stats.pending_count, replica_c.tcsb().inbox_len());
+        assert_eq!(stats.log_size, 0);
+
+        replica_c.receive(event_a);
+        replica_a.receive(event_b);
+        let event_c = replica_c.send(Inc(1)).unwrap();
+        replica_a.receive(event_c.clone());
+        replica_b.receive(event_c);
+
+        let stats = replica_a.stats();
+        let prune_stats = replica_a.state().prune_stats();
+        assert_eq!(
+            stats.log_size,
+            prune_stats.live_events + prune_stats.tombstones + prune_stats.stable_ops
+        );
+        assert_eq!(stats.log_size, 3);
+        assert_eq!(stats.my_clock.sum(), 3);
+        // b has not acknowledged the event of c yet
+        assert_eq!(stats.stable_count, 2);
+        assert_eq!(stats.pending_count, 0);
+        assert!(stats.to_string().starts_with("a: members [a, b, c]"));
+    }
+
+    /// Records the names of the created spans.
+    #[cfg(feature = "tracing")]
+    #[derive(Default)]
+    struct SpanNames {
+        names: Arc<Mutex<Vec<&'static str>>>,
+        next_id: AtomicU64,
+    }
+
+    #[cfg(feature = "tracing")]
+    impl Subscriber for SpanNames {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            self.names.lock().unwrap().push(span.metadata().name());
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, _event: &TracingEvent<'_>) {}
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn receive_emits_spans() {
+        let members = ["a", "b"];
+        let mut replica_a: IncReplica = Replica::bootstrap("a".to_string(), &members);
+        let mut replica_b: IncReplica = Replica::bootstrap("b".to_string(), &members);
+        let event = replica_b.send(Inc(1)).unwrap();
+        replica_a.receive(event);
+        let event = replica_a.send(Inc(1)).unwrap();
+
+        let subscriber = SpanNames::default();
+        let names = subscriber.names.clone();
+        tracing::subscriber::with_default(subscriber, || {
+            replica_b.receive(event);
+            assert_eq!(replica_b.query(Read::new()), 2);
+        });
+
+        assert_eq!(
+            *names.lock().unwrap(),
+            vec!["receive", "new_event", "prune", "stabilize", "eval"]
+        );
+    }
+
+    #[test]
+    fn subscribe_to_changes() {
+        let members = ["a", "b"];
+        let mut replica_a: IncReplica = Replica::bootstrap("a".to_string(), &members);
+        let mut replica_b: IncReplica = Replica::bootstrap("b".to_string(), &members);
+        let changes = replica_a.subscribe(Read::<usize>::new());
+        assert!(changes.try_recv().is_err());
+
+        replica_a.send(Inc(1)).unwrap();
+        let event = replica_b.send(Inc(1)).unwrap();
+        replica_a.receive(event);
+        assert_eq!(changes.try_iter().collect::<Vec<_>>(), vec![1, 2]);
+
+        // Dropping the receiver ends the subscription
+        drop(changes);
+        replica_a.send(Inc(1)).unwrap();
+        assert!(replica_a.subscriptions.is_empty());
+    }
+
+    #[test]
+    fn apply_batch_notifies_once() {
+        let members = ["a", "b"];
+        let mut replica_a: IncReplica = Replica::bootstrap("a".to_string(), &members);
+        let mut replica_b: IncReplica = Replica::bootstrap("b".to_string(), &members);
+        let changes = replica_a.subscribe(Read::<usize>::new());
+
+        replica_a.apply_batch((1..=4).map(Inc)).unwrap();
+        assert_eq!(changes.try_iter().collect::<Vec<_>>(), vec![10]);
+        assert_eq!(replica_a.num_delivered_events(), 4);
+
+        replica_b.receive_batch(replica_a.pull(replica_b.since()));
+        assert_eq!(replica_b.query(Read::new()), 10);
+    }
+
+    #[test]
+    fn builder_options() {
+        let solo: IncReplica = Replica::builder("a".to_string()).build().unwrap();
+        assert_eq!(solo.tcsb().members().len(), 1);
+
+        let mut replica_a: IncReplica = Replica::builder("a".to_string())
+            .with_members(&["a", "b"])
+            .with_max_unstable(2)
+            .build()
+            .unwrap();
+        assert_eq!(replica_a.tcsb().members().len(), 2);
+        assert_eq!(replica_a.tcsb().max_unstable(), Some(2));
+        assert!(matches!(
+            replica_a.try_send(Inc(1)),
+            Ok(SendOutcome::Sent(_))
+        ));
+        assert!(matches!(
+            replica_a.try_send(Inc(1)),
+            Ok(SendOutcome::Sent(_))
+        ));
+        assert!(matches!(
+            replica_a.try_send(Inc(1)),
+            Ok(SendOutcome::Backpressure { .. })
+        ));
+        assert_eq!(replica_a.query(Read::new()), 2);
+    }
+
+    thread_local! {
+        static CLONES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    /// Value counting how many times it is cloned.
+    #[derive(Debug, Default, PartialEq)]
+    struct Counted(usize);
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            CLONES.with(|clones| clones.set(clones.get() + 1));
+            Self(self.0)
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct CountedInc;
+
+    impl PureCRDT for CountedInc {
+        type Value = Counted;
+        type StableState = Vec<Self>;
+        type Rejection = Infallible;
+    }
+
+    impl InternalizeOp for CountedInc {
+        fn internalize(self, _interner: &Interner) -> Self {
+            self
+        }
+    }
+
+    impl<U: IsUnstableCore<CountedInc>> Eval<Read<Counted>, U> for CountedInc {
+        fn execute_query(_q: Read<Counted>, stable: &Vec<CountedInc>, unstable: &U) -> Counted {
+            Counted(stable.len() + unstable.len())
+        }
+    }
+
+    /// Increment of the counter at a key.
+    #[derive(Clone, Debug)]
+    struct Tally(&'static str);
+
+    impl PureCRDT for Tally {
+        type Value = BTreeMap<&'static str, Counted>;
+        type StableState = Vec<Self>;
+        type Rejection = Infallible;
+    }
+
+    impl InternalizeOp for Tally {
+        fn internalize(self, _interner: &Interner) -> Self {
+            self
+        }
+    }
+
+    impl<U: IsUnstableCore<Tally>> Eval<Read<BTreeMap<&'static str, Counted>>, U> for Tally {
+        fn execute_query(
+            _q: Read<BTreeMap<&'static str, Counted>>,
+            stable: &Vec<Tally>,
+            unstable: &U,
+        ) -> BTreeMap<&'static str, Counted> {
+            let mut map = BTreeMap::<&'static str, Counted>::new();
+            let ops = stable.iter().chain(unstable.iter().map(|t| t.op()));
+            for Tally(key) in ops {
+                map.entry(*key).or_default().0 += 1;
+            }
+            map
+        }
+    }
+
+    #[test]
+    fn with_value_does_not_clone() {
+        let members = ["a", "b"];
+        let mut counter_a: Replica<CachedLog<VecLog<CountedInc>>, Tcsb<CountedInc>> =
+            Replica::bootstrap("a".to_string(), &members);
+        let mut counter_b: Replica<CachedLog<VecLog<CountedInc>>, Tcsb<CountedInc>> =
+            Replica::bootstrap("b".to_string(), &members);
+        counter_b.receive(counter_a.send(CountedInc).unwrap());
+        counter_a.receive(counter_b.send(CountedInc).unwrap());
+
+        let mut map_a: Replica<CachedLog<VecLog<Tally>>, Tcsb<Tally>> =
+            Replica::bootstrap("a".to_string(), &members);
+        map_a.send(Tally("x")).unwrap();
+        map_a.send(Tally("y")).unwrap();
+        map_a.send(Tally("x")).unwrap();
+
+        CLONES.with(|clones| clones.set(0));
+        for _ in 0..2 {
+            assert_eq!(counter_a.with_value(|value| value.0), 2);
+            assert_eq!(counter_b.with_value(|value| value.0), 2);
+            assert_eq!(map_a.with_value(|map| map["x"].0), 2);
+            assert_eq!(map_a.with_value(|map| map.len()), 2);
+        }
+        assert_eq!(CLONES.with(|clones| clones.get()), 0);
+
+        // Reading an owned value is where a clone would come from
+        let owned = map_a.with_value(|map| map.clone());
+        assert_eq!(owned["y"], Counted(1));
+        assert_eq!(CLONES.with(|clones| clones.get()), 2);
+    }
+}
@@ -1,8 +1,4 @@
-use std::{
-    cell::OnceCell,
-    fmt,
-    fmt::Debug,
-};
+use std::{cell::OnceCell, fmt, fmt::Debug};
 
 use crate::{
     clock::version_vector::Version,
@@ -11,17 +7,16 @@ use crate::{
         query::{QueryOperation, Read},
     },
     event::Event,
-    state::{effect_context::EffectContext, log::IsLog},
+    state::{
+        effect_context::EffectContext,
+        log::{HistoricLog, IsLog, PruneStats, StableSnapshot},
+    },
 };
 
 #[cfg(feature = "test_utils")]
 use crate::{
     crdt::pure_crdt::PureCRDT,
-    state::{
-        log::IsLogTest,
-        stable_state::IsStableState,
-        unstable_state::CausalReplay,
-    },
+    state::{log::IsLogTest, stable_state::IsStableState, unstable_state::CausalReplay},
 };
 #[cfg(feature = "test_utils")]
 use deepsize::DeepSizeOf;
@@ -107,10 +102,30 @@ impl<L: IsLog> Default for CachedLog<L> {
     }
 }
 
+/// The cached read is not part of the state.
+impl<L: IsLog + PartialEq> PartialEq for CachedLog<L> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<L> FromIterator<L::Op> for CachedLog<L>
+where
+    L: IsLog + FromIterator<L::Op>,
+{
+    fn from_iter<I: IntoIterator<Item = L::Op>>(ops: I) -> Self {
+        Self {
+            inner: L::from_iter(ops),
+            read_cache: CacheCell::new(),
+        }
+    }
+}
+
 impl<L: IsLog> IsLog for CachedLog<L> {
     type Value = L::Value;
     type Op = L::Op;
     type Rejection = L::Rejection;
+    const NEEDS_CAUSAL_DEPS: bool = L::NEEDS_CAUSAL_DEPS;
 
     fn new() -> Self {
         Self {
@@ -127,6 +142,10 @@ impl<L: IsLog> IsLog for CachedLog<L> {
         self.inner.is_enabled(op)
     }
 
+    fn is_enabled_at(&self, op: &Self::Op, delivered: &Version) -> Result<(), Self::Rejection> {
+        self.inner.is_enabled_at(op, delivered)
+    }
+
     fn effect(&mut self, event: Event<Self::Op>, ctx: &mut EffectContext<'_>) {
         self.read_cache.invalidate();
         self.inner.effect(event, ctx);
@@ -145,6 +164,28 @@ impl<L: IsLog> IsLog for CachedLog<L> {
     fn is_default(&self) -> bool {
         self.inner.is_default()
     }
+
+    fn prune_stats(&self) -> PruneStats {
+        self.inner.prune_stats()
+    }
+}
+
+impl<L: HistoricLog> HistoricLog for CachedLog<L> {
+    fn at(&self, version: &Version, stable: &Version) -> Option<Self> {
+        Some(Self {
+            inner: self.inner.at(version, stable)?,
+            read_cache: CacheCell::new(),
+        })
+    }
+}
+
+impl<L: StableSnapshot> StableSnapshot for CachedLog<L> {
+    fn stable_snapshot(&self, stable: &Version) -> Self {
+        Self {
+            inner: self.inner.stable_snapshot(stable),
+            read_cache: CacheCell::new(),
+        }
+    }
 }
 
 impl<Q, L> EvalNested<Q> for CachedLog<L>
@@ -187,3 +228,70 @@ where
         self.inner.unstable_mut()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, convert::Infallible};
+
+    use crate::{
+        broadcast::tcsb::Tcsb,
+        crdt::{eval::Eval, pure_crdt::PureCRDT, query::Read},
+        replica::{IsReplica, Replica},
+        state::{cache::CachedLog, graph_log::GraphLog, unstable_state::IsUnstableCore},
+        utils::intern_str::{InternalizeOp, Interner},
+    };
+
+    thread_local! {
+        static EVALS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Counter increment, counting its evaluations.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Inc;
+
+    impl PureCRDT for Inc {
+        type Value = usize;
+        type StableState = Vec<Self>;
+        type Rejection = Infallible;
+        const DISABLE_R_WHEN_R: bool = true;
+        const DISABLE_R_WHEN_NOT_R: bool = true;
+    }
+
+    impl InternalizeOp for Inc {
+        fn internalize(self, _interner: &Interner) -> Self {
+            self
+        }
+    }
+
+    impl<U: IsUnstableCore<Inc>> Eval<Read<usize>, U> for Inc {
+        fn execute_query(_q: Read<usize>, stable: &Vec<Inc>, unstable: &U) -> usize {
+            EVALS.set(EVALS.get() + 1);
+            stable.len() + unstable.len()
+        }
+    }
+
+    type IncReplica = Replica<CachedLog<GraphLog<Inc>>, Tcsb<Inc>>;
+
+    #[test]
+    fn reads_until_the_log_changes() {
+        let mut replica_a: IncReplica = Replica::bootstrap("a".to_string(), &["a", "b"]);
+        let mut replica_b: IncReplica = Replica::bootstrap("b".to_string(), &["a", "b"]);
+
+        replica_a.send(Inc).unwrap();
+        assert_eq!(*replica_a.read_ref(), 1);
+        assert_eq!(*replica_a.read_ref(), 1);
+        assert_eq!(EVALS.get(), 1);
+
+        let event = replica_b.send(Inc).unwrap();
+        replica_a.receive(event);
+        assert_eq!(*replica_a.read_ref(), 2);
+        assert_eq!(*replica_a.read_ref(), 2);
+        assert_eq!(EVALS.get(), 2);
+
+        // A cloned log starts without a cached value
+        let replica_c: IncReplica =
+            Replica::bootstrap_with_state("a".to_string(), &["a", "b"], replica_a.state().clone());
+        assert_eq!(*replica_c.read_ref(), 2);
+        assert_eq!(EVALS.get(), 3);
+    }
+}
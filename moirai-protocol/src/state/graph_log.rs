@@ -14,7 +14,7 @@ use crate::{
     state::{
         cache::CacheCell,
         effect_context::EffectContext,
-        log::IsLog,
+        log::{HistoricLog, IsLog, PruneStats, StableSnapshot},
         stable_state::IsStableState,
         unstable_state::{IsUnstableCore, IsUnstablePrune, event_graph::EventGraph},
     },
@@ -44,6 +44,16 @@ where
     }
 }
 
+impl<O> PartialEq for GraphLog<O>
+where
+    O: PureCRDT + PartialEq,
+    O::StableState: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.stable == other.stable && self.unstable == other.unstable
+    }
+}
+
 impl<O> IsLog for GraphLog<O>
 where
     O: PureCRDT + Clone,
@@ -96,10 +106,55 @@ where
         O::is_enabled(op, &self.stable, &self.unstable)
     }
 
+    fn is_enabled_at(&self, op: &Self::Op, delivered: &Version) -> Result<(), Self::Rejection> {
+        self.is_enabled(op)?;
+        O::is_enabled_at(op, delivered)
+    }
+
     fn stabilize(&mut self, version: &Version) {
         self.read_cache.invalidate();
         self.unstable.stabilize(version);
     }
+
+    fn prune_stats(&self) -> PruneStats {
+        PruneStats::of::<O>(&self.stable, &self.unstable)
+    }
+}
+
+/// The event graph keeps every unstable event, so any past version including the stable one can be rebuilt.
+impl<O> HistoricLog for GraphLog<O>
+where
+    O: PureCRDT + Clone,
+    O::StableState: Clone,
+{
+    fn at(&self, version: &Version, stable: &Version) -> Option<Self> {
+        if !version.includes(stable) {
+            return None;
+        }
+        Some(Self {
+            stable: self.stable.clone(),
+            unstable: self
+                .unstable
+                .filtered(|tagged_op| tagged_op.id().is_predecessor_of(version)),
+            read_cache: CacheCell::new(),
+        })
+    }
+}
+
+impl<O> StableSnapshot for GraphLog<O>
+where
+    O: PureCRDT + Clone,
+    O::StableState: Clone,
+{
+    fn stable_snapshot(&self, stable: &Version) -> Self {
+        Self {
+            stable: self.stable.clone(),
+            unstable: self
+                .unstable
+                .filtered(|tagged_op| tagged_op.id().is_predecessor_of(stable)),
+            read_cache: CacheCell::new(),
+        }
+    }
 }
 
 impl<O> Default for GraphLog<O>
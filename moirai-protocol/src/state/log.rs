@@ -1,30 +1,92 @@
-use std::fmt::{Debug, Display};
+use std::{
+    fmt::{Debug, Display},
+    iter::Sum,
+    ops::{Add, AddAssign},
+};
 
 #[cfg(feature = "test_utils")]
 use deepsize::DeepSizeOf;
 
-use crate::{
-    clock::version_vector::Version,
-    crdt::{eval::EvalNested, query::QueryOperation},
-    event::Event,
-    state::effect_context::EffectContext,
-};
 #[cfg(feature = "test_utils")]
+use crate::state::{
+    po_log::POLog,
+    unstable_state::{CausalReplay, IsUnstableState},
+};
 use crate::{
-    crdt::pure_crdt::PureCRDT,
+    clock::version_vector::Version,
+    crdt::{eval::EvalNested, pure_crdt::PureCRDT, query::QueryOperation},
+    event::{Event, tagged_op::TaggedOp},
     state::{
-        po_log::POLog,
-        stable_state::IsStableState,
-        unstable_state::{CausalReplay, IsUnstableState},
+        effect_context::EffectContext, stable_state::IsStableState, unstable_state::IsUnstableCore,
     },
 };
 
+/// Breakdown of what a log keeps in memory, to measure how effective pruning is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneStats {
+    /// Unstable events that contribute to the value.
+    pub live_events: usize,
+    /// Unstable events kept only to mask concurrent operations (see `PureCRDT::is_tombstone`).
+    pub tombstones: usize,
+    /// Entries of the stable state.
+    pub stable_ops: usize,
+    /// Estimate of the memory used by the log, in bytes. Only the inline size of the values
+    /// is counted, not what they own on the heap, e.g., the characters of a `String`:
+    /// it is a lower bound, to compare logs holding the same types.
+    pub bytes_estimate: usize,
+}
+
+impl PruneStats {
+    /// Stats of a log made of a stable state and of unstable operations.
+    pub fn of<O>(stable: &O::StableState, unstable: &impl IsUnstableCore<O>) -> Self
+    where
+        O: PureCRDT,
+    {
+        let tombstones = unstable
+            .iter()
+            .filter(|tagged_op| O::is_tombstone(tagged_op.op()))
+            .count();
+        Self {
+            live_events: unstable.len() - tombstones,
+            tombstones,
+            stable_ops: stable.num_entries(),
+            bytes_estimate: unstable.len() * size_of::<TaggedOp<O>>() + size_of_val(stable),
+        }
+    }
+}
+
+impl Add for PruneStats {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self += rhs;
+        self
+    }
+}
+
+impl AddAssign for PruneStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.live_events += rhs.live_events;
+        self.tombstones += rhs.tombstones;
+        self.stable_ops += rhs.stable_ops;
+        self.bytes_estimate += rhs.bytes_estimate;
+    }
+}
+
+impl Sum for PruneStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
 pub trait IsLog: Default + Debug {
     // TODO: is Value really needed?
     type Value: Default + Debug;
     /// Stored operation type.
     type Op: Debug + Clone;
     type Rejection: Debug + Display;
+    /// See [`PureCRDT::NEEDS_CAUSAL_DEPS`](crate::crdt::pure_crdt::PureCRDT::NEEDS_CAUSAL_DEPS).
+    const NEEDS_CAUSAL_DEPS: bool = true;
 
     fn new() -> Self {
         Self::default()
@@ -37,17 +99,42 @@ pub trait IsLog: Default + Debug {
     fn is_enabled(&self, _op: &Self::Op) -> Result<(), Self::Rejection> {
         Ok(())
     }
+    /// Same as `is_enabled`, also checking the operation against the version `delivered`
+    /// by the local replica (see [`PureCRDT::is_enabled_at`]).
+    fn is_enabled_at(&self, op: &Self::Op, _delivered: &Version) -> Result<(), Self::Rejection> {
+        self.is_enabled(op)
+    }
     fn effect(&mut self, event: Event<Self::Op>, ctx: &mut EffectContext<'_>);
     fn eval<Q>(&self, q: Q) -> Q::Response
     where
         Q: QueryOperation,
         Self: EvalNested<Q>,
     {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("eval").entered();
         Self::execute_query(self, q)
     }
     fn stabilize(&mut self, version: &Version);
     fn redundant_by_parent(&mut self, version: &Version, conservative: bool);
     fn is_default(&self) -> bool;
+    /// What the log keeps in memory. Nested logs sum the stats of their children.
+    fn prune_stats(&self) -> PruneStats;
+}
+
+/// Log able to rebuild one of its past states from the events it retains.
+pub trait HistoricLog: IsLog + Sized {
+    /// The log as it was with only the events included in `version`,
+    /// or `None` if they cannot be told apart anymore.
+    /// `stable` is the last stable version: stable events are usually folded into the stable state.
+    fn at(&self, version: &Version, stable: &Version) -> Option<Self>;
+}
+
+/// Log able to split off its stable part, e.g., for a fresh replica to start from it.
+pub trait StableSnapshot: IsLog + Sized {
+    /// The log with only the events included in the stable version `stable`.
+    /// Unlike [`HistoricLog::at`], the stable state may miss operations pruned by unstable ones:
+    /// the snapshot is meant to deliver these unstable events again.
+    fn stable_snapshot(&self, stable: &Version) -> Self;
 }
 
 #[doc(hidden)]
@@ -95,6 +182,7 @@ impl<L: IsLog> IsLog for Box<L> {
     type Value = L::Value;
     type Op = Box<L::Op>;
     type Rejection = L::Rejection;
+    const NEEDS_CAUSAL_DEPS: bool = L::NEEDS_CAUSAL_DEPS;
 
     fn new() -> Self {
         Box::new(L::new())
@@ -104,6 +192,10 @@ impl<L: IsLog> IsLog for Box<L> {
         (**self).is_enabled(op)
     }
 
+    fn is_enabled_at(&self, op: &Self::Op, delivered: &Version) -> Result<(), Self::Rejection> {
+        (**self).is_enabled_at(op, delivered)
+    }
+
     fn effect(&mut self, event: Event<Self::Op>, ctx: &mut EffectContext<'_>) {
         let inner_op = *event.op().clone();
         let inner_event = event.unfold(inner_op);
@@ -121,4 +213,8 @@ impl<L: IsLog> IsLog for Box<L> {
     fn is_default(&self) -> bool {
         (**self).is_default()
     }
+
+    fn prune_stats(&self) -> PruneStats {
+        (**self).prune_stats()
+    }
 }
@@ -6,5 +6,6 @@ pub mod object_path;
 pub mod po_log;
 pub mod sink;
 pub mod stable_state;
+pub mod traced_log;
 pub mod unstable_state;
 pub use unstable_state::event_graph;
@@ -7,13 +7,16 @@ use crate::{
     clock::version_vector::Version,
     crdt::{
         eval::{Eval, EvalNested},
-        pure_crdt::{CausalReset, PureCRDT},
+        pure_crdt::{CausalReset, PureCRDT, StableFold},
         query::QueryOperation,
         redundancy::RedundancyRelation,
     },
     event::{Event, id::EventId, lamport::Lamport, tagged_op::TaggedOp},
     state::{
-        cache::CachedLog, effect_context::EffectContext, log::IsLog, stable_state::IsStableState,
+        cache::CachedLog,
+        effect_context::EffectContext,
+        log::{HistoricLog, IsLog, PruneStats, StableSnapshot},
+        stable_state::IsStableState,
         unstable_state::IsUnstableState,
     },
 };
@@ -35,6 +38,46 @@ where
     pub(crate) unstable: U,
 }
 
+/// Same stable state and same unstable events. The unstable events are compared by id,
+/// whatever the order in which they were delivered.
+impl<O, U> PartialEq for POLog<O, U>
+where
+    O: PureCRDT + PartialEq,
+    O::StableState: PartialEq,
+    U: IsUnstableState<O>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        fn sorted<O, U: IsUnstableState<O>>(unstable: &U) -> Vec<&TaggedOp<O>> {
+            let mut tagged_ops: Vec<&TaggedOp<O>> = unstable.iter().collect();
+            tagged_ops.sort_by(|t1, t2| t1.id().cmp(t2.id()));
+            tagged_ops
+        }
+
+        self.stable == other.stable && sorted(&self.unstable) == sorted(&other.unstable)
+    }
+}
+
+/// Log whose operations are all stable, e.g., an initial state shared by every replica
+/// (see [`crate::replica::Replica::bootstrap_with_state`]).
+///
+/// Only for CRDTs whose stable state applies any operation, such as a grow-only set (see [`StableFold`]).
+impl<O, U> FromIterator<O> for POLog<O, U>
+where
+    O: StableFold,
+    U: Default,
+{
+    fn from_iter<I: IntoIterator<Item = O>>(ops: I) -> Self {
+        let mut stable = O::StableState::default();
+        for op in ops {
+            stable.apply(op);
+        }
+        Self {
+            stable,
+            unstable: U::default(),
+        }
+    }
+}
+
 impl<O, U> IsLog for POLog<O, U>
 where
     O: PureCRDT + Clone,
@@ -43,6 +86,7 @@ where
     type Value = O::Value;
     type Op = O;
     type Rejection = O::Rejection;
+    const NEEDS_CAUSAL_DEPS: bool = O::NEEDS_CAUSAL_DEPS;
 
     fn new() -> Self {
         Self {
@@ -55,8 +99,15 @@ where
         O::is_enabled(op, &self.stable, &self.unstable)
     }
 
+    fn is_enabled_at(&self, op: &Self::Op, delivered: &Version) -> Result<(), Self::Rejection> {
+        self.is_enabled(op)?;
+        O::is_enabled_at(op, delivered)
+    }
+
     fn effect(&mut self, event: Event<Self::Op>, _ctx: &mut EffectContext<'_>) {
         let new_tagged_op = TaggedOp::from(&event);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("prune").entered();
         if O::redundant_itself(&new_tagged_op, &self.stable, self.unstable.iter()) {
             if !O::DISABLE_R_WHEN_R {
                 self.prune_redundant_ops(
@@ -66,7 +117,7 @@ where
                 );
             }
         } else {
-            if !O::DISABLE_R_WHEN_NOT_R {
+            if !O::DISABLE_R_WHEN_NOT_R && O::prunes_when_not_redundant(&new_tagged_op) {
                 self.prune_redundant_ops(
                     O::redundant_by_when_not_redundant,
                     &new_tagged_op,
@@ -123,6 +174,57 @@ where
     fn is_default(&self) -> bool {
         self.stable.is_default() && self.unstable.is_empty()
     }
+
+    fn prune_stats(&self) -> PruneStats {
+        PruneStats::of::<O>(&self.stable, &self.unstable)
+    }
+}
+
+/// The unstable operations are filtered, which requires that none of them were pruned
+/// by later ones and that the stable state only holds operations included in the version.
+///
+/// # Compile-time check
+/// Rebuilding the log of a CRDT that prunes redundant operations, i.e., without both
+/// `DISABLE_R_WHEN_R` and `DISABLE_R_WHEN_NOT_R`, fails to compile.
+impl<O, U> HistoricLog for POLog<O, U>
+where
+    O: PureCRDT + Clone,
+    O::StableState: Clone,
+    U: IsUnstableState<O> + Default + Debug,
+{
+    fn at(&self, version: &Version, stable: &Version) -> Option<Self> {
+        const {
+            assert!(
+                O::DISABLE_R_WHEN_R && O::DISABLE_R_WHEN_NOT_R,
+                "Past versions of a log pruning redundant operations cannot be rebuilt"
+            )
+        };
+        if !version.includes(stable) {
+            return None;
+        }
+        Some(Self {
+            stable: self.stable.clone(),
+            unstable: self
+                .unstable
+                .filtered(|tagged_op| tagged_op.id().is_predecessor_of(version)),
+        })
+    }
+}
+
+impl<O, U> StableSnapshot for POLog<O, U>
+where
+    O: PureCRDT + Clone,
+    O::StableState: Clone,
+    U: IsUnstableState<O> + Default + Debug,
+{
+    fn stable_snapshot(&self, stable: &Version) -> Self {
+        Self {
+            stable: self.stable.clone(),
+            unstable: self
+                .unstable
+                .filtered(|tagged_op| tagged_op.id().is_predecessor_of(stable)),
+        }
+    }
 }
 
 impl<O, U> Default for POLog<O, U>
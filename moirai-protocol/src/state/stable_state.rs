@@ -7,6 +7,11 @@ pub trait IsStableState<O>: Default + Debug {
     fn apply(&mut self, value: O);
     fn clear(&mut self);
     fn prune_redundant_ops(&mut self, rdnt: RedundancyRelation<O>, tagged_op: &TaggedOp<O>);
+    /// Number of entries kept by the stable state.
+    /// A state compacted into a single value (e.g., a counter) counts as one entry.
+    fn num_entries(&self) -> usize {
+        usize::from(!self.is_default())
+    }
 }
 
 impl<O> IsStableState<O> for Vec<O>
@@ -25,6 +30,10 @@ where
         self.clear();
     }
 
+    fn num_entries(&self) -> usize {
+        self.len()
+    }
+
     fn prune_redundant_ops(&mut self, rdnt: RedundancyRelation<O>, new_tagged_op: &TaggedOp<O>) {
         self.retain(|o| {
             let is_rdnt = rdnt(o, None, false, new_tagged_op);
@@ -0,0 +1,233 @@
+#[cfg(feature = "test_utils")]
+use deepsize::DeepSizeOf;
+
+use crate::{
+    clock::version_vector::Version,
+    crdt::{
+        eval::{BorrowedRead, EvalNested},
+        query::QueryOperation,
+    },
+    event::Event,
+    state::{
+        effect_context::EffectContext,
+        log::{HistoricLog, IsLog, PruneStats, StableSnapshot},
+    },
+};
+#[cfg(feature = "test_utils")]
+use crate::{
+    crdt::pure_crdt::PureCRDT,
+    state::{log::IsLogTest, stable_state::IsStableState, unstable_state::CausalReplay},
+};
+
+/// Call that changed a [`TracedLog`].
+#[derive(Clone, Debug)]
+pub enum LogCall<O> {
+    Effect(Event<O>),
+    Stabilize(Version),
+    RedundantByParent {
+        version: Version,
+        conservative: bool,
+    },
+}
+
+/// Log recording every call that changes the log it wraps, to debug a CRDT.
+///
+/// The trace of a failing run can be cut down and replayed on a new log with
+/// [`TracedLog::replay`], without the replicas and the network of the run.
+#[derive(Debug, Clone)]
+pub struct TracedLog<L: IsLog> {
+    inner: L,
+    trace: Vec<LogCall<L::Op>>,
+}
+
+impl<L: IsLog> TracedLog<L> {
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> L {
+        self.inner
+    }
+
+    /// Calls since the log was created, oldest first.
+    pub fn trace(&self) -> &[LogCall<L::Op>] {
+        &self.trace
+    }
+
+    /// Apply the calls of a trace to a new log.
+    pub fn replay(trace: &[LogCall<L::Op>]) -> L {
+        let mut log = L::new();
+        for call in trace {
+            match call {
+                LogCall::Effect(event) => {
+                    log.effect(event.clone(), &mut EffectContext::root("root", None))
+                }
+                LogCall::Stabilize(version) => log.stabilize(version),
+                LogCall::RedundantByParent {
+                    version,
+                    conservative,
+                } => log.redundant_by_parent(version, *conservative),
+            }
+        }
+        log
+    }
+}
+
+impl<L: IsLog> Default for TracedLog<L> {
+    fn default() -> Self {
+        Self {
+            inner: L::default(),
+            trace: Vec::new(),
+        }
+    }
+}
+
+impl<L: IsLog> IsLog for TracedLog<L> {
+    type Value = L::Value;
+    type Op = L::Op;
+    type Rejection = L::Rejection;
+    const NEEDS_CAUSAL_DEPS: bool = L::NEEDS_CAUSAL_DEPS;
+
+    fn new() -> Self {
+        Self {
+            inner: L::new(),
+            trace: Vec::new(),
+        }
+    }
+
+    fn prepare(op: Self::Op) -> Self::Op {
+        L::prepare(op)
+    }
+
+    fn is_enabled(&self, op: &Self::Op) -> Result<(), Self::Rejection> {
+        self.inner.is_enabled(op)
+    }
+
+    fn is_enabled_at(&self, op: &Self::Op, delivered: &Version) -> Result<(), Self::Rejection> {
+        self.inner.is_enabled_at(op, delivered)
+    }
+
+    fn effect(&mut self, event: Event<Self::Op>, ctx: &mut EffectContext<'_>) {
+        self.trace.push(LogCall::Effect(event.clone()));
+        self.inner.effect(event, ctx);
+    }
+
+    fn stabilize(&mut self, version: &Version) {
+        self.trace.push(LogCall::Stabilize(version.clone()));
+        self.inner.stabilize(version);
+    }
+
+    fn redundant_by_parent(&mut self, version: &Version, conservative: bool) {
+        self.trace.push(LogCall::RedundantByParent {
+            version: version.clone(),
+            conservative,
+        });
+        self.inner.redundant_by_parent(version, conservative);
+    }
+
+    fn is_default(&self) -> bool {
+        self.inner.is_default()
+    }
+
+    fn prune_stats(&self) -> PruneStats {
+        self.inner.prune_stats()
+    }
+}
+
+/// The past state keeps the whole trace: it is meant to be inspected, not replayed.
+impl<L: HistoricLog> HistoricLog for TracedLog<L> {
+    fn at(&self, version: &Version, stable: &Version) -> Option<Self> {
+        Some(Self {
+            inner: self.inner.at(version, stable)?,
+            trace: self.trace.clone(),
+        })
+    }
+}
+
+/// The snapshot keeps the whole trace, as [`HistoricLog::at`] does.
+impl<L: StableSnapshot> StableSnapshot for TracedLog<L> {
+    fn stable_snapshot(&self, stable: &Version) -> Self {
+        Self {
+            inner: self.inner.stable_snapshot(stable),
+            trace: self.trace.clone(),
+        }
+    }
+}
+
+impl<Q, L> EvalNested<Q> for TracedLog<L>
+where
+    Q: QueryOperation,
+    L: IsLog + EvalNested<Q>,
+{
+    fn execute_query(&self, q: Q) -> Q::Response {
+        self.inner.execute_query(q)
+    }
+}
+
+impl<L: BorrowedRead> BorrowedRead for TracedLog<L> {
+    fn read_ref(&self) -> &Self::Value {
+        self.inner.read_ref()
+    }
+}
+
+#[cfg(feature = "test_utils")]
+impl<L> IsLogTest for TracedLog<L>
+where
+    L: IsLogTest,
+    L::Op: PureCRDT + DeepSizeOf,
+    <L::Op as PureCRDT>::StableState: IsStableState<L::Op>,
+{
+    fn stable(&self) -> &<Self::Op as PureCRDT>::StableState {
+        self.inner.stable()
+    }
+
+    fn unstable(&self) -> &(impl CausalReplay<Self::Op> + DeepSizeOf) {
+        self.inner.unstable()
+    }
+
+    fn unstable_mut(&mut self) -> &mut (impl CausalReplay<Self::Op> + DeepSizeOf) {
+        self.inner.unstable_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        broadcast::tcsb::Tcsb,
+        crdt::query::Read,
+        replica::{IsReplica, Replica},
+        state::po_log::VecLog,
+        utils::test_ops::Inc,
+    };
+
+    #[test]
+    fn trace_counter_log() {
+        let members = ["a", "b"];
+        let mut replica_a: Replica<TracedLog<VecLog<Inc>>, Tcsb<Inc>> =
+            Replica::bootstrap("a".to_string(), &members);
+        let mut replica_b: Replica<VecLog<Inc>, Tcsb<Inc>> =
+            Replica::bootstrap("b".to_string(), &members);
+
+        let event = replica_a.send(Inc(2)).unwrap();
+        replica_b.receive(event);
+        let event = replica_b.send(Inc(5)).unwrap();
+        replica_a.receive(event);
+        assert_eq!(replica_a.query(Read::new()), 7);
+
+        let trace = replica_a.state().trace();
+        let effects: Vec<_> = trace
+            .iter()
+            .filter_map(|call| match call {
+                LogCall::Effect(event) => Some((event.id().origin_id(), event.op().0)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(effects, vec![("a", 2), ("b", 5)]);
+        // Both events are delivered by both replicas once a receives the one of b
+        assert!(matches!(trace.last(), Some(LogCall::Stabilize(_))));
+
+        let replayed = TracedLog::<VecLog<Inc>>::replay(trace);
+        assert_eq!(replayed.eval(Read::new()), 7);
+    }
+}
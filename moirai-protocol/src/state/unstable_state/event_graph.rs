@@ -23,6 +23,10 @@ use crate::{
     },
 };
 
+/// Unstable events linked to their immediate causal predecessors.
+///
+/// Reads replay the graph every time, the graph does not keep the last value: wrap the log in a
+/// [`CachedLog`](crate::state::cache::CachedLog) to only evaluate it once per change.
 #[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
 #[derive(Debug, Clone)]
 pub struct EventGraph<O> {
@@ -210,6 +214,14 @@ where
         }
     }
 
+    /// The graph is copied whole before being pruned, as its edges are derived from the versions
+    /// of the events, which are not kept.
+    fn filtered<T: Fn(&TaggedOp<O>) -> bool>(&self, predicate: T) -> Self {
+        let mut graph = self.clone();
+        graph.retain(predicate);
+        graph
+    }
+
     fn clear(&mut self) {
         self.graph.clear();
         self.map.clear();
@@ -235,6 +247,33 @@ where
 //     }
 // }
 
+/// Same events, with the same ops and dependencies. Node indices depend on the delivery order,
+/// so events are matched by id: two replicas that delivered the same events are equal.
+impl<O: PartialEq> PartialEq for EventGraph<O> {
+    fn eq(&self, other: &Self) -> bool {
+        fn parent_ids<O>(event_graph: &EventGraph<O>, idx: NodeIndex) -> Vec<&EventId> {
+            let mut ids: Vec<&EventId> = event_graph
+                .graph
+                .neighbors_directed(idx, Direction::Outgoing)
+                .map(|parent_idx| event_graph.map.get_by_left(&parent_idx).unwrap())
+                .collect();
+            ids.sort();
+            ids
+        }
+
+        self.graph.node_count() == other.graph.node_count()
+            && self.heads == other.heads
+            && self.map.iter().all(|(idx, id)| {
+                other.map.get_by_right(id).is_some_and(|other_idx| {
+                    self.graph[*idx] == other.graph[*other_idx]
+                        && parent_ids(self, *idx) == parent_ids(other, *other_idx)
+                })
+            })
+    }
+}
+
+impl<O: Eq> Eq for EventGraph<O> {}
+
 impl<O> Default for EventGraph<O> {
     fn default() -> Self {
         Self {
@@ -330,6 +369,68 @@ where
     pub fn heads(&self) -> &HashSet<EventId> {
         &self.heads
     }
+
+    /// Check that the index structures agree with the graph, for tests and debugging:
+    /// - the map is a bijection between the nodes of the graph and the ids of their events,
+    /// - the heads are nodes without children,
+    /// - the cutter only retains nodes of the graph, under the id of their event.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.map.len() != self.graph.node_count() {
+            return Err(format!(
+                "{} nodes but {} mapped ids",
+                self.graph.node_count(),
+                self.map.len()
+            ));
+        }
+        for node_idx in self.graph.node_indices() {
+            let tagged_op = self.graph.node_weight(node_idx).unwrap();
+            match self.map.get_by_left(&node_idx) {
+                Some(id) if id == tagged_op.id() => {}
+                Some(id) => {
+                    return Err(format!(
+                        "node {} holds {} but is mapped to {}",
+                        node_idx.index(),
+                        tagged_op.id(),
+                        id
+                    ));
+                }
+                None => return Err(format!("node {} is not mapped", node_idx.index())),
+            }
+        }
+        for head in &self.heads {
+            let Some(node_idx) = self.map.get_by_right(head) else {
+                return Err(format!("head {} is not in the graph", head));
+            };
+            if self
+                .graph
+                .neighbors_directed(*node_idx, Direction::Incoming)
+                .next()
+                .is_some()
+            {
+                return Err(format!("head {} has children", head));
+            }
+        }
+        for (idx, seq_map) in &self.cutter.0 {
+            for (seq, node_idx) in seq_map {
+                match self.map.get_by_left(node_idx) {
+                    Some(id) if id.idx() == *idx && id.seq() == *seq => {}
+                    Some(id) => {
+                        return Err(format!(
+                            "cutter entry ({}, {}) points to {}",
+                            idx.0, seq, id
+                        ));
+                    }
+                    None => {
+                        return Err(format!(
+                            "cutter entry ({}, {}) is not in the graph",
+                            idx.0, seq
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(feature = "test_utils")]
@@ -454,3 +555,123 @@ impl Default for Cutter {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        broadcast::tcsb::{IsTcsb, Tcsb},
+        replica::{IsReplica, Replica},
+        state::{
+            graph_log::GraphLog,
+            unstable_state::{IsUnstableCore, IsUnstablePrune, event_graph::EventGraph},
+        },
+        utils::{intern_str::Interner, test_ops::UnprunedInc},
+    };
+
+    #[test]
+    fn invariants_hold_after_removals_and_stabilization() {
+        let members = ["a", "b", "c"];
+        let mut replicas: Vec<Replica<GraphLog<UnprunedInc>, Tcsb<UnprunedInc>>> = members
+            .iter()
+            .map(|id| Replica::bootstrap(id.to_string(), &members))
+            .collect();
+
+        // Rounds of concurrent events, each round delivered everywhere before the next one
+        let mut events = Vec::new();
+        for round in 0..4 {
+            let messages: Vec<_> = replicas
+                .iter_mut()
+                .enumerate()
+                .filter(|(i, _)| (i + round) % 3 != 0)
+                .map(|(i, replica)| (i, replica.send(UnprunedInc).unwrap()))
+                .collect();
+            for (from, message) in messages {
+                events.push(message.event().clone());
+                for (i, replica) in replicas.iter_mut().enumerate() {
+                    if i != from {
+                        replica.receive(message.clone());
+                    }
+                }
+            }
+        }
+
+        let mut graph = EventGraph::<UnprunedInc>::default();
+        for event in &events {
+            graph.append(event.clone());
+            graph.check_invariants().unwrap();
+        }
+
+        // Remove a head, an event with children, then a whole replica
+        graph.remove(events.last().unwrap().id());
+        graph.check_invariants().unwrap();
+        graph.remove(events[2].id());
+        graph.check_invariants().unwrap();
+        let origin = events[0].id().idx();
+        graph.retain(|tagged_op| tagged_op.id().idx() != origin);
+        graph.check_invariants().unwrap();
+
+        graph.stabilize(events[5].version());
+        graph.check_invariants().unwrap();
+
+        graph.stabilize(events.last().unwrap().version());
+        graph.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn equal_regardless_of_delivery_order() {
+        let members = ["a", "b", "c"];
+        let mut tcsbs: Vec<Tcsb<UnprunedInc>> = members
+            .iter()
+            .map(|id| {
+                let mut interner = Interner::new();
+                let (idx, _) = interner.intern(id);
+                for member in members {
+                    interner.intern(member);
+                }
+                Tcsb::new(idx, interner)
+            })
+            .collect();
+        // Events as delivered to `a`, so that they all share its indices
+        let mut events = Vec::new();
+        for _ in 0..3 {
+            let messages: Vec<_> = tcsbs
+                .iter_mut()
+                .map(|tcsb| tcsb.send(UnprunedInc))
+                .collect();
+            for (from, message) in messages.iter().enumerate() {
+                if from == 0 {
+                    events.push(message.event().clone());
+                }
+                for (i, tcsb) in tcsbs.iter_mut().enumerate() {
+                    if i != from {
+                        tcsb.receive(message.clone());
+                        while let Some(event) = tcsb.next_causally_ready() {
+                            if i == 0 {
+                                events.push(event);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut graph_1 = EventGraph::<UnprunedInc>::default();
+        for event in &events {
+            graph_1.append(event.clone());
+        }
+        // Concurrent events in the reverse order, so that they get other node indices
+        events.sort_by(|e1, e2| {
+            e1.lamport()
+                .cmp(e2.lamport())
+                .then_with(|| e2.id().origin_id().cmp(e1.id().origin_id()))
+        });
+        let mut graph_2 = EventGraph::<UnprunedInc>::default();
+        for event in &events {
+            graph_2.append(event.clone());
+        }
+        assert_eq!(graph_1, graph_2);
+
+        graph_2.remove(events.last().unwrap().id());
+        assert_ne!(graph_1, graph_2);
+    }
+}
@@ -21,6 +21,13 @@ where
         HashMap::retain(self, |_, to| predicate(to));
     }
 
+    fn filtered<T: Fn(&TaggedOp<O>) -> bool>(&self, predicate: T) -> Self {
+        self.iter()
+            .filter(|(_, to)| predicate(to))
+            .map(|(id, to)| (id.clone(), to.clone()))
+            .collect()
+    }
+
     fn clear(&mut self) {
         HashMap::clear(self);
     }
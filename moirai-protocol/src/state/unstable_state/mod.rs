@@ -38,6 +38,10 @@ pub trait IsUnstableKeyed<O>: Debug {
 pub trait IsUnstablePrune<O>: IsUnstableCore<O> {
     fn remove(&mut self, event_id: &EventId);
     fn retain<T: Fn(&TaggedOp<O>) -> bool>(&mut self, predicate: T);
+    /// A copy of the state with only the operations satisfying the predicate.
+    fn filtered<T: Fn(&TaggedOp<O>) -> bool>(&self, predicate: T) -> Self
+    where
+        Self: Sized;
     fn clear(&mut self);
 }
 
@@ -70,6 +70,10 @@ where
         Vec::retain(self, predicate);
     }
 
+    fn filtered<T: Fn(&TaggedOp<O>) -> bool>(&self, predicate: T) -> Self {
+        self.iter().filter(|to| predicate(to)).cloned().collect()
+    }
+
     fn clear(&mut self) {
         Vec::clear(self);
     }
@@ -37,6 +37,21 @@ impl Resolver {
     pub fn into_vec(&self) -> Vec<ReplicaIdOwned> {
         (*self.inner).clone().into_vec()
     }
+
+    /// Ids of the replicas, by index.
+    pub fn members(&self) -> impl Iterator<Item = &ReplicaId> {
+        self.inner.iter()
+    }
+
+    /// Index of a replica id, `None` if it is not in the view.
+    ///
+    /// # Complexity
+    /// `O(n)`: the resolver only maps indices to ids, see [`Interner::get`] for the converse.
+    pub fn index_of(&self, id: &ReplicaId) -> Option<ReplicaIdx> {
+        self.members()
+            .position(|member| member == id)
+            .map(ReplicaIdx)
+    }
 }
 
 impl Debug for Resolver {
@@ -164,6 +179,15 @@ impl Interner {
     pub fn resolver(&self) -> &Resolver {
         &self.int_to_str
     }
+
+    /// Number of interned replica IDs.
+    pub fn len(&self) -> usize {
+        self.int_to_str.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.int_to_str.is_empty()
+    }
 }
 
 pub trait InternalizeOp {
@@ -1,2 +1,4 @@
 pub mod boxer;
 pub mod intern_str;
+#[cfg(any(test, feature = "test_utils"))]
+pub mod test_ops;
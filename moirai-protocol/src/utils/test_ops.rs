@@ -0,0 +1,78 @@
+use std::convert::Infallible;
+
+#[cfg(feature = "test_utils")]
+use deepsize::DeepSizeOf;
+
+use crate::{
+    crdt::{eval::Eval, pure_crdt::PureCRDT, query::Read},
+    persistence::OpCodec,
+    state::unstable_state::IsUnstableCore,
+    utils::intern_str::{InternalizeOp, Interner},
+};
+
+/// Counter increment, read as the sum of the increments.
+/// Shared by the tests exercising the protocol rather than a particular CRDT.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub struct Inc(pub u8);
+
+impl PureCRDT for Inc {
+    type Value = usize;
+    type StableState = Vec<Self>;
+    type Rejection = Infallible;
+}
+
+impl InternalizeOp for Inc {
+    fn internalize(self, _interner: &Interner) -> Self {
+        self
+    }
+}
+
+impl<U: IsUnstableCore<Inc>> Eval<Read<usize>, U> for Inc {
+    fn execute_query(_q: Read<usize>, stable: &Vec<Inc>, unstable: &U) -> usize {
+        let stable: usize = stable.iter().map(|op| op.0 as usize).sum();
+        let unstable: usize = unstable.iter().map(|t| t.op().0 as usize).sum();
+        stable + unstable
+    }
+}
+
+impl OpCodec for Inc {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(self.0);
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [v] => Some(Inc(*v)),
+            _ => None,
+        }
+    }
+}
+
+/// Counter increment that never makes logged operations redundant, as required by the
+/// [`GraphLog`](crate::state::graph_log::GraphLog) and wanted by benchmarks that should not
+/// measure the redundancy scan.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "test_utils", derive(DeepSizeOf))]
+pub struct UnprunedInc;
+
+impl PureCRDT for UnprunedInc {
+    type Value = usize;
+    type StableState = Vec<Self>;
+    type Rejection = Infallible;
+
+    const DISABLE_R_WHEN_R: bool = true;
+    const DISABLE_R_WHEN_NOT_R: bool = true;
+}
+
+impl InternalizeOp for UnprunedInc {
+    fn internalize(self, _interner: &Interner) -> Self {
+        self
+    }
+}
+
+impl<U: IsUnstableCore<UnprunedInc>> Eval<Read<usize>, U> for UnprunedInc {
+    fn execute_query(_q: Read<usize>, stable: &Vec<UnprunedInc>, unstable: &U) -> usize {
+        stable.len() + unstable.len()
+    }
+}